@@ -0,0 +1,153 @@
+//! Provides configuration of the acquisition burst.
+//!
+//! By default, [`data_raw`](crate::L3GD20::data_raw) reads `OUT_TEMP` through `OUT_Z_H`
+//! in one burst. At high output data rates with tight SPI budgets, the burst can start
+//! at `STATUS_REG` instead, saving a byte per sample, while the temperature is sampled
+//! separately at a low rate.
+
+use crate::{SensorData, Transport, L3GD20};
+
+/// The registers read by [`data_raw`](crate::L3GD20::data_raw).
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum AcquisitionBurst {
+    /// Read `OUT_TEMP`, `STATUS_REG` and the axis registers in one burst.
+    #[default]
+    Full,
+    /// Read only `STATUS_REG` and the axis registers, skipping `OUT_TEMP`.
+    ///
+    /// The temperature is read separately before every `temperature_every`-th sample
+    /// and reported from a cache otherwise; only the actual reads are recorded in the
+    /// [thermal statistics](crate::L3GD20::thermal_stats). The freshness of the axis
+    /// values is reported as with [`Full`](Self::Full) bursts.
+    AxesOnly {
+        /// The number of samples between temperature reads; at least one.
+        temperature_every: u16,
+    },
+}
+
+/// The state of the acquisition burst configuration.
+#[derive(Debug, Default)]
+pub(crate) struct Acquisition {
+    burst: AcquisitionBurst,
+    temperature: Option<u8>,
+    since_temperature: u16,
+}
+
+impl<T, E> L3GD20<T>
+where
+    T: Transport<Error = E>,
+{
+    /// Configures the registers read by [`data_raw`](Self::data_raw).
+    pub fn set_acquisition_burst(&mut self, burst: AcquisitionBurst) {
+        self.acquisition = Acquisition {
+            burst,
+            ..Acquisition::default()
+        };
+    }
+
+    /// Gets the registers read by [`data_raw`](Self::data_raw).
+    pub fn acquisition_burst(&self) -> AcquisitionBurst {
+        self.acquisition.burst
+    }
+
+    /// Reads a sample according to the configured acquisition burst.
+    pub(crate) fn acquire(&mut self) -> Result<SensorData, E> {
+        let AcquisitionBurst::AxesOnly { temperature_every } = self.acquisition.burst else {
+            return self.read_burst(None);
+        };
+
+        let temperature = match self.acquisition.temperature {
+            Some(temperature) if self.acquisition.since_temperature < temperature_every => {
+                temperature
+            }
+            _ => {
+                let temperature = self.temp_raw()?;
+                self.acquisition.temperature = Some(temperature);
+                self.acquisition.since_temperature = 0;
+                temperature
+            }
+        };
+
+        let data = self.read_burst(Some(temperature))?;
+        self.acquisition.since_temperature = self.acquisition.since_temperature.saturating_add(1);
+        Ok(data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::MockError;
+    use crate::sim::Simulator;
+    #[cfg(feature = "calibration")]
+    use crate::{
+        calibration::{CalibrationTask, Progress},
+        F32x3,
+    };
+    use crate::{Freshness, I16x3, Status};
+
+    #[test]
+    fn axes_only_bursts_sample_temperature_separately() {
+        let mut sim = Simulator::<MockError>::new();
+        sim.set_temperature(42);
+        sim.set_xyz(1, 2, 3);
+        let mut driver = L3GD20::with_transport(sim).unwrap();
+        driver.set_acquisition_burst(AcquisitionBurst::AxesOnly {
+            temperature_every: 3,
+        });
+
+        let start = driver.transport.transfers();
+        for _ in 0..6 {
+            let data = driver.data_raw().unwrap();
+            assert_eq!(data.temperature, 42);
+            assert_eq!(data.freshness(), Freshness::Stale);
+            assert_eq!(data.status, Status::default());
+            assert_eq!(I16x3::from(data), I16x3::new(1, 2, 3));
+        }
+
+        // Six axis bursts and two temperature reads.
+        assert_eq!(driver.transport.transfers() - start, 8);
+    }
+
+    #[test]
+    fn axes_only_bursts_report_the_freshness() {
+        let mut sim = Simulator::<MockError>::new();
+        sim.set_xyz(1, 2, 3);
+        sim.set_status(0b0000_1111);
+        let mut driver = L3GD20::with_transport(sim).unwrap();
+        driver.set_acquisition_burst(AcquisitionBurst::AxesOnly {
+            temperature_every: 8,
+        });
+
+        let data = driver.data_raw().unwrap();
+        assert_eq!(data.freshness(), Freshness::Fresh);
+        assert_eq!(I16x3::from(data), I16x3::new(1, 2, 3));
+
+        // Reading OUT_Z_H cleared the status.
+        assert_eq!(driver.data_raw().unwrap().freshness(), Freshness::Stale);
+    }
+
+    #[cfg(feature = "calibration")]
+    #[test]
+    fn calibration_runs_on_axes_only_bursts() {
+        let mut sim = Simulator::<MockError>::new();
+        sim.set_temperature(21);
+        sim.generate(1, |_| [10, -20, 30]);
+        let mut driver = L3GD20::with_transport(sim).unwrap();
+        driver.set_acquisition_burst(AcquisitionBurst::AxesOnly {
+            temperature_every: 4,
+        });
+
+        let mut task = CalibrationTask::new(8);
+        let bias = loop {
+            match task.poll(&mut driver).unwrap() {
+                Progress::Done(bias) => break bias,
+                Progress::Pending { .. } => {}
+            }
+        };
+        assert_eq!(bias.offset, F32x3::new(10.0, -20.0, 30.0));
+        assert_eq!(bias.temperature, 21);
+        assert_eq!(bias.samples, 8);
+    }
+}
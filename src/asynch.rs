@@ -0,0 +1,243 @@
+//! Provides the asynchronous driver.
+//!
+//! [`L3GD20SpiAsync`] is built on [`embedded_hal_async::spi::SpiDevice`], so that the sensor
+//! can be used from async executors such as Embassy without blocking. It shares the register
+//! framing and decoding with the blocking [`L3GD20`](crate::L3GD20) driver. Note that
+//! `embedded-hal-async` requires Rust 1.75.
+
+use crate::common::{decode, framing};
+use crate::repair;
+use crate::temperature::Variant;
+use crate::{Error, I16x3, L3GD20Settings, SensorData};
+use embedded_hal_async::spi::SpiDevice;
+use l3gd20_registers::prelude::SPIRegister;
+use l3gd20_registers::*;
+
+/// Asynchronous SPI driver for the STMicroelectronics L3GD20 gyroscope.
+pub struct L3GD20SpiAsync<SPI> {
+    spi: SPI,
+    big_endian: bool,
+}
+
+impl<SPI> L3GD20SpiAsync<SPI>
+where
+    SPI: SpiDevice<u8>,
+{
+    /// Initializes the driver over the SPI device and resets the sensor.
+    ///
+    /// Like [`L3GD20::with_transport`](crate::L3GD20::with_transport), the device is
+    /// identified first, failing with [`Error::UnknownDevice`] without writing anything if
+    /// it does not respond as an L3GD20 or L3GD20H.
+    pub async fn new(spi: SPI) -> Result<Self, Error<SPI::Error>> {
+        let mut device = Self {
+            spi,
+            big_endian: false,
+        };
+        let who_am_i = device
+            .read_register::<WhoAmI>()
+            .await
+            .map_err(Error::Bus)?
+            .ident();
+        if Variant::from_who_am_i(who_am_i).is_none() {
+            return Err(Error::UnknownDevice(who_am_i));
+        }
+
+        device.reset().await.map_err(Error::Bus)?;
+        Ok(device)
+    }
+
+    /// Releases the SPI device.
+    pub fn release(self) -> SPI {
+        self.spi
+    }
+
+    /// Identifies this chip by querying the `WHO_AM_I` register.
+    ///
+    /// Both the L3GD20 and the L3GD20H are accepted, like with
+    /// [`L3GD20::identify`](crate::L3GD20::identify).
+    pub async fn identify(&mut self) -> Result<bool, SPI::Error> {
+        Ok(self.variant().await?.is_some())
+    }
+
+    /// Detects the [`Variant`] of the device from its `WHO_AM_I` register.
+    pub async fn variant(&mut self) -> Result<Option<Variant>, SPI::Error> {
+        let ident = self.read_register::<WhoAmI>().await?;
+        Ok(Variant::from_who_am_i(ident.ident()))
+    }
+
+    /// Resets the device to the defaults of [`L3GD20::reset`](crate::L3GD20::reset),
+    /// writing the same register sequence.
+    pub async fn reset(&mut self) -> Result<(), SPI::Error> {
+        for (address, value) in repair::reset_writes(&L3GD20Settings::default(), [true; 3]) {
+            let mut buffer = [framing::write_single_cmd(address), value];
+            self.spi.transfer_in_place(&mut buffer).await?;
+        }
+        self.big_endian = false;
+        Ok(())
+    }
+
+    /// Selects the byte order of the output registers (`BLE`); readings are decoded
+    /// accordingly.
+    pub async fn set_big_endian(&mut self, big_endian: bool) -> Result<(), SPI::Error> {
+        self.modify_register(|reg: ControlRegister4| reg.with_big_endian(big_endian))
+            .await
+    }
+
+    /// Fetches the raw X, Y and Z-axis readings off the sensor.
+    pub async fn xyz_raw(&mut self) -> Result<I16x3, SPI::Error> {
+        let mut buffer = [0; 7];
+        buffer[0] = framing::read_multi_cmd(*OutXLow::REGISTER_ADDRESS);
+        self.spi.transfer_in_place(&mut buffer).await?;
+
+        let mut data = [0; 6];
+        data.copy_from_slice(&buffer[1..]);
+        if self.big_endian {
+            decode::swap_axis_bytes(&mut data);
+        }
+        Ok(decode::xyz(&data))
+    }
+
+    /// Fetches all data off the sensor.
+    pub async fn data_raw(&mut self) -> Result<SensorData, SPI::Error> {
+        let mut buffer = [0; 9];
+        buffer[0] = framing::read_multi_cmd(*TemperatureRegister::REGISTER_ADDRESS);
+        self.spi.transfer_in_place(&mut buffer).await?;
+
+        let mut data = [0; 8];
+        data.copy_from_slice(&buffer[1..]);
+        if self.big_endian {
+            decode::swap_axis_bytes(&mut data[2..]);
+        }
+        Ok(decode::data(&data))
+    }
+
+    /// Reads a single register.
+    pub async fn read_register<R>(&mut self) -> Result<R, SPI::Error>
+    where
+        R: Register,
+    {
+        let mut buffer = [framing::read_single_cmd(*R::REGISTER_ADDRESS), 0];
+        self.spi.transfer_in_place(&mut buffer).await?;
+        Ok(R::from_bits(buffer[1]))
+    }
+
+    /// Writes a single register.
+    pub async fn write_register<B, R>(&mut self, register: B) -> Result<(), SPI::Error>
+    where
+        B: core::borrow::Borrow<R>,
+        R: WritableRegister,
+    {
+        let mut buffer = [
+            framing::write_single_cmd(*R::REGISTER_ADDRESS),
+            register.borrow().to_bits(),
+        ];
+        self.spi.transfer_in_place(&mut buffer).await?;
+        if *R::REGISTER_ADDRESS == *ControlRegister4::REGISTER_ADDRESS {
+            self.big_endian = ControlRegister4::from_bits(buffer[1]).big_endian();
+        }
+        Ok(())
+    }
+
+    /// Modifies a single register.
+    pub async fn modify_register<F, R>(&mut self, f: F) -> Result<(), SPI::Error>
+    where
+        F: FnOnce(R) -> R,
+        R: WritableRegister,
+    {
+        let register: R = self.read_register().await?;
+        self.write_register(f(register)).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{block_on, MockError};
+    use crate::sim::Simulator;
+    use crate::Transport;
+    use embedded_hal_async::spi::{ErrorType, Operation};
+
+    /// An SPI device backed by the simulator, framing each transaction as one transfer.
+    struct SimDevice(Simulator<MockError>);
+
+    impl ErrorType for SimDevice {
+        type Error = MockError;
+    }
+
+    impl SpiDevice<u8> for SimDevice {
+        async fn transaction(
+            &mut self,
+            operations: &mut [Operation<'_, u8>],
+        ) -> Result<(), MockError> {
+            for operation in operations {
+                if let Operation::TransferInPlace(buffer) = operation {
+                    self.0.transfer(buffer)?;
+                }
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn async_driver_reads_the_sensor() {
+        let mut sim = Simulator::new();
+        sim.registers_mut()[0x0F] = 0xD4;
+        sim.set_temperature(25);
+        sim.set_xyz(7, -8, 9);
+
+        block_on(async {
+            let mut driver = L3GD20SpiAsync::new(SimDevice(sim)).await.unwrap();
+            assert!(driver.identify().await.unwrap());
+            assert_eq!(driver.xyz_raw().await.unwrap(), I16x3::new(7, -8, 9));
+
+            let data = driver.data_raw().await.unwrap();
+            assert_eq!(data.temperature, 25);
+
+            driver
+                .modify_register(|reg: ControlRegister4| reg.with_block_data_update(true))
+                .await
+                .unwrap();
+            let reg: ControlRegister4 = driver.read_register().await.unwrap();
+            assert!(reg.block_data_update());
+
+            driver.set_big_endian(true).await.unwrap();
+            driver.spi.0.set_xyz(7, -8, 9);
+            assert_eq!(driver.xyz_raw().await.unwrap(), I16x3::new(7, -8, 9));
+            assert_eq!(*driver.data_raw().await.unwrap().y, -8);
+        });
+    }
+
+    #[test]
+    fn reset_matches_the_blocking_driver() {
+        let mut sim = Simulator::<MockError>::new();
+        sim.registers_mut()[0x0F] = 0xD7;
+        let mut blocking = crate::L3GD20::with_transport(sim).unwrap();
+        blocking.reset().unwrap();
+
+        let mut sim = Simulator::new();
+        sim.registers_mut()[0x0F] = 0xD7;
+        block_on(async {
+            let mut driver = L3GD20SpiAsync::new(SimDevice(sim)).await.unwrap();
+            assert!(driver.identify().await.unwrap());
+            assert_eq!(driver.variant().await.unwrap(), Some(Variant::L3GD20H));
+            assert_eq!(
+                driver.spi.0.registers_mut()[0x20..0x25],
+                blocking.transport.registers_mut()[0x20..0x25]
+            );
+        });
+    }
+
+    #[test]
+    fn unknown_devices_are_rejected() {
+        let mut sim = Simulator::new();
+        sim.registers_mut()[0x0F] = 0x33;
+        let registers = *sim.registers();
+
+        let mut device = SimDevice(sim);
+        block_on(async {
+            let result = L3GD20SpiAsync::new(&mut device).await;
+            assert!(matches!(result, Err(Error::UnknownDevice(0x33))));
+        });
+        assert_eq!(*device.0.registers(), registers);
+    }
+}
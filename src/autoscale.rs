@@ -0,0 +1,164 @@
+//! Provides automatic selection of the full scale during startup.
+//!
+//! Data loggers deployed on unknown platforms cannot know the rates to expect in advance.
+//! After [`start_auto_scale`](crate::L3GD20::start_auto_scale), the driver watches the
+//! samples read by [`auto_scale_update`](crate::L3GD20::auto_scale_update) for saturation
+//! during an observation window, raising the full scale whenever a sample clips. Once the
+//! window has elapsed, the smallest full scale that did not clip is locked in.
+
+use crate::conversions::OutputDataRateExt;
+use crate::{I16x3, Transport, L3GD20};
+use l3gd20_registers::Sensitivity;
+
+/// The magnitude, in LSB, at which a sample is considered clipped.
+const CLIP_LSB: u16 = 32_000;
+
+/// The progress of the full-scale auto-selection.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum AutoScale {
+    /// The observation window is still running with the given full scale.
+    Observing(Sensitivity),
+    /// The full scale was selected and locked in.
+    Locked(Sensitivity),
+}
+
+/// The state of the full-scale auto-selection.
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct AutoScaleState {
+    full_scale: Sensitivity,
+    window_us: u64,
+    started_us: Option<u64>,
+    elapsed_us: u64,
+    locked: bool,
+}
+
+/// Gets the next larger full scale, if any.
+fn next_full_scale(fs: Sensitivity) -> Option<Sensitivity> {
+    match fs {
+        Sensitivity::D250 => Some(Sensitivity::D500),
+        Sensitivity::D500 => Some(Sensitivity::D2000),
+        Sensitivity::D2000 | Sensitivity::D2000_11 => None,
+    }
+}
+
+/// Determines whether any axis of the sample is clipped.
+fn clipped(sample: I16x3) -> bool {
+    [sample.x, sample.y, sample.z]
+        .into_iter()
+        .any(|value| value.unsigned_abs() >= CLIP_LSB)
+}
+
+impl<T, E> L3GD20<T>
+where
+    T: Transport<Error = E>,
+{
+    /// Starts the full-scale auto-selection with an observation window of the given duration,
+    /// e.g. 1000 ms after boot, selecting the smallest full scale (250 dps) to begin with.
+    pub fn start_auto_scale(&mut self, window_ms: u32) -> Result<(), E> {
+        self.set_sensitivity(Sensitivity::D250)?;
+        self.auto_scale = Some(AutoScaleState {
+            full_scale: Sensitivity::D250,
+            window_us: u64::from(window_ms) * 1000,
+            started_us: self.now_micros(),
+            elapsed_us: 0,
+            locked: false,
+        });
+        Ok(())
+    }
+
+    /// Reads a sample and advances the full-scale auto-selection.
+    ///
+    /// If the sample clips, the next larger full scale is selected, converting the bias like
+    /// [`set_scale`](Self::set_scale). Without a
+    /// [time source](crate::time), every call is assumed to take one sample period at the
+    /// configured output data rate. Returns `None` if the auto-selection was not started.
+    pub fn auto_scale_update(&mut self) -> Result<Option<AutoScale>, E> {
+        let Some(mut state) = self.auto_scale else {
+            return Ok(None);
+        };
+        if state.locked {
+            return Ok(Some(AutoScale::Locked(state.full_scale)));
+        }
+
+        let sample = self.xyz_raw()?;
+        if clipped(sample) {
+            if let Some(fs) = next_full_scale(state.full_scale) {
+                self.set_sensitivity(fs)?;
+                state.full_scale = fs;
+            }
+        }
+
+        state.elapsed_us = match (state.started_us, self.now_micros()) {
+            (Some(started), Some(now)) => now.saturating_sub(started),
+            _ => {
+                let hz = self.expected_settings.output_data_rate.hz();
+                state.elapsed_us + 1_000_000 / u64::from(hz)
+            }
+        };
+        state.locked = state.elapsed_us >= state.window_us;
+        self.auto_scale = Some(state);
+
+        Ok(Some(if state.locked {
+            AutoScale::Locked(state.full_scale)
+        } else {
+            AutoScale::Observing(state.full_scale)
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::MockError;
+    use crate::sim::Simulator;
+    use crate::{Bias, F32x3};
+    use l3gd20_registers::ControlRegister4;
+
+    #[test]
+    fn settles_on_the_smallest_full_scale_that_does_not_clip() {
+        let mut driver = L3GD20::with_transport(Simulator::<MockError>::new()).unwrap();
+        assert_eq!(driver.auto_scale_update().unwrap(), None);
+
+        // At 95 Hz, 30 ms take three samples.
+        driver.start_auto_scale(30).unwrap();
+        driver.transport.set_xyz(0, -32_768, 0);
+        assert_eq!(
+            driver.auto_scale_update().unwrap(),
+            Some(AutoScale::Observing(Sensitivity::D500))
+        );
+        driver.transport.set_xyz(100, 0, 0);
+        driver.auto_scale_update().unwrap();
+        assert_eq!(
+            driver.auto_scale_update().unwrap(),
+            Some(AutoScale::Locked(Sensitivity::D500))
+        );
+
+        driver.transport.set_xyz(32_767, 0, 0);
+        assert_eq!(
+            driver.auto_scale_update().unwrap(),
+            Some(AutoScale::Locked(Sensitivity::D500))
+        );
+        let reg: ControlRegister4 = driver.read_register().unwrap();
+        assert_eq!(reg.full_scale(), Sensitivity::D500);
+    }
+
+    #[test]
+    fn range_switches_keep_the_bias() {
+        let mut driver = L3GD20::with_transport(Simulator::<MockError>::new()).unwrap();
+        driver.start_auto_scale(1000).unwrap();
+        driver.set_bias(Bias {
+            offset: F32x3::new(40.0, -20.0, 0.0),
+            ..Bias::default()
+        });
+        driver.transport.set_xyz(32_000, 0, 0);
+        assert_eq!(
+            driver.auto_scale_update().unwrap(),
+            Some(AutoScale::Observing(Sensitivity::D500))
+        );
+
+        // The bias of 0.35 °/s on X is 20 LSB at 500 dps.
+        driver.transport.set_xyz(20, -10, 0);
+        assert_eq!(driver.xyz_dps().unwrap(), F32x3::new(0.0, 0.0, 0.0));
+    }
+}
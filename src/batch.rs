@@ -0,0 +1,129 @@
+//! Provides conversion of captured raw sample buffers.
+//!
+//! Large captures, e.g. FIFO drains or DMA transfers of the axis output registers, can
+//! be stored as raw bytes and converted in a single pass afterwards.
+
+use crate::common::decode;
+use crate::orientation::AxisMap;
+use crate::{Bias, Characteristics, F32x3};
+
+/// The length of a raw sample: `OUT_X_L` (28h) to `OUT_Z_H` (2Dh).
+pub const SAMPLE_LEN: usize = 6;
+
+/// Decodes, maps, bias-corrects and scales a buffer of raw samples to degrees per second.
+///
+/// The buffer holds consecutive six-byte samples in the register order of `OUT_X_L` (28h)
+/// to `OUT_Z_H` (2Dh), with the bytes of each axis in the order selected by `BLE`; a
+/// trailing partial sample is ignored. Like the readings of the driver, the samples are
+/// mapped to the body axes before the body-frame offset of the bias is removed. Pass the
+/// driver's [`axis_map`](crate::L3GD20::axis_map) and
+/// [`big_endian`](crate::L3GD20::big_endian) at the time of the capture. The sensitivity
+/// is taken from the characteristics.
+///
+/// ```
+/// # #[cfg(feature = "sim")] {
+/// use l3gd20_ng::{batch::convert_block, Bias, L3GD20, sim::Simulator};
+///
+/// # let mut driver = L3GD20::with_transport(Simulator::<()>::new()).unwrap();
+/// let characteristics = driver.characteristics()?;
+/// let raw = [0xE8, 0x03, 0x18, 0xFC, 0x00, 0x00];
+/// let rates: Vec<_> = convert_block(
+///     &raw,
+///     &characteristics,
+///     driver.bias(),
+///     driver.axis_map(),
+///     driver.big_endian(),
+/// )
+/// .collect();
+/// assert_eq!(rates.len(), 1);
+/// assert!((rates[0].x - 8.75).abs() < 1e-4);
+/// assert!((rates[0].y + 8.75).abs() < 1e-4);
+/// # }
+/// # Ok::<(), ()>(())
+/// ```
+pub fn convert_block<'a>(
+    raw: &'a [u8],
+    characteristics: &Characteristics,
+    bias: &Bias,
+    axis_map: AxisMap,
+    big_endian: bool,
+) -> impl Iterator<Item = F32x3> + 'a {
+    let sensitivity = characteristics.sensitivity;
+    let offset = bias.offset;
+    raw.chunks_exact(SAMPLE_LEN).map(move |sample| {
+        let mut data = [0; SAMPLE_LEN];
+        data.copy_from_slice(sample);
+        if big_endian {
+            decode::swap_axis_bytes(&mut data);
+        }
+        let xyz = axis_map.apply(decode::xyz(&data));
+        F32x3::new(
+            (f32::from(xyz.x) - offset.x) * sensitivity,
+            (f32::from(xyz.y) - offset.y) * sensitivity,
+            (f32::from(xyz.z) - offset.z) * sensitivity,
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::orientation::SignedAxis;
+    use std::vec::Vec;
+
+    #[test]
+    fn matches_the_register_decoding() {
+        let raw = [0x34, 0x12, 0xCC, 0xED, 0x01, 0x80, 0xFF];
+        let characteristics = Characteristics::new(
+            l3gd20_registers::Sensitivity::D500,
+            l3gd20_registers::OutputDataRate::Hz95,
+            l3gd20_registers::Bandwidth::Narrowest,
+            0,
+        );
+        let bias = Bias {
+            offset: F32x3::new(4.0, -4.0, 0.0),
+            ..Bias::default()
+        };
+
+        let rates: Vec<_> =
+            convert_block(&raw, &characteristics, &bias, AxisMap::IDENTITY, false).collect();
+        let mut sample = [0; 6];
+        sample.copy_from_slice(&raw[..6]);
+        let expected = decode::xyz(&sample);
+
+        assert_eq!(rates.len(), 1);
+        assert_eq!(rates[0].x, (f32::from(expected.x) - 4.0) * 0.0175);
+        assert_eq!(rates[0].y, (f32::from(expected.y) + 4.0) * 0.0175);
+        assert_eq!(rates[0].z, f32::from(expected.z) * 0.0175);
+    }
+
+    #[test]
+    fn matches_the_driver_readings() {
+        let mut driver =
+            crate::L3GD20::with_transport(crate::sim::Simulator::<crate::mock::MockError>::new())
+                .unwrap();
+        let map =
+            AxisMap::new(SignedAxis::PLUS_Y, SignedAxis::MINUS_X, SignedAxis::PLUS_Z).unwrap();
+        driver.set_axis_map(map);
+        driver.set_big_endian(true).unwrap();
+        driver.set_bias(Bias {
+            offset: F32x3::new(10.0, -20.0, 5.0),
+            ..Bias::default()
+        });
+        driver.transport.set_xyz(1000, -300, 42);
+        let expected = driver.xyz_dps().unwrap();
+
+        // X, Y and Z as captured with BLE set.
+        let raw = [0x03, 0xE8, 0xFE, 0xD4, 0x00, 0x2A];
+        let characteristics = driver.characteristics().unwrap();
+        let rates: Vec<_> = convert_block(
+            &raw,
+            &characteristics,
+            driver.bias(),
+            driver.axis_map(),
+            driver.big_endian(),
+        )
+        .collect();
+        assert_eq!(rates, [expected]);
+    }
+}
@@ -0,0 +1,1236 @@
+//! Provides the blocking driver.
+
+use crate::common::{decode, framing, Compensation};
+use crate::conversions::DEG_TO_RAD;
+#[cfg(feature = "fixed")]
+use crate::CorrectionMatrixQ14;
+use crate::{
+    characteristics, health, time, Axis, Bias, BiasReport, Characteristics, CorrectionMatrix,
+    Error, F32x3, I16x3, L3GD20Settings, SensorData, Status,
+};
+use chip_select::ChipSelectGuarded;
+use embedded_hal::blocking::delay::DelayUs;
+use embedded_hal::blocking::spi::Transfer;
+use l3gd20_registers::prelude::SPIRegister;
+use l3gd20_registers::*;
+
+/// A bus over which the register file of the L3GD20 can be accessed.
+///
+/// Transfers are framed the way the SPI interface of the device expects them: the first
+/// byte of the buffer is the command byte encoding the read flag, the auto-increment flag
+/// and the register address. For reads, the remaining bytes are replaced with the register
+/// contents; for writes, they are written to the registers.
+pub trait Transport {
+    /// The error type of the bus.
+    type Error;
+
+    /// Executes a single framed transfer.
+    fn transfer(&mut self, buffer: &mut [u8]) -> Result<(), Self::Error>;
+}
+
+impl<T> Transport for &mut T
+where
+    T: Transport + ?Sized,
+{
+    type Error = T::Error;
+
+    fn transfer(&mut self, buffer: &mut [u8]) -> Result<(), Self::Error> {
+        T::transfer(self, buffer)
+    }
+}
+
+/// A [`Transport`] over an SPI bus with a dedicated chip select line.
+pub struct SpiTransport<CS, SPI> {
+    cs: CS,
+    spi: SPI,
+}
+
+impl<CS, SPI> SpiTransport<CS, SPI> {
+    /// Creates a new SPI transport.
+    pub fn new(spi: SPI, chip_select: CS) -> Self {
+        Self {
+            cs: chip_select,
+            spi,
+        }
+    }
+
+    /// Releases the SPI bus and chip select line.
+    pub fn release(self) -> (SPI, CS) {
+        (self.spi, self.cs)
+    }
+
+    /// Replaces the SPI bus, returning the previous one.
+    pub fn replace_bus(&mut self, spi: SPI) -> SPI {
+        core::mem::replace(&mut self.spi, spi)
+    }
+
+    /// Gets the SPI bus.
+    pub(crate) fn spi_mut(&mut self) -> &mut SPI {
+        &mut self.spi
+    }
+}
+
+impl<CS, SPI, E> Transport for SpiTransport<CS, SPI>
+where
+    CS: ChipSelectGuarded,
+    SPI: Transfer<u8, Error = E>,
+{
+    type Error = E;
+
+    fn transfer(&mut self, buffer: &mut [u8]) -> Result<(), E> {
+        let _guard = self.cs.select_guard();
+        self.spi.transfer(buffer)?;
+        Ok(())
+    }
+}
+
+/// Driver for the STMicroelectronics L3GD20 Gyroscope, generic over the [`Transport`].
+pub struct L3GD20<T> {
+    pub(crate) transport: T,
+    pub(crate) compensation: Compensation,
+    pub(crate) bias_epoch: u32,
+    pub(crate) bias_timestamp: Option<u64>,
+    pub(crate) sample_counter: u32,
+    pub(crate) clock: Option<&'static dyn time::TimeSource>,
+    pub(crate) degraded: Option<health::AxisFault>,
+    pub(crate) block_data_update: bool,
+    pub(crate) big_endian: bool,
+    pub(crate) full_scale: Sensitivity,
+    #[cfg(feature = "thermal")]
+    pub(crate) thermal: crate::thermal::ThermalLog,
+    pub(crate) expected_settings: L3GD20Settings,
+    #[cfg(feature = "acquisition")]
+    pub(crate) acquisition: crate::acquisition::Acquisition,
+    #[cfg(feature = "interrupts")]
+    pub(crate) events: crate::events::EventDelivery,
+    #[cfg(feature = "fifo")]
+    pub(crate) streaming_drdy: Option<crate::drdy::DrdyConfig>,
+    pub(crate) temperature: crate::temperature::TemperatureState,
+    pub(crate) watchdog: Option<fn()>,
+    pub(crate) register_cache: Option<crate::cache::RegisterCache>,
+    max_burst_len: Option<usize>,
+    #[cfg(feature = "dsp")]
+    pub(crate) peak_hold: Option<crate::peak::PeakHold>,
+    #[cfg(feature = "units")]
+    pub(crate) output_unit: crate::units::OutputUnit,
+    pub(crate) axis_map: crate::orientation::AxisMap,
+    pub(crate) spi_clock_hz: Option<u32>,
+    #[cfg(feature = "sync")]
+    pub(crate) sync: Option<&'static dyn crate::sync::SyncSource>,
+    #[cfg(feature = "dsp")]
+    pub(crate) auto_scale: Option<crate::autoscale::AutoScaleState>,
+    pub(crate) powered_down: bool,
+    #[cfg(feature = "l3gd20h")]
+    pub(crate) low_odr: bool,
+    #[cfg(feature = "calibration")]
+    pub(crate) recal: Option<crate::recal::RecalState>,
+    #[cfg(feature = "dsp")]
+    pub(crate) zupt: Option<crate::zupt::StationaryDetector>,
+    pub(crate) pending_config: Option<crate::repair::PendingConfig>,
+    pub(crate) read_priority: crate::bus::ReadPriority,
+    #[cfg(feature = "debug")]
+    pub(crate) write_log: crate::debug::WriteLog,
+    #[cfg(feature = "profiling")]
+    pub(crate) profile: crate::profiling::TransferProfile,
+    #[cfg(feature = "profiling")]
+    pub(crate) profile_clock: Option<&'static dyn time::TimeSource>,
+}
+
+/// SPI Driver for the STMicroelectronics L3GD20 Gyroscope.
+#[allow(clippy::upper_case_acronyms)]
+pub type L3GD20SPI<CS, SPI> = L3GD20<SpiTransport<CS, SPI>>;
+
+impl<CS, SPI, E> L3GD20<SpiTransport<CS, SPI>>
+where
+    CS: ChipSelectGuarded,
+    SPI: Transfer<u8, Error = E>,
+{
+    /// Initialize the SPI connection.
+    ///
+    /// See [`with_transport`](Self::with_transport).
+    pub fn new(spi: SPI, chip_select: CS) -> Result<Self, Error<E>> {
+        Self::with_transport(SpiTransport::new(spi, chip_select))
+    }
+
+    /// Initialize the SPI connection without identifying the device.
+    ///
+    /// See [`with_transport_unidentified`](Self::with_transport_unidentified).
+    pub fn new_unidentified(spi: SPI, chip_select: CS) -> Result<Self, E> {
+        Self::with_transport_unidentified(SpiTransport::new(spi, chip_select))
+    }
+
+    /// Wires up the SPI connection without accessing the device.
+    ///
+    /// See [`with_transport_unchecked`](Self::with_transport_unchecked).
+    pub fn new_unchecked(spi: SPI, chip_select: CS) -> Self {
+        Self::with_transport_unchecked(SpiTransport::new(spi, chip_select))
+    }
+
+    /// Initializes the SPI connection, keeping the configuration of the device.
+    ///
+    /// See [`with_transport_preserve_config`](Self::with_transport_preserve_config).
+    pub fn new_preserve_config(spi: SPI, chip_select: CS) -> Result<Self, Error<E>> {
+        Self::with_transport_preserve_config(SpiTransport::new(spi, chip_select))
+    }
+
+    /// Replaces the SPI bus, returning the previous one.
+    ///
+    /// This is intended for systems that reinitialize their SPI peripheral, e.g. after a
+    /// low-power state or a bus error. The device is not reset, and the driver state such
+    /// as the bias, calibration and statistics is retained.
+    pub fn replace_bus(&mut self, spi: SPI) -> SPI {
+        self.transport.replace_bus(spi)
+    }
+
+    /// Releases the SPI bus and chip select line, e.g. to hand the bus to another device.
+    ///
+    /// The device is left in its current state.
+    pub fn release(self) -> (SPI, CS) {
+        self.transport.release()
+    }
+
+    /// Releases the SPI bus and chip select line, like [`release`](Self::release).
+    pub fn into_inner(self) -> (SPI, CS) {
+        self.release()
+    }
+}
+
+impl<T, E> L3GD20<T>
+where
+    T: Transport<Error = E>,
+{
+    /// Initializes the driver over the given transport.
+    ///
+    /// The device is identified first, failing with [`Error::UnknownDevice`] if it does not
+    /// respond as an L3GD20 or L3GD20H, and the detected [`variant`](Self::variant) is kept.
+    /// It is then [reset](Self::reset_checked).
+    pub fn with_transport(transport: T) -> Result<Self, Error<E>> {
+        let mut device = Self::with_transport_unchecked(transport);
+        device.check_identity()?;
+
+        // Apply standard configuration.
+        device.reset_checked()?;
+        Ok(device)
+    }
+
+    /// Initializes the driver over the given transport without identifying the device.
+    ///
+    /// The device is reset like with [`with_transport`](Self::with_transport); use
+    /// [`identify`](Self::identify) to check its identity separately.
+    pub fn with_transport_unidentified(transport: T) -> Result<Self, E> {
+        let mut device = Self::with_transport_unchecked(transport);
+
+        // Apply standard configuration.
+        device.reset()?;
+        Ok(device)
+    }
+
+    /// Initializes the driver over the given transport, keeping the configuration of the
+    /// device, e.g. as set up by a bootloader or a previous boot stage.
+    ///
+    /// The device is identified first, failing with [`Error::UnknownDevice`] if it does not
+    /// respond as an L3GD20 or L3GD20H. The control registers are then read to synchronize
+    /// the driver state, see [`sync_config`](Self::sync_config).
+    pub fn with_transport_preserve_config(transport: T) -> Result<Self, Error<E>> {
+        let mut device = Self::with_transport_unchecked(transport);
+        device.check_identity()?;
+        device.sync_config().map_err(Error::Bus)?;
+        Ok(device)
+    }
+
+    /// Initializes the driver over the given transport without accessing the device.
+    ///
+    /// The device is neither identified nor reset, and its registers are left untouched.
+    /// The driver assumes the [reset](Self::reset) configuration until
+    /// [`sync_config`](Self::sync_config) is called.
+    pub fn with_transport_unchecked(transport: T) -> Self {
+        Self {
+            transport,
+            compensation: Compensation::default(),
+            bias_epoch: 0,
+            bias_timestamp: None,
+            sample_counter: 0,
+            clock: None,
+            degraded: None,
+            block_data_update: false,
+            big_endian: false,
+            full_scale: Sensitivity::D250,
+            #[cfg(feature = "thermal")]
+            thermal: crate::thermal::ThermalLog::default(),
+            expected_settings: L3GD20Settings::default(),
+            #[cfg(feature = "acquisition")]
+            acquisition: crate::acquisition::Acquisition::default(),
+            #[cfg(feature = "interrupts")]
+            events: crate::events::EventDelivery::default(),
+            #[cfg(feature = "fifo")]
+            streaming_drdy: None,
+            temperature: crate::temperature::TemperatureState::default(),
+            watchdog: None,
+            register_cache: None,
+            max_burst_len: None,
+            #[cfg(feature = "dsp")]
+            peak_hold: None,
+            #[cfg(feature = "units")]
+            output_unit: crate::units::OutputUnit::default(),
+            axis_map: crate::orientation::AxisMap::IDENTITY,
+            spi_clock_hz: None,
+            #[cfg(feature = "sync")]
+            sync: None,
+            #[cfg(feature = "dsp")]
+            auto_scale: None,
+            powered_down: false,
+            #[cfg(feature = "l3gd20h")]
+            low_odr: false,
+            #[cfg(feature = "calibration")]
+            recal: None,
+            #[cfg(feature = "dsp")]
+            zupt: None,
+            pending_config: None,
+            read_priority: crate::bus::ReadPriority::Normal,
+            #[cfg(feature = "debug")]
+            write_log: crate::debug::WriteLog::new(),
+            #[cfg(feature = "profiling")]
+            profile: crate::profiling::TransferProfile::default(),
+            #[cfg(feature = "profiling")]
+            profile_clock: None,
+        }
+    }
+
+    /// Releases the transport.
+    pub fn into_transport(self) -> T {
+        self.transport
+    }
+
+    /// Identifies this chip by querying the `WHO_AM_I` register.
+    ///
+    /// Both the L3GD20 and the L3GD20H are accepted; the detected variant is kept like with
+    /// [`detect_variant`](Self::detect_variant).
+    pub fn identify(&mut self) -> Result<bool, E> {
+        if self.detect_variant()?.is_some() {
+            Ok(true)
+        } else {
+            #[cfg(feature = "defmt")]
+            defmt::debug!("L3GD20 sensor identification failed");
+            Ok(false)
+        }
+    }
+
+    /// Fails with [`Error::UnknownDevice`] unless the device identifies as a known
+    /// [`Variant`](crate::temperature::Variant), which is then kept like with
+    /// [`detect_variant`](Self::detect_variant).
+    fn check_identity(&mut self) -> Result<(), Error<E>> {
+        let who_am_i = self.read_register::<WhoAmI>().map_err(Error::Bus)?.ident();
+        let variant = crate::temperature::Variant::from_who_am_i(who_am_i)
+            .ok_or(Error::UnknownDevice(who_am_i))?;
+        self.temperature.variant = Some(variant);
+        Ok(())
+    }
+
+    /// Resets the device to reasonable defaults.
+    ///
+    /// If a write fails, the remaining writes can be completed with
+    /// [`repair_config`](Self::repair_config).
+    ///
+    /// The device is given the latency of the following bus transactions to reboot; see
+    /// [`reset_with_delay`](Self::reset_with_delay) to wait explicitly.
+    pub fn reset(&mut self) -> Result<(), E> {
+        self.reset_to(L3GD20Settings::default(), None)
+    }
+
+    /// Resets the device to defaults suited for sensor fusion, see
+    /// [`L3gd20Config::fusion`](crate::L3gd20Config::fusion).
+    ///
+    /// Unlike [`reset`](Self::reset), block data update is enabled and the device runs at
+    /// 190 Hz with the medium bandwidth and ±500 °/s.
+    pub fn reset_for_fusion(&mut self) -> Result<(), E> {
+        self.reset_to(crate::L3gd20Config::fusion().settings(), None)
+    }
+
+    /// Resets the device, applying the output data rate, bandwidth, full scale and block
+    /// data update selection of the settings.
+    ///
+    /// The delay, if any, is used to wait for the device to reboot.
+    pub(crate) fn reset_to(
+        &mut self,
+        settings: L3GD20Settings,
+        delay: Option<&mut dyn DelayUs<u32>>,
+    ) -> Result<(), E> {
+        let axes = [
+            self.axis_enabled(Axis::X),
+            self.axis_enabled(Axis::Y),
+            self.axis_enabled(Axis::Z),
+        ];
+        self.write_config(
+            &crate::repair::reset_writes(&settings, axes),
+            settings,
+            delay,
+        )
+    }
+
+    /// Resets the device like [`reset`](Self::reset), reporting how far the sequence got
+    /// if a write fails.
+    pub fn reset_checked(&mut self) -> Result<(), Error<E>> {
+        self.reset()
+            .map_err(|error| self.partial_config_error(error))
+    }
+
+    /// Sets the be powered up and active.
+    ///
+    /// See [`power_up_with_delay`](Self::power_up_with_delay) to wait for the device to
+    /// settle after leaving power-down.
+    pub fn power_up(&mut self) -> Result<(), E> {
+        self.power_up_to(None)
+    }
+
+    /// Powers the device up, waiting with the delay, if any, when it was powered down.
+    pub(crate) fn power_up_to(&mut self, delay: Option<&mut dyn DelayUs<u32>>) -> Result<(), E> {
+        let x = self.axis_enabled(Axis::X);
+        let y = self.axis_enabled(Axis::Y);
+        let z = self.axis_enabled(Axis::Z);
+        let was_powered_down = self.powered_down;
+        self.modify_register(|reg: ControlRegister1| {
+            reg.with_power_up(true)
+                .with_x_enable(x)
+                .with_y_enable(y)
+                .with_z_enable(z)
+        })?;
+        if was_powered_down {
+            self.settle(delay, crate::settle::TURN_ON_TIME_US);
+        }
+        Ok(())
+    }
+
+    /// Sets the device to sleep mode.
+    ///
+    /// See [`sleep_mode_with_delay`](Self::sleep_mode_with_delay) to wait for the device to
+    /// settle after leaving power-down.
+    pub fn sleep_mode(&mut self) -> Result<(), E> {
+        self.sleep_mode_to(None)
+    }
+
+    /// Enters sleep mode, waiting with the delay, if any, when the device was powered down.
+    pub(crate) fn sleep_mode_to(&mut self, delay: Option<&mut dyn DelayUs<u32>>) -> Result<(), E> {
+        let was_powered_down = self.powered_down;
+        self.modify_register(|reg: ControlRegister1| {
+            reg.with_power_up(true)
+                .with_x_enable(false)
+                .with_y_enable(false)
+                .with_z_enable(false)
+        })?;
+        if was_powered_down {
+            self.settle(delay, crate::settle::TURN_ON_TIME_US);
+        }
+        Ok(())
+    }
+
+    /// Sets the device to be powered down.
+    pub fn power_down(&mut self) -> Result<(), E> {
+        self.modify_register(|reg: ControlRegister1| reg.with_power_up(false))
+    }
+
+    /// Enables the data ready interrupt.
+    ///
+    /// Only `I2_DRDY` is changed; see [`configure_drdy`](Self::configure_drdy) for the
+    /// remaining DRDY/INT2 routing bits and the pin polarity.
+    pub fn enable_data_ready(&mut self, enabled: bool) -> Result<(), E> {
+        self.modify_register(|reg: ControlRegister3| reg.with_i2drdy(enabled))
+    }
+
+    /// Sets the output data rate.
+    pub fn set_odr(&mut self, data_rate: impl Into<OutputDataRate>) -> Result<(), E> {
+        let data_rate = data_rate.into();
+        self.modify_register(|reg: ControlRegister1| reg.with_output_data_rate(data_rate))?;
+        self.expected_settings.output_data_rate = data_rate;
+        Ok(())
+    }
+
+    /// Sets the full scale, i.e. the measurement range.
+    ///
+    /// The selected scale is kept by the driver, so that converted readings and the
+    /// [characteristics](Self::characteristics) follow it without reading `CTRL_REG4`.
+    /// The [bias](Self::bias), which is kept in raw LSB, is converted to the new scale.
+    pub fn set_scale(&mut self, fs: impl Into<Sensitivity>) -> Result<(), E> {
+        let fs = fs.into();
+        self.modify_register(|reg: ControlRegister4| reg.with_full_scale(fs))?;
+        self.expected_settings.full_scale = fs;
+        Ok(())
+    }
+
+    /// Sets the sensitivity, like [`set_scale`](Self::set_scale).
+    pub fn set_sensitivity(&mut self, fs: impl Into<Sensitivity>) -> Result<(), E> {
+        self.set_scale(fs)
+    }
+
+    /// Gets the full scale last written by the driver.
+    pub fn full_scale(&self) -> Sensitivity {
+        self.full_scale
+    }
+
+    /// Enables or disables block data update (`BDU`), so that the output registers are not
+    /// updated until both bytes of a reading were read.
+    pub fn set_block_data_update(&mut self, enabled: bool) -> Result<(), E> {
+        self.modify_register(|reg: ControlRegister4| reg.with_block_data_update(enabled))?;
+        self.expected_settings.block_data_update = enabled;
+        Ok(())
+    }
+
+    /// Gets whether block data update was last enabled by the driver.
+    pub fn block_data_update(&self) -> bool {
+        self.block_data_update
+    }
+
+    /// Selects the byte order of the output registers (`BLE`).
+    ///
+    /// Readings are decoded according to the selected order, e.g. for DMA transfers
+    /// that expect big endian data.
+    pub fn set_big_endian(&mut self, big_endian: bool) -> Result<(), E> {
+        self.modify_register(|reg: ControlRegister4| reg.with_big_endian(big_endian))
+    }
+
+    /// Gets whether big endian output was last selected by the driver.
+    pub fn big_endian(&self) -> bool {
+        self.big_endian
+    }
+
+    /// Sets the output data rate.
+    pub fn set_bandwidth(&mut self, bandwidth: impl Into<Bandwidth>) -> Result<(), E> {
+        let bandwidth = bandwidth.into();
+        self.modify_register(|reg: ControlRegister1| reg.with_bandwidth(bandwidth))?;
+        self.expected_settings.bandwidth = bandwidth;
+        Ok(())
+    }
+
+    /// Obtains sensor characteristics.
+    /// The results of this call depend on the current configuration of the sensor and should
+    /// be obtained when the sensor configuration was changed.
+    pub fn characteristics(&mut self) -> Result<Characteristics, E> {
+        let data = self.temp_raw()?;
+        let reg1 = self.read_register::<ControlRegister1>()?;
+
+        Ok(Characteristics::new(
+            self.full_scale,
+            reg1.output_data_rate(),
+            reg1.bandwidth(),
+            data,
+        ))
+    }
+
+    /// Reads the raw `OUT_TEMP` register.
+    ///
+    /// The value decreases by one LSB per °C; use [`temp_celsius`](Self::temp_celsius) or
+    /// [`temperature_celsius`](Self::temperature_celsius) to convert it.
+    pub fn temp_raw(&mut self) -> Result<u8, E> {
+        let temp = self.read_register::<TemperatureRegister>()?.temp();
+        #[cfg(feature = "thermal")]
+        self.thermal.record(temp, self.now_micros());
+        Ok(temp)
+    }
+
+    /// Reads the status register, decoding the per-axis data available and overrun flags.
+    pub fn status(&mut self) -> Result<Status, E> {
+        self.read_register::<StatusRegister>().map(Status::from)
+    }
+
+    /// Determines whether a new sample is available on all axes.
+    ///
+    /// This is the minimal-latency poll: it transfers two bytes to read `STATUS_REG`
+    /// and only evaluates the `ZYXDA` bit. See [`status`](Self::status) for all flags.
+    pub fn has_new_sample(&mut self) -> Result<bool, E> {
+        let mut buffer = [
+            framing::read_single_cmd(*StatusRegister::REGISTER_ADDRESS),
+            0,
+        ];
+        self.transfer(&mut buffer)?;
+        Ok(StatusRegister::from_bits(buffer[1]).zyx_da())
+    }
+
+    /// Fetches X, Y and Z-axis data off the sensor.
+    pub fn xyz_raw(&mut self) -> Result<I16x3, E> {
+        self.debug_assert_powered();
+        let command = framing::read_multi_cmd(*OutXLow::REGISTER_ADDRESS);
+        let mut buffer = [command, 0, 0, 0, 0, 0, 0];
+        self.transfer(&mut buffer)?;
+
+        // skip the command byte [0].
+        let mut data = [0; 6];
+        data.copy_from_slice(&buffer[1..]);
+        if self.big_endian {
+            decode::swap_axis_bytes(&mut data);
+        }
+
+        self.sample_counter = self.sample_counter.wrapping_add(1);
+        let xyz = self.axis_map.apply(decode::xyz(&data));
+        self.track_sample(xyz);
+        Ok(xyz)
+    }
+
+    /// Gets the bias that is removed from readings in [`xyz_dps`](Self::xyz_dps).
+    pub fn bias(&self) -> &Bias {
+        &self.compensation.bias
+    }
+
+    /// Sets the bias that is removed from readings in [`xyz_dps`](Self::xyz_dps).
+    ///
+    /// See [`CalibrationTask`](crate::calibration::CalibrationTask) for a way to estimate it.
+    pub fn set_bias(&mut self, bias: Bias) {
+        self.compensation.bias = bias;
+        self.bias_epoch = self.sample_counter;
+        self.bias_timestamp = self.now_micros();
+    }
+
+    /// Assesses the current bias estimate against the current sensor state.
+    ///
+    /// See [`BiasReport`] for details.
+    pub fn bias_report(&mut self) -> Result<BiasReport, E> {
+        let temperature = self.temp_raw()?;
+        let fs = self.full_scale;
+        Ok(BiasReport::new(
+            self.compensation.bias,
+            self.sample_counter.wrapping_sub(self.bias_epoch),
+            self.bias_timestamp
+                .zip(self.now_micros())
+                .map(|(then, now)| now.saturating_sub(then)),
+            temperature,
+            self.compensation.sensitivity(fs),
+            characteristics::zero_rate_level_temp_coefficient(fs),
+        ))
+    }
+
+    /// Estimates the expected heading error, in degrees, after integrating the gyro
+    /// alone for the given duration in seconds.
+    ///
+    /// This combines the current [`bias_report`](Self::bias_report) with the rate noise
+    /// density; see [`BiasReport::heading_drift`]. Applications can use it to decide
+    /// whether gyro-only heading is still trustworthy or re-zeroing is needed.
+    pub fn drift_budget(&mut self, duration_s: f32) -> Result<f32, E> {
+        let report = self.bias_report()?;
+        Ok(report.heading_drift(duration_s, characteristics::RATE_NOISE_DENSITY))
+    }
+
+    /// Gets the per-axis gain correction applied in [`xyz_dps`](Self::xyz_dps).
+    pub fn gain(&self) -> &F32x3 {
+        &self.compensation.gain
+    }
+
+    /// Sets the per-axis gain correction applied in [`xyz_dps`](Self::xyz_dps).
+    ///
+    /// See [`GainCalibration`](crate::calibration::GainCalibration) for a way to estimate it.
+    pub fn set_gain(&mut self, gain: F32x3) {
+        self.compensation.gain = gain;
+    }
+
+    /// Overrides the datasheet sensitivity used by all scaling paths with a per-axis
+    /// calibrated sensitivity, in millidegrees/second per LSB.
+    ///
+    /// The override applies regardless of the full scale selection and should be
+    /// updated when the full scale is changed.
+    pub fn set_sensitivity_override(&mut self, mdps_per_lsb: [f32; 3]) {
+        let [x, y, z] = mdps_per_lsb;
+        self.compensation.sensitivity_override = Some(F32x3::new(x * 0.001, y * 0.001, z * 0.001));
+    }
+
+    /// Removes the sensitivity override, falling back to the datasheet values.
+    pub fn clear_sensitivity_override(&mut self) {
+        self.compensation.sensitivity_override = None;
+    }
+
+    /// Gets the per-axis sensitivity in degrees/second per LSB used for scaling readings.
+    ///
+    /// The full scale last written by the driver is used if no override is set.
+    pub(crate) fn scale(&mut self) -> Result<F32x3, E> {
+        if let Some(sensitivity) = self.compensation.sensitivity_override {
+            return Ok(sensitivity);
+        }
+        Ok(self.compensation.sensitivity(self.full_scale))
+    }
+
+    /// Gets the cross-axis correction matrix applied in [`xyz_dps`](Self::xyz_dps).
+    pub fn correction_matrix(&self) -> &CorrectionMatrix {
+        &self.compensation.correction
+    }
+
+    /// Sets the cross-axis correction matrix applied in [`xyz_dps`](Self::xyz_dps).
+    ///
+    /// When the `fixed` feature is enabled, this also updates the fixed-point matrix
+    /// used by [`xyz_raw_corrected`](Self::xyz_raw_corrected).
+    pub fn set_correction_matrix(&mut self, matrix: CorrectionMatrix) {
+        #[cfg(feature = "fixed")]
+        {
+            self.compensation.correction_q14 = CorrectionMatrixQ14::from(&matrix);
+        }
+        self.compensation.correction = matrix;
+    }
+
+    /// Sets the fixed-point cross-axis correction matrix applied in
+    /// [`xyz_raw_corrected`](Self::xyz_raw_corrected).
+    #[cfg(feature = "fixed")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "fixed")))]
+    pub fn set_correction_matrix_q14(&mut self, matrix: CorrectionMatrixQ14) {
+        self.compensation.correction_q14 = matrix;
+    }
+
+    /// Fetches X, Y and Z-axis data off the sensor and applies the fixed-point
+    /// cross-axis correction matrix, without using floating-point math.
+    #[cfg(feature = "fixed")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "fixed")))]
+    pub fn xyz_raw_corrected(&mut self) -> Result<I16x3, E> {
+        let raw = self.xyz_raw()?;
+        Ok(self.compensation.apply_fixed(raw))
+    }
+
+    /// Fetches X, Y and Z-axis data off the sensor, removes the bias, scales
+    /// the result to degrees per second and applies the gain and cross-axis corrections.
+    pub fn xyz_dps(&mut self) -> Result<F32x3, E> {
+        let scale = self.scale()?;
+        let raw = self.xyz_raw()?;
+        Ok(self.compensation.apply(raw, scale))
+    }
+
+    /// Fetches X, Y and Z-axis data off the sensor in radians per second, compensated
+    /// like in [`xyz_dps`](Self::xyz_dps).
+    pub fn xyz_rad_s(&mut self) -> Result<F32x3, E> {
+        let dps = self.xyz_dps()?;
+        Ok(F32x3::new(
+            dps.x * DEG_TO_RAD,
+            dps.y * DEG_TO_RAD,
+            dps.z * DEG_TO_RAD,
+        ))
+    }
+
+    /// Fetches X, Y and Z-axis data off the sensor, failing with [`Error::PoweredDown`]
+    /// if the driver powered the device down.
+    pub fn xyz_raw_checked(&mut self) -> Result<I16x3, Error<E>> {
+        self.ensure_powered()?;
+        self.xyz_raw().map_err(Error::Bus)
+    }
+
+    /// Fetches all data off the sensor, failing with [`Error::PoweredDown`]
+    /// if the driver powered the device down.
+    pub fn data_raw_checked(&mut self) -> Result<SensorData, Error<E>> {
+        self.ensure_powered()?;
+        self.data_raw().map_err(Error::Bus)
+    }
+
+    /// Fails with [`Error::PoweredDown`] if the device was powered down by the driver.
+    ///
+    /// The power state is tracked from the writes to `CTRL_REG1` made through the driver.
+    pub fn ensure_powered(&self) -> Result<(), Error<E>> {
+        if self.powered_down {
+            Err(Error::PoweredDown)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Catches reads of the output registers while the device is known to be powered down
+    /// in debug builds, as these would only return stale values.
+    fn debug_assert_powered(&self) {
+        debug_assert!(
+            !self.powered_down,
+            "reading sensor data while the device is powered down"
+        );
+    }
+
+    /// Fetches all data off the sensor.
+    ///
+    /// See [`set_acquisition_burst`](Self::set_acquisition_burst) for the registers read.
+    pub fn data_raw(&mut self) -> Result<SensorData, E> {
+        #[cfg(feature = "acquisition")]
+        let data = self.acquire()?;
+        #[cfg(not(feature = "acquisition"))]
+        let data = self.read_burst(None)?;
+        Ok(health::tag_degraded(self.degraded, data))
+    }
+
+    /// Reads `OUT_TEMP` through `OUT_Z_H` in one burst.
+    ///
+    /// If a `temperature` is given, it is reported instead and the burst starts at
+    /// `STATUS_REG`, so that the freshness of the axis values is still known.
+    pub(crate) fn read_burst(&mut self, temperature: Option<u8>) -> Result<SensorData, E> {
+        self.debug_assert_powered();
+        let (start, address) = match temperature {
+            Some(_) => (1, *StatusRegister::REGISTER_ADDRESS),
+            None => (0, *TemperatureRegister::REGISTER_ADDRESS),
+        };
+        let mut buffer = [0; 9];
+        buffer[start] = framing::read_multi_cmd(address);
+        self.transfer(&mut buffer[start..])?;
+
+        // skip the command byte at [0].
+        let mut data = [0; 8];
+        data.copy_from_slice(&buffer[1..]);
+        if let Some(temperature) = temperature {
+            data[0] = temperature;
+        }
+        if self.big_endian {
+            decode::swap_axis_bytes(&mut data[2..]);
+        }
+
+        self.sample_counter = self.sample_counter.wrapping_add(1);
+        let data = self.axis_map.apply_data(decode::data(&data));
+        #[cfg(feature = "thermal")]
+        if temperature.is_none() {
+            self.thermal.record(data.temperature, self.now_micros());
+        }
+        self.track_sample(I16x3::from(data));
+        Ok(data)
+    }
+
+    /// Feeds a sample to the peak hold and the stationary detector, if enabled.
+    #[cfg_attr(not(feature = "dsp"), allow(unused_variables))]
+    fn track_sample(&mut self, xyz: I16x3) {
+        #[cfg(feature = "dsp")]
+        {
+            self.record_peak(xyz);
+            self.record_zupt(xyz);
+        }
+    }
+
+    /// Fetches all data off the sensor such that the freshness and overrun flags always
+    /// correspond to the returned axis values.
+    ///
+    /// This enables block data update (BDU) in `CTRL_REG4` if it is not already enabled,
+    /// like with [`set_block_data_update`](Self::set_block_data_update), so that the output
+    /// registers are not updated while they are being read. It then reads the temperature,
+    /// the status register and the axis values in a single burst starting at `OUT_TEMP`
+    /// (26h). Without BDU, a sample produced mid-burst may pair stale status flags with
+    /// fresh axis values (or vice versa), which corrupts fusion filters.
+    pub fn read_coherent(&mut self) -> Result<SensorData, E> {
+        if !self.block_data_update {
+            self.set_block_data_update(true)?;
+        }
+        let data = self.read_burst(None)?;
+        Ok(health::tag_degraded(self.degraded, data))
+    }
+
+    /// Limits the length of bus transactions, including the command byte, e.g. to satisfy
+    /// DMA constraints; `None` removes the limit. Limits below two bytes are raised to two.
+    ///
+    /// Longer burst accesses, such as FIFO drains and register block reads, are split into
+    /// consecutive transactions. To keep reads of the output registers coherent across
+    /// transactions, block data update is enabled before the first such split read.
+    pub fn set_max_burst_len(&mut self, bytes: Option<usize>) {
+        self.max_burst_len = bytes.map(|bytes| bytes.max(2));
+    }
+
+    /// Gets the maximum length of bus transactions, if limited.
+    pub fn max_burst_len(&self) -> Option<usize> {
+        self.max_burst_len
+    }
+
+    /// Executes a framed transfer, splitting it into chunks if it exceeds the maximum burst length.
+    pub(crate) fn transfer(&mut self, buffer: &mut [u8]) -> Result<(), E> {
+        match (self.max_burst_len, buffer.first()) {
+            (Some(max), Some(&command)) if buffer.len() > max && framing::is_multi(command) => {
+                self.transfer_chunked(buffer, max)
+            }
+            _ => self.transfer_single(buffer),
+        }
+    }
+
+    /// Executes a multi-address transfer as a sequence of transactions of at most `max` bytes.
+    ///
+    /// Each chunk borrows the byte preceding its data as the command slot, which is
+    /// restored after the chunk was transferred. Enabling block data update for split reads
+    /// of the output registers is recorded like [`set_block_data_update`](Self::set_block_data_update).
+    fn transfer_chunked(&mut self, buffer: &mut [u8], max: usize) -> Result<(), E> {
+        let command = buffer[0];
+        let first = framing::address(command);
+        let last = usize::from(first) + buffer.len() - 2;
+        let outputs =
+            usize::from(*OutXLow::REGISTER_ADDRESS)..=usize::from(*OutZHigh::REGISTER_ADDRESS);
+        if framing::is_read(command)
+            && !self.block_data_update
+            && usize::from(first) <= *outputs.end()
+            && last >= *outputs.start()
+        {
+            self.set_block_data_update(true)?;
+        }
+
+        let mut start = 0;
+        while start + 1 < buffer.len() {
+            let end = buffer.len().min(start + max);
+            let saved = buffer[start];
+            buffer[start] = framing::with_address(command, burst_address(first, start));
+            let result = self.transfer_single(&mut buffer[start..end]);
+            buffer[start] = saved;
+            result?;
+            start = end - 1;
+        }
+        Ok(())
+    }
+
+    /// Executes a single framed transfer.
+    ///
+    /// A full-duplex bus replaces the whole buffer with the received bytes, so the command
+    /// byte and the written bytes are kept to observe the transfer afterwards.
+    fn transfer_single(&mut self, buffer: &mut [u8]) -> Result<(), E> {
+        let Some(&command) = buffer.first() else {
+            return self.transport.transfer(buffer);
+        };
+        let mut written = [0; OBSERVED_WRITE_LEN];
+        let written_len = if framing::is_read(command) {
+            0
+        } else {
+            (buffer.len() - 1).min(OBSERVED_WRITE_LEN)
+        };
+        written[..written_len].copy_from_slice(&buffer[1..=written_len]);
+
+        #[cfg(feature = "profiling")]
+        let start = self.profile_clock.map(|clock| clock.now_micros());
+        self.transport.transfer(buffer)?;
+        let data = if framing::is_read(command) {
+            &buffer[1..]
+        } else {
+            &written[..written_len]
+        };
+        if let Some(cache) = self.register_cache.as_mut() {
+            cache.observe(command, data);
+        }
+        #[cfg(feature = "profiling")]
+        {
+            let end = self.profile_clock.map(|clock| clock.now_micros());
+            let duration = start.zip(end).map(|(start, end)| end.saturating_sub(start));
+            self.profile.record(command, buffer.len(), duration);
+        }
+        #[cfg(feature = "debug")]
+        self.write_log.record(command, data, self.sample_counter);
+        Ok(())
+    }
+
+    /// Reads a single register.
+    pub fn read_register<R>(&mut self) -> Result<R, E>
+    where
+        R: Register,
+    {
+        let command = framing::read_single_cmd(*R::REGISTER_ADDRESS);
+        let mut buffer = [command, 0];
+        self.transfer(&mut buffer)?;
+        Ok(R::from_bits(buffer[1]))
+    }
+
+    /// Writes a single register.
+    pub fn write_register<B, R>(&mut self, register: B) -> Result<(), E>
+    where
+        B: core::borrow::Borrow<R>,
+        R: WritableRegister,
+    {
+        let byte = register.borrow().to_bits();
+        let command = framing::write_single_cmd(*R::REGISTER_ADDRESS);
+        let mut buffer = [command, byte];
+        self.transfer(&mut buffer)?;
+        self.note_register_write(*R::REGISTER_ADDRESS, byte);
+        Ok(())
+    }
+
+    /// Updates the cached power state, the state cached from `CTRL_REG4` or the low output
+    /// data rate selection after a register write.
+    pub(crate) fn note_register_write(&mut self, address: u8, value: u8) {
+        if address == *ControlRegister1::REGISTER_ADDRESS {
+            self.powered_down = !ControlRegister1::from_bits(value).power_up();
+        }
+        if address == *ControlRegister4::REGISTER_ADDRESS {
+            self.note_ctrl4_write(value);
+        }
+        #[cfg(feature = "l3gd20h")]
+        if address == crate::l3gd20h::LOW_ODR_ADDRESS {
+            self.low_odr = value & crate::l3gd20h::LOW_ODR != 0;
+        }
+    }
+
+    /// Updates the cached `CTRL_REG4` state after writing it, converting the bias, which is
+    /// kept in raw LSB, to a changed full scale.
+    pub(crate) fn note_ctrl4_write(&mut self, value: u8) {
+        let previous = self.full_scale;
+        self.cache_ctrl4(value);
+        if self.full_scale != previous {
+            self.compensation.bias = self.compensation.bias.rescaled(previous, self.full_scale);
+        }
+    }
+
+    /// Updates the cached block data update, endianness and full scale state after
+    /// writing `CTRL_REG4`.
+    pub(crate) fn cache_ctrl4(&mut self, value: u8) {
+        let ctrl4 = ControlRegister4::from_bits(value);
+        self.block_data_update = ctrl4.block_data_update();
+        self.big_endian = ctrl4.big_endian();
+        self.full_scale = ctrl4.full_scale();
+    }
+
+    /// Modifies a single register.
+    ///
+    /// With the [register cache](Self::set_register_cache) enabled, a cached control
+    /// register is not read first.
+    pub fn modify_register<F, R>(&mut self, f: F) -> Result<(), E>
+    where
+        F: FnOnce(R) -> R,
+        R: WritableRegister,
+    {
+        let cached = self
+            .register_cache
+            .and_then(|cache| cache.get(*R::REGISTER_ADDRESS));
+        let register: R = match cached {
+            Some(value) => R::from_bits(value),
+            None => self.read_register()?,
+        };
+        let register = f(register);
+        self.write_register(register)
+    }
+}
+
+/// The number of written bytes kept to observe a transfer, covering the whole register file.
+const OBSERVED_WRITE_LEN: usize = 0x40;
+
+/// Gets the address of the byte at `offset` into the data of a burst starting at `first`.
+///
+/// Bursts starting at an output register are FIFO drains, whose address wraps from
+/// `OUT_Z_H` back to `OUT_X_L`; all other bursts advance linearly.
+fn burst_address(first: u8, offset: usize) -> u8 {
+    let x_low = *OutXLow::REGISTER_ADDRESS;
+    let window = usize::from(*OutZHigh::REGISTER_ADDRESS - x_low) + 1;
+    if (x_low..=*OutZHigh::REGISTER_ADDRESS).contains(&first) {
+        x_low + ((usize::from(first - x_low) + offset) % window) as u8
+    } else {
+        (usize::from(first) + offset) as u8
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::MockError;
+    use crate::sim::Simulator;
+
+    #[test]
+    fn unchecked_construction_does_not_access_the_device() {
+        let driver = L3GD20::with_transport_unchecked(Simulator::<MockError>::new());
+        assert_eq!(driver.transport.transfers(), 0);
+    }
+
+    #[test]
+    fn preserved_configuration_is_adopted() {
+        let mut sim = Simulator::<MockError>::new();
+        sim.registers_mut()[0x20] = 0b1100_1111;
+        sim.registers_mut()[0x23] = 0b1010_0000;
+        let before = *sim.registers();
+
+        let driver = L3GD20::with_transport_preserve_config(sim).unwrap();
+        assert_eq!(*driver.transport.registers(), before);
+        assert_eq!(driver.transport.transfers(), 2);
+        assert!(driver.block_data_update);
+        assert!(!driver.powered_down);
+        assert_eq!(driver.expected_settings.full_scale, Sensitivity::D2000);
+        assert_eq!(
+            driver.expected_settings.output_data_rate,
+            OutputDataRate::Hz760
+        );
+    }
+
+    #[test]
+    fn preserving_construction_identifies_the_device() {
+        let mut sim = Simulator::<MockError>::new();
+        sim.registers_mut()[0x0F] = 0xD3;
+        assert!(matches!(
+            L3GD20::with_transport_preserve_config(sim),
+            Err(Error::UnknownDevice(0xD3))
+        ));
+    }
+
+    #[test]
+    fn construction_keeps_the_detected_variant() {
+        let mut sim = Simulator::<MockError>::new();
+        sim.registers_mut()[0x0F] = 0xD7;
+        let driver = L3GD20::with_transport(sim).unwrap();
+        assert_eq!(driver.variant(), Some(crate::temperature::Variant::L3GD20H));
+
+        let mut sim = Simulator::<MockError>::new();
+        sim.registers_mut()[0x0F] = 0xD7;
+        let driver = L3GD20::with_transport_preserve_config(sim).unwrap();
+        assert_eq!(driver.variant(), Some(crate::temperature::Variant::L3GD20H));
+
+        let driver = L3GD20::with_transport(Simulator::<MockError>::new()).unwrap();
+        assert_eq!(driver.variant(), Some(crate::temperature::Variant::L3GD20));
+    }
+
+    #[test]
+    fn construction_identifies_the_device() {
+        let mut sim = Simulator::<MockError>::new();
+        sim.registers_mut()[0x0F] = 0xD3;
+        assert!(matches!(
+            L3GD20::with_transport(sim),
+            Err(Error::UnknownDevice(0xD3))
+        ));
+
+        let mut sim = Simulator::<MockError>::new();
+        sim.registers_mut()[0x0F] = 0xD3;
+        let mut driver = L3GD20::with_transport_unidentified(sim).unwrap();
+        assert_eq!(driver.identify(), Ok(false));
+    }
+
+    #[test]
+    fn checked_reads_fail_while_powered_down() {
+        let mut driver = L3GD20::with_transport(Simulator::<MockError>::new()).unwrap();
+        driver.power_down().unwrap();
+        assert_eq!(driver.xyz_raw_checked(), Err(Error::PoweredDown));
+        assert!(matches!(driver.data_raw_checked(), Err(Error::PoweredDown)));
+
+        driver.power_up().unwrap();
+        assert!(driver.xyz_raw_checked().is_ok());
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    #[should_panic(expected = "powered down")]
+    fn reads_while_powered_down_are_caught_in_debug_builds() {
+        let mut driver = L3GD20::with_transport(Simulator::<MockError>::new()).unwrap();
+        driver.power_down().unwrap();
+        let _ = driver.xyz_raw();
+    }
+
+    #[test]
+    fn bursts_are_split_at_the_maximum_length() {
+        let mut driver = L3GD20::with_transport(Simulator::<MockError>::new()).unwrap();
+        driver.transport.set_temperature(21);
+        driver.transport.set_xyz(-100, 200, -300);
+        driver.set_max_burst_len(Some(4));
+
+        let transfers = driver.transport.transfers();
+        let data = driver.data_raw().unwrap();
+        assert_eq!(data.temperature, 21);
+        assert_eq!((*data.x, *data.y, *data.z), (-100, 200, -300));
+        // Enabling block data update takes two transfers, the eight data bytes three more.
+        assert_eq!(driver.transport.transfers(), transfers + 5);
+        assert!(driver.block_data_update);
+        assert_eq!(driver.settings(), Ok(driver.expected_settings));
+    }
+
+    #[test]
+    fn split_bursts_wrap_around_the_output_registers() {
+        assert_eq!(burst_address(0x28, 6), 0x28);
+        assert_eq!(burst_address(0x28, 13), 0x29);
+        assert_eq!(burst_address(0x2C, 3), 0x29);
+        assert_eq!(burst_address(0x26, 8), 0x2E);
+    }
+
+    #[test]
+    fn has_new_sample_reads_zyxda() {
+        let mut driver = L3GD20::with_transport(Simulator::<MockError>::new()).unwrap();
+        assert!(!driver.has_new_sample().unwrap());
+
+        driver.transport.set_status(0b0000_1000);
+        let transfers = driver.transport.transfers();
+        assert!(driver.has_new_sample().unwrap());
+        assert_eq!(driver.transport.transfers(), transfers + 1);
+        assert!(!driver.status().unwrap().x_da);
+    }
+
+    #[test]
+    fn byte_order_follows_ble() {
+        let mut driver = L3GD20::with_transport(Simulator::<MockError>::new()).unwrap();
+        driver.set_block_data_update(true).unwrap();
+        driver.set_big_endian(true).unwrap();
+        assert_eq!(driver.transport.registers()[0x23], 0b1100_0000);
+        assert!(driver.block_data_update() && driver.big_endian());
+
+        driver.transport.set_xyz(0x1234, -2, 1);
+        assert_eq!(driver.xyz_raw().unwrap(), I16x3::new(0x1234, -2, 1));
+        assert_eq!(*driver.data_raw().unwrap().y, -2);
+
+        driver.set_big_endian(false).unwrap();
+        driver.transport.set_xyz(0x1234, -2, 1);
+        assert_eq!(driver.xyz_raw().unwrap(), I16x3::new(0x1234, -2, 1));
+        assert!(!driver.big_endian());
+    }
+
+    #[test]
+    fn sensitivity_override_scales_readings() {
+        let mut sim = Simulator::<MockError>::new();
+        sim.set_xyz(100, 100, -100);
+        let mut driver = L3GD20::with_transport(sim).unwrap();
+        assert_eq!(driver.xyz_dps().unwrap(), F32x3::new(0.875, 0.875, -0.875));
+
+        driver.set_sensitivity_override([10.0, 20.0, 5.0]);
+        let dps = driver.xyz_dps().unwrap();
+        assert!(libm::fabsf(dps.x - 1.0) < 1e-5);
+        assert!(libm::fabsf(dps.y - 2.0) < 1e-5);
+        assert!(libm::fabsf(dps.z + 0.5) < 1e-5);
+
+        driver.clear_sensitivity_override();
+        assert_eq!(driver.xyz_dps().unwrap(), F32x3::new(0.875, 0.875, -0.875));
+    }
+
+    #[test]
+    fn replacing_the_bus_keeps_the_driver_state() {
+        use crate::mock::{MockCs, MockSpi};
+
+        let mut driver = L3GD20SPI::new(MockSpi::new(), MockCs).unwrap();
+        driver.set_gain(F32x3::new(2.0, 2.0, 2.0));
+
+        let mut spi = MockSpi::new();
+        spi.set_xyz(100, 0, 0);
+        let old = driver.replace_bus(spi);
+        assert!(old.transfers > 0);
+        assert_eq!(driver.gain(), &F32x3::new(2.0, 2.0, 2.0));
+        assert_eq!(driver.xyz_dps().unwrap(), F32x3::new(1.75, 0.0, 0.0));
+    }
+
+    #[test]
+    fn rates_are_read_in_radians_per_second() {
+        let mut sim = Simulator::<MockError>::new();
+        sim.set_xyz(0, 0, -1000);
+        let mut driver = L3GD20::with_transport(sim).unwrap();
+
+        let rates = driver.xyz_rad_s().unwrap();
+        assert!(libm::fabsf(rates.z + 0.152_716) < 1e-5);
+    }
+
+    #[test]
+    fn scale_is_kept_by_the_driver() {
+        let mut sim = Simulator::<MockError>::new();
+        sim.set_xyz(1000, 0, 0);
+        let mut driver = L3GD20::with_transport(sim).unwrap();
+        driver.set_scale(crate::Sensitivity::D2000).unwrap();
+        assert_eq!(driver.full_scale(), Sensitivity::D2000);
+        assert_eq!(driver.transport.registers()[0x23], 0b0010_0000);
+
+        let transfers = driver.transport.transfers();
+        assert_eq!(driver.xyz_dps().unwrap().x, 70.0);
+        assert_eq!(driver.transport.transfers(), transfers + 1);
+        assert_eq!(driver.characteristics().unwrap().full_scale, 2000);
+    }
+
+    #[test]
+    fn bias_follows_the_scale() {
+        let mut sim = Simulator::<MockError>::new();
+        sim.set_xyz(800, -80, 0);
+        let mut driver = L3GD20::with_transport(sim).unwrap();
+        driver.set_bias(Bias {
+            offset: F32x3::new(800.0, -80.0, 8.0),
+            noise: F32x3::new(16.0, 16.0, 16.0),
+            ..Bias::default()
+        });
+        assert_eq!(driver.xyz_dps().unwrap(), F32x3::new(0.0, 0.0, -0.07));
+
+        driver.set_scale(Sensitivity::D2000).unwrap();
+        assert_eq!(driver.bias().offset, F32x3::new(100.0, -10.0, 1.0));
+        assert_eq!(driver.bias().noise, F32x3::new(2.0, 2.0, 2.0));
+        driver.transport.set_xyz(100, -10, 0);
+        assert_eq!(driver.xyz_dps().unwrap(), F32x3::new(0.0, 0.0, -0.07));
+
+        driver.reset().unwrap();
+        assert_eq!(driver.bias().offset, F32x3::new(800.0, -80.0, 8.0));
+    }
+
+    #[test]
+    fn releasing_returns_the_bus() {
+        use crate::mock::{MockCs, MockSpi};
+
+        let driver = L3GD20SPI::new(MockSpi::new(), MockCs).unwrap();
+        let (spi, _cs) = driver.release();
+        assert!(spi.transfers > 0);
+
+        let driver = L3GD20SPI::new(spi, MockCs).unwrap();
+        let (spi, _cs) = driver.into_transport().release();
+        assert!(spi.transfers > 0);
+    }
+}
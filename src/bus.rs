@@ -0,0 +1,201 @@
+//! Provides exclusive use of a shared bus across several driver calls.
+//!
+//! A burst of related operations, e.g. reconfiguring the sensor and reading back the
+//! result, may need to see a quiescent bus. [`L3GD20::acquire_bus`] locks a free shared bus,
+//! such as [`SharedBus`](crate::wrapper::shared::SharedBus), for as long as the returned
+//! [`BusGuard`] is held, so that other bus users cannot interleave their transfers.
+//!
+//! Conversely, reads of a control loop should not be held up indefinitely by another bus
+//! user. With [`ReadPriority::High`], [`L3GD20::xyz_raw_prioritized`] retries while the bus
+//! is busy for a bounded time, and fails with [`Error::BusBusy`] afterwards.
+
+use crate::{Error, I16x3, Transport, L3GD20};
+use core::ops::{Deref, DerefMut};
+use embedded_hal::blocking::delay::DelayUs;
+
+/// The interval at which a busy bus is retried, in microseconds.
+const BUSY_POLL_INTERVAL_US: u32 = 50;
+
+/// An error that can indicate that a shared bus is in use by another device.
+pub trait BusyError {
+    /// Determines whether the transfer failed because the bus is busy.
+    fn is_busy(&self) -> bool;
+}
+
+/// The priority of reads over a shared bus.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ReadPriority {
+    /// A busy bus fails the read immediately.
+    #[default]
+    Normal,
+    /// A busy bus is retried until the wait exceeds the bound.
+    High {
+        /// The longest time to wait for the bus, in microseconds.
+        max_wait_us: u32,
+    },
+}
+
+/// A transport over a shared bus that can be locked for exclusive use.
+pub trait BusLock {
+    /// Locks the bus for exclusive use by this transport.
+    ///
+    /// A lock held by another bus user must not be taken over. Returns whether this
+    /// transport holds the lock.
+    #[must_use]
+    fn lock_bus(&mut self) -> bool;
+
+    /// Releases the lock taken by [`lock_bus`](Self::lock_bus).
+    fn unlock_bus(&mut self);
+}
+
+impl<T> BusLock for &mut T
+where
+    T: BusLock + ?Sized,
+{
+    fn lock_bus(&mut self) -> bool {
+        T::lock_bus(self)
+    }
+
+    fn unlock_bus(&mut self) {
+        T::unlock_bus(self);
+    }
+}
+
+/// Exclusive use of the bus, released when dropped.
+///
+/// The guard dereferences to the driver, so that driver calls can be made while it is held.
+pub struct BusGuard<'a, T>
+where
+    T: Transport + BusLock,
+{
+    driver: &'a mut L3GD20<T>,
+}
+
+impl<T> Deref for BusGuard<'_, T>
+where
+    T: Transport + BusLock,
+{
+    type Target = L3GD20<T>;
+
+    fn deref(&self) -> &Self::Target {
+        self.driver
+    }
+}
+
+impl<T> DerefMut for BusGuard<'_, T>
+where
+    T: Transport + BusLock,
+{
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.driver
+    }
+}
+
+impl<T> Drop for BusGuard<'_, T>
+where
+    T: Transport + BusLock,
+{
+    fn drop(&mut self) {
+        self.driver.transport.unlock_bus();
+    }
+}
+
+impl<T, E> L3GD20<T>
+where
+    T: Transport<Error = E> + BusLock,
+{
+    /// Locks the bus for exclusive use until the returned guard is dropped.
+    ///
+    /// Fails with [`Error::BusBusy`] if the bus is locked by another device.
+    pub fn acquire_bus(&mut self) -> Result<BusGuard<'_, T>, Error<E>> {
+        if !self.transport.lock_bus() {
+            return Err(Error::BusBusy);
+        }
+        Ok(BusGuard { driver: self })
+    }
+}
+
+impl<T, E> L3GD20<T>
+where
+    T: Transport<Error = E>,
+    E: BusyError,
+{
+    /// Selects the priority of [`xyz_raw_prioritized`](Self::xyz_raw_prioritized).
+    pub fn set_read_priority(&mut self, priority: ReadPriority) {
+        self.read_priority = priority;
+    }
+
+    /// Gets the priority of [`xyz_raw_prioritized`](Self::xyz_raw_prioritized).
+    pub fn read_priority(&self) -> ReadPriority {
+        self.read_priority
+    }
+
+    /// Fetches X, Y and Z-axis data off the sensor, failing with [`Error::BusBusy`] if the
+    /// bus is held by another device for longer than the [read priority](ReadPriority)
+    /// allows.
+    pub fn xyz_raw_prioritized<D>(&mut self, delay: &mut D) -> Result<I16x3, Error<E>>
+    where
+        D: DelayUs<u32>,
+    {
+        let max_wait_us = match self.read_priority {
+            ReadPriority::Normal => 0,
+            ReadPriority::High { max_wait_us } => max_wait_us,
+        };
+
+        let mut waited = 0;
+        loop {
+            match self.xyz_raw() {
+                Err(error) if error.is_busy() => {
+                    if waited >= max_wait_us {
+                        return Err(Error::BusBusy);
+                    }
+                    delay.delay_us(BUSY_POLL_INTERVAL_US);
+                    waited = waited.saturating_add(BUSY_POLL_INTERVAL_US);
+                }
+                result => return result.map_err(Error::Bus),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{MockCs, MockSpi};
+    use crate::wrapper::shared::{SharedBus, SharedSpi};
+
+    /// A delay that releases the bus held by another device after the given number of calls.
+    struct ReleaseAfter<'a> {
+        other: SharedSpi<'a, MockSpi>,
+        calls: u32,
+    }
+
+    impl DelayUs<u32> for ReleaseAfter<'_> {
+        fn delay_us(&mut self, _us: u32) {
+            self.calls = self.calls.saturating_sub(1);
+            if self.calls == 0 {
+                self.other.unlock();
+            }
+        }
+    }
+
+    #[test]
+    fn high_priority_reads_wait_a_bounded_time() {
+        let bus = SharedBus::new(MockSpi::new());
+        let mut driver = L3GD20::new(bus.device(0), MockCs).unwrap();
+        let mut other = bus.device(1);
+        assert!(other.lock());
+
+        let mut delay = ReleaseAfter { other, calls: 3 };
+        assert_eq!(driver.xyz_raw_prioritized(&mut delay), Err(Error::BusBusy));
+
+        driver.set_read_priority(ReadPriority::High { max_wait_us: 100 });
+        assert_eq!(driver.xyz_raw_prioritized(&mut delay), Err(Error::BusBusy));
+
+        assert!(delay.other.lock());
+        delay.calls = 3;
+        driver.set_read_priority(ReadPriority::High { max_wait_us: 150 });
+        assert!(driver.xyz_raw_prioritized(&mut delay).is_ok());
+    }
+}
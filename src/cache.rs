@@ -0,0 +1,146 @@
+//! Provides the write-through shadow cache of the control registers.
+//!
+//! [`L3GD20::modify_register`] reads a register before writing it back, doubling the bus
+//! traffic of configuration changes. With the cache enabled via
+//! [`set_register_cache`](L3GD20::set_register_cache), the contents of `CTRL_REG1` through
+//! `CTRL_REG5` are remembered from every transfer that reads or writes them, and modifying
+//! a known register takes a single write.
+//!
+//! The cache assumes the driver is the only one changing the control registers. If they are
+//! changed otherwise, e.g. by another bus master, call
+//! [`invalidate_cache`](L3GD20::invalidate_cache) or [`sync_config`](L3GD20::sync_config).
+
+use crate::common::framing;
+use crate::{Transport, L3GD20};
+use l3gd20_registers::prelude::SPIRegister;
+use l3gd20_registers::{ControlRegister1, ControlRegister5};
+
+/// The `BOOT` bit of `CTRL_REG5`, which clears itself once the reboot completes.
+const BOOT: u8 = 0b1000_0000;
+
+/// The cached contents of `CTRL_REG1` through `CTRL_REG5`.
+#[derive(Debug, Default, Copy, Clone)]
+pub(crate) struct RegisterCache {
+    values: [Option<u8>; 5],
+}
+
+impl RegisterCache {
+    /// Gets the index of a control register address.
+    fn index(address: u8) -> Option<usize> {
+        let first = *ControlRegister1::REGISTER_ADDRESS;
+        address
+            .checked_sub(first)
+            .map(usize::from)
+            .filter(|&index| index < 5)
+    }
+
+    /// Gets the cached value of a register, if known.
+    pub(crate) fn get(&self, address: u8) -> Option<u8> {
+        Self::index(address).and_then(|index| self.values[index])
+    }
+
+    /// Records the control register contents read or written by a completed transfer,
+    /// given its command byte and the bytes read or written.
+    pub(crate) fn observe(&mut self, command: u8, data: &[u8]) {
+        let mut address = framing::address(command);
+        for &value in data {
+            if let Some(index) = Self::index(address) {
+                let value = if address == *ControlRegister5::REGISTER_ADDRESS {
+                    value & !BOOT
+                } else {
+                    value
+                };
+                self.values[index] = Some(value);
+            }
+            if framing::is_multi(command) {
+                address = address.wrapping_add(1);
+            }
+        }
+    }
+}
+
+impl<T, E> L3GD20<T>
+where
+    T: Transport<Error = E>,
+{
+    /// Enables or disables the shadow cache of the control registers.
+    ///
+    /// The cache starts out empty; each register is read once on its first modification.
+    pub fn set_register_cache(&mut self, enabled: bool) {
+        self.register_cache = enabled.then(RegisterCache::default);
+    }
+
+    /// Determines whether the shadow cache of the control registers is enabled.
+    pub fn register_cache_enabled(&self) -> bool {
+        self.register_cache.is_some()
+    }
+
+    /// Discards the cached control register contents, so that each register is read again
+    /// on its next modification.
+    pub fn invalidate_cache(&mut self) {
+        if let Some(cache) = self.register_cache.as_mut() {
+            *cache = RegisterCache::default();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::MockError;
+    use crate::signal_path::SignalStage;
+    use crate::sim::Simulator;
+    use crate::{OutputDataRate, Sensitivity};
+
+    #[test]
+    fn modifications_of_cached_registers_take_one_write() {
+        let mut driver = L3GD20::with_transport(Simulator::<MockError>::new()).unwrap();
+        driver.set_register_cache(true);
+
+        let transfers = driver.transport.transfers();
+        driver.set_odr(OutputDataRate::Hz190).unwrap();
+        driver.set_odr(OutputDataRate::Hz380).unwrap();
+        driver.set_scale(Sensitivity::D500).unwrap();
+        assert_eq!(driver.transport.transfers(), transfers + 5);
+        assert_eq!(driver.transport.registers()[0x20] >> 6, 0b10);
+
+        driver.transport.registers_mut()[0x20] = 0b0000_1111;
+        driver.invalidate_cache();
+        driver.set_odr(OutputDataRate::Hz760).unwrap();
+        assert_eq!(driver.transport.registers()[0x20], 0b1100_1111);
+    }
+
+    #[test]
+    fn cache_is_filled_from_the_transmitted_bytes() {
+        use crate::mock::{MockCs, MockSpi};
+        use crate::L3GD20SPI;
+
+        let mut spi = MockSpi::new();
+        spi.full_duplex = true;
+        let mut driver = L3GD20SPI::new(spi, MockCs).unwrap();
+        driver.set_register_cache(true);
+
+        driver.set_odr(OutputDataRate::Hz190).unwrap();
+        let transfers = driver.transport.spi_mut().transfers;
+        driver.set_scale(Sensitivity::D500).unwrap();
+        driver.set_scale(Sensitivity::D2000).unwrap();
+        driver.set_odr(OutputDataRate::Hz380).unwrap();
+        assert_eq!(driver.transport.spi_mut().transfers, transfers + 4);
+        assert_eq!(driver.transport.spi_mut().registers[0x20] >> 6, 0b10);
+        assert_eq!(driver.transport.spi_mut().registers[0x23], 0b0010_0000);
+    }
+
+    #[test]
+    fn boot_is_not_written_back() {
+        let mut driver = L3GD20::with_transport(Simulator::<MockError>::new()).unwrap();
+        driver.set_register_cache(true);
+        driver
+            .write_register(ControlRegister5::default().with_boot(true))
+            .unwrap();
+
+        let transfers = driver.transport.transfers();
+        driver.set_data_path(SignalStage::Lpf2).unwrap();
+        assert_eq!(driver.transport.transfers(), transfers + 1);
+        assert_eq!(driver.transport.registers()[0x24], 0b0000_0010);
+    }
+}
@@ -0,0 +1,518 @@
+//! Provides zero-rate level (bias) and scale factor (gain) calibration.
+//!
+//! The [`Bias`] and [`BiasReport`] are part of the driver core; the calibration routines
+//! require the `calibration` feature.
+
+use crate::{characteristics, F32x3};
+#[cfg(feature = "calibration")]
+use crate::{Axis, Transport, L3GD20};
+use l3gd20_registers::Sensitivity;
+
+/// A zero-rate level (bias) estimate.
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Bias {
+    /// The zero-rate offset per axis, in raw LSB.
+    pub offset: F32x3,
+    /// The raw temperature reading at the time of estimation.
+    pub temperature: u8,
+    /// The number of samples the estimate was averaged from.
+    pub samples: u16,
+    /// The standard deviation of the samples per axis, in raw LSB.
+    pub noise: F32x3,
+}
+
+impl Bias {
+    /// Converts a bias estimated at the full scale `from` to the raw LSB of the full scale `to`.
+    #[must_use]
+    pub fn rescaled(&self, from: Sensitivity, to: Sensitivity) -> Self {
+        let ratio = characteristics::sensitivity(from) / characteristics::sensitivity(to);
+        let scale = |value: F32x3| F32x3::new(value.x * ratio, value.y * ratio, value.z * ratio);
+        Self {
+            offset: scale(self.offset),
+            noise: scale(self.noise),
+            ..*self
+        }
+    }
+}
+
+/// An assessment of how trustworthy the current bias estimate is.
+///
+/// This is intended for fusion stacks (e.g. EKF-based) that need to decide whether the
+/// gyro bias can be treated as known or needs to be re-estimated as part of the filter state.
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct BiasReport {
+    /// The bias estimate.
+    pub bias: Bias,
+    /// The number of sensor reads since the bias was applied.
+    pub age: u32,
+    /// The time since the bias was applied, in microseconds.
+    ///
+    /// This requires a [`TimeSource`](crate::time::TimeSource) to have been installed
+    /// before the bias was applied.
+    pub age_us: Option<u64>,
+    /// The temperature change since the estimate was taken, in °C.
+    ///
+    /// This assumes the nominal temperature slope of −1 LSB/°C.
+    pub temperature_delta: f32,
+    /// The estimated standard uncertainty (1σ) of the bias per axis, in degrees/second.
+    ///
+    /// This combines the standard error of the mean with the zero-rate level drift
+    /// expected from the temperature change.
+    pub uncertainty: F32x3,
+    /// A heuristic confidence figure between `0.0` (unknown) and `1.0` (well known).
+    ///
+    /// The value decreases with fewer calibration samples and with temperature drift
+    /// dominating the statistical uncertainty.
+    pub confidence: f32,
+}
+
+impl BiasReport {
+    /// Assesses a bias estimate.
+    ///
+    /// ## Arguments
+    /// * `bias` - The bias estimate.
+    /// * `age` - The number of sensor reads since the estimate was applied.
+    /// * `age_us` - The time since the estimate was applied, if known.
+    /// * `temperature` - The current raw temperature reading.
+    /// * `sensitivity` - The per-axis sensitivity in degrees/second per LSB.
+    /// * `temp_coefficient` - The zero-rate level change in degrees/second per °C.
+    pub(crate) fn new(
+        bias: Bias,
+        age: u32,
+        age_us: Option<u64>,
+        temperature: u8,
+        sensitivity: F32x3,
+        temp_coefficient: f32,
+    ) -> Self {
+        // The temperature counter decreases as the temperature rises.
+        let temperature_delta =
+            -f32::from((temperature as i8).wrapping_sub(bias.temperature as i8));
+
+        if bias.samples == 0 {
+            return Self {
+                bias,
+                age,
+                age_us,
+                temperature_delta,
+                uncertainty: F32x3::new(f32::INFINITY, f32::INFINITY, f32::INFINITY),
+                confidence: 0.0,
+            };
+        }
+
+        let n = f32::from(bias.samples);
+        let sqrt_n = libm::sqrtf(n);
+        let drift = libm::fabsf(temperature_delta) * temp_coefficient;
+        let assess = |noise: f32, sensitivity: f32| {
+            let standard_error = noise * sensitivity / sqrt_n;
+            let uncertainty = libm::sqrtf(standard_error * standard_error + drift * drift);
+            let ratio = if uncertainty > 0.0 {
+                standard_error / uncertainty
+            } else {
+                1.0
+            };
+            (uncertainty, ratio)
+        };
+
+        let (ux, rx) = assess(bias.noise.x, sensitivity.x);
+        let (uy, ry) = assess(bias.noise.y, sensitivity.y);
+        let (uz, rz) = assess(bias.noise.z, sensitivity.z);
+        let ratio = rx.min(ry).min(rz);
+
+        Self {
+            bias,
+            age,
+            age_us,
+            temperature_delta,
+            uncertainty: F32x3::new(ux, uy, uz),
+            confidence: n / (n + 64.0) * ratio,
+        }
+    }
+
+    /// Estimates the expected (1σ) heading error, in degrees, after integrating the
+    /// Z-axis rate for the given duration in seconds without external aiding.
+    ///
+    /// The residual bias uncertainty grows the error linearly with time, while the
+    /// angle random walk from the rate noise density grows it with the square root of time.
+    /// Returns infinity if no bias estimate is available.
+    ///
+    /// ## Arguments
+    /// * `duration_s` - The intended integration time in seconds.
+    /// * `noise_density` - The rate noise density in degrees/second/√Hz.
+    #[must_use]
+    pub fn heading_drift(&self, duration_s: f32, noise_density: f32) -> f32 {
+        let duration_s = duration_s.max(0.0);
+        let bias_drift = self.uncertainty.z * duration_s;
+        let random_walk = noise_density * noise_density * duration_s;
+        libm::sqrtf(bias_drift * bias_drift + random_walk)
+    }
+}
+
+/// The progress of a [`CalibrationTask`].
+#[cfg(feature = "calibration")]
+#[cfg_attr(docsrs, doc(cfg(feature = "calibration")))]
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Progress {
+    /// More samples are required.
+    Pending {
+        /// The number of samples collected so far.
+        collected: u16,
+        /// The number of samples required in total.
+        total: u16,
+    },
+    /// The calibration is complete and the bias was applied to the driver.
+    Done(Bias),
+}
+
+/// A resumable bias calibration.
+///
+/// Each call to [`poll`](CalibrationTask::poll) performs at most a single sensor read,
+/// so the sample collection can be spread over many short time slices of a cooperative
+/// scheduler. The device must be kept stationary until the task completes.
+#[cfg(feature = "calibration")]
+#[cfg_attr(docsrs, doc(cfg(feature = "calibration")))]
+#[derive(Debug, Clone)]
+pub struct CalibrationTask {
+    total: u16,
+    collected: u16,
+    sum: [i32; 3],
+    sum_squares: [i64; 3],
+    temperature_sum: i32,
+    result: Option<Bias>,
+}
+
+#[cfg(feature = "calibration")]
+impl CalibrationTask {
+    /// Creates a task that averages the given number of fresh samples.
+    ///
+    /// A value of zero is treated as one.
+    #[must_use]
+    pub const fn new(samples: u16) -> Self {
+        Self {
+            total: if samples == 0 { 1 } else { samples },
+            collected: 0,
+            sum: [0; 3],
+            sum_squares: [0; 3],
+            temperature_sum: 0,
+            result: None,
+        }
+    }
+
+    /// Restarts the calibration from scratch.
+    pub fn reset(&mut self) {
+        *self = Self::new(self.total);
+    }
+
+    /// Indicates whether the calibration has completed.
+    #[must_use]
+    pub fn is_done(&self) -> bool {
+        self.result.is_some()
+    }
+
+    /// Advances the calibration by reading at most one sample from the sensor.
+    ///
+    /// Samples that are not fresh on all axes are ignored. When the last sample was
+    /// collected, the resulting bias is applied to the driver via
+    /// [`L3GD20::set_bias`]. Once done, further calls return the same result
+    /// without accessing the bus.
+    pub fn poll<T, E>(&mut self, driver: &mut L3GD20<T>) -> Result<Progress, E>
+    where
+        T: Transport<Error = E>,
+    {
+        if let Some(bias) = self.result {
+            return Ok(Progress::Done(bias));
+        }
+
+        let data = driver.data_raw()?;
+        if data.x.fresh_or_overrun() && data.y.fresh_or_overrun() && data.z.fresh_or_overrun() {
+            for (i, value) in [*data.x, *data.y, *data.z].into_iter().enumerate() {
+                self.sum[i] += i32::from(value);
+                self.sum_squares[i] += i64::from(value) * i64::from(value);
+            }
+            self.temperature_sum += i32::from(data.temperature as i8);
+            self.collected += 1;
+        }
+
+        if self.collected < self.total {
+            return Ok(Progress::Pending {
+                collected: self.collected,
+                total: self.total,
+            });
+        }
+
+        let n = f32::from(self.collected);
+        let std_dev = |i: usize| {
+            let mean = self.sum[i] as f32 / n;
+            let variance = self.sum_squares[i] as f32 / n - mean * mean;
+            libm::sqrtf(variance.max(0.0))
+        };
+        let bias = Bias {
+            offset: F32x3::new(
+                self.sum[0] as f32 / n,
+                self.sum[1] as f32 / n,
+                self.sum[2] as f32 / n,
+            ),
+            temperature: (self.temperature_sum / i32::from(self.collected)) as i8 as u8,
+            samples: self.collected,
+            noise: F32x3::new(std_dev(0), std_dev(1), std_dev(2)),
+        };
+
+        driver.set_bias(bias);
+        self.result = Some(bias);
+        Ok(Progress::Done(bias))
+    }
+}
+
+/// A guided per-axis scale factor calibration.
+///
+/// The board is rotated about a single axis by a precisely known angle (e.g. exactly 360°
+/// on a turntable) while [`update`](GainCalibration::update) is called at a steady pace to
+/// integrate the measured rate. [`finish`](GainCalibration::finish) then compares the
+/// integrated angle with the known one and stores the corrected gain in the driver.
+///
+/// The bias should be calibrated beforehand, as any residual offset is integrated as well.
+#[cfg(feature = "calibration")]
+#[cfg_attr(docsrs, doc(cfg(feature = "calibration")))]
+#[derive(Debug, Clone)]
+pub struct GainCalibration {
+    axis: Axis,
+    angle: f32,
+    last_us: Option<u64>,
+}
+
+#[cfg(feature = "calibration")]
+impl GainCalibration {
+    /// Starts a gain calibration for the specified axis.
+    #[must_use]
+    pub const fn new(axis: Axis) -> Self {
+        Self {
+            axis,
+            angle: 0.0,
+            last_us: None,
+        }
+    }
+
+    /// Gets the axis that is being calibrated.
+    #[must_use]
+    pub fn axis(&self) -> Axis {
+        self.axis
+    }
+
+    /// Gets the angle integrated so far, in degrees.
+    #[must_use]
+    pub fn angle(&self) -> f32 {
+        self.angle
+    }
+
+    /// Reads one sample and integrates it over the given time step in seconds.
+    ///
+    /// Returns the angle integrated so far, in degrees.
+    pub fn update<T, E>(&mut self, driver: &mut L3GD20<T>, dt: f32) -> Result<f32, E>
+    where
+        T: Transport<Error = E>,
+    {
+        let rate = driver.xyz_dps()?;
+        let rate = match self.axis {
+            Axis::X => rate.x,
+            Axis::Y => rate.y,
+            Axis::Z => rate.z,
+        };
+        self.angle += rate * dt;
+        Ok(self.angle)
+    }
+
+    /// Reads one sample and integrates it over the time elapsed since the previous call,
+    /// as determined by the driver's [`TimeSource`](crate::time::TimeSource).
+    ///
+    /// The first call only establishes the time reference. If no time source was installed,
+    /// nothing is integrated. Returns the angle integrated so far, in degrees.
+    pub fn update_timed<T, E>(&mut self, driver: &mut L3GD20<T>) -> Result<f32, E>
+    where
+        T: Transport<Error = E>,
+    {
+        let Some(now) = driver.now_micros() else {
+            return Ok(self.angle);
+        };
+        let Some(last) = self.last_us.replace(now) else {
+            return Ok(self.angle);
+        };
+        let dt = now.saturating_sub(last) as f32 * 1e-6;
+        self.update(driver, dt)
+    }
+
+    /// Completes the calibration given the angle, in degrees, the board was actually rotated by.
+    ///
+    /// The direction of rotation is irrelevant. Returns the new gain of the axis, or `None`
+    /// if no rotation was measured, in which case the driver is left unchanged.
+    pub fn finish<T, E>(self, driver: &mut L3GD20<T>, reference: f32) -> Option<f32>
+    where
+        T: Transport<Error = E>,
+    {
+        let measured = libm::fabsf(self.angle);
+        let reference = libm::fabsf(reference);
+        if measured < f32::EPSILON {
+            return None;
+        }
+
+        let mut gain = *driver.gain();
+        let value = match self.axis {
+            Axis::X => &mut gain.x,
+            Axis::Y => &mut gain.y,
+            Axis::Z => &mut gain.z,
+        };
+        *value *= reference / measured;
+        let result = *value;
+        driver.set_gain(gain);
+        Some(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "calibration")]
+    use crate::mock::{MockCs, MockSpi};
+    #[cfg(feature = "calibration")]
+    use crate::L3GD20SPI;
+
+    #[cfg(feature = "calibration")]
+    #[test]
+    fn calibration_skips_stale_samples() {
+        let mut spi = MockSpi::new();
+        spi.set_xyz(10, -20, 30);
+        spi.set_status(0b0000_0000);
+        let mut driver = L3GD20SPI::new(spi, MockCs).unwrap();
+
+        let mut task = CalibrationTask::new(2);
+        assert_eq!(
+            task.poll(&mut driver),
+            Ok(Progress::Pending {
+                collected: 0,
+                total: 2
+            })
+        );
+    }
+
+    #[cfg(feature = "calibration")]
+    #[test]
+    fn calibration_applies_bias() {
+        let mut spi = MockSpi::new();
+        spi.set_xyz(10, -20, 30);
+        spi.set_status(0b0000_1111);
+        spi.registers[0x26] = 0xFE;
+        let mut driver = L3GD20SPI::new(spi, MockCs).unwrap();
+
+        let mut task = CalibrationTask::new(3);
+        assert!(matches!(
+            task.poll(&mut driver),
+            Ok(Progress::Pending { .. })
+        ));
+        assert!(matches!(
+            task.poll(&mut driver),
+            Ok(Progress::Pending { .. })
+        ));
+        let Ok(Progress::Done(bias)) = task.poll(&mut driver) else {
+            panic!("calibration should be done");
+        };
+
+        assert_eq!(bias.offset, F32x3::new(10.0, -20.0, 30.0));
+        assert_eq!(bias.temperature, 0xFE);
+        assert_eq!(bias.samples, 3);
+        assert_eq!(bias.noise, F32x3::default());
+        assert_eq!(driver.bias(), &bias);
+        assert!(task.is_done());
+    }
+
+    #[cfg(feature = "calibration")]
+    #[test]
+    fn gain_calibration_scales_axis() {
+        let mut spi = MockSpi::new();
+        spi.set_xyz(0, 0, 4000);
+        spi.set_status(0b0000_1111);
+        let mut driver = L3GD20SPI::new(spi, MockCs).unwrap();
+
+        // 4000 LSB at 8.75 mdps/LSB are 35 dps; ten seconds yield 350°.
+        let mut calibration = GainCalibration::new(Axis::Z);
+        for _ in 0..10 {
+            calibration.update(&mut driver, 1.0).unwrap();
+        }
+
+        let gain = calibration.finish(&mut driver, 360.0).unwrap();
+        assert!(libm::fabsf(gain - 360.0 / 350.0) < 1e-4);
+        assert_eq!(driver.gain().x, 1.0);
+        assert_eq!(driver.gain().z, gain);
+    }
+
+    #[test]
+    fn bias_report_degrades_with_temperature() {
+        let bias = Bias {
+            offset: F32x3::new(1.0, 2.0, 3.0),
+            temperature: 10,
+            samples: 1000,
+            noise: F32x3::new(20.0, 20.0, 20.0),
+        };
+
+        let report = BiasReport::new(
+            bias,
+            0,
+            None,
+            10,
+            F32x3::new(0.00875, 0.00875, 0.00875),
+            0.03,
+        );
+        assert_eq!(report.temperature_delta, 0.0);
+        assert!(report.confidence > 0.9);
+
+        let report = BiasReport::new(
+            bias,
+            0,
+            None,
+            5,
+            F32x3::new(0.00875, 0.00875, 0.00875),
+            0.03,
+        );
+        assert_eq!(report.temperature_delta, 5.0);
+        assert!(report.confidence < 0.1);
+        assert!(report.uncertainty.x > 0.15);
+    }
+
+    #[test]
+    fn bias_report_without_estimate() {
+        let report = BiasReport::new(
+            Bias::default(),
+            0,
+            None,
+            0,
+            F32x3::new(0.00875, 0.00875, 0.00875),
+            0.03,
+        );
+        assert_eq!(report.confidence, 0.0);
+    }
+
+    #[test]
+    fn heading_drift_grows_with_time() {
+        let bias = Bias {
+            offset: F32x3::default(),
+            temperature: 10,
+            samples: 1000,
+            noise: F32x3::new(20.0, 20.0, 20.0),
+        };
+        let report = BiasReport::new(
+            bias,
+            0,
+            None,
+            10,
+            F32x3::new(0.00875, 0.00875, 0.00875),
+            0.03,
+        );
+        let short = report.heading_drift(1.0, 0.03);
+        let long = report.heading_drift(100.0, 0.03);
+        assert!(short > 0.03 && short < 0.04);
+        assert!(long > 10.0 * short);
+
+        let unknown = BiasReport::new(Bias::default(), 0, None, 0, F32x3::default(), 0.03);
+        assert_eq!(unknown.heading_drift(1.0, 0.03), f32::INFINITY);
+    }
+}
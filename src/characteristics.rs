@@ -1,3 +1,6 @@
+use crate::BandwidthExt;
+use l3gd20_registers::{Bandwidth, OutputDataRate, Sensitivity};
+
 /// Scale and noise characteristics of the sensor.
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
@@ -17,10 +20,109 @@ pub struct Characteristics {
     pub zero_rate_level_temp: f32,
 
     /// The frequency-dependent rate noise level in degrees/second.
-    /// The rate noise density is at 0.03 dps/√Hz.
+    /// The rate noise density of 0.03 dps/√Hz over the low-pass cutoff frequency of the
+    /// selected bandwidth.
     pub rate_noise_density: f32,
 }
 
+impl Characteristics {
+    /// Determines the characteristics for the given configuration and raw temperature reading.
+    pub(crate) fn new(
+        fs: Sensitivity,
+        odr: OutputDataRate,
+        bw: Bandwidth,
+        temperature: u8,
+    ) -> Self {
+        Self {
+            full_scale: match fs {
+                Sensitivity::D250 => 250,
+                Sensitivity::D500 => 500,
+                Sensitivity::D2000 => 2000,
+                Sensitivity::D2000_11 => 2000,
+            },
+            sensitivity: sensitivity(fs),
+            zero_rate_noise: match fs {
+                Sensitivity::D250 => 10.0,
+                Sensitivity::D500 => 15.0,
+                Sensitivity::D2000 => 75.0,
+                Sensitivity::D2000_11 => 75.0,
+            },
+            zero_rate_level_temp: zero_rate_level_temp_coefficient(fs) * f32::from(temperature),
+            rate_noise_density: RATE_NOISE_DENSITY * libm::sqrtf(bw.cutoff_hz(odr)),
+        }
+    }
+}
+
+impl Characteristics {
+    /// Enumerates every combination of full scale, output data rate and bandwidth with its
+    /// characteristics, e.g. to search for the configuration meeting a noise requirement.
+    ///
+    /// The alternative `FS = 11` code is skipped, as it matches ±2000 °/s. As no temperature
+    /// is known, [`zero_rate_level_temp`](Self::zero_rate_level_temp) is given per °C.
+    ///
+    /// ```
+    /// use l3gd20_ng::Characteristics;
+    ///
+    /// assert_eq!(Characteristics::all_configs().count(), 48);
+    /// let quietest = Characteristics::all_configs()
+    ///     .filter(|config| config.characteristics.full_scale >= 500)
+    ///     .min_by(|a, b| {
+    ///         let a = a.characteristics.rate_noise_density;
+    ///         a.total_cmp(&b.characteristics.rate_noise_density)
+    ///     })
+    ///     .unwrap();
+    /// assert_eq!(quietest.characteristics.full_scale, 500);
+    /// ```
+    pub fn all_configs() -> impl Iterator<Item = ConfigCharacteristics> {
+        const FULL_SCALES: [Sensitivity; 3] =
+            [Sensitivity::D250, Sensitivity::D500, Sensitivity::D2000];
+        const DATA_RATES: [OutputDataRate; 4] = [
+            OutputDataRate::Hz95,
+            OutputDataRate::Hz190,
+            OutputDataRate::Hz380,
+            OutputDataRate::Hz760,
+        ];
+        const BANDWIDTHS: [Bandwidth; 4] = [
+            Bandwidth::Narrowest,
+            Bandwidth::Narrow,
+            Bandwidth::Medium,
+            Bandwidth::Wide,
+        ];
+
+        FULL_SCALES.into_iter().flat_map(|full_scale| {
+            DATA_RATES.into_iter().flat_map(move |output_data_rate| {
+                BANDWIDTHS
+                    .into_iter()
+                    .map(move |bandwidth| ConfigCharacteristics {
+                        full_scale,
+                        output_data_rate,
+                        bandwidth,
+                        characteristics: Characteristics::new(
+                            full_scale,
+                            output_data_rate,
+                            bandwidth,
+                            1,
+                        ),
+                    })
+            })
+        })
+    }
+}
+
+/// A configuration along with its characteristics, see [`Characteristics::all_configs`].
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ConfigCharacteristics {
+    /// The full scale selection.
+    pub full_scale: Sensitivity,
+    /// The output data rate.
+    pub output_data_rate: OutputDataRate,
+    /// The bandwidth.
+    pub bandwidth: Bandwidth,
+    /// The characteristics of the configuration.
+    pub characteristics: Characteristics,
+}
+
 impl Default for Characteristics {
     fn default() -> Self {
         #[allow(clippy::excessive_precision)]
@@ -33,3 +135,57 @@ impl Default for Characteristics {
         }
     }
 }
+
+/// The rate noise density in degrees/second/√Hz.
+pub(crate) const RATE_NOISE_DENSITY: f32 = 0.03;
+
+/// Determines the sensitivity in degrees/second per LSB for the given full scale selection.
+pub(crate) fn sensitivity(fs: Sensitivity) -> f32 {
+    match fs {
+        Sensitivity::D250 => 8.75 * 0.001,     // mdeg/1000
+        Sensitivity::D500 => 17.5 * 0.001,     // mdeg/1000
+        Sensitivity::D2000 => 70.0 * 0.001,    // mdeg/1000
+        Sensitivity::D2000_11 => 70.0 * 0.001, // mdeg/1000
+    }
+}
+
+/// Determines the zero-rate level change versus temperature in ±degrees/second per °C
+/// for the given full scale selection.
+pub(crate) fn zero_rate_level_temp_coefficient(fs: Sensitivity) -> f32 {
+    match fs {
+        Sensitivity::D250 => 0.03,
+        Sensitivity::D500 => 0.03,
+        Sensitivity::D2000 => 0.04,
+        Sensitivity::D2000_11 => 0.04,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rate_noise_follows_the_cutoff_frequency() {
+        for config in Characteristics::all_configs() {
+            let cutoff_hz = config.bandwidth.cutoff_hz(config.output_data_rate);
+            let noise = config.characteristics.rate_noise_density;
+            assert!((noise * noise / (0.03 * 0.03) - cutoff_hz).abs() < 1e-3);
+        }
+        let narrow = Characteristics::new(
+            Sensitivity::D250,
+            OutputDataRate::Hz380,
+            Bandwidth::Narrow,
+            0,
+        );
+        assert!((narrow.rate_noise_density - 0.15).abs() < 1e-6);
+    }
+
+    #[test]
+    fn both_2000_dps_codes_share_the_zero_rate_level_drift() {
+        for fs in [Sensitivity::D2000, Sensitivity::D2000_11] {
+            let characteristics =
+                Characteristics::new(fs, OutputDataRate::Hz95, Bandwidth::Narrowest, 10);
+            assert!((characteristics.zero_rate_level_temp - 0.4).abs() < 1e-6);
+        }
+    }
+}
@@ -0,0 +1,259 @@
+//! Provides a compact command set for tuning the sensor remotely.
+//!
+//! Devices can expose the gyroscope configuration over a UART or BLE link by passing the
+//! received frames to [`L3GD20::apply_command`]. Each frame holds a single command in
+//! tag-length-value form:
+//!
+//! | Offset | Size     | Field                             |
+//! |--------|----------|-----------------------------------|
+//! | 0      | 1        | Tag                               |
+//! | 1      | 1        | Length of the value, in bytes     |
+//! | 2      | `length` | Value, multi-byte fields little endian |
+//!
+//! | Tag    | Command                      | Value                                   |
+//! |--------|------------------------------|-----------------------------------------|
+//! | `0x01` | [`SET_ODR`]                  | `u16` output data rate in Hz            |
+//! | `0x02` | [`SET_BANDWIDTH`]            | `u8` two-bit `BW` code                  |
+//! | `0x03` | [`SET_FULL_SCALE`]           | `u16` full scale in °/s                 |
+//! | `0x04` | [`SET_THRESHOLD`]            | `u8` axis (0 = X, 1 = Y, 2 = Z), `u16` threshold in LSB |
+//! | `0x05` | [`START_CAPTURE`]            | optional `u8` FIFO watermark, 0 to 31   |
+//! | `0x06` | [`STOP_CAPTURE`]             | none                                    |
+//! | `0x07` | [`GET_SETTINGS`]             | none                                    |
+//!
+//! Without the `interrupts` feature, [`SET_THRESHOLD`] is rejected as an unknown command;
+//! likewise [`START_CAPTURE`] and [`STOP_CAPTURE`] without the `fifo` feature.
+//!
+//! ```
+//! # #[cfg(feature = "sim")] {
+//! use l3gd20_ng::command::{Response, SET_ODR};
+//! # use l3gd20_ng::{sim::Simulator, L3GD20};
+//! # let mut driver = L3GD20::with_transport(Simulator::<()>::new()).unwrap();
+//!
+//! let response = driver.apply_command(&[SET_ODR, 2, 0x7C, 0x01]).unwrap();
+//! assert_eq!(response, Response::Ack);
+//! # }
+//! ```
+
+#[cfg(feature = "interrupts")]
+use crate::common::framing;
+#[cfg(feature = "fifo")]
+use crate::fifo::{FifoMode, MAX_WATERMARK};
+#[cfg(feature = "interrupts")]
+use crate::interrupts::{threshold_bytes, MAX_THRESHOLD_LSB};
+use crate::{Bandwidth, L3GD20Settings, OutputDataRate, Sensitivity, Transport, L3GD20};
+#[cfg(feature = "interrupts")]
+use l3gd20_registers::prelude::SPIRegister;
+#[cfg(feature = "interrupts")]
+use l3gd20_registers::Int1ThresholdRegisterXH;
+
+/// Sets the output data rate.
+pub const SET_ODR: u8 = 0x01;
+
+/// Sets the bandwidth.
+pub const SET_BANDWIDTH: u8 = 0x02;
+
+/// Sets the full scale.
+pub const SET_FULL_SCALE: u8 = 0x03;
+
+/// Sets the INT1 threshold of an axis.
+pub const SET_THRESHOLD: u8 = 0x04;
+
+/// Starts capturing samples into the FIFO in Stream mode.
+pub const START_CAPTURE: u8 = 0x05;
+
+/// Stops capturing samples into the FIFO.
+pub const STOP_CAPTURE: u8 = 0x06;
+
+/// Reads the current settings.
+pub const GET_SETTINGS: u8 = 0x07;
+
+/// The response to a command.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Response {
+    /// The command was applied.
+    Ack,
+    /// The current settings, encoded with [`L3GD20Settings::to_bytes`].
+    Settings([u8; L3GD20Settings::ENCODED_LEN]),
+}
+
+/// An error applying a command.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum CommandError<E> {
+    /// A bus transfer failed.
+    Bus(E),
+    /// The frame is shorter than its length field requires, or has trailing bytes.
+    Malformed,
+    /// The tag is unknown.
+    UnknownCommand(u8),
+    /// The value has the wrong length or is out of range for the command.
+    InvalidValue(u8),
+}
+
+impl<E> From<E> for CommandError<E> {
+    fn from(error: E) -> Self {
+        CommandError::Bus(error)
+    }
+}
+
+impl<T, E> L3GD20<T>
+where
+    T: Transport<Error = E>,
+{
+    /// Applies a command frame.
+    ///
+    /// The value is validated before the device is accessed, so that a rejected command
+    /// leaves the configuration untouched.
+    pub fn apply_command(&mut self, frame: &[u8]) -> Result<Response, CommandError<E>> {
+        let (&tag, rest) = frame.split_first().ok_or(CommandError::Malformed)?;
+        let (&len, value) = rest.split_first().ok_or(CommandError::Malformed)?;
+        if value.len() != usize::from(len) {
+            return Err(CommandError::Malformed);
+        }
+        let invalid = CommandError::InvalidValue(tag);
+
+        match (tag, value) {
+            (SET_ODR, &[lo, hi]) => {
+                let odr =
+                    OutputDataRate::try_from(u16::from_le_bytes([lo, hi])).map_err(|_| invalid)?;
+                self.set_odr(odr)?;
+            }
+            (SET_BANDWIDTH, &[code]) => {
+                let bandwidth = Bandwidth::try_from(u16::from(code)).map_err(|_| invalid)?;
+                self.set_bandwidth(bandwidth)?;
+            }
+            (SET_FULL_SCALE, &[lo, hi]) => {
+                let full_scale =
+                    Sensitivity::try_from(u16::from_le_bytes([lo, hi])).map_err(|_| invalid)?;
+                self.set_sensitivity(full_scale)?;
+            }
+            #[cfg(feature = "interrupts")]
+            (SET_THRESHOLD, &[axis, lo, hi]) => {
+                let threshold = u16::from_le_bytes([lo, hi]);
+                if axis > 2 || threshold > MAX_THRESHOLD_LSB {
+                    return Err(invalid);
+                }
+                let (high, low) = threshold_bytes(threshold);
+                let address = *Int1ThresholdRegisterXH::REGISTER_ADDRESS + 2 * axis;
+                let mut buffer = [framing::write_multi_cmd(address), high, low];
+                self.transfer(&mut buffer)?;
+            }
+            #[cfg(not(feature = "interrupts"))]
+            (SET_THRESHOLD, _) => return Err(CommandError::UnknownCommand(tag)),
+            #[cfg(feature = "fifo")]
+            (START_CAPTURE, &[]) => self.enable_fifo(FifoMode::Stream)?,
+            #[cfg(feature = "fifo")]
+            (START_CAPTURE, &[watermark]) => {
+                if watermark > MAX_WATERMARK {
+                    return Err(invalid);
+                }
+                self.set_fifo_watermark(watermark)?;
+                self.enable_fifo(FifoMode::Stream)?;
+            }
+            #[cfg(feature = "fifo")]
+            (STOP_CAPTURE, &[]) => self.disable_fifo()?,
+            #[cfg(not(feature = "fifo"))]
+            (START_CAPTURE | STOP_CAPTURE, _) => return Err(CommandError::UnknownCommand(tag)),
+            (GET_SETTINGS, &[]) => return Ok(Response::Settings(self.settings()?.to_bytes())),
+            (SET_ODR..=GET_SETTINGS, _) => return Err(invalid),
+            _ => return Err(CommandError::UnknownCommand(tag)),
+        }
+        Ok(Response::Ack)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::MockError;
+    use crate::sim::Simulator;
+
+    #[test]
+    #[cfg(all(feature = "fifo", feature = "interrupts"))]
+    fn commands_are_applied() {
+        let mut driver = L3GD20::with_transport(Simulator::<MockError>::new()).unwrap();
+
+        assert_eq!(
+            driver.apply_command(&[SET_ODR, 2, 0xF8, 0x02]),
+            Ok(Response::Ack)
+        );
+        assert_eq!(
+            driver.apply_command(&[SET_FULL_SCALE, 2, 0xD0, 0x07]),
+            Ok(Response::Ack)
+        );
+        assert_eq!(
+            driver.apply_command(&[SET_THRESHOLD, 3, 1, 0x34, 0x12]),
+            Ok(Response::Ack)
+        );
+        assert_eq!(driver.transport.registers()[0x34..0x36], [0x12, 0x34]);
+
+        let Ok(Response::Settings(bytes)) = driver.apply_command(&[GET_SETTINGS, 0]) else {
+            panic!("expected the settings");
+        };
+        let settings = L3GD20Settings::from_bytes(&bytes).unwrap();
+        assert_eq!(settings.output_data_rate, OutputDataRate::Hz760.into());
+        assert_eq!(settings.full_scale, Sensitivity::D2000.into());
+
+        assert_eq!(
+            driver.apply_command(&[START_CAPTURE, 1, 16]),
+            Ok(Response::Ack)
+        );
+        assert_eq!(driver.transport.registers()[0x2E], 0b0101_0000);
+        assert_eq!(driver.apply_command(&[STOP_CAPTURE, 0]), Ok(Response::Ack));
+        assert_eq!(driver.transport.registers()[0x2E], 0b0001_0000);
+    }
+
+    #[test]
+    #[cfg(all(feature = "fifo", feature = "interrupts"))]
+    fn invalid_frames_are_rejected() {
+        let mut driver = L3GD20::with_transport(Simulator::<MockError>::new()).unwrap();
+        let transfers = driver.transport.transfers();
+
+        assert_eq!(driver.apply_command(&[]), Err(CommandError::Malformed));
+        assert_eq!(
+            driver.apply_command(&[SET_ODR, 2, 0x5F]),
+            Err(CommandError::Malformed)
+        );
+        assert_eq!(
+            driver.apply_command(&[SET_ODR, 2, 100, 0]),
+            Err(CommandError::InvalidValue(SET_ODR))
+        );
+        assert_eq!(
+            driver.apply_command(&[SET_THRESHOLD, 3, 3, 0, 0]),
+            Err(CommandError::InvalidValue(SET_THRESHOLD))
+        );
+        assert_eq!(
+            driver.apply_command(&[START_CAPTURE, 1, MAX_WATERMARK + 1]),
+            Err(CommandError::InvalidValue(START_CAPTURE))
+        );
+        assert_eq!(
+            driver.apply_command(&[STOP_CAPTURE, 1, 0]),
+            Err(CommandError::InvalidValue(STOP_CAPTURE))
+        );
+        assert_eq!(
+            driver.apply_command(&[0x42, 0]),
+            Err(CommandError::UnknownCommand(0x42))
+        );
+        assert_eq!(driver.transport.transfers(), transfers);
+    }
+
+    #[test]
+    #[cfg(not(all(feature = "fifo", feature = "interrupts")))]
+    fn commands_of_disabled_subsystems_are_unknown() {
+        let mut driver = L3GD20::with_transport(Simulator::<MockError>::new()).unwrap();
+        let transfers = driver.transport.transfers();
+
+        #[cfg(not(feature = "interrupts"))]
+        assert_eq!(
+            driver.apply_command(&[SET_THRESHOLD, 3, 1, 0x34, 0x12]),
+            Err(CommandError::UnknownCommand(SET_THRESHOLD))
+        );
+        #[cfg(not(feature = "fifo"))]
+        assert_eq!(
+            driver.apply_command(&[START_CAPTURE, 0]),
+            Err(CommandError::UnknownCommand(START_CAPTURE))
+        );
+        assert_eq!(driver.transport.transfers(), transfers);
+    }
+}
@@ -0,0 +1,11 @@
+//! The I/O-free core of the driver.
+//!
+//! This module contains the register framing, decoding and compensation logic shared by
+//! the I/O front-ends, such as the [`blocking`](crate::blocking) driver. None of it
+//! performs any bus access, so it can be unit-tested on the host without a HAL.
+
+mod compensation;
+pub(crate) mod decode;
+pub mod framing;
+
+pub use compensation::Compensation;
@@ -0,0 +1,72 @@
+//! Provides the compensation of raw readings.
+
+#[cfg(feature = "fixed")]
+use crate::CorrectionMatrixQ14;
+use crate::{characteristics, Bias, CorrectionMatrix, F32x3, I16x3};
+use l3gd20_registers::Sensitivity;
+
+/// The corrections applied to raw readings in the compensated read path.
+///
+/// Readings are compensated by removing the [`Bias`], scaling by the sensitivity,
+/// applying the per-axis gain and finally the cross-axis [`CorrectionMatrix`].
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Compensation {
+    /// The zero-rate level to remove.
+    pub bias: Bias,
+    /// The per-axis gain correction.
+    pub gain: F32x3,
+    /// The per-axis sensitivity in degrees/second per LSB to use instead of the datasheet values.
+    pub sensitivity_override: Option<F32x3>,
+    /// The cross-axis correction matrix.
+    pub correction: CorrectionMatrix,
+    /// The fixed-point cross-axis correction matrix.
+    #[cfg(feature = "fixed")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "fixed")))]
+    pub correction_q14: CorrectionMatrixQ14,
+}
+
+impl Compensation {
+    /// Gets the per-axis sensitivity in degrees/second per LSB for the given full scale
+    /// selection, which is the override if one is set.
+    #[must_use]
+    pub fn sensitivity(&self, fs: Sensitivity) -> F32x3 {
+        self.sensitivity_override.unwrap_or_else(|| {
+            let sensitivity = characteristics::sensitivity(fs);
+            F32x3::new(sensitivity, sensitivity, sensitivity)
+        })
+    }
+
+    /// Compensates a raw reading given the per-axis sensitivity in degrees/second per LSB.
+    #[must_use]
+    pub fn apply(&self, raw: I16x3, sensitivity: F32x3) -> F32x3 {
+        let offset = self.bias.offset;
+        let gain = self.gain;
+        self.correction.apply(F32x3::new(
+            (f32::from(raw.x) - offset.x) * sensitivity.x * gain.x,
+            (f32::from(raw.y) - offset.y) * sensitivity.y * gain.y,
+            (f32::from(raw.z) - offset.z) * sensitivity.z * gain.z,
+        ))
+    }
+
+    /// Applies the fixed-point cross-axis correction to a raw reading.
+    #[cfg(feature = "fixed")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "fixed")))]
+    #[must_use]
+    pub fn apply_fixed(&self, raw: I16x3) -> I16x3 {
+        self.correction_q14.apply(raw)
+    }
+}
+
+impl Default for Compensation {
+    fn default() -> Self {
+        Self {
+            bias: Bias::default(),
+            gain: F32x3::new(1.0, 1.0, 1.0),
+            sensitivity_override: None,
+            correction: CorrectionMatrix::IDENTITY,
+            #[cfg(feature = "fixed")]
+            correction_q14: CorrectionMatrixQ14::IDENTITY,
+        }
+    }
+}
@@ -0,0 +1,53 @@
+//! Provides decoding of register contents.
+
+use crate::{I16x3, SensorData};
+use l3gd20_registers::*;
+
+/// Decodes the axis output registers `OUT_X_L` (28h) to `OUT_Z_H` (2Dh).
+pub(crate) fn xyz(buffer: &[u8; 6]) -> I16x3 {
+    // The registers come in the order XL (0x28), XH, YL, YH, ZL, ZH (0x2D)
+    let xlo = OutXLow::from_bits(buffer[0]);
+    let xhi = OutXHigh::from_bits(buffer[1]);
+    let ylo = OutYLow::from_bits(buffer[2]);
+    let yhi = OutYHigh::from_bits(buffer[3]);
+    let zlo = OutZLow::from_bits(buffer[4]);
+    let zhi = OutZHigh::from_bits(buffer[5]);
+
+    let x = xhi + xlo;
+    let y = yhi + ylo;
+    let z = zhi + zlo;
+
+    I16x3::new(x, y, z)
+}
+
+/// Swaps the bytes of each axis reading, converting the big endian output data
+/// selected by `CTRL_REG4` to the little endian layout expected by the decoders.
+pub(crate) fn swap_axis_bytes(buffer: &mut [u8]) {
+    for pair in buffer.chunks_exact_mut(2) {
+        pair.swap(0, 1);
+    }
+}
+
+/// Decodes the output registers `OUT_TEMP` (26h) to `OUT_Z_H` (2Dh).
+pub(crate) fn data(buffer: &[u8; 8]) -> SensorData {
+    // The registers come in the order Temperature (0x26), Status (0x27), XL, XH, YL, YH, ZL, ZH (0x2D)
+    let temp = TemperatureRegister::from_bits(buffer[0]);
+    let status = StatusRegister::from_bits(buffer[1]);
+    let xyz = xyz(&[
+        buffer[2], buffer[3], buffer[4], buffer[5], buffer[6], buffer[7],
+    ]);
+    SensorData::new(temp.temp(), xyz.x, xyz.y, xyz.z, status)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn data_is_little_endian() {
+        let data = data(&[0x19, 0b0000_1111, 0x01, 0x02, 0xFF, 0xFF, 0x00, 0x80]);
+        assert_eq!(data.temperature, 0x19);
+        assert!(data.x.fresh());
+        assert_eq!(I16x3::from(data), I16x3::new(0x0201, -1, i16::MIN));
+    }
+}
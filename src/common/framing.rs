@@ -0,0 +1,84 @@
+//! Provides the command framing of register accesses.
+//!
+//! Every SPI transaction starts with a command byte holding the read/write flag, the
+//! multi-address (auto-increment) flag and the register address. These helpers allow
+//! building command bytes for raw transfers outside the driver, e.g. for DMA descriptors.
+//!
+//! ```
+//! use l3gd20_ng::framing;
+//!
+//! // Read OUT_X_L (28h) through OUT_Z_H (2Dh) in one burst.
+//! let command = framing::read_multi_cmd(0x28);
+//! assert_eq!(command, 0xE8);
+//! assert!(framing::is_read(command) && framing::is_multi(command));
+//! assert_eq!(framing::address(command), 0x28);
+//! ```
+
+/// Bit flag for a read command.
+pub const READ: u8 = 0b1000_0000;
+
+/// Bit flag for a write command.
+pub const WRITE: u8 = 0b0000_0000;
+
+/// Bit flag for a multi-address command; auto-increments addresses after each transfer.
+pub const MULTI: u8 = 0b0100_0000;
+
+/// Bit flag for a single-address command.
+pub const SINGLE: u8 = 0b0000_0000;
+
+/// Mask for register addresses.
+pub const REG_ADDR_MASK: u8 = 0b0011_1111;
+
+/// Creates a read command for a given address. Does not auto-increment the address afterward.
+pub const fn read_single_cmd(address: u8) -> u8 {
+    READ | SINGLE | (address & REG_ADDR_MASK)
+}
+
+/// Creates a read command for a given address. Auto-increments the address afterward.
+pub const fn read_multi_cmd(address: u8) -> u8 {
+    READ | MULTI | (address & REG_ADDR_MASK)
+}
+
+/// Creates a write command for a given address. Does not auto-increment the address afterward.
+pub const fn write_single_cmd(address: u8) -> u8 {
+    WRITE | SINGLE | (address & REG_ADDR_MASK)
+}
+
+/// Creates a write command for a given address. Auto-increments the address afterward.
+pub const fn write_multi_cmd(address: u8) -> u8 {
+    WRITE | MULTI | (address & REG_ADDR_MASK)
+}
+
+/// Determines whether the command is a read command.
+pub const fn is_read(command: u8) -> bool {
+    command & READ != 0
+}
+
+/// Determines whether the command auto-increments the address.
+pub const fn is_multi(command: u8) -> bool {
+    command & MULTI != 0
+}
+
+/// Gets the register address of the command.
+pub const fn address(command: u8) -> u8 {
+    command & REG_ADDR_MASK
+}
+
+/// Replaces the register address of the command.
+pub const fn with_address(command: u8, address: u8) -> u8 {
+    (command & !REG_ADDR_MASK) | (address & REG_ADDR_MASK)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn commands_are_framed() {
+        assert_eq!(read_single_cmd(0x0F), 0x8F);
+        assert_eq!(write_single_cmd(0x20), 0x20);
+        assert_eq!(write_multi_cmd(0x30), 0x70);
+        assert!(!is_read(write_multi_cmd(0x30)));
+        assert_eq!(with_address(read_multi_cmd(0x26), 0x28), 0xE8);
+    }
+}
@@ -0,0 +1,402 @@
+//! Provides a builder for the complete sensor configuration.
+//!
+//! [`L3gd20Config`] describes the contents of `CTRL_REG1` through `CTRL_REG5` and the FIFO
+//! control register. [`L3GD20::apply_config`] writes the control registers in a single
+//! auto-incrementing transaction, rather than register by register as [`L3GD20::reset`] does.
+//!
+//! The configuration can declare the SPI clock frequency the bus runs at. Exceeding the
+//! [maximum](MAX_SPI_CLOCK_HZ) of the device silently corrupts transfers, so that
+//! [`L3GD20::apply_config`] refuses such a configuration.
+
+use crate::common::framing;
+use crate::settings::ControlBlock;
+use crate::{Error, L3GD20Settings, Transport, L3GD20};
+use l3gd20_registers::prelude::{SPIRegister, ToBits};
+use l3gd20_registers::*;
+
+/// The largest FIFO watermark level.
+const MAX_WATERMARK: u8 = 31;
+
+/// The highest SPI clock frequency supported by the device, in Hertz.
+pub const MAX_SPI_CLOCK_HZ: u32 = 10_000_000;
+
+/// The complete configuration of the sensor.
+///
+/// The [`Default`] configuration matches the one written by [`L3GD20::reset`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct L3gd20Config {
+    output_data_rate: OutputDataRate,
+    bandwidth: Bandwidth,
+    full_scale: Sensitivity,
+    powered_up: bool,
+    axes: [bool; 3],
+    block_data_update: bool,
+    big_endian: bool,
+    highpass_mode: HighpassFilterMode,
+    highpass_cutoff: u8,
+    highpass_enabled: bool,
+    int1_generator: bool,
+    int1_boot: bool,
+    int1_active_low: bool,
+    open_drain: bool,
+    int2_data_ready: bool,
+    int2_watermark: bool,
+    int2_overrun: bool,
+    int2_empty: bool,
+    fifo_mode: FifoMode,
+    fifo_watermark: u8,
+    spi_clock_hz: Option<u32>,
+}
+
+impl Default for L3gd20Config {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl L3gd20Config {
+    /// Initializes the configuration written by [`L3GD20::reset`].
+    pub const fn new() -> Self {
+        Self {
+            output_data_rate: OutputDataRate::Hz95,
+            bandwidth: Bandwidth::Narrowest,
+            full_scale: Sensitivity::D250,
+            powered_up: true,
+            axes: [true; 3],
+            block_data_update: false,
+            big_endian: false,
+            highpass_mode: HighpassFilterMode::NormalModeResetFilter,
+            highpass_cutoff: 0,
+            highpass_enabled: false,
+            int1_generator: false,
+            int1_boot: false,
+            int1_active_low: false,
+            open_drain: false,
+            int2_data_ready: false,
+            int2_watermark: false,
+            int2_overrun: false,
+            int2_empty: false,
+            fifo_mode: FifoMode::Bypass,
+            fifo_watermark: 0,
+            spi_clock_hz: None,
+        }
+    }
+
+    /// Initializes the configuration written by [`L3GD20::reset_for_fusion`].
+    ///
+    /// Block data update keeps the high and low bytes of a reading together, and 190 Hz
+    /// with the medium bandwidth of 50 Hz at ±500 °/s suits attitude estimation.
+    pub const fn fusion() -> Self {
+        let mut config = Self::new();
+        config.output_data_rate = OutputDataRate::Hz190;
+        config.bandwidth = Bandwidth::Medium;
+        config.full_scale = Sensitivity::D500;
+        config.block_data_update = true;
+        config
+    }
+
+    /// Sets the output data rate.
+    pub fn with_output_data_rate(mut self, rate: impl Into<OutputDataRate>) -> Self {
+        self.output_data_rate = rate.into();
+        self
+    }
+
+    /// Sets the bandwidth.
+    pub fn with_bandwidth(mut self, bandwidth: impl Into<Bandwidth>) -> Self {
+        self.bandwidth = bandwidth.into();
+        self
+    }
+
+    /// Sets the full scale selection.
+    pub fn with_full_scale(mut self, full_scale: impl Into<Sensitivity>) -> Self {
+        self.full_scale = full_scale.into();
+        self
+    }
+
+    /// Selects whether the sensor is powered up, or powered down.
+    pub const fn with_power_up(mut self, powered_up: bool) -> Self {
+        self.powered_up = powered_up;
+        self
+    }
+
+    /// Enables or disables the X, Y and Z axes.
+    pub const fn with_axes(mut self, x: bool, y: bool, z: bool) -> Self {
+        self.axes = [x, y, z];
+        self
+    }
+
+    /// Enables or disables block data update.
+    pub const fn with_block_data_update(mut self, enabled: bool) -> Self {
+        self.block_data_update = enabled;
+        self
+    }
+
+    /// Selects big endian output data instead of little endian.
+    ///
+    /// The driver swaps the bytes back when decoding readings, so this only affects the
+    /// raw register contents.
+    pub const fn with_big_endian(mut self, big_endian: bool) -> Self {
+        self.big_endian = big_endian;
+        self
+    }
+
+    /// Sets the high-pass filter mode and cutoff frequency configuration (`HPCF`, four bits).
+    pub fn with_highpass(mut self, mode: impl Into<HighpassFilterMode>, cutoff: u8) -> Self {
+        self.highpass_mode = mode.into();
+        self.highpass_cutoff = cutoff & 0b1111;
+        self
+    }
+
+    /// Enables or disables the high-pass filter.
+    pub const fn with_highpass_enabled(mut self, enabled: bool) -> Self {
+        self.highpass_enabled = enabled;
+        self
+    }
+
+    /// Routes the interrupt generator and the boot status to the INT1 pin.
+    pub const fn with_int1_routing(mut self, generator: bool, boot: bool) -> Self {
+        self.int1_generator = generator;
+        self.int1_boot = boot;
+        self
+    }
+
+    /// Routes data ready, FIFO watermark, FIFO overrun and FIFO empty to the DRDY/INT2 pin.
+    pub const fn with_int2_routing(
+        mut self,
+        data_ready: bool,
+        watermark: bool,
+        overrun: bool,
+        empty: bool,
+    ) -> Self {
+        self.int2_data_ready = data_ready;
+        self.int2_watermark = watermark;
+        self.int2_overrun = overrun;
+        self.int2_empty = empty;
+        self
+    }
+
+    /// Selects an active low INT1 pin, and open drain instead of push-pull outputs.
+    pub const fn with_pin_mode(mut self, int1_active_low: bool, open_drain: bool) -> Self {
+        self.int1_active_low = int1_active_low;
+        self.open_drain = open_drain;
+        self
+    }
+
+    /// Sets the FIFO mode and watermark level, clamped to 31.
+    ///
+    /// Any mode other than [`FifoMode::Bypass`] enables the FIFO.
+    pub fn with_fifo(mut self, mode: FifoMode, watermark: u8) -> Self {
+        self.fifo_mode = mode;
+        self.fifo_watermark = watermark.min(MAX_WATERMARK);
+        self
+    }
+
+    /// Declares the SPI clock frequency of the bus, in Hertz.
+    ///
+    /// The frequency is checked against [`MAX_SPI_CLOCK_HZ`] when the configuration is
+    /// applied, and against the bus load of [`L3GD20::with_high_rate`].
+    pub const fn with_spi_clock_hz(mut self, hz: u32) -> Self {
+        self.spi_clock_hz = Some(hz);
+        self
+    }
+
+    /// Gets the declared SPI clock frequency, in Hertz.
+    pub const fn spi_clock_hz(&self) -> Option<u32> {
+        self.spi_clock_hz
+    }
+
+    /// Gets the settings captured by this configuration.
+    pub fn settings(&self) -> L3GD20Settings {
+        L3GD20Settings {
+            output_data_rate: self.output_data_rate,
+            bandwidth: self.bandwidth,
+            full_scale: self.full_scale,
+            highpass_mode: self.highpass_mode,
+            highpass_cutoff: self.highpass_cutoff,
+            highpass_enabled: self.highpass_enabled,
+            block_data_update: self.block_data_update,
+        }
+    }
+
+    /// Encodes `CTRL_REG1` through `CTRL_REG5`.
+    fn control_block(&self) -> ControlBlock {
+        let [x, y, z] = self.axes;
+        let ctrl1 = ControlRegister1::default()
+            .with_power_up(self.powered_up)
+            .with_x_enable(x)
+            .with_y_enable(y)
+            .with_z_enable(z)
+            .with_output_data_rate(self.output_data_rate)
+            .with_bandwidth(self.bandwidth);
+        let ctrl2 = ControlRegister2::default()
+            .with_hpm(self.highpass_mode)
+            .with_hpcf(self.highpass_cutoff);
+        let ctrl3 = ControlRegister3::default()
+            .with_i1int1(self.int1_generator)
+            .with_i1boot(self.int1_boot)
+            .with_int1_low(self.int1_active_low)
+            .with_open_drain(self.open_drain)
+            .with_i2drdy(self.int2_data_ready)
+            .with_i2wtm(self.int2_watermark)
+            .with_i2orun(self.int2_overrun)
+            .with_i2empty(self.int2_empty);
+        let ctrl4 = ControlRegister4::default()
+            .with_block_data_update(self.block_data_update)
+            .with_big_endian(self.big_endian)
+            .with_full_scale(self.full_scale);
+        let ctrl5 = ControlRegister5::default()
+            .with_fifo_enable(self.fifo_mode != FifoMode::Bypass)
+            .with_hpen(self.highpass_enabled);
+        [
+            ctrl1.to_bits(),
+            ctrl2.to_bits(),
+            ctrl3.to_bits(),
+            ctrl4.to_bits(),
+            ctrl5.to_bits(),
+        ]
+    }
+
+    /// Encodes the FIFO control register.
+    fn fifo_control(&self) -> u8 {
+        FifoControlRegister::default()
+            .with_fifo_mode(self.fifo_mode)
+            .with_watermark(self.fifo_watermark)
+            .to_bits()
+    }
+}
+
+impl<T, E> L3GD20<T>
+where
+    T: Transport<Error = E>,
+{
+    /// Applies the configuration.
+    ///
+    /// `CTRL_REG1` through `CTRL_REG5` are written in a single auto-incrementing
+    /// transaction. The FIFO control register does not follow them in the address space
+    /// and is written in a second transaction.
+    ///
+    /// Any configuration sequence left pending by a failed write is discarded.
+    ///
+    /// Fails with [`Error::SpiClockTooHigh`] without writing anything if the declared SPI
+    /// clock exceeds [`MAX_SPI_CLOCK_HZ`].
+    pub fn apply_config(&mut self, config: &L3gd20Config) -> Result<(), Error<E>> {
+        if let Some(hz) = config.spi_clock_hz.filter(|&hz| hz > MAX_SPI_CLOCK_HZ) {
+            return Err(Error::SpiClockTooHigh { hz });
+        }
+
+        self.pending_config = None;
+        let block = config.control_block();
+        self.write_control_range(&block, 0, block.len() - 1)
+            .map_err(Error::Bus)?;
+
+        let mut buffer = [
+            framing::write_single_cmd(*FifoControlRegister::REGISTER_ADDRESS),
+            config.fifo_control(),
+        ];
+        self.transfer(&mut buffer).map_err(Error::Bus)?;
+
+        self.expected_settings = config.settings();
+        self.spi_clock_hz = config.spi_clock_hz;
+        Ok(())
+    }
+
+    /// Gets the SPI clock frequency declared by the configuration last applied, in Hertz.
+    pub fn spi_clock_hz(&self) -> Option<u32> {
+        self.spi_clock_hz
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::MockError;
+    use crate::sim::Simulator;
+    use crate::I16x3;
+
+    #[test]
+    fn default_config_matches_reset() {
+        let mut driver = L3GD20::with_transport(Simulator::<MockError>::new()).unwrap();
+        let reset = driver.transport.registers()[0x20..=0x24].to_vec();
+        driver.transport.registers_mut()[0x20..=0x24].fill(0xFF);
+
+        driver.apply_config(&L3gd20Config::default()).unwrap();
+        assert_eq!(driver.transport.registers()[0x20..=0x24], reset[..]);
+    }
+
+    #[test]
+    fn control_registers_are_written_in_one_transaction() {
+        let mut driver = L3GD20::with_transport(Simulator::<MockError>::new()).unwrap();
+        let config = L3gd20Config::new()
+            .with_output_data_rate(OutputDataRate::Hz760)
+            .with_full_scale(Sensitivity::D2000)
+            .with_block_data_update(true)
+            .with_highpass(HighpassFilterMode::NormalMode, 0b0011)
+            .with_highpass_enabled(true)
+            .with_int2_routing(false, true, false, false)
+            .with_fifo(FifoMode::Stream, 40);
+
+        let transfers = driver.transport.transfers();
+        driver.apply_config(&config).unwrap();
+        assert_eq!(driver.transport.transfers(), transfers + 2);
+
+        let registers = driver.transport.registers();
+        assert_eq!(registers[0x20] >> 6, 0b11);
+        assert_eq!(registers[0x21], 0b0010_0011);
+        assert_eq!(registers[0x22], 0b0000_0100);
+        assert_eq!(registers[0x23], 0b1010_0000);
+        assert_eq!(registers[0x24], 0b0101_0000);
+        assert_eq!(registers[0x2E], 0b0101_1111);
+        assert_eq!(driver.expected_settings, config.settings());
+        assert!(driver.block_data_update);
+    }
+
+    #[test]
+    fn big_endian_readings_are_decoded() {
+        let mut driver = L3GD20::with_transport(Simulator::<MockError>::new()).unwrap();
+        driver
+            .apply_config(&L3gd20Config::new().with_big_endian(true))
+            .unwrap();
+
+        let registers = driver.transport.registers_mut();
+        registers[0x28..0x2E].copy_from_slice(&[0x12, 0x34, 0xFF, 0xFE, 0x00, 0x01]);
+        assert_eq!(driver.xyz_raw().unwrap(), I16x3::new(0x1234, -2, 1));
+    }
+
+    #[test]
+    fn fusion_preset_matches_its_reset() {
+        let mut driver = L3GD20::with_transport(Simulator::<MockError>::new()).unwrap();
+        driver.reset_for_fusion().unwrap();
+        let reset = *driver.transport.registers();
+        assert_eq!(reset[0x20], 0b0110_1111);
+        assert_eq!(reset[0x23], 0b1001_0000);
+        assert!(driver.block_data_update);
+        assert_eq!(driver.full_scale(), Sensitivity::D500);
+
+        let mut driver = L3GD20::with_transport(Simulator::<MockError>::new()).unwrap();
+        driver.apply_config(&L3gd20Config::fusion()).unwrap();
+        assert_eq!(driver.transport.registers()[0x20..0x25], reset[0x20..0x25]);
+        assert_eq!(driver.settings(), Ok(L3gd20Config::fusion().settings()));
+    }
+
+    #[test]
+    fn spi_clock_above_the_maximum_is_refused() {
+        let mut driver = L3GD20::with_transport(Simulator::<MockError>::new()).unwrap();
+        let transfers = driver.transport.transfers();
+        let config = L3gd20Config::new()
+            .with_output_data_rate(OutputDataRate::Hz760)
+            .with_spi_clock_hz(12_000_000);
+
+        assert_eq!(
+            driver.apply_config(&config),
+            Err(Error::SpiClockTooHigh { hz: 12_000_000 })
+        );
+        assert_eq!(driver.transport.transfers(), transfers);
+        assert_eq!(driver.spi_clock_hz(), None);
+
+        driver
+            .apply_config(&config.with_spi_clock_hz(MAX_SPI_CLOCK_HZ))
+            .unwrap();
+        assert_eq!(driver.spi_clock_hz(), Some(MAX_SPI_CLOCK_HZ));
+    }
+}
@@ -0,0 +1,50 @@
+//! Provides numeric conversions of the register enums.
+//!
+//! The traits are implemented as extensions of the [`l3gd20_registers`] enums, so that
+//! user code does not need to match on the enums to recover the numbers they stand for.
+
+use l3gd20_registers::{Bandwidth, OutputDataRate};
+
+/// Degrees to radians.
+pub(crate) const DEG_TO_RAD: f32 = core::f32::consts::PI / 180.0;
+
+/// Numeric conversions of [`OutputDataRate`].
+pub trait OutputDataRateExt {
+    /// Gets the output data rate in Hertz.
+    fn hz(&self) -> u16;
+}
+
+impl OutputDataRateExt for OutputDataRate {
+    fn hz(&self) -> u16 {
+        match self {
+            OutputDataRate::Hz95 => 95,
+            OutputDataRate::Hz190 => 190,
+            OutputDataRate::Hz380 => 380,
+            OutputDataRate::Hz760 => 760,
+        }
+    }
+}
+
+/// Numeric conversions of [`Bandwidth`].
+pub trait BandwidthExt {
+    /// Gets the low-pass cutoff frequency in Hertz at the given output data rate.
+    fn cutoff_hz(&self, odr: OutputDataRate) -> f32;
+}
+
+impl BandwidthExt for Bandwidth {
+    fn cutoff_hz(&self, odr: OutputDataRate) -> f32 {
+        self.hz_at(odr)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn numbers_match_the_datasheet() {
+        assert_eq!(OutputDataRate::Hz380.hz(), 380);
+        assert_eq!(Bandwidth::Narrowest.cutoff_hz(OutputDataRate::Hz95), 12.5);
+        assert_eq!(Bandwidth::Wide.cutoff_hz(OutputDataRate::Hz760), 100.0);
+    }
+}
@@ -0,0 +1,138 @@
+//! Provides cross-axis sensitivity and misalignment correction.
+
+use crate::F32x3;
+#[cfg(feature = "fixed")]
+use crate::I16x3;
+
+/// A 3×3 correction matrix applied to scaled readings.
+///
+/// The matrix compensates for cross-axis sensitivity (specified at ±2% for the L3GD20)
+/// and for the sensor being mounted slightly misaligned with the board axes.
+/// The default is the identity matrix, i.e. no correction.
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct CorrectionMatrix {
+    /// The matrix elements in row-major order.
+    pub elements: [f32; 9],
+}
+
+impl CorrectionMatrix {
+    /// The identity matrix.
+    pub const IDENTITY: Self = Self::new([1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0]);
+
+    /// Creates a correction matrix from its elements in row-major order.
+    #[must_use]
+    pub const fn new(elements: [f32; 9]) -> Self {
+        Self { elements }
+    }
+
+    /// Applies the correction to a vector.
+    #[must_use]
+    pub fn apply(&self, value: F32x3) -> F32x3 {
+        let m = &self.elements;
+        F32x3::new(
+            m[0] * value.x + m[1] * value.y + m[2] * value.z,
+            m[3] * value.x + m[4] * value.y + m[5] * value.z,
+            m[6] * value.x + m[7] * value.y + m[8] * value.z,
+        )
+    }
+}
+
+impl Default for CorrectionMatrix {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+/// A 3×3 correction matrix in Q2.14 fixed-point format, for targets without an FPU.
+///
+/// An element value of `16384` represents `1.0`; the representable range is `[-2, 2)`.
+#[cfg(feature = "fixed")]
+#[cfg_attr(docsrs, doc(cfg(feature = "fixed")))]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct CorrectionMatrixQ14 {
+    /// The matrix elements in row-major order.
+    pub elements: [i16; 9],
+}
+
+#[cfg(feature = "fixed")]
+impl CorrectionMatrixQ14 {
+    /// The value representing `1.0`.
+    pub const ONE: i16 = 1 << 14;
+
+    /// The identity matrix.
+    pub const IDENTITY: Self = Self::new([Self::ONE, 0, 0, 0, Self::ONE, 0, 0, 0, Self::ONE]);
+
+    /// Creates a correction matrix from its elements in row-major order.
+    #[must_use]
+    pub const fn new(elements: [i16; 9]) -> Self {
+        Self { elements }
+    }
+
+    /// Applies the correction to a raw reading, saturating on overflow.
+    #[must_use]
+    pub fn apply(&self, value: I16x3) -> I16x3 {
+        let m = &self.elements;
+        let row = |a: i16, b: i16, c: i16| {
+            let sum = i32::from(a) * i32::from(value.x)
+                + i32::from(b) * i32::from(value.y)
+                + i32::from(c) * i32::from(value.z);
+            let sum = (sum + (1 << 13)) >> 14;
+            sum.clamp(i32::from(i16::MIN), i32::from(i16::MAX)) as i16
+        };
+        I16x3::new(
+            row(m[0], m[1], m[2]),
+            row(m[3], m[4], m[5]),
+            row(m[6], m[7], m[8]),
+        )
+    }
+}
+
+#[cfg(feature = "fixed")]
+impl Default for CorrectionMatrixQ14 {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+#[cfg(feature = "fixed")]
+impl From<&CorrectionMatrix> for CorrectionMatrixQ14 {
+    fn from(value: &CorrectionMatrix) -> Self {
+        let mut elements = [0; 9];
+        for (q, f) in elements.iter_mut().zip(value.elements.iter()) {
+            let scaled = *f * f32::from(Self::ONE);
+            let rounded = if scaled < 0.0 {
+                scaled - 0.5
+            } else {
+                scaled + 0.5
+            };
+            *q = rounded.clamp(f32::from(i16::MIN), f32::from(i16::MAX)) as i16;
+        }
+        Self { elements }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn identity_is_neutral() {
+        let value = F32x3::new(1.0, -2.0, 3.0);
+        assert_eq!(CorrectionMatrix::default().apply(value), value);
+    }
+
+    #[cfg(feature = "fixed")]
+    #[test]
+    fn fixed_matches_float() {
+        let matrix = CorrectionMatrix::new([1.01, 0.02, 0.0, -0.02, 0.99, 0.01, 0.0, 0.0, 1.0]);
+        let fixed = CorrectionMatrixQ14::from(&matrix);
+
+        let float = matrix.apply(F32x3::new(1000.0, -2000.0, 3000.0));
+        let int = fixed.apply(I16x3::new(1000, -2000, 3000));
+        assert!(libm::fabsf(float.x - f32::from(int.x)) <= 1.0);
+        assert!(libm::fabsf(float.y - f32::from(int.y)) <= 1.0);
+        assert!(libm::fabsf(float.z - f32::from(int.z)) <= 1.0);
+    }
+}
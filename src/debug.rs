@@ -0,0 +1,136 @@
+//! Provides a log of recent register writes for debugging.
+//!
+//! When a configuration mysteriously changes mid-run, the writes retrieved via
+//! [`recent_writes`](crate::L3GD20::recent_writes) (e.g. from a crash dump) identify
+//! the culprit call.
+
+use crate::common::framing;
+use crate::{Transport, L3GD20};
+use heapless::HistoryBuffer;
+
+/// The number of register writes kept in the log.
+pub const WRITE_LOG_LEN: usize = 16;
+
+/// A register write recorded in the write log.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct RegisterWrite {
+    /// The register address.
+    pub address: u8,
+    /// The previous register value, if it was known to the driver.
+    pub old: Option<u8>,
+    /// The written register value.
+    pub new: u8,
+    /// The sample counter at the time of the write.
+    pub sample: u32,
+}
+
+/// The write log, along with the last known value of each register.
+pub(crate) struct WriteLog {
+    shadow: [u8; 0x40],
+    known: u64,
+    writes: HistoryBuffer<RegisterWrite, WRITE_LOG_LEN>,
+}
+
+impl WriteLog {
+    /// Creates an empty log.
+    pub(crate) fn new() -> Self {
+        Self {
+            shadow: [0; 0x40],
+            known: 0,
+            writes: HistoryBuffer::new(),
+        }
+    }
+
+    /// Records a completed transfer given its command byte and data bytes.
+    pub(crate) fn record(&mut self, command: u8, data: &[u8], sample: u32) {
+        let read = framing::is_read(command);
+        let multi = framing::is_multi(command);
+        let mut address = framing::address(command);
+        for &value in data {
+            let bit = 1u64 << address;
+            if !read {
+                let old = (self.known & bit != 0).then_some(self.shadow[usize::from(address)]);
+                self.writes.write(RegisterWrite {
+                    address,
+                    old,
+                    new: value,
+                    sample,
+                });
+            }
+            self.shadow[usize::from(address)] = value;
+            self.known |= bit;
+            if multi {
+                address = framing::address(address + 1);
+            }
+        }
+    }
+}
+
+impl<T, E> L3GD20<T>
+where
+    T: Transport<Error = E>,
+{
+    /// Gets the most recent register writes, oldest first.
+    ///
+    /// At most [`WRITE_LOG_LEN`] writes are kept.
+    pub fn recent_writes(&self) -> impl Iterator<Item = &RegisterWrite> + '_ {
+        self.write_log.writes.oldest_ordered()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::MockError;
+    use crate::sim::Simulator;
+    use l3gd20_registers::{ControlRegister4, Sensitivity};
+
+    #[test]
+    fn writes_are_logged_with_previous_values() {
+        let mut driver = L3GD20::with_transport(Simulator::<MockError>::new()).unwrap();
+        driver.xyz_raw().unwrap();
+        driver.set_sensitivity(Sensitivity::D500).unwrap();
+
+        let last = driver.recent_writes().last().copied().unwrap();
+        assert_eq!(
+            last,
+            RegisterWrite {
+                address: 0x23,
+                old: Some(0),
+                new: ControlRegister4::default()
+                    .with_full_scale(Sensitivity::D500)
+                    .into(),
+                sample: 1,
+            }
+        );
+    }
+
+    #[test]
+    fn writes_are_logged_from_the_transmitted_bytes() {
+        use crate::mock::{MockCs, MockSpi};
+        use crate::L3GD20SPI;
+
+        let mut spi = MockSpi::new();
+        spi.full_duplex = true;
+        let mut driver = L3GD20SPI::new(spi, MockCs).unwrap();
+        driver.set_sensitivity(Sensitivity::D2000).unwrap();
+
+        let last = driver.recent_writes().last().copied().unwrap();
+        assert_eq!(last.address, 0x23);
+        assert_eq!(last.old, Some(0));
+        assert_eq!(last.new, 0b0010_0000);
+    }
+
+    #[test]
+    fn log_keeps_the_most_recent_writes() {
+        let mut log = WriteLog::new();
+        for value in 0..20 {
+            log.record(0x20, &[value], 0);
+        }
+        assert_eq!(log.writes.len(), WRITE_LOG_LEN);
+        let first = log.writes.oldest_ordered().next().unwrap();
+        assert_eq!(first.old, Some(3));
+        assert_eq!(first.new, 4);
+    }
+}
@@ -0,0 +1,451 @@
+//! Provides a static description of the device for generic tooling.
+//!
+//! Generic device-driver frameworks built on [`hardware-registers`] enumerate devices by
+//! their identity and register map rather than by driver-specific calls. [`DESCRIPTOR`]
+//! describes the L3GD20 along with its [`REGISTER_MAP`], and [`L3GD20::export_registers`]
+//! and [`L3GD20::write_mapped_register`] access the registers by address.
+//!
+//! [`L3GD20::write_registers_multi`] and its typed counterpart [`L3GD20::write_registers`]
+//! write a contiguous run of registers in a single auto-incrementing transaction.
+//!
+//! For remote debugging, [`L3GD20::export_register_map`] dumps the registers as
+//! address/value byte pairs that can be sent over RTT or a UART as is, and decoded on the
+//! host with the help of the [`REGISTER_MAP`].
+//!
+//! [`hardware-registers`]: https://crates.io/crates/hardware-registers
+
+use crate::common::framing;
+use crate::{Error, Transport, L3GD20};
+use l3gd20_registers::prelude::ToBits;
+use l3gd20_registers::WritableRegister;
+
+/// The access permitted to a register.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Access {
+    /// The register can only be read.
+    ReadOnly,
+    /// The register can be read and written.
+    ReadWrite,
+}
+
+/// A register of the device.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct RegisterInfo {
+    /// The name of the register, as given by the datasheet.
+    pub name: &'static str,
+    /// The register address.
+    pub address: u8,
+    /// The access permitted to the register.
+    pub access: Access,
+    /// The value after power-up, if defined by the datasheet.
+    pub reset: Option<u8>,
+}
+
+/// A bus interface of the device.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Interface {
+    /// The 3- or 4-wire SPI interface.
+    Spi,
+    /// The I²C interface.
+    I2c,
+}
+
+/// The static description of a device.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct DeviceDescriptor {
+    /// The manufacturer of the device.
+    pub vendor: &'static str,
+    /// The part number of the device.
+    pub part: &'static str,
+    /// The address of the identification register.
+    pub who_am_i_address: u8,
+    /// The value of the identification register.
+    pub who_am_i: u8,
+    /// The supported bus interfaces.
+    pub interfaces: &'static [Interface],
+    /// The I²C addresses the device can respond to.
+    pub i2c_addresses: &'static [u8],
+    /// The registers of the device, in ascending address order.
+    pub registers: &'static [RegisterInfo],
+}
+
+impl DeviceDescriptor {
+    /// Gets the register at the given address.
+    pub fn register(&self, address: u8) -> Option<&'static RegisterInfo> {
+        self.registers.iter().find(|info| info.address == address)
+    }
+}
+
+macro_rules! register {
+    ($name:literal, $address:literal, $access:ident, $reset:expr) => {
+        RegisterInfo {
+            name: $name,
+            address: $address,
+            access: Access::$access,
+            reset: $reset,
+        }
+    };
+}
+
+/// The registers of the L3GD20, in ascending address order.
+pub const REGISTER_MAP: [RegisterInfo; 26] = [
+    register!("WHO_AM_I", 0x0F, ReadOnly, Some(0xD4)),
+    register!("CTRL_REG1", 0x20, ReadWrite, Some(0x07)),
+    register!("CTRL_REG2", 0x21, ReadWrite, Some(0x00)),
+    register!("CTRL_REG3", 0x22, ReadWrite, Some(0x00)),
+    register!("CTRL_REG4", 0x23, ReadWrite, Some(0x00)),
+    register!("CTRL_REG5", 0x24, ReadWrite, Some(0x00)),
+    register!("REFERENCE", 0x25, ReadWrite, Some(0x00)),
+    register!("OUT_TEMP", 0x26, ReadOnly, None),
+    register!("STATUS_REG", 0x27, ReadOnly, None),
+    register!("OUT_X_L", 0x28, ReadOnly, None),
+    register!("OUT_X_H", 0x29, ReadOnly, None),
+    register!("OUT_Y_L", 0x2A, ReadOnly, None),
+    register!("OUT_Y_H", 0x2B, ReadOnly, None),
+    register!("OUT_Z_L", 0x2C, ReadOnly, None),
+    register!("OUT_Z_H", 0x2D, ReadOnly, None),
+    register!("FIFO_CTRL_REG", 0x2E, ReadWrite, Some(0x00)),
+    register!("FIFO_SRC_REG", 0x2F, ReadOnly, None),
+    register!("INT1_CFG", 0x30, ReadWrite, Some(0x00)),
+    register!("INT1_SRC", 0x31, ReadOnly, None),
+    register!("INT1_TSH_XH", 0x32, ReadWrite, Some(0x00)),
+    register!("INT1_TSH_XL", 0x33, ReadWrite, Some(0x00)),
+    register!("INT1_TSH_YH", 0x34, ReadWrite, Some(0x00)),
+    register!("INT1_TSH_YL", 0x35, ReadWrite, Some(0x00)),
+    register!("INT1_TSH_ZH", 0x36, ReadWrite, Some(0x00)),
+    register!("INT1_TSH_ZL", 0x37, ReadWrite, Some(0x00)),
+    register!("INT1_DURATION", 0x38, ReadWrite, Some(0x00)),
+];
+
+/// The description of the L3GD20.
+pub const DESCRIPTOR: DeviceDescriptor = DeviceDescriptor {
+    vendor: "STMicroelectronics",
+    part: "L3GD20",
+    who_am_i_address: 0x0F,
+    who_am_i: 0xD4,
+    interfaces: &[Interface::Spi, Interface::I2c],
+    i2c_addresses: &[0x6A, 0x6B],
+    registers: &REGISTER_MAP,
+};
+
+/// The number of bytes written by [`L3GD20::export_register_map`] for the complete map.
+pub const REGISTER_MAP_EXPORT_LEN: usize = 2 * REGISTER_MAP.len();
+
+/// The longest run of consecutive writable registers, `INT1_TSH_XH` through `INT1_DURATION`.
+const MAX_WRITE_RUN: usize = 7;
+
+/// The size of the register address space, which is addressed with six bits.
+const ADDRESS_SPACE_LEN: usize = 0x40;
+
+/// A contiguous run of writable registers, written by [`L3GD20::write_registers`].
+///
+/// This is implemented for tuples of up to seven registers, in ascending address order.
+pub trait RegisterRun {
+    /// Encodes the registers into `out`, returning the address of the first register and
+    /// the number of registers, or the address of the first register out of sequence.
+    fn encode(&self, out: &mut [u8; MAX_WRITE_RUN]) -> Result<(u8, usize), u8>;
+}
+
+macro_rules! register_run {
+    ($($name:ident: $index:tt),+) => {
+        impl<$($name),+> RegisterRun for ($($name,)+)
+        where
+            $($name: WritableRegister + ToBits<Target = u8>),+
+        {
+            fn encode(&self, out: &mut [u8; MAX_WRITE_RUN]) -> Result<(u8, usize), u8> {
+                let addresses = [$(*$name::REGISTER_ADDRESS),+];
+                let first = addresses[0];
+                for (offset, &address) in (0..).zip(addresses.iter()) {
+                    if address != first.wrapping_add(offset) {
+                        return Err(address);
+                    }
+                }
+                $(out[$index] = self.$index.to_bits();)+
+                Ok((first, addresses.len()))
+            }
+        }
+    };
+}
+
+register_run!(A: 0);
+register_run!(A: 0, B: 1);
+register_run!(A: 0, B: 1, C: 2);
+register_run!(A: 0, B: 1, C: 2, D: 3);
+register_run!(A: 0, B: 1, C: 2, D: 3, F: 4);
+register_run!(A: 0, B: 1, C: 2, D: 3, F: 4, G: 5);
+register_run!(A: 0, B: 1, C: 2, D: 3, F: 4, G: 5, H: 6);
+
+/// The address ranges read in one burst each by [`L3GD20::export_registers`].
+///
+/// The output registers end a range, as the read address wraps from `OUT_Z_H` back to
+/// `OUT_X_L` while the FIFO is enabled.
+const EXPORT_RANGES: [(u8, u8); 3] = [(0x0F, 0x0F), (0x20, 0x2D), (0x2E, 0x38)];
+
+impl<T, E> L3GD20<T>
+where
+    T: Transport<Error = E>,
+{
+    /// Gets the description of the device.
+    pub const fn descriptor() -> &'static DeviceDescriptor {
+        &DESCRIPTOR
+    }
+
+    /// Reads every register of the [`REGISTER_MAP`], reporting each with its value.
+    ///
+    /// Reading the output registers consumes a sample if the FIFO is enabled.
+    pub fn export_registers<F>(&mut self, mut f: F) -> Result<(), E>
+    where
+        F: FnMut(&RegisterInfo, u8),
+    {
+        let mut registers = REGISTER_MAP.iter();
+        for (first, last) in EXPORT_RANGES {
+            let len = usize::from(last - first) + 1;
+            let mut buffer = [0; 15];
+            buffer[0] = if len == 1 {
+                framing::read_single_cmd(first)
+            } else {
+                framing::read_multi_cmd(first)
+            };
+            self.transfer(&mut buffer[..=len])?;
+            // The buffer is iterated first, so that no register is skipped once it ends.
+            for (&value, info) in buffer[1..=len].iter().zip(registers.by_ref()) {
+                f(info, value);
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes the address and value of every register of the [`REGISTER_MAP`] to `out` as
+    /// consecutive byte pairs, in ascending address order, returning the number of bytes
+    /// written.
+    ///
+    /// If `out` is shorter than [`REGISTER_MAP_EXPORT_LEN`], only the pairs that fit are
+    /// written. Reading the output registers consumes a sample if the FIFO is enabled.
+    pub fn export_register_map(&mut self, out: &mut [u8]) -> Result<usize, E> {
+        let mut pairs = out.chunks_exact_mut(2);
+        let mut written = 0;
+        self.export_registers(|info, value| {
+            if let Some(pair) = pairs.next() {
+                pair.copy_from_slice(&[info.address, value]);
+                written += 2;
+            }
+        })?;
+        Ok(written)
+    }
+
+    /// Writes consecutive registers of the [`REGISTER_MAP`], starting at `start`, in a
+    /// single auto-incrementing transaction.
+    ///
+    /// Fails with [`Error::InvalidRegister`] without writing anything if any of the
+    /// registers is not writable or the run extends past the register address space. The
+    /// cached driver state and the [expected settings](Self::preflight_check) are updated, and a
+    /// sequence pending [`repair_config`](Self::repair_config) is discarded, as it would
+    /// overwrite the written registers.
+    pub fn write_registers_multi(&mut self, start: u8, values: &[u8]) -> Result<(), Error<E>> {
+        if usize::from(start) + values.len() > ADDRESS_SPACE_LEN {
+            return Err(Error::InvalidRegister {
+                address: start.max(ADDRESS_SPACE_LEN as u8),
+            });
+        }
+        for address in (start..).take(values.len()) {
+            match DESCRIPTOR.register(address) {
+                Some(info) if info.access == Access::ReadWrite => {}
+                _ => return Err(Error::InvalidRegister { address }),
+            }
+        }
+        if values.is_empty() {
+            return Ok(());
+        }
+
+        let mut buffer = [0; MAX_WRITE_RUN + 1];
+        buffer[0] = framing::write_multi_cmd(start);
+        buffer[1..=values.len()].copy_from_slice(values);
+        self.transfer(&mut buffer[..=values.len()])
+            .map_err(Error::Bus)?;
+        self.pending_config = None;
+        for (address, &value) in (start..).zip(values) {
+            self.note_register_write(address, value);
+            self.expected_settings = self.expected_settings.with_register_write(address, value);
+        }
+        Ok(())
+    }
+
+    /// Writes a contiguous run of registers in a single auto-incrementing transaction.
+    ///
+    /// Fails with [`Error::InvalidRegister`] without writing anything if the registers are
+    /// not in consecutive address order.
+    ///
+    /// ```
+    /// # #[cfg(feature = "sim")] {
+    /// # use l3gd20_ng::{sim::Simulator, L3GD20};
+    /// use l3gd20_registers::{ControlRegister1, ControlRegister2};
+    /// # let mut driver = L3GD20::with_transport(Simulator::<()>::new()).unwrap();
+    ///
+    /// let ctrl1 = ControlRegister1::default().with_power_up(true);
+    /// driver.write_registers((ctrl1, ControlRegister2::default())).unwrap();
+    /// # }
+    /// ```
+    pub fn write_registers<R>(&mut self, run: R) -> Result<(), Error<E>>
+    where
+        R: RegisterRun,
+    {
+        let mut values = [0; MAX_WRITE_RUN];
+        let (start, len) = run
+            .encode(&mut values)
+            .map_err(|address| Error::InvalidRegister { address })?;
+        self.write_registers_multi(start, &values[..len])
+    }
+
+    /// Writes a register of the [`REGISTER_MAP`] by its address.
+    ///
+    /// Fails with [`Error::InvalidRegister`] if the register is not writable. The driver
+    /// state is updated like with [`write_registers_multi`](Self::write_registers_multi).
+    pub fn write_mapped_register(&mut self, address: u8, value: u8) -> Result<(), Error<E>> {
+        self.write_registers_multi(address, &[value])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::MockError;
+    use crate::sim::Simulator;
+
+    #[test]
+    fn register_map_matches_the_export_ranges() {
+        let mut addresses = REGISTER_MAP.iter().map(|info| info.address);
+        for (first, last) in EXPORT_RANGES {
+            for address in first..=last {
+                assert_eq!(addresses.next(), Some(address));
+            }
+        }
+        assert_eq!(addresses.next(), None);
+    }
+
+    #[test]
+    fn registers_are_exported() {
+        let mut driver = L3GD20::with_transport(Simulator::<MockError>::new()).unwrap();
+        driver.transport.registers_mut()[0x38] = 0x42;
+        let expected = *driver.transport.registers();
+
+        let transfers = driver.transport.transfers();
+        let mut count = 0;
+        driver
+            .export_registers(|info, value| {
+                assert_eq!(value, expected[usize::from(info.address)], "{}", info.name);
+                count += 1;
+            })
+            .unwrap();
+        assert_eq!(count, REGISTER_MAP.len());
+        assert_eq!(
+            driver.transport.transfers(),
+            transfers + EXPORT_RANGES.len()
+        );
+    }
+
+    #[test]
+    fn register_map_is_exported_as_pairs() {
+        let mut driver = L3GD20::with_transport(Simulator::<MockError>::new()).unwrap();
+        driver.transport.registers_mut()[0x38] = 0x42;
+
+        let mut out = [0; REGISTER_MAP_EXPORT_LEN + 1];
+        assert_eq!(
+            driver.export_register_map(&mut out),
+            Ok(REGISTER_MAP_EXPORT_LEN)
+        );
+        assert_eq!(out[..2], [0x0F, 0xD4]);
+        assert_eq!(out[REGISTER_MAP_EXPORT_LEN - 2..], [0x38, 0x42, 0]);
+
+        let mut out = [0; 5];
+        assert_eq!(driver.export_register_map(&mut out), Ok(4));
+        assert_eq!(out, [0x0F, 0xD4, 0x20, 0x0F, 0]);
+    }
+
+    #[test]
+    fn control_registers_are_written_at_once() {
+        let mut driver = L3GD20::with_transport(Simulator::<MockError>::new()).unwrap();
+        driver
+            .transport
+            .fail_at(driver.transport.transfers() + 2, MockError);
+        assert!(driver.reset().is_err());
+        assert!(driver.has_pending_config());
+
+        let transfers = driver.transport.transfers();
+        driver
+            .write_registers_multi(0x20, &[0x6F, 0x01, 0x02, 0x90, 0x10])
+            .unwrap();
+        assert_eq!(driver.transport.transfers(), transfers + 1);
+        assert!(!driver.has_pending_config());
+        assert_eq!(
+            driver.transport.registers()[0x20..0x25],
+            [0x6F, 0x01, 0x02, 0x90, 0x10]
+        );
+        assert!(driver.block_data_update);
+
+        assert_eq!(
+            driver.write_registers_multi(0x2D, &[0, 0]),
+            Err(Error::InvalidRegister { address: 0x2D })
+        );
+        assert_eq!(
+            driver.write_registers_multi(0x38, &[0; 9]),
+            Err(Error::InvalidRegister { address: 0x40 })
+        );
+        assert_eq!(
+            driver.write_registers_multi(0xFF, &[0, 0]),
+            Err(Error::InvalidRegister { address: 0xFF })
+        );
+        assert_eq!(driver.transport.transfers(), transfers + 1);
+        assert_eq!(driver.settings(), Ok(driver.expected_settings));
+    }
+
+    #[test]
+    fn typed_runs_must_be_contiguous() {
+        use l3gd20_registers::{ControlRegister1, ControlRegister2, ControlRegister4};
+
+        let mut driver = L3GD20::with_transport(Simulator::<MockError>::new()).unwrap();
+        let ctrl1 = ControlRegister1::default().with_power_up(true);
+        let ctrl2 = ControlRegister2::default().with_hpcf(3);
+        driver.write_registers((ctrl1, ctrl2)).unwrap();
+        assert_eq!(driver.transport.registers()[0x20..0x22], [0x0F, 0x03]);
+
+        assert_eq!(
+            driver.write_registers((ctrl1, ControlRegister4::default())),
+            Err(Error::InvalidRegister { address: 0x23 })
+        );
+    }
+
+    #[test]
+    fn only_writable_registers_are_written() {
+        let mut driver = L3GD20::with_transport(Simulator::<MockError>::new()).unwrap();
+        driver.write_mapped_register(0x32, 0x12).unwrap();
+        assert_eq!(driver.transport.registers()[0x32], 0x12);
+        assert_eq!(
+            driver.write_mapped_register(0x0F, 0),
+            Err(Error::InvalidRegister { address: 0x0F })
+        );
+        assert_eq!(
+            driver.write_mapped_register(0x10, 0),
+            Err(Error::InvalidRegister { address: 0x10 })
+        );
+    }
+
+    #[test]
+    fn mapped_register_writes_update_the_driver_state() {
+        let mut driver = L3GD20::with_transport(Simulator::<MockError>::new()).unwrap();
+        driver
+            .transport
+            .fail_at(driver.transport.transfers() + 2, MockError);
+        assert!(driver.reset().is_err());
+        assert!(driver.has_pending_config());
+
+        driver.write_mapped_register(0x23, 0x90).unwrap();
+        assert!(!driver.has_pending_config());
+        assert!(driver.block_data_update);
+        assert_eq!(driver.full_scale, l3gd20_registers::Sensitivity::D500);
+        assert_eq!(driver.settings(), Ok(driver.expected_settings));
+    }
+}
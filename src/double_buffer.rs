@@ -0,0 +1,204 @@
+//! Provides a double buffer decoupling the acquisition from consumers of the latest sample.
+//!
+//! The acquisition, e.g. in the data-ready interrupt handler, publishes each sample into a
+//! [`DoubleBuffered`] through its [`BufferWriter`] with
+//! [`acquire_into`](L3GD20::acquire_into). A display or telemetry task reads the most recent
+//! complete sample through a [`BufferReader`] at its own pace. Neither side blocks the other:
+//! the writer fills the slot not holding the latest sample and then swaps, and a reader
+//! retries if the slot it copied was overwritten meanwhile.
+//!
+//! ```
+//! use l3gd20_ng::double_buffer::DoubleBuffered;
+//!
+//! let mut buffer = DoubleBuffered::new();
+//! let (mut writer, reader) = buffer.split();
+//! assert_eq!(reader.latest(), None);
+//!
+//! writer.publish([1, 2, 3]);
+//! writer.publish([4, 5, 6]);
+//! assert_eq!(reader.latest(), Some([4, 5, 6]));
+//! ```
+
+use crate::{SensorData, Transport, L3GD20};
+use core::cell::UnsafeCell;
+use core::mem::MaybeUninit;
+use core::sync::atomic::{fence, AtomicBool, AtomicUsize, Ordering};
+
+/// A pair of slots holding the latest complete value and the one being written.
+///
+/// The sequence counter is odd while a value is being written. The value published with
+/// the `k`-th write (counting from zero) is held by slot `k % 2`.
+pub struct DoubleBuffered<T> {
+    slots: [UnsafeCell<MaybeUninit<T>>; 2],
+    sequence: AtomicUsize,
+    published: AtomicBool,
+}
+
+// SAFETY: The slots are only written through the unique `BufferWriter`, and readers discard
+// copies that may have raced with a write, see `DoubleBuffered::latest`.
+unsafe impl<T> Sync for DoubleBuffered<T> where T: Copy + Send {}
+
+impl<T> Default for DoubleBuffered<T>
+where
+    T: Copy,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> DoubleBuffered<T>
+where
+    T: Copy,
+{
+    /// Creates an empty buffer.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            slots: [
+                UnsafeCell::new(MaybeUninit::uninit()),
+                UnsafeCell::new(MaybeUninit::uninit()),
+            ],
+            sequence: AtomicUsize::new(0),
+            published: AtomicBool::new(false),
+        }
+    }
+
+    /// Splits the buffer into its unique writer and a reader, which can be copied freely.
+    pub fn split(&mut self) -> (BufferWriter<'_, T>, BufferReader<'_, T>) {
+        (BufferWriter { buffer: self }, BufferReader { buffer: self })
+    }
+
+    /// Gets a copy of the latest complete value, or `None` if none was published yet.
+    pub fn latest(&self) -> Option<T> {
+        if !self.published.load(Ordering::Acquire) {
+            return None;
+        }
+        loop {
+            let before = self.sequence.load(Ordering::Acquire);
+            // The latest complete value was published by the write preceding the current
+            // (or next) one, whether or not that one is in progress.
+            let slot = (before / 2).wrapping_sub(1) % 2;
+            // SAFETY: The slot was initialized by a completed write. A concurrent write
+            // into it is detected below, and the copy is discarded; `T: Copy` ensures that
+            // discarding a torn copy has no effect.
+            let value = unsafe { core::ptr::read_volatile(self.slots[slot].get()) };
+            fence(Ordering::Acquire);
+            let after = self.sequence.load(Ordering::Relaxed);
+            // The slot is overwritten next by the write starting at `before / 2 * 2 + 2`,
+            // which makes the counter odd at `before / 2 * 2 + 3`.
+            if after.wrapping_sub(before & !1) <= 2 {
+                // SAFETY: See above; the copy did not race with a write.
+                return Some(unsafe { value.assume_init() });
+            }
+        }
+    }
+
+    /// Writes a value into the slot not holding the latest value, then publishes it.
+    fn publish(&self, value: T) {
+        let sequence = self.sequence.load(Ordering::Relaxed);
+        let slot = (sequence / 2) % 2;
+        self.sequence
+            .store(sequence.wrapping_add(1), Ordering::Relaxed);
+        fence(Ordering::Release);
+        // SAFETY: Only the unique writer writes the slots; readers detect the overlap.
+        unsafe { core::ptr::write_volatile(self.slots[slot].get(), MaybeUninit::new(value)) };
+        self.sequence
+            .store(sequence.wrapping_add(2), Ordering::Release);
+        self.published.store(true, Ordering::Release);
+    }
+}
+
+/// The writing side of a [`DoubleBuffered`].
+pub struct BufferWriter<'a, T> {
+    buffer: &'a DoubleBuffered<T>,
+}
+
+impl<'a, T> BufferWriter<'a, T>
+where
+    T: Copy,
+{
+    /// Publishes a value as the latest one.
+    pub fn publish(&mut self, value: T) {
+        self.buffer.publish(value);
+    }
+}
+
+/// The reading side of a [`DoubleBuffered`].
+pub struct BufferReader<'a, T> {
+    buffer: &'a DoubleBuffered<T>,
+}
+
+impl<'a, T> Clone for BufferReader<'a, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, T> Copy for BufferReader<'a, T> {}
+
+impl<'a, T> BufferReader<'a, T>
+where
+    T: Copy,
+{
+    /// Gets a copy of the latest complete value, or `None` if none was published yet.
+    pub fn latest(&self) -> Option<T> {
+        self.buffer.latest()
+    }
+}
+
+impl<T, E> L3GD20<T>
+where
+    T: Transport<Error = E>,
+{
+    /// Reads a sample like [`data_raw`](Self::data_raw) and publishes it into a double buffer.
+    pub fn acquire_into(&mut self, writer: &mut BufferWriter<'_, SensorData>) -> Result<(), E> {
+        let data = self.data_raw()?;
+        writer.publish(data);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::MockError;
+    use crate::sim::Simulator;
+    use std::boxed::Box;
+
+    #[test]
+    fn samples_are_published() {
+        let mut buffer = DoubleBuffered::new();
+        let (mut writer, reader) = buffer.split();
+        let mut driver = L3GD20::with_transport(Simulator::<MockError>::new()).unwrap();
+
+        driver.transport.set_xyz(1, 2, 3);
+        driver.acquire_into(&mut writer).unwrap();
+        driver.transport.set_xyz(4, 5, 6);
+        driver.acquire_into(&mut writer).unwrap();
+        assert_eq!(*reader.latest().unwrap().x, 4);
+    }
+
+    #[test]
+    fn readers_never_see_torn_values() {
+        let buffer: &'static mut DoubleBuffered<[u32; 16]> =
+            Box::leak(Box::new(DoubleBuffered::new()));
+        let (mut writer, reader) = buffer.split();
+
+        std::thread::scope(|scope| {
+            scope.spawn(move || {
+                for i in 0..100_000 {
+                    writer.publish([i; 16]);
+                }
+            });
+            let mut last = 0;
+            while last < 99_999 {
+                if let Some(value) = reader.latest() {
+                    assert!(value.iter().all(|&v| v == value[0]), "{value:?}");
+                    assert!(value[0] >= last);
+                    last = value[0];
+                }
+            }
+        });
+    }
+}
@@ -0,0 +1,130 @@
+//! Provides the configuration of the DRDY/INT2 pin.
+//!
+//! The pin signals new data or FIFO events, selected by the INT2 routing bits of
+//! `CTRL_REG3`. [`configure_drdy`](L3GD20::configure_drdy) writes them together with the
+//! output stage of the pins in a single register write.
+//!
+//! The DRDY/INT2 pin of the L3GD20 is active high; the `H_Lactive` bit of `CTRL_REG3` only
+//! selects the polarity of INT1. The L3GD20H can select an active low DRDY/INT2 pin with
+//! [`set_drdy_active_low`](L3GD20::set_drdy_active_low).
+//!
+//! ```
+//! # #[cfg(feature = "sim")] {
+//! # use l3gd20_ng::{sim::Simulator, L3GD20};
+//! use l3gd20_ng::drdy::DrdyConfig;
+//!
+//! # let mut driver = L3GD20::with_transport(Simulator::<()>::new()).unwrap();
+//! let config = DrdyConfig {
+//!     data_ready: true,
+//!     open_drain: true,
+//!     ..DrdyConfig::default()
+//! };
+//! let previous = driver.configure_drdy(config).unwrap();
+//! assert_eq!(previous, DrdyConfig::default());
+//! # }
+//! ```
+
+use crate::{Transport, L3GD20};
+use l3gd20_registers::ControlRegister3;
+
+/// The DRDY/INT2 related bits of `CTRL_REG3`.
+///
+/// The default is the reset state of the register: no signals routed and push-pull.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct DrdyConfig {
+    /// Signals new data on DRDY/INT2 (`I2_DRDY`).
+    pub data_ready: bool,
+    /// Signals the FIFO watermark on DRDY/INT2 (`I2_WTM`).
+    pub watermark: bool,
+    /// Signals a FIFO overrun on DRDY/INT2 (`I2_ORun`).
+    pub overrun: bool,
+    /// Signals an empty FIFO on DRDY/INT2 (`I2_Empty`).
+    pub empty: bool,
+    /// Selects open drain rather than push-pull outputs (`PP_OD`).
+    pub open_drain: bool,
+}
+
+impl DrdyConfig {
+    /// Applies the configuration to `CTRL_REG3`, keeping the INT1 routing and polarity bits.
+    #[must_use]
+    pub fn apply(&self, register: ControlRegister3) -> ControlRegister3 {
+        register
+            .with_i2drdy(self.data_ready)
+            .with_i2wtm(self.watermark)
+            .with_i2orun(self.overrun)
+            .with_i2empty(self.empty)
+            .with_open_drain(self.open_drain)
+    }
+}
+
+impl From<ControlRegister3> for DrdyConfig {
+    fn from(value: ControlRegister3) -> Self {
+        Self {
+            data_ready: value.i2drdy(),
+            watermark: value.i2wtm(),
+            overrun: value.i2orun(),
+            empty: value.i2empty(),
+            open_drain: value.open_drain(),
+        }
+    }
+}
+
+impl<T, E> L3GD20<T>
+where
+    T: Transport<Error = E>,
+{
+    /// Configures the DRDY/INT2 pin and returns the previous configuration.
+    ///
+    /// Unlike [`enable_data_ready`](Self::enable_data_ready), this sets all INT2 routing
+    /// bits as well as the output stage. The INT1 routing and polarity bits (`I1_Int1`,
+    /// `I1_Boot`, `H_Lactive`) are kept.
+    pub fn configure_drdy(&mut self, config: DrdyConfig) -> Result<DrdyConfig, E> {
+        let reg: ControlRegister3 = self.read_register()?;
+        self.write_register(config.apply(reg))?;
+        Ok(reg.into())
+    }
+
+    /// Reads the configuration of the DRDY/INT2 pin.
+    pub fn drdy_config(&mut self) -> Result<DrdyConfig, E> {
+        Ok(self.read_register::<ControlRegister3>()?.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::MockError;
+    use crate::sim::Simulator;
+    use l3gd20_registers::prelude::{SPIRegister, ToBits};
+
+    #[test]
+    fn drdy_configuration_replaces_the_int2_bits() {
+        let mut driver = L3GD20::with_transport(Simulator::<MockError>::new()).unwrap();
+        let address = *ControlRegister3::REGISTER_ADDRESS as usize;
+        // I1_Int1, H_Lactive, I2_DRDY and I2_Empty set.
+        driver.transport.registers_mut()[address] = 0b1010_1001;
+
+        let config = DrdyConfig {
+            watermark: true,
+            overrun: true,
+            open_drain: true,
+            ..DrdyConfig::default()
+        };
+        let previous = driver.configure_drdy(config).unwrap();
+        assert_eq!(
+            previous,
+            DrdyConfig {
+                data_ready: true,
+                empty: true,
+                ..DrdyConfig::default()
+            }
+        );
+
+        let reg: ControlRegister3 = driver.read_register().unwrap();
+        assert_eq!(reg.to_bits(), 0b1011_0110);
+        assert!(reg.int1_low());
+        assert_eq!(driver.drdy_config(), Ok(config));
+        assert_eq!(driver.configure_drdy(previous), Ok(config));
+    }
+}
@@ -0,0 +1,242 @@
+//! Provides compact binary encodings of readings, e.g. for telemetry links.
+//!
+//! The byte order is selected by a type parameter, so there is no runtime overhead
+//! for choosing it:
+//!
+//! ```
+//! use l3gd20_ng::encoding::{BigEndian, LittleEndian};
+//! use l3gd20_ng::I16x3;
+//!
+//! let value = I16x3::new(1, -2, 3);
+//! assert_eq!(value.to_bytes::<LittleEndian>(), value.to_le_bytes());
+//! assert_eq!(value.to_bytes::<BigEndian>(), [0, 1, 0xFF, 0xFE, 0, 3]);
+//! ```
+
+use crate::{F32x3, I16x3, SensorData};
+
+mod private {
+    pub trait Sealed {}
+}
+
+/// A byte order used for encoding values.
+///
+/// This trait is sealed; see [`LittleEndian`] and [`BigEndian`].
+pub trait ByteOrder: private::Sealed {
+    /// Encodes a 16-bit integer.
+    fn i16_to_bytes(value: i16) -> [u8; 2];
+
+    /// Decodes a 16-bit integer.
+    fn i16_from_bytes(bytes: [u8; 2]) -> i16;
+
+    /// Encodes a 16-bit unsigned integer.
+    fn u16_to_bytes(value: u16) -> [u8; 2];
+
+    /// Decodes a 16-bit unsigned integer.
+    fn u16_from_bytes(bytes: [u8; 2]) -> u16;
+
+    /// Encodes a 32-bit float.
+    fn f32_to_bytes(value: f32) -> [u8; 4];
+
+    /// Decodes a 32-bit float.
+    fn f32_from_bytes(bytes: [u8; 4]) -> f32;
+}
+
+/// Little endian byte order, i.e. least significant byte first.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum LittleEndian {}
+
+/// Big endian (network) byte order, i.e. most significant byte first.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum BigEndian {}
+
+impl private::Sealed for LittleEndian {}
+impl private::Sealed for BigEndian {}
+
+impl ByteOrder for LittleEndian {
+    #[inline]
+    fn i16_to_bytes(value: i16) -> [u8; 2] {
+        value.to_le_bytes()
+    }
+
+    #[inline]
+    fn i16_from_bytes(bytes: [u8; 2]) -> i16 {
+        i16::from_le_bytes(bytes)
+    }
+
+    #[inline]
+    fn u16_to_bytes(value: u16) -> [u8; 2] {
+        value.to_le_bytes()
+    }
+
+    #[inline]
+    fn u16_from_bytes(bytes: [u8; 2]) -> u16 {
+        u16::from_le_bytes(bytes)
+    }
+
+    #[inline]
+    fn f32_to_bytes(value: f32) -> [u8; 4] {
+        value.to_le_bytes()
+    }
+
+    #[inline]
+    fn f32_from_bytes(bytes: [u8; 4]) -> f32 {
+        f32::from_le_bytes(bytes)
+    }
+}
+
+impl ByteOrder for BigEndian {
+    #[inline]
+    fn i16_to_bytes(value: i16) -> [u8; 2] {
+        value.to_be_bytes()
+    }
+
+    #[inline]
+    fn i16_from_bytes(bytes: [u8; 2]) -> i16 {
+        i16::from_be_bytes(bytes)
+    }
+
+    #[inline]
+    fn u16_to_bytes(value: u16) -> [u8; 2] {
+        value.to_be_bytes()
+    }
+
+    #[inline]
+    fn u16_from_bytes(bytes: [u8; 2]) -> u16 {
+        u16::from_be_bytes(bytes)
+    }
+
+    #[inline]
+    fn f32_to_bytes(value: f32) -> [u8; 4] {
+        value.to_be_bytes()
+    }
+
+    #[inline]
+    fn f32_from_bytes(bytes: [u8; 4]) -> f32 {
+        f32::from_be_bytes(bytes)
+    }
+}
+
+impl I16x3 {
+    /// Encodes the components in X, Y, Z order using the specified byte order.
+    #[must_use]
+    pub fn to_bytes<O: ByteOrder>(&self) -> [u8; 6] {
+        let [x0, x1] = O::i16_to_bytes(self.x);
+        let [y0, y1] = O::i16_to_bytes(self.y);
+        let [z0, z1] = O::i16_to_bytes(self.z);
+        [x0, x1, y0, y1, z0, z1]
+    }
+
+    /// Encodes the components in X, Y, Z order using little endian byte order.
+    #[must_use]
+    pub fn to_le_bytes(&self) -> [u8; 6] {
+        self.to_bytes::<LittleEndian>()
+    }
+
+    /// Encodes the components in X, Y, Z order using big endian byte order.
+    #[must_use]
+    pub fn to_be_bytes(&self) -> [u8; 6] {
+        self.to_bytes::<BigEndian>()
+    }
+
+    /// Decodes components encoded by [`to_bytes`](Self::to_bytes).
+    #[must_use]
+    pub fn from_bytes<O: ByteOrder>(bytes: [u8; 6]) -> Self {
+        Self::new(
+            O::i16_from_bytes([bytes[0], bytes[1]]),
+            O::i16_from_bytes([bytes[2], bytes[3]]),
+            O::i16_from_bytes([bytes[4], bytes[5]]),
+        )
+    }
+}
+
+impl F32x3 {
+    /// Encodes the components in X, Y, Z order using the specified byte order.
+    #[must_use]
+    pub fn to_bytes<O: ByteOrder>(&self) -> [u8; 12] {
+        let mut bytes = [0; 12];
+        bytes[0..4].copy_from_slice(&O::f32_to_bytes(self.x));
+        bytes[4..8].copy_from_slice(&O::f32_to_bytes(self.y));
+        bytes[8..12].copy_from_slice(&O::f32_to_bytes(self.z));
+        bytes
+    }
+
+    /// Encodes the components in X, Y, Z order using little endian byte order.
+    #[must_use]
+    pub fn to_le_bytes(&self) -> [u8; 12] {
+        self.to_bytes::<LittleEndian>()
+    }
+
+    /// Encodes the components in X, Y, Z order using big endian byte order.
+    #[must_use]
+    pub fn to_be_bytes(&self) -> [u8; 12] {
+        self.to_bytes::<BigEndian>()
+    }
+
+    /// Decodes components encoded by [`to_bytes`](Self::to_bytes).
+    #[must_use]
+    pub fn from_bytes<O: ByteOrder>(bytes: [u8; 12]) -> Self {
+        let component =
+            |i: usize| O::f32_from_bytes([bytes[i], bytes[i + 1], bytes[i + 2], bytes[i + 3]]);
+        Self::new(component(0), component(4), component(8))
+    }
+}
+
+impl SensorData {
+    /// Encodes the reading into eight bytes using the specified byte order.
+    ///
+    /// The layout is the raw temperature, a flags byte and the X, Y and Z components.
+    /// Bits 0 to 2 of the flags byte indicate fresh X, Y and Z readings, bits 4 to 6
+    /// indicate overrun X, Y and Z readings.
+    #[must_use]
+    pub fn to_bytes<O: ByteOrder>(&self) -> [u8; 8] {
+        let flags = u8::from(self.x.fresh())
+            | u8::from(self.y.fresh()) << 1
+            | u8::from(self.z.fresh()) << 2
+            | u8::from(self.x.overrun()) << 4
+            | u8::from(self.y.overrun()) << 5
+            | u8::from(self.z.overrun()) << 6;
+        let [x0, x1, y0, y1, z0, z1] = I16x3::new(*self.x, *self.y, *self.z).to_bytes::<O>();
+        [self.temperature, flags, x0, x1, y0, y1, z0, z1]
+    }
+
+    /// Encodes the reading into eight bytes using little endian byte order.
+    ///
+    /// See [`to_bytes`](Self::to_bytes) for the layout.
+    #[must_use]
+    pub fn to_le_bytes(&self) -> [u8; 8] {
+        self.to_bytes::<LittleEndian>()
+    }
+
+    /// Encodes the reading into eight bytes using big endian byte order.
+    ///
+    /// See [`to_bytes`](Self::to_bytes) for the layout.
+    #[must_use]
+    pub fn to_be_bytes(&self) -> [u8; 8] {
+        self.to_bytes::<BigEndian>()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn f32x3_roundtrip() {
+        let value = F32x3::new(1.5, -2.25, 1e6);
+        assert_eq!(F32x3::from_bytes::<BigEndian>(value.to_be_bytes()), value);
+        assert_eq!(
+            F32x3::from_bytes::<LittleEndian>(value.to_le_bytes()),
+            value
+        );
+    }
+
+    #[test]
+    fn sensor_data_layout() {
+        let status = l3gd20_registers::StatusRegister::from_bits(0b0011_0100);
+        let data = SensorData::new(0x12, 0x0102, -1, 3, status);
+        assert_eq!(
+            data.to_be_bytes(),
+            [0x12, 0b0011_0100, 0x01, 0x02, 0xFF, 0xFF, 0x00, 0x03]
+        );
+    }
+}
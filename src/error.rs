@@ -0,0 +1,89 @@
+//! Provides the driver error type.
+
+/// An error of a driver operation.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Error<E> {
+    /// A bus transfer failed.
+    Bus(E),
+    /// The device is known to be powered down, so that its output registers would
+    /// only return stale values.
+    PoweredDown,
+    /// A configuration sequence failed partway; the remaining writes can be re-attempted
+    /// with [`repair_config`](crate::L3GD20::repair_config).
+    PartialConfig {
+        /// The bus error.
+        error: E,
+        /// The address of the last register written successfully, if any.
+        last_written: Option<u8>,
+    },
+    /// The shared bus stayed busy for longer than the bounded wait of a
+    /// [high priority](crate::bus::ReadPriority::High) read.
+    BusBusy,
+    /// The device did not identify as an L3GD20 or L3GD20H; holds the value read from
+    /// the `WHO_AM_I` register.
+    UnknownDevice(u8),
+    /// The register does not exist or cannot be written.
+    InvalidRegister {
+        /// The register address.
+        address: u8,
+    },
+    /// The high-pass filter output was selected while the filter is disabled.
+    HighpassDisabled,
+    /// No new sample became available in time.
+    Timeout,
+    /// The requested configuration is not supported by the device.
+    InvalidConfig,
+    /// The declared SPI clock exceeds the maximum of the device.
+    SpiClockTooHigh {
+        /// The declared SPI clock frequency, in Hertz.
+        hz: u32,
+    },
+    /// The declared SPI clock is too slow to read every sample at the output data rate.
+    SpiClockTooLow {
+        /// The declared SPI clock frequency, in Hertz.
+        hz: u32,
+        /// The lowest sufficient SPI clock frequency, in Hertz.
+        required_hz: u32,
+    },
+}
+
+impl<E> core::fmt::Display for Error<E>
+where
+    E: core::fmt::Debug,
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Error::Bus(error) => write!(f, "bus error: {error:?}"),
+            Error::PoweredDown => f.write_str("device is powered down"),
+            Error::PartialConfig {
+                error,
+                last_written: Some(address),
+            } => write!(
+                f,
+                "configuration written up to register {address:#04x}: {error:?}"
+            ),
+            Error::PartialConfig {
+                error,
+                last_written: None,
+            } => write!(f, "configuration not written: {error:?}"),
+            Error::BusBusy => f.write_str("bus busy"),
+            Error::UnknownDevice(who_am_i) => {
+                write!(f, "unknown device, WHO_AM_I is {who_am_i:#04x}")
+            }
+            Error::InvalidRegister { address } => {
+                write!(f, "register {address:#04x} is not writable")
+            }
+            Error::HighpassDisabled => f.write_str("high-pass filter is disabled"),
+            Error::Timeout => f.write_str("timed out waiting for data"),
+            Error::InvalidConfig => f.write_str("configuration not supported by the device"),
+            Error::SpiClockTooHigh { hz } => {
+                write!(f, "SPI clock of {hz} Hz exceeds the maximum of the device")
+            }
+            Error::SpiClockTooLow { hz, required_hz } => write!(
+                f,
+                "SPI clock of {hz} Hz is too slow, at least {required_hz} Hz are required"
+            ),
+        }
+    }
+}
@@ -0,0 +1,284 @@
+//! Provides delivery of interrupt events, either driven by the interrupt pins or by polling.
+//!
+//! On boards where INT1/INT2 are not routed, [`InterruptDelivery::Polling`] makes all
+//! interrupt features work by reading the source registers on a schedule instead, delivering
+//! the same [`InterruptEvent`]s. The interrupt enables of `INT1_CFG` and the INT2 routing bits
+//! of `CTRL_REG3` select the events in both modes.
+
+use crate::common::framing;
+use crate::{Axis, Transport, L3GD20};
+use l3gd20_registers::prelude::SPIRegister;
+use l3gd20_registers::*;
+
+/// A threshold direction.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ThresholdKind {
+    /// The rate exceeded the high threshold.
+    High,
+    /// The rate fell below the low threshold.
+    Low,
+}
+
+/// An interrupt event.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum InterruptEvent {
+    /// An INT1 threshold event.
+    Threshold {
+        /// The axis the event occurred on.
+        axis: Axis,
+        /// The threshold direction.
+        kind: ThresholdKind,
+    },
+    /// The FIFO watermark level was reached.
+    FifoWatermark,
+    /// The FIFO overran.
+    FifoOverrun,
+    /// The FIFO is empty.
+    FifoEmpty,
+}
+
+/// How interrupt events are delivered.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum InterruptDelivery {
+    /// The interrupt pins are wired; the sources are checked on every call to
+    /// [`service_interrupts`](crate::L3GD20::service_interrupts), e.g. when a pin fired.
+    #[default]
+    Pins,
+    /// The interrupt pins are not wired; the sources are polled at the given interval.
+    ///
+    /// Without a [time source](crate::time), the sources are polled on every call.
+    Polling {
+        /// The polling interval in milliseconds.
+        interval_ms: u16,
+    },
+}
+
+/// A fixed-capacity queue of interrupt events.
+///
+/// This decouples the production of events, e.g. by
+/// [`service_interrupts`](crate::L3GD20::service_interrupts) in an interrupt handler, from
+/// their consumption in the main loop. When the queue is full, new events are dropped and
+/// counted. Sharing the queue between execution contexts requires a mutex, such as a
+/// critical section.
+#[derive(Debug, Default)]
+pub struct EventQueue<const N: usize> {
+    events: heapless::Deque<InterruptEvent, N>,
+    dropped: u32,
+}
+
+impl<const N: usize> EventQueue<N> {
+    /// Creates an empty queue.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            events: heapless::Deque::new(),
+            dropped: 0,
+        }
+    }
+
+    /// Appends an event, returning `false` and counting it as dropped if the queue is full.
+    pub fn push(&mut self, event: InterruptEvent) -> bool {
+        if self.events.push_back(event).is_ok() {
+            true
+        } else {
+            self.dropped = self.dropped.saturating_add(1);
+            false
+        }
+    }
+
+    /// Removes the oldest event.
+    pub fn pop(&mut self) -> Option<InterruptEvent> {
+        self.events.pop_front()
+    }
+
+    /// Gets the number of queued events.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    /// Determines whether no events are queued.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    /// Gets the number of events dropped since the last call to [`take_dropped`](Self::take_dropped).
+    #[must_use]
+    pub fn dropped(&self) -> u32 {
+        self.dropped
+    }
+
+    /// Gets and clears the number of dropped events.
+    pub fn take_dropped(&mut self) -> u32 {
+        core::mem::take(&mut self.dropped)
+    }
+}
+
+/// The state of the interrupt event delivery.
+#[derive(Debug, Default)]
+pub(crate) struct EventDelivery {
+    mode: InterruptDelivery,
+    last_us: Option<u64>,
+}
+
+impl EventDelivery {
+    /// Determines whether the sources are due to be checked at the given time.
+    fn is_due(&self, now_us: Option<u64>) -> bool {
+        match (self.mode, self.last_us, now_us) {
+            (InterruptDelivery::Polling { interval_ms }, Some(last), Some(now)) => {
+                now.saturating_sub(last) >= u64::from(interval_ms) * 1000
+            }
+            _ => true,
+        }
+    }
+}
+
+impl<T, E> L3GD20<T>
+where
+    T: Transport<Error = E>,
+{
+    /// Configures how interrupt events are delivered.
+    pub fn set_interrupt_delivery(&mut self, mode: InterruptDelivery) {
+        self.events = EventDelivery {
+            mode,
+            last_us: None,
+        };
+    }
+
+    /// Checks the interrupt sources and calls `f` for every pending event.
+    ///
+    /// In [`Polling`](InterruptDelivery::Polling) mode, this is intended to be called from the
+    /// main loop and only reads the source registers once the polling interval elapsed.
+    /// Returns whether the sources were checked.
+    pub fn service_interrupts<F>(&mut self, mut f: F) -> Result<bool, E>
+    where
+        F: FnMut(InterruptEvent),
+    {
+        let now = self.now_micros();
+        if !self.events.is_due(now) {
+            return Ok(false);
+        }
+        self.events.last_us = now;
+
+        // INT1_CFG and INT1_SRC in one burst; reading the source acknowledges latched events.
+        let mut buffer = [
+            framing::read_multi_cmd(*Int1ConfigurationRegister::REGISTER_ADDRESS),
+            0,
+            0,
+        ];
+        self.transfer(&mut buffer)?;
+        let config = Int1ConfigurationRegister::from_bits(buffer[1]);
+        let source = Int1SourceRegisterA::from_bits(buffer[2]);
+        if source.ia() {
+            let thresholds = [
+                (Axis::X, ThresholdKind::High, config.xhie(), source.x_high()),
+                (Axis::X, ThresholdKind::Low, config.xlie(), source.x_low()),
+                (Axis::Y, ThresholdKind::High, config.yhie(), source.y_high()),
+                (Axis::Y, ThresholdKind::Low, config.ylie(), source.y_low()),
+                (Axis::Z, ThresholdKind::High, config.zhie(), source.z_high()),
+                (Axis::Z, ThresholdKind::Low, config.zlie(), source.z_low()),
+            ];
+            for (axis, kind, _, _) in thresholds
+                .into_iter()
+                .filter(|(_, _, enabled, active)| *enabled && *active)
+            {
+                f(InterruptEvent::Threshold { axis, kind });
+            }
+        }
+
+        let routing: ControlRegister3 = self.read_register()?;
+        if routing.i2wtm() || routing.i2orun() || routing.i2empty() {
+            let fifo: FifoSourceRegister = self.read_register()?;
+            if routing.i2wtm() && fifo.wtm() {
+                f(InterruptEvent::FifoWatermark);
+            }
+            if routing.i2orun() && fifo.ovrn_fifo() {
+                f(InterruptEvent::FifoOverrun);
+            }
+            if routing.i2empty() && fifo.empty() {
+                f(InterruptEvent::FifoEmpty);
+            }
+        }
+
+        Ok(true)
+    }
+
+    /// Checks the interrupt sources like [`service_interrupts`](Self::service_interrupts),
+    /// appending the pending events to the queue.
+    pub fn service_interrupts_into<const N: usize>(
+        &mut self,
+        queue: &mut EventQueue<N>,
+    ) -> Result<bool, E> {
+        self.service_interrupts(|event| {
+            queue.push(event);
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::MockError;
+    use crate::sim::Simulator;
+    use crate::time::FixedStep;
+    use std::boxed::Box;
+    use std::vec::Vec;
+
+    #[test]
+    fn polled_sources_deliver_enabled_events() {
+        let mut driver = L3GD20::with_transport(Simulator::<MockError>::new()).unwrap();
+        driver.set_interrupt_delivery(InterruptDelivery::Polling { interval_ms: 10 });
+        driver.set_time_source(Box::leak(Box::new(FixedStep::new(4_000))));
+
+        let registers = driver.transport.registers_mut();
+        registers[0x30] = 0b0000_1010; // YHIE, XHIE
+        registers[0x31] = 0b0100_1110; // IA, YH, YL, XH
+        registers[0x22] = 0b0000_0100; // I2_WTM
+        registers[0x2F] = 0b1110_0000; // WTM, OVRN, EMPTY
+
+        let mut events = Vec::new();
+        assert!(driver
+            .service_interrupts(|event| events.push(event))
+            .unwrap());
+        assert_eq!(
+            events,
+            [
+                InterruptEvent::Threshold {
+                    axis: Axis::X,
+                    kind: ThresholdKind::High
+                },
+                InterruptEvent::Threshold {
+                    axis: Axis::Y,
+                    kind: ThresholdKind::High
+                },
+                InterruptEvent::FifoWatermark,
+            ]
+        );
+
+        // The next poll is not due before 10 ms have passed.
+        assert!(!driver.service_interrupts(|_| {}).unwrap());
+        assert!(!driver.service_interrupts(|_| {}).unwrap());
+        assert!(driver.service_interrupts(|_| {}).unwrap());
+    }
+
+    #[test]
+    fn queue_counts_dropped_events() {
+        let mut driver = L3GD20::with_transport(Simulator::<MockError>::new()).unwrap();
+        let registers = driver.transport.registers_mut();
+        registers[0x22] = 0b0000_0111; // I2_WTM, I2_ORun, I2_Empty
+        registers[0x2F] = 0b1110_0000; // WTM, OVRN, EMPTY
+
+        let mut queue = EventQueue::<2>::new();
+        driver.service_interrupts_into(&mut queue).unwrap();
+        assert_eq!(queue.len(), 2);
+        assert_eq!(queue.take_dropped(), 1);
+        assert_eq!(queue.dropped(), 0);
+        assert_eq!(queue.pop(), Some(InterruptEvent::FifoWatermark));
+        assert_eq!(queue.pop(), Some(InterruptEvent::FifoOverrun));
+        assert!(queue.is_empty());
+    }
+}
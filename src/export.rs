@@ -0,0 +1,134 @@
+//! Provides host-side export of captured sample windows.
+//!
+//! Windows recorded with e.g. [`capture_window`](crate::L3GD20::capture_window) can be written
+//! as CSV or as a three-channel 16-bit WAV file, so that vibration captures can be loaded
+//! directly into spreadsheets, audio editors or analysis tools. Both formats carry the
+//! sample rate the window was recorded at.
+
+use crate::SensorData;
+use std::io::{Error, ErrorKind, Result, Write};
+
+/// The number of channels written to WAV files: one per axis.
+const WAV_CHANNELS: u16 = 3;
+
+/// The number of bytes per sample and channel in WAV files.
+const WAV_SAMPLE_BYTES: u16 = 2;
+
+/// Writes the samples as CSV.
+///
+/// The first line is a `# sample_rate_hz=<rate>` comment, followed by a header line and
+/// one row of `index,time_s,temperature,x,y,z` per sample, using the raw sensor values.
+///
+/// ```
+/// use l3gd20_ng::{export::export_csv, SensorData};
+/// # use l3gd20_registers::StatusRegister;
+///
+/// let samples = [SensorData::new(20, 1, -2, 3, StatusRegister::default())];
+/// let mut csv = Vec::new();
+/// export_csv(&mut csv, &samples, 100).unwrap();
+/// assert_eq!(
+///     String::from_utf8(csv).unwrap(),
+///     "# sample_rate_hz=100\nindex,time_s,temperature,x,y,z\n0,0,20,1,-2,3\n"
+/// );
+/// ```
+pub fn export_csv<W>(mut writer: W, samples: &[SensorData], sample_rate_hz: u32) -> Result<()>
+where
+    W: Write,
+{
+    writeln!(writer, "# sample_rate_hz={sample_rate_hz}")?;
+    writeln!(writer, "index,time_s,temperature,x,y,z")?;
+    for (index, sample) in samples.iter().enumerate() {
+        let time_s = index as f64 / f64::from(sample_rate_hz.max(1));
+        writeln!(
+            writer,
+            "{index},{time_s},{},{},{},{}",
+            sample.temperature, *sample.x, *sample.y, *sample.z
+        )?;
+    }
+    Ok(())
+}
+
+/// Writes the samples as a three-channel 16-bit PCM WAV file.
+///
+/// The channels hold the raw X, Y and Z readings in this order; the sample rate of the
+/// file is the rate the window was recorded at.
+///
+/// Fails with [`ErrorKind::InvalidInput`] without writing anything if the window or the
+/// byte rate is too large for the 32-bit fields of the header.
+pub fn export_wav<W>(mut writer: W, samples: &[SensorData], sample_rate_hz: u32) -> Result<()>
+where
+    W: Write,
+{
+    let block_align = WAV_CHANNELS * WAV_SAMPLE_BYTES;
+    let data_len = samples
+        .len()
+        .checked_mul(usize::from(block_align))
+        .and_then(|len| u32::try_from(len).ok())
+        .ok_or_else(|| invalid_input("too many samples for a WAV file"))?;
+    let riff_len = data_len
+        .checked_add(36)
+        .ok_or_else(|| invalid_input("too many samples for a WAV file"))?;
+    let byte_rate = sample_rate_hz
+        .checked_mul(u32::from(block_align))
+        .ok_or_else(|| invalid_input("sample rate too high for a WAV file"))?;
+
+    writer.write_all(b"RIFF")?;
+    writer.write_all(&riff_len.to_le_bytes())?;
+    writer.write_all(b"WAVE")?;
+
+    writer.write_all(b"fmt ")?;
+    writer.write_all(&16u32.to_le_bytes())?;
+    writer.write_all(&1u16.to_le_bytes())?; // PCM
+    writer.write_all(&WAV_CHANNELS.to_le_bytes())?;
+    writer.write_all(&sample_rate_hz.to_le_bytes())?;
+    writer.write_all(&byte_rate.to_le_bytes())?;
+    writer.write_all(&block_align.to_le_bytes())?;
+    writer.write_all(&(WAV_SAMPLE_BYTES * 8).to_le_bytes())?;
+
+    writer.write_all(b"data")?;
+    writer.write_all(&data_len.to_le_bytes())?;
+    for sample in samples {
+        for value in [*sample.x, *sample.y, *sample.z] {
+            writer.write_all(&value.to_le_bytes())?;
+        }
+    }
+    Ok(())
+}
+
+/// Creates an [`ErrorKind::InvalidInput`] error.
+fn invalid_input(message: &'static str) -> Error {
+    Error::new(ErrorKind::InvalidInput, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use l3gd20_registers::StatusRegister;
+    use std::vec::Vec;
+
+    #[test]
+    fn wav_header_describes_the_window() {
+        let samples = [
+            SensorData::new(0, 1, 2, 3, StatusRegister::default()),
+            SensorData::new(0, -1, -2, -3, StatusRegister::default()),
+        ];
+        let mut wav = Vec::new();
+        export_wav(&mut wav, &samples, 190).unwrap();
+
+        assert_eq!(wav.len(), 44 + 2 * 6);
+        assert_eq!(&wav[..4], b"RIFF");
+        assert_eq!(&wav[22..24], &3u16.to_le_bytes());
+        assert_eq!(&wav[24..28], &190u32.to_le_bytes());
+        assert_eq!(&wav[40..44], &12u32.to_le_bytes());
+        assert_eq!(&wav[44..46], &1i16.to_le_bytes());
+        assert_eq!(&wav[54..56], &(-3i16).to_le_bytes());
+    }
+
+    #[test]
+    fn wav_header_fields_must_not_overflow() {
+        let mut wav = Vec::new();
+        let error = export_wav(&mut wav, &[], u32::MAX).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidInput);
+        assert!(wav.is_empty());
+    }
+}
@@ -0,0 +1,109 @@
+/// XYZ triple
+///
+/// Converts to and from `[f32; 3]`, and with the `mint` feature to and from
+/// [`mint::Vector3<f32>`](https://docs.rs/mint), which the vector types of `nalgebra`, `glam`
+/// and `cgmath` accept. The rates of [`xyz_rad_s`](crate::L3GD20::xyz_rad_s) can thus be fed to
+/// an AHRS filter directly, e.g. `nalgebra::Vector3::from(mint::Vector3::from(rates))`.
+#[derive(Copy, Clone, PartialEq, Default)]
+pub struct F32x3 {
+    /// X component
+    pub x: f32,
+    /// Y component
+    pub y: f32,
+    /// Z component
+    pub z: f32,
+}
+
+impl F32x3 {
+    /// Creates a new instance of the [`F32x3`] struct from its components.
+    #[must_use]
+    pub fn new(x: f32, y: f32, z: f32) -> Self {
+        Self { x, y, z }
+    }
+}
+
+impl From<[f32; 3]> for F32x3 {
+    fn from([x, y, z]: [f32; 3]) -> Self {
+        Self { x, y, z }
+    }
+}
+
+impl From<F32x3> for [f32; 3] {
+    fn from(value: F32x3) -> Self {
+        [value.x, value.y, value.z]
+    }
+}
+
+#[cfg(feature = "mint")]
+#[cfg_attr(docsrs, doc(cfg(feature = "mint")))]
+impl From<mint::Vector3<f32>> for F32x3 {
+    fn from(value: mint::Vector3<f32>) -> Self {
+        Self {
+            x: value.x,
+            y: value.y,
+            z: value.z,
+        }
+    }
+}
+
+#[cfg(feature = "mint")]
+#[cfg_attr(docsrs, doc(cfg(feature = "mint")))]
+impl From<F32x3> for mint::Vector3<f32> {
+    fn from(value: F32x3) -> Self {
+        Self {
+            x: value.x,
+            y: value.y,
+            z: value.z,
+        }
+    }
+}
+
+#[cfg(feature = "defmt")]
+#[cfg_attr(docsrs, doc(cfg(feature = "defmt")))]
+impl defmt::Format for F32x3 {
+    fn format(&self, fmt: defmt::Formatter) {
+        defmt::write!(fmt, "({}, {}, {})", self.x, self.y, self.z);
+    }
+}
+
+impl core::fmt::Debug for F32x3 {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        use core::fmt::Write;
+        f.write_char('(')?;
+        core::fmt::Debug::fmt(&self.x, f)?;
+        f.write_str(", ")?;
+        core::fmt::Debug::fmt(&self.y, f)?;
+        f.write_str(", ")?;
+        core::fmt::Debug::fmt(&self.z, f)?;
+        f.write_char(')')
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn f32x3_debug() {
+        let value = F32x3 {
+            x: 1.0,
+            y: 2.5,
+            z: -3.0,
+        };
+        test_format::assert_debug_fmt!(value, "(1.0, 2.5, -3.0)");
+    }
+
+    #[test]
+    fn f32x3_conversions() {
+        let value = F32x3::new(1.0, 2.5, -3.0);
+        assert_eq!(<[f32; 3]>::from(value), [1.0, 2.5, -3.0]);
+        assert_eq!(F32x3::from([1.0, 2.5, -3.0]), value);
+
+        #[cfg(feature = "mint")]
+        {
+            let vector = mint::Vector3::from(value);
+            assert_eq!(vector, mint::Vector3::from([1.0, 2.5, -3.0]));
+            assert_eq!(F32x3::from(vector), value);
+        }
+    }
+}
@@ -0,0 +1,714 @@
+//! Provides support for the on-chip FIFO.
+
+use crate::common::{decode, framing};
+use crate::drdy::DrdyConfig;
+use crate::{I16x3, SensorData, Transport, L3GD20};
+use embedded_hal::blocking::delay::DelayUs;
+use l3gd20_registers::prelude::SPIRegister;
+use l3gd20_registers::{
+    ControlRegister3, ControlRegister5, FifoControlRegister, FifoSourceRegister, OutXLow,
+    StatusRegister,
+};
+
+pub use l3gd20_registers::FifoMode;
+
+/// The interval at which [`L3GD20::wait_fifo_empty`] and `wait_fifo_empty_async` poll the
+/// FIFO status, in microseconds.
+const EMPTY_POLL_INTERVAL_US: u32 = 500;
+
+/// The number of samples the FIFO holds.
+pub const FIFO_DEPTH: usize = 32;
+
+/// The largest FIFO watermark level.
+pub const MAX_WATERMARK: u8 = 31;
+
+/// The length of a sample in the FIFO, in bytes.
+const SAMPLE_LEN: usize = 6;
+
+/// The `STATUS_REG` value flagging new data on all axes, reported for drained samples.
+const FRESH_STATUS: u8 = 0b0000_1111;
+
+/// The state of the FIFO, as reported by `FIFO_SRC_REG`.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct FifoStatus {
+    /// The number of unread samples.
+    pub level: u8,
+    /// Whether the FIFO is empty.
+    pub empty: bool,
+    /// Whether samples were overwritten since the FIFO was last read.
+    pub overrun: bool,
+    /// Whether the level reached the watermark.
+    pub watermark: bool,
+}
+
+/// The position of a FIFO sample relative to the watermark and to lost samples.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SampleTag {
+    /// Whether the sample was stored at or above the watermark level.
+    pub over_watermark: bool,
+    /// Whether the sample borders samples lost to an overrun, and may be corrupted.
+    pub overrun_boundary: bool,
+}
+
+/// A FIFO sample read by [`L3GD20::read_fifo_tagged`].
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct TaggedSample {
+    /// The X, Y and Z-axis data.
+    pub xyz: I16x3,
+    /// The position of the sample in the FIFO.
+    pub tag: SampleTag,
+}
+
+impl TaggedSample {
+    /// Determines whether the sample can be used without reservation.
+    pub fn is_clean(&self) -> bool {
+        !self.tag.overrun_boundary
+    }
+}
+
+impl<T, E> L3GD20<T>
+where
+    T: Transport<Error = E>,
+{
+    /// Enables the FIFO in the given mode.
+    ///
+    /// The FIFO is passed through Bypass mode first, which discards its content.
+    /// Selecting [`FifoMode::Bypass`] disables the FIFO.
+    pub fn enable_fifo(&mut self, mode: FifoMode) -> Result<(), E> {
+        self.modify_register(|reg: FifoControlRegister| reg.with_fifo_mode(FifoMode::Bypass))?;
+        self.modify_register(|reg: ControlRegister5| {
+            reg.with_fifo_enable(mode != FifoMode::Bypass)
+        })?;
+        if mode != FifoMode::Bypass {
+            self.modify_register(|reg: FifoControlRegister| reg.with_fifo_mode(mode))?;
+        }
+        Ok(())
+    }
+
+    /// Disables the FIFO and discards its content.
+    pub fn disable_fifo(&mut self) -> Result<(), E> {
+        self.enable_fifo(FifoMode::Bypass)
+    }
+
+    /// Sets the FIFO watermark level, clamped to [`MAX_WATERMARK`].
+    pub fn set_fifo_watermark(&mut self, level: u8) -> Result<(), E> {
+        let level = level.min(MAX_WATERMARK);
+        self.modify_register(|reg: FifoControlRegister| reg.with_watermark(level))
+    }
+
+    /// Reads the state of the FIFO.
+    pub fn fifo_status(&mut self) -> Result<FifoStatus, E> {
+        let src: FifoSourceRegister = self.read_register()?;
+        Ok(FifoStatus {
+            level: fifo_level(src),
+            empty: src.empty(),
+            overrun: src.ovrn_fifo(),
+            watermark: src.wtm(),
+        })
+    }
+
+    /// Drains the FIFO into the buffer in a single burst transaction.
+    ///
+    /// Reads as many samples as are stored in the FIFO, up to the length of the buffer,
+    /// and returns their number. This relies on the read address wrapping from `OUT_Z_H`
+    /// back to `OUT_X_L` while the FIFO is enabled.
+    pub fn read_fifo(&mut self, samples: &mut [I16x3]) -> Result<usize, E> {
+        let level = usize::from(self.fifo_status()?.level);
+        let count = level.min(samples.len());
+        self.read_fifo_burst(count, |index, xyz| samples[index] = xyz)?;
+        Ok(count)
+    }
+
+    /// Drains the FIFO like [`read_fifo`](Self::read_fifo), tagging each sample with its
+    /// position relative to the watermark and to samples lost to an overrun.
+    ///
+    /// The tags are derived from `FIFO_CTRL_REG` and `FIFO_SRC_REG`, which are read in a
+    /// single transaction before draining.
+    pub fn read_fifo_tagged(&mut self, samples: &mut [TaggedSample]) -> Result<usize, E> {
+        let mut buffer = [
+            framing::read_multi_cmd(*FifoControlRegister::REGISTER_ADDRESS),
+            0,
+            0,
+        ];
+        self.transfer(&mut buffer)?;
+        let [_, fifo_ctrl, fifo_src] = buffer;
+
+        let src = FifoSourceRegister::from_bits(fifo_src);
+        let level = fifo_level(src);
+        let count = usize::from(level).min(samples.len());
+        let watermark = fifo_ctrl & MAX_WATERMARK;
+        // In FIFO mode, the FIFO stops filling when full, so samples are lost after the
+        // newest one; in the streaming modes, the oldest samples are overwritten.
+        let boundary = match (
+            src.ovrn_fifo(),
+            fifo_ctrl >> 5 == FifoMode::FIFO.into_bits(),
+        ) {
+            (false, _) => None,
+            (true, true) => Some(usize::from(level).saturating_sub(1)),
+            (true, false) => Some(0),
+        };
+
+        self.read_fifo_burst(count, |index, xyz| {
+            samples[index] = TaggedSample {
+                xyz,
+                tag: SampleTag {
+                    over_watermark: index + 1 >= usize::from(watermark),
+                    overrun_boundary: boundary == Some(index),
+                },
+            };
+        })
+        .map(|_| count)
+    }
+
+    /// Reads the given number of samples off the FIFO in a single burst transaction.
+    fn read_fifo_burst<F>(&mut self, count: usize, mut f: F) -> Result<(), E>
+    where
+        F: FnMut(usize, I16x3),
+    {
+        if count == 0 {
+            return Ok(());
+        }
+
+        let mut buffer = [0; 1 + FIFO_DEPTH * SAMPLE_LEN];
+        let buffer = &mut buffer[..=count * SAMPLE_LEN];
+        buffer[0] = framing::read_multi_cmd(*OutXLow::REGISTER_ADDRESS);
+        self.transfer(buffer)?;
+
+        if self.big_endian {
+            decode::swap_axis_bytes(&mut buffer[1..]);
+        }
+        for (index, bytes) in buffer[1..].chunks_exact(SAMPLE_LEN).enumerate() {
+            let mut data = [0; SAMPLE_LEN];
+            data.copy_from_slice(bytes);
+            f(index, self.axis_map.apply(decode::xyz(&data)));
+        }
+        Ok(())
+    }
+
+    /// Starts batched acquisition through the FIFO in Stream mode.
+    ///
+    /// Sets the watermark level, clamped to [`MAX_WATERMARK`], enables Stream mode and
+    /// routes the watermark interrupt to the INT2/DRDY pin (`I2_WTM`) in place of the
+    /// data-ready interrupt until [`stop_streaming`](Self::stop_streaming) restores the
+    /// previous routing. The interrupt handler then drains the FIFO with
+    /// [`on_watermark`](Self::on_watermark), once per batch rather than once per sample.
+    pub fn start_streaming(&mut self, watermark: u8) -> Result<(), E> {
+        self.set_fifo_watermark(watermark)?;
+        self.enable_fifo(FifoMode::Stream)?;
+        let current = self.drdy_config()?;
+        let previous = self.configure_drdy(DrdyConfig {
+            data_ready: false,
+            watermark: true,
+            ..current
+        })?;
+        // Keep the routing from before the first start when streaming is restarted.
+        self.streaming_drdy.get_or_insert(previous);
+        Ok(())
+    }
+
+    /// Stops the acquisition started by [`start_streaming`](Self::start_streaming).
+    ///
+    /// Restores the INT2/DRDY routing from before streaming was started and disables the
+    /// FIFO, discarding its content.
+    pub fn stop_streaming(&mut self) -> Result<(), E> {
+        let current = self.drdy_config()?;
+        let routing = self.streaming_drdy.take().unwrap_or(DrdyConfig {
+            watermark: false,
+            ..current
+        });
+        self.configure_drdy(DrdyConfig {
+            open_drain: current.open_drain,
+            ..routing
+        })?;
+        self.disable_fifo()
+    }
+
+    /// Drains the FIFO after a watermark interrupt and passes the samples to the closure.
+    ///
+    /// The samples are read in a single burst, oldest first, and their number is returned.
+    /// The closure is not called if the FIFO is empty.
+    pub fn on_watermark<F>(&mut self, f: F) -> Result<usize, E>
+    where
+        F: FnOnce(&[I16x3]),
+    {
+        let mut samples = [I16x3::new(0, 0, 0); FIFO_DEPTH];
+        let count = self.read_fifo(&mut samples)?;
+        if count > 0 {
+            f(&samples[..count]);
+        }
+        Ok(count)
+    }
+
+    /// Enables routing the FIFO empty interrupt to the INT2/DRDY pin (`I2_Empty`).
+    pub fn enable_fifo_empty_interrupt(&mut self, enabled: bool) -> Result<(), E> {
+        self.modify_register(|reg: ControlRegister3| reg.with_i2empty(enabled))
+    }
+
+    /// Determines whether the FIFO is empty.
+    pub fn fifo_empty(&mut self) -> Result<bool, E> {
+        Ok(self.read_register::<FifoSourceRegister>()?.empty())
+    }
+
+    /// Blocks until the FIFO is empty, polling the FIFO source register.
+    ///
+    /// Returns `true` if the FIFO was found empty, or `false` if the timeout elapsed first.
+    pub fn wait_fifo_empty<D>(&mut self, delay: &mut D, timeout_us: u32) -> Result<bool, E>
+    where
+        D: DelayUs<u32>,
+    {
+        let mut waited = 0;
+        loop {
+            if self.fifo_empty()? {
+                return Ok(true);
+            }
+            if waited >= timeout_us {
+                return Ok(false);
+            }
+            self.feed_watchdog();
+            delay.delay_us(EMPTY_POLL_INTERVAL_US);
+            waited = waited.saturating_add(EMPTY_POLL_INTERVAL_US);
+        }
+    }
+
+    /// Records all samples produced during a time window.
+    ///
+    /// The FIFO is switched to Stream mode for the duration of the window and drained
+    /// every millisecond, so that windows longer than the 32-sample FIFO depth can be
+    /// captured. Capturing stops early once `N` samples were collected. The previous
+    /// FIFO configuration is restored afterwards.
+    ///
+    /// This gives an easy way to record e.g. 100 ms of rotation around an event for
+    /// impact or gesture analysis.
+    pub fn capture_window<D, const N: usize>(
+        &mut self,
+        duration_ms: u32,
+        delay: &mut D,
+    ) -> Result<heapless::Vec<SensorData, N>, E>
+    where
+        D: DelayUs<u32>,
+    {
+        let mut samples = heapless::Vec::new();
+        self.capture(duration_ms, delay, &mut samples)?;
+        Ok(samples)
+    }
+
+    /// Records the samples of a time window of the given duration, in milliseconds,
+    /// into a heap-allocated vector.
+    ///
+    /// This behaves like [`capture_window`](Self::capture_window) without a limit
+    /// on the number of samples.
+    #[cfg(feature = "alloc")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "alloc")))]
+    pub fn capture_window_vec<D>(
+        &mut self,
+        duration_ms: u32,
+        delay: &mut D,
+    ) -> Result<alloc::vec::Vec<SensorData>, E>
+    where
+        D: DelayUs<u32>,
+    {
+        let mut samples = alloc::vec::Vec::new();
+        self.capture(duration_ms, delay, &mut samples)?;
+        Ok(samples)
+    }
+
+    /// Records a time window into the sink and restores the FIFO configuration afterwards.
+    fn capture<D, S>(&mut self, duration_ms: u32, delay: &mut D, samples: &mut S) -> Result<(), E>
+    where
+        D: DelayUs<u32>,
+        S: SampleSink,
+    {
+        let ctrl5: ControlRegister5 = self.read_register()?;
+        let fifo_ctrl: FifoControlRegister = self.read_register()?;
+
+        let result = self.capture_into(duration_ms, delay, samples);
+        let restored = self
+            .write_register(fifo_ctrl.with_fifo_mode(FifoMode::Bypass))
+            .and_then(|_| self.write_register(ctrl5))
+            .and_then(|_| self.write_register(fifo_ctrl));
+        result?;
+        restored
+    }
+
+    /// Runs the capture loop of [`capture_window`](Self::capture_window).
+    fn capture_into<D, S>(
+        &mut self,
+        duration_ms: u32,
+        delay: &mut D,
+        samples: &mut S,
+    ) -> Result<(), E>
+    where
+        D: DelayUs<u32>,
+        S: SampleSink,
+    {
+        // Switching to Bypass mode first discards any stale FIFO content.
+        self.modify_register(|reg: FifoControlRegister| reg.with_fifo_mode(FifoMode::Bypass))?;
+        self.modify_register(|reg: ControlRegister5| reg.with_fifo_enable(true))?;
+        self.modify_register(|reg: FifoControlRegister| reg.with_fifo_mode(FifoMode::Stream))?;
+
+        let mut elapsed = 0;
+        while elapsed < duration_ms && samples.remaining() > 0 {
+            self.feed_watchdog();
+            delay.delay_us(1000);
+            elapsed += 1;
+            self.drain_fifo_into(samples)?;
+        }
+        Ok(())
+    }
+
+    /// Reads all samples currently stored in the FIFO into the sink, until it is full.
+    ///
+    /// The samples are read in a single burst like in [`read_fifo`](Self::read_fifo). As
+    /// the FIFO only stores the axes, the temperature is read once per drain, and each
+    /// sample is reported as fresh, being a distinct output of the sensor.
+    fn drain_fifo_into<S>(&mut self, samples: &mut S) -> Result<(), E>
+    where
+        S: SampleSink,
+    {
+        let level = fifo_level(self.read_register()?);
+        let count = usize::from(level).min(samples.remaining());
+        if count == 0 {
+            return Ok(());
+        }
+
+        let temperature = self.temp_raw()?;
+        let status = StatusRegister::from_bits(FRESH_STATUS);
+        self.read_fifo_burst(count, |_, xyz| {
+            samples.push(SensorData::new(temperature, xyz.x, xyz.y, xyz.z, status));
+        })
+    }
+
+    /// Waits until the FIFO is empty, polling the FIFO source register and awaiting
+    /// the delay between polls.
+    ///
+    /// Returns `true` if the FIFO was found empty, or `false` if the timeout elapsed first.
+    #[cfg(feature = "async")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "async")))]
+    pub async fn wait_fifo_empty_async<D>(
+        &mut self,
+        delay: &mut D,
+        timeout_us: u32,
+    ) -> Result<bool, E>
+    where
+        D: embedded_hal_async::delay::DelayNs,
+    {
+        let mut waited = 0;
+        loop {
+            if self.fifo_empty()? {
+                return Ok(true);
+            }
+            if waited >= timeout_us {
+                return Ok(false);
+            }
+            self.feed_watchdog();
+            delay.delay_us(EMPTY_POLL_INTERVAL_US).await;
+            waited = waited.saturating_add(EMPTY_POLL_INTERVAL_US);
+        }
+    }
+}
+
+/// A buffer that captured samples are collected in.
+trait SampleSink {
+    /// Gets the number of samples that can still be stored.
+    fn remaining(&self) -> usize;
+
+    /// Stores a sample, returning `false` if the sink is full.
+    fn push(&mut self, data: SensorData) -> bool;
+}
+
+impl<const N: usize> SampleSink for heapless::Vec<SensorData, N> {
+    fn remaining(&self) -> usize {
+        N - self.len()
+    }
+
+    fn push(&mut self, data: SensorData) -> bool {
+        heapless::Vec::push(self, data).is_ok()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl SampleSink for alloc::vec::Vec<SensorData> {
+    fn remaining(&self) -> usize {
+        usize::MAX
+    }
+
+    fn push(&mut self, data: SensorData) -> bool {
+        alloc::vec::Vec::push(self, data);
+        true
+    }
+}
+
+/// Determines the number of unread samples in the FIFO.
+///
+/// The five-bit `FSS` field wraps to zero when all 32 slots are filled, in which case
+/// the empty flag is used to tell a full FIFO apart from an empty one.
+pub(crate) fn fifo_level(src: FifoSourceRegister) -> u8 {
+    match (src.fss(), src.empty()) {
+        (_, true) => 0,
+        (0, false) => 32,
+        (level, false) => level,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{MockError, NoDelay};
+    use crate::sim::Simulator;
+    use core::sync::atomic::{AtomicUsize, Ordering};
+    use embedded_hal::blocking::delay::DelayUs;
+
+    fn driver_with_fifo_level(level: u8) -> L3GD20<Simulator<MockError>> {
+        let mut sim = Simulator::<MockError>::new();
+        sim.registers_mut()[0x2F] = level;
+        L3GD20::with_transport(sim).unwrap()
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn wait_fifo_empty_async_is_bounded() {
+        struct CountingDelay(u32);
+
+        impl embedded_hal_async::delay::DelayNs for CountingDelay {
+            async fn delay_ns(&mut self, ns: u32) {
+                self.0 += ns / 1000;
+            }
+        }
+
+        let mut driver = driver_with_fifo_level(3);
+        let mut delay = CountingDelay(0);
+        let empty = crate::mock::block_on(driver.wait_fifo_empty_async(&mut delay, 2000));
+        assert!(!empty.unwrap());
+        assert_eq!(delay.0, 2000);
+
+        driver.transport.registers_mut()[0x2F] = 0x20;
+        let empty = crate::mock::block_on(driver.wait_fifo_empty_async(&mut delay, 2000));
+        assert!(empty.unwrap());
+        assert_eq!(delay.0, 2000);
+    }
+
+    #[test]
+    fn capture_window_drains_the_simulated_fifo() {
+        struct CountingDelay(u32);
+
+        impl DelayUs<u32> for CountingDelay {
+            fn delay_us(&mut self, us: u32) {
+                self.0 += us;
+            }
+        }
+
+        let mut driver = L3GD20::with_transport(Simulator::<MockError>::new()).unwrap();
+        driver.transport.registers_mut()[0x2E] = 0x45;
+        for i in 0..5 {
+            driver.transport.push_fifo(i, -i, 2 * i);
+        }
+
+        let mut delay = CountingDelay(0);
+        let samples = driver.capture_window::<_, 16>(3, &mut delay).unwrap();
+        assert_eq!(delay.0, 3000);
+        assert_eq!(samples.len(), 5);
+        for (i, data) in (0..5).zip(&samples) {
+            assert_eq!((*data.x, *data.y, *data.z), (i, -i, 2 * i));
+        }
+
+        assert!(driver.fifo_empty().unwrap());
+        assert_eq!(driver.transport.registers_mut()[0x2E], 0x45);
+        assert_eq!(driver.transport.registers_mut()[0x24] & 0b0100_0000, 0);
+    }
+
+    #[test]
+    fn capture_window_drains_in_a_single_burst() {
+        let mut driver = L3GD20::with_transport(Simulator::<MockError>::new()).unwrap();
+        driver.enable_fifo(FifoMode::Stream).unwrap();
+        driver.transport.set_temperature(42);
+        for i in 0..5 {
+            driver.transport.push_fifo(i, -i, 2 * i);
+        }
+
+        let mut samples = heapless::Vec::<SensorData, 4>::new();
+        let transfers = driver.transport.transfers();
+        driver.drain_fifo_into(&mut samples).unwrap();
+        // FIFO_SRC_REG, OUT_TEMP and the burst.
+        assert_eq!(driver.transport.transfers(), transfers + 3);
+        assert_eq!(samples.len(), 4);
+        for (i, data) in (0..4).zip(&samples) {
+            assert_eq!(data.temperature, 42);
+            assert_eq!(data.freshness(), crate::Freshness::Fresh);
+            assert_eq!(I16x3::from(*data), I16x3::new(i, -i, 2 * i));
+        }
+    }
+
+    #[test]
+    fn capture_window_stops_when_full() {
+        let mut driver = driver_with_fifo_level(3);
+        let samples = driver.capture_window::<_, 5>(10, &mut NoDelay).unwrap();
+        assert_eq!(samples.len(), 5);
+    }
+
+    #[test]
+    fn capture_window_feeds_the_watchdog() {
+        static FED: AtomicUsize = AtomicUsize::new(0);
+
+        let mut driver = driver_with_fifo_level(1);
+        driver.set_watchdog_feed(Some(|| {
+            FED.fetch_add(1, Ordering::Relaxed);
+        }));
+        driver.capture_window::<_, 32>(4, &mut NoDelay).unwrap();
+        assert_eq!(FED.load(Ordering::Relaxed), 4);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn capture_window_vec_is_unbounded() {
+        let mut driver = driver_with_fifo_level(0);
+        let samples = driver.capture_window_vec(4, &mut NoDelay).unwrap();
+        assert_eq!(samples.len(), 4 * 32);
+    }
+
+    #[test]
+    fn read_fifo_drains_in_a_single_burst() {
+        let mut driver = L3GD20::with_transport(Simulator::<MockError>::new()).unwrap();
+        driver.enable_fifo(FifoMode::Stream).unwrap();
+        driver.set_fifo_watermark(2).unwrap();
+        for i in 0..3 {
+            driver.transport.push_fifo(i, -i, 10 * i);
+        }
+
+        let status = driver.fifo_status().unwrap();
+        assert_eq!(
+            status,
+            FifoStatus {
+                level: 3,
+                empty: false,
+                overrun: false,
+                watermark: true,
+            }
+        );
+
+        let mut samples = [I16x3::new(0, 0, 0); 2];
+        let transfers = driver.transport.transfers();
+        assert_eq!(driver.read_fifo(&mut samples).unwrap(), 2);
+        assert_eq!(driver.transport.transfers(), transfers + 2);
+        assert_eq!(samples, [I16x3::new(0, 0, 0), I16x3::new(1, -1, 10)]);
+
+        assert_eq!(driver.read_fifo(&mut samples).unwrap(), 1);
+        assert_eq!(samples[0], I16x3::new(2, -2, 20));
+        assert!(driver.fifo_status().unwrap().empty);
+        assert_eq!(driver.read_fifo(&mut samples).unwrap(), 0);
+    }
+
+    #[test]
+    fn split_drains_keep_the_samples_intact() {
+        let mut driver = L3GD20::with_transport(Simulator::<MockError>::new()).unwrap();
+        driver.enable_fifo(FifoMode::Stream).unwrap();
+        driver.set_max_burst_len(Some(8));
+        for i in 1..=5 {
+            driver.transport.push_fifo(i, -i, 10 * i);
+        }
+
+        let mut samples = [I16x3::new(0, 0, 0); 5];
+        assert_eq!(driver.read_fifo(&mut samples).unwrap(), 5);
+        for (i, sample) in (1..).zip(samples) {
+            assert_eq!(sample, I16x3::new(i, -i, 10 * i));
+        }
+        assert!(driver.block_data_update());
+        assert_eq!(driver.settings(), Ok(driver.expected_settings));
+    }
+
+    #[test]
+    fn fifo_overrun_is_reported() {
+        let mut driver = L3GD20::with_transport(Simulator::<MockError>::new()).unwrap();
+        driver.enable_fifo(FifoMode::Stream).unwrap();
+        for i in 0..=FIFO_DEPTH as i16 {
+            driver.transport.push_fifo(i, 0, 0);
+        }
+
+        let status = driver.fifo_status().unwrap();
+        assert_eq!(status.level, 32);
+        assert!(status.overrun);
+
+        let mut samples = [I16x3::new(0, 0, 0); FIFO_DEPTH];
+        assert_eq!(driver.read_fifo(&mut samples).unwrap(), FIFO_DEPTH);
+        assert_eq!(samples[0].x, 1);
+        assert_eq!(samples[FIFO_DEPTH - 1].x, 32);
+
+        driver.disable_fifo().unwrap();
+        let ctrl5: ControlRegister5 = driver.read_register().unwrap();
+        assert!(!ctrl5.fifo_enable());
+    }
+
+    #[test]
+    fn fifo_samples_are_tagged() {
+        let mut driver = L3GD20::with_transport(Simulator::<MockError>::new()).unwrap();
+        driver.enable_fifo(FifoMode::Stream).unwrap();
+        driver.set_fifo_watermark(2).unwrap();
+        for i in 0..3 {
+            driver.transport.push_fifo(i, 0, 0);
+        }
+
+        let mut samples = [TaggedSample {
+            xyz: I16x3::new(0, 0, 0),
+            tag: SampleTag::default(),
+        }; FIFO_DEPTH];
+        assert_eq!(driver.read_fifo_tagged(&mut samples).unwrap(), 3);
+        let over_watermark = [0, 1, 2].map(|index| samples[index].tag.over_watermark);
+        assert_eq!(over_watermark, [false, true, true]);
+        assert!(samples[..3].iter().all(TaggedSample::is_clean));
+        assert_eq!(samples[2].xyz, I16x3::new(2, 0, 0));
+
+        for i in 0..=FIFO_DEPTH as i16 {
+            driver.transport.push_fifo(i, 0, 0);
+        }
+        assert_eq!(driver.read_fifo_tagged(&mut samples).unwrap(), FIFO_DEPTH);
+        assert!(samples[0].tag.overrun_boundary);
+        assert_eq!(samples[0].xyz.x, 1);
+        assert!(samples[1..].iter().all(TaggedSample::is_clean));
+    }
+
+    #[test]
+    fn streaming_drains_batches_on_watermark() {
+        let mut driver = L3GD20::with_transport(Simulator::<MockError>::new()).unwrap();
+        driver.enable_data_ready(true).unwrap();
+        driver.start_streaming(4).unwrap();
+        assert_eq!(
+            driver.transport.registers()[0x22] & 0b0000_1100,
+            0b0000_0100
+        );
+        assert_eq!(
+            driver.transport.registers()[0x24] & 0b0100_0000,
+            0b0100_0000
+        );
+        assert_eq!(driver.transport.registers()[0x2E], 0b0100_0100);
+
+        for i in 0..5 {
+            driver.transport.push_fifo(i, 0, 0);
+        }
+        assert!(driver.fifo_status().unwrap().watermark);
+        let mut batch = std::vec::Vec::new();
+        let count = driver
+            .on_watermark(|samples| batch.extend(samples.iter().map(|xyz| xyz.x)))
+            .unwrap();
+        assert_eq!(count, 5);
+        assert_eq!(batch, [0, 1, 2, 3, 4]);
+        assert_eq!(driver.on_watermark(|_| unreachable!()).unwrap(), 0);
+
+        driver.stop_streaming().unwrap();
+        assert_eq!(
+            driver.transport.registers()[0x22] & 0b0000_1100,
+            0b0000_1000
+        );
+        assert_eq!(driver.transport.registers()[0x24] & 0b0100_0000, 0);
+
+        // A restart keeps the routing from before the first start.
+        driver.start_streaming(4).unwrap();
+        driver.start_streaming(8).unwrap();
+        driver.stop_streaming().unwrap();
+        assert_eq!(
+            driver.transport.registers()[0x22] & 0b0000_1100,
+            0b0000_1000
+        );
+    }
+}
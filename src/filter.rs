@@ -0,0 +1,372 @@
+//! Provides angle integration and a complementary filter.
+//!
+//! The [`Integrator`] accumulates the angular rate into an angle per axis. Since any residual
+//! bias is integrated as well, the angle drifts over time. The [`ComplementaryFilter`]
+//! corrects this drift by blending in an absolute angle reference, such as the tilt derived
+//! from an accelerometer or the heading from a magnetometer:
+//!
+//! ```text
+//! angle = α · (angle + rate · dt) + (1 − α) · reference
+//! ```
+//!
+//! With the `fixed` feature, [`IntegratorQ16`] and [`ComplementaryFilterQ16`] implement the
+//! same computations using integer math only, for MCUs without an FPU. Angles and rates are
+//! represented in Q16.16 format, i.e. `65536` represents one degree (per second), and time
+//! steps are given in microseconds. Over 1000 steps, the fixed-point results stay within
+//! 0.01° of the floating-point ones.
+
+use crate::F32x3;
+#[cfg(feature = "fixed")]
+use crate::{I16x3, Sensitivity};
+
+/// Integrates the angular rate into an angle per axis.
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Integrator {
+    angle: F32x3,
+}
+
+impl Integrator {
+    /// Creates an integrator starting at zero.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            angle: F32x3 {
+                x: 0.0,
+                y: 0.0,
+                z: 0.0,
+            },
+        }
+    }
+
+    /// Integrates the rate, in degrees/second, over the time step in seconds.
+    ///
+    /// Returns the angle integrated so far, in degrees.
+    pub fn update(&mut self, rate_dps: F32x3, dt_s: f32) -> F32x3 {
+        self.angle.x += rate_dps.x * dt_s;
+        self.angle.y += rate_dps.y * dt_s;
+        self.angle.z += rate_dps.z * dt_s;
+        self.angle
+    }
+
+    /// Gets the angle integrated so far, in degrees.
+    #[must_use]
+    pub fn angle(&self) -> F32x3 {
+        self.angle
+    }
+
+    /// Resets the angle to zero.
+    pub fn reset(&mut self) {
+        *self = Self::new();
+    }
+}
+
+/// A complementary filter fusing the angular rate with an absolute angle reference.
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ComplementaryFilter {
+    alpha: f32,
+    angle: F32x3,
+}
+
+impl ComplementaryFilter {
+    /// Creates a filter starting at zero.
+    ///
+    /// `alpha` is the weight of the integrated rate in `[0, 1]`; values close to one trust
+    /// the gyroscope in the short term and the reference in the long term.
+    #[must_use]
+    pub fn new(alpha: f32) -> Self {
+        Self {
+            alpha: alpha.clamp(0.0, 1.0),
+            angle: F32x3::new(0.0, 0.0, 0.0),
+        }
+    }
+
+    /// Gets the weight of the integrated rate.
+    #[must_use]
+    pub fn alpha(&self) -> f32 {
+        self.alpha
+    }
+
+    /// Updates the filter with the rate in degrees/second and the reference angle in
+    /// degrees, over the time step in seconds.
+    ///
+    /// Returns the filtered angle, in degrees.
+    pub fn update(&mut self, rate_dps: F32x3, reference_deg: F32x3, dt_s: f32) -> F32x3 {
+        let alpha = self.alpha;
+        let blend = |angle: f32, rate: f32, reference: f32| {
+            alpha * (angle + rate * dt_s) + (1.0 - alpha) * reference
+        };
+        self.angle = F32x3::new(
+            blend(self.angle.x, rate_dps.x, reference_deg.x),
+            blend(self.angle.y, rate_dps.y, reference_deg.y),
+            blend(self.angle.z, rate_dps.z, reference_deg.z),
+        );
+        self.angle
+    }
+
+    /// Gets the filtered angle, in degrees.
+    #[must_use]
+    pub fn angle(&self) -> F32x3 {
+        self.angle
+    }
+
+    /// Sets the filtered angle, e.g. to initialize it from the reference.
+    pub fn set_angle(&mut self, angle_deg: F32x3) {
+        self.angle = angle_deg;
+    }
+}
+
+/// The value representing `1.0` in Q16.16 format.
+#[cfg(feature = "fixed")]
+#[cfg_attr(docsrs, doc(cfg(feature = "fixed")))]
+pub const Q16_ONE: i32 = 1 << 16;
+
+/// The value representing `1.0` in Q15 format, as used for the filter weight.
+#[cfg(feature = "fixed")]
+#[cfg_attr(docsrs, doc(cfg(feature = "fixed")))]
+pub const Q15_ONE: u16 = 1 << 15;
+
+/// Converts a raw reading to degrees/second in Q16.16 format at the given full scale.
+#[cfg(feature = "fixed")]
+#[cfg_attr(docsrs, doc(cfg(feature = "fixed")))]
+#[must_use]
+pub fn rate_q16(raw: I16x3, full_scale: Sensitivity) -> [i32; 3] {
+    let sensitivity = i64::from(full_scale.centi_mdps_per_lsb());
+    // One LSB corresponds to sensitivity / 100 000 dps.
+    let scale = |value: i16| {
+        let scaled = i64::from(value) * sensitivity * i64::from(Q16_ONE);
+        div_round(scaled, 100_000) as i32
+    };
+    [scale(raw.x), scale(raw.y), scale(raw.z)]
+}
+
+/// Divides, rounding to the nearest integer with ties away from zero.
+#[cfg(feature = "fixed")]
+fn div_round(value: i64, divisor: i64) -> i64 {
+    if value < 0 {
+        (value - divisor / 2) / divisor
+    } else {
+        (value + divisor / 2) / divisor
+    }
+}
+
+/// Integrates `rate · dt` for a rate in Q16.16 and a time step in microseconds.
+#[cfg(feature = "fixed")]
+fn step_q16(rate: i32, dt_us: u32) -> i64 {
+    div_round(i64::from(rate) * i64::from(dt_us), 1_000_000)
+}
+
+/// Saturates a 64-bit intermediate to the Q16.16 range.
+#[cfg(feature = "fixed")]
+fn saturate(value: i64) -> i32 {
+    value.clamp(i64::from(i32::MIN), i64::from(i32::MAX)) as i32
+}
+
+/// An integer-only [`Integrator`] operating in Q16.16 degrees.
+#[cfg(feature = "fixed")]
+#[cfg_attr(docsrs, doc(cfg(feature = "fixed")))]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct IntegratorQ16 {
+    angle: [i32; 3],
+}
+
+#[cfg(feature = "fixed")]
+impl IntegratorQ16 {
+    /// Creates an integrator starting at zero.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self { angle: [0; 3] }
+    }
+
+    /// Integrates the rate, in Q16.16 degrees/second, over the time step in microseconds.
+    ///
+    /// Returns the angle integrated so far, in Q16.16 degrees, saturating on overflow.
+    pub fn update(&mut self, rate_q16: [i32; 3], dt_us: u32) -> [i32; 3] {
+        for (angle, rate) in self.angle.iter_mut().zip(rate_q16) {
+            *angle = saturate(i64::from(*angle) + step_q16(rate, dt_us));
+        }
+        self.angle
+    }
+
+    /// Gets the angle integrated so far, in Q16.16 degrees.
+    #[must_use]
+    pub fn angle(&self) -> [i32; 3] {
+        self.angle
+    }
+
+    /// Resets the angle to zero.
+    pub fn reset(&mut self) {
+        *self = Self::new();
+    }
+}
+
+/// An integer-only [`ComplementaryFilter`] operating in Q16.16 degrees.
+#[cfg(feature = "fixed")]
+#[cfg_attr(docsrs, doc(cfg(feature = "fixed")))]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ComplementaryFilterQ16 {
+    alpha: u16,
+    angle: [i32; 3],
+}
+
+#[cfg(feature = "fixed")]
+impl ComplementaryFilterQ16 {
+    /// Creates a filter starting at zero.
+    ///
+    /// `alpha_q15` is the weight of the integrated rate in Q15 format, limited to
+    /// [`Q15_ONE`], which represents `1.0`.
+    #[must_use]
+    pub fn new(alpha_q15: u16) -> Self {
+        Self {
+            alpha: alpha_q15.min(Q15_ONE),
+            angle: [0; 3],
+        }
+    }
+
+    /// Gets the weight of the integrated rate in Q15 format.
+    #[must_use]
+    pub fn alpha(&self) -> u16 {
+        self.alpha
+    }
+
+    /// Updates the filter with the rate in Q16.16 degrees/second and the reference angle
+    /// in Q16.16 degrees, over the time step in microseconds.
+    ///
+    /// Returns the filtered angle, in Q16.16 degrees.
+    pub fn update(&mut self, rate_q16: [i32; 3], reference_q16: [i32; 3], dt_us: u32) -> [i32; 3] {
+        let alpha = i64::from(self.alpha);
+        let beta = i64::from(Q15_ONE) - alpha;
+        for ((angle, rate), reference) in self.angle.iter_mut().zip(rate_q16).zip(reference_q16) {
+            let predicted = i64::from(*angle) + step_q16(rate, dt_us);
+            let blended = alpha * predicted + beta * i64::from(reference);
+            *angle = saturate(div_round(blended, i64::from(Q15_ONE)));
+        }
+        self.angle
+    }
+
+    /// Gets the filtered angle, in Q16.16 degrees.
+    #[must_use]
+    pub fn angle(&self) -> [i32; 3] {
+        self.angle
+    }
+
+    /// Sets the filtered angle in Q16.16 degrees, e.g. to initialize it from the reference.
+    pub fn set_angle(&mut self, angle_q16: [i32; 3]) {
+        self.angle = angle_q16;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn integrator_accumulates_the_rate() {
+        let mut integrator = Integrator::new();
+        integrator.update(F32x3::new(90.0, -45.0, 0.0), 0.5);
+        let angle = integrator.update(F32x3::new(90.0, -45.0, 10.0), 0.5);
+        assert_eq!(angle, F32x3::new(90.0, -45.0, 5.0));
+    }
+
+    #[test]
+    fn complementary_filter_converges_to_the_reference() {
+        let mut filter = ComplementaryFilter::new(0.9);
+        let reference = F32x3::new(10.0, 0.0, -10.0);
+        for _ in 0..200 {
+            filter.update(F32x3::new(0.0, 0.0, 0.0), reference, 0.01);
+        }
+        let angle = filter.angle();
+        assert!(libm::fabsf(angle.x - 10.0) < 1e-3);
+        assert!(libm::fabsf(angle.z + 10.0) < 1e-3);
+    }
+
+    #[cfg(feature = "fixed")]
+    fn to_f32(value: [i32; 3]) -> F32x3 {
+        let one = Q16_ONE as f32;
+        F32x3::new(
+            value[0] as f32 / one,
+            value[1] as f32 / one,
+            value[2] as f32 / one,
+        )
+    }
+
+    #[cfg(feature = "fixed")]
+    fn assert_close(a: F32x3, b: F32x3, tolerance: f32) {
+        assert!(libm::fabsf(a.x - b.x) <= tolerance, "{a:?} != {b:?}");
+        assert!(libm::fabsf(a.y - b.y) <= tolerance, "{a:?} != {b:?}");
+        assert!(libm::fabsf(a.z - b.z) <= tolerance, "{a:?} != {b:?}");
+    }
+
+    /// A deterministic raw rate sequence covering both signs and large values.
+    #[cfg(feature = "fixed")]
+    fn raw(step: i32) -> I16x3 {
+        let wave = |period: i32, amplitude: i32| {
+            let phase = step % period;
+            let half = period / 2;
+            let triangle = if phase < half { phase } else { period - phase };
+            (amplitude * (2 * triangle - half) / half) as i16
+        };
+        I16x3::new(wave(100, 20_000), wave(37, 3_000), wave(250, -32_000))
+    }
+
+    #[cfg(feature = "fixed")]
+    #[test]
+    fn fixed_integrator_matches_float() {
+        let full_scale = Sensitivity::D500;
+        let dt_us = 10_000;
+        let mut float = Integrator::new();
+        let mut fixed = IntegratorQ16::new();
+        for step in 0..1000 {
+            let raw = raw(step);
+            let rate = to_f32(rate_q16(raw, full_scale));
+            let sensitivity = 0.0175;
+            assert_close(
+                rate,
+                F32x3::new(
+                    f32::from(raw.x) * sensitivity,
+                    f32::from(raw.y) * sensitivity,
+                    f32::from(raw.z) * sensitivity,
+                ),
+                1e-4,
+            );
+
+            float.update(rate, dt_us as f32 * 1e-6);
+            fixed.update(rate_q16(raw, full_scale), dt_us);
+        }
+        assert_close(float.angle(), to_f32(fixed.angle()), 0.01);
+    }
+
+    #[cfg(feature = "fixed")]
+    #[test]
+    fn fixed_complementary_filter_matches_float() {
+        let full_scale = Sensitivity::D250;
+        let dt_us = 5_000;
+        // 0.98 in Q15, so that both filters use the exact same weight.
+        let alpha = 32113;
+        let mut float = ComplementaryFilter::new(f32::from(alpha) / f32::from(Q15_ONE));
+        let mut fixed = ComplementaryFilterQ16::new(alpha);
+        let mut max_error = 0f32;
+        for step in 0..1000 {
+            let raw = raw(step);
+            let reference = [step * 1000, -step * 2000, 45 * Q16_ONE];
+
+            let expected = float.update(
+                to_f32(rate_q16(raw, full_scale)),
+                to_f32(reference),
+                dt_us as f32 * 1e-6,
+            );
+            let actual = to_f32(fixed.update(rate_q16(raw, full_scale), reference, dt_us));
+            for (a, b) in [
+                (expected.x, actual.x),
+                (expected.y, actual.y),
+                (expected.z, actual.z),
+            ] {
+                max_error = max_error.max(libm::fabsf(a - b));
+            }
+        }
+        assert!(max_error <= 0.01, "{max_error}");
+    }
+}
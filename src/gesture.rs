@@ -0,0 +1,245 @@
+//! Provides detection of simple rotation gestures.
+
+use crate::{Axis, F32x3};
+
+/// The direction of a rotation about an axis.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Direction {
+    /// A rotation with positive angular rate.
+    Positive,
+    /// A rotation with negative angular rate.
+    Negative,
+}
+
+/// A detected gesture.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Gesture {
+    /// A sustained rotation about an axis in one direction.
+    Rotation {
+        /// The axis of rotation.
+        axis: Axis,
+        /// The direction of rotation.
+        direction: Direction,
+    },
+    /// An oscillating back-and-forth rotation about an axis.
+    Shake {
+        /// The axis of rotation.
+        axis: Axis,
+    },
+}
+
+/// Configuration of the sustained rotation gesture.
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct RotationConfig {
+    /// The angular rate to exceed, in degrees/second.
+    pub threshold: f32,
+    /// The time the rate needs to be exceeded for, in milliseconds.
+    pub min_duration_ms: u32,
+}
+
+impl Default for RotationConfig {
+    fn default() -> Self {
+        Self {
+            threshold: 90.0,
+            min_duration_ms: 200,
+        }
+    }
+}
+
+/// Configuration of the shake gesture.
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ShakeConfig {
+    /// The angular rate to exceed in either direction, in degrees/second.
+    pub threshold: f32,
+    /// The number of direction reversals required.
+    pub min_reversals: u8,
+    /// The time window the reversals need to occur in, in milliseconds.
+    pub window_ms: u32,
+}
+
+impl Default for ShakeConfig {
+    fn default() -> Self {
+        Self {
+            threshold: 120.0,
+            min_reversals: 3,
+            window_ms: 800,
+        }
+    }
+}
+
+/// Per-axis detector state.
+#[derive(Debug, Copy, Clone, Default)]
+struct AxisState {
+    /// The direction the rate currently exceeds the rotation threshold in.
+    rotation: Option<Direction>,
+    /// The time the rotation threshold has been exceeded for.
+    rotation_ms: u32,
+    /// Whether the current rotation was already reported.
+    rotation_reported: bool,
+    /// The direction of the last shake half-cycle.
+    shake: Option<Direction>,
+    /// The number of reversals in the current shake window.
+    reversals: u8,
+    /// The time since the first reversal of the current shake window.
+    shake_ms: u32,
+}
+
+/// Recognizes sustained rotations and shakes from a stream of angular rates.
+///
+/// Feed scaled readings (e.g. from [`L3GD20SPI::xyz_dps`](crate::L3GD20::xyz_dps)) into
+/// [`update`](GestureDetector::update) at a steady pace; detected gestures are passed to the
+/// provided callback. Each sustained rotation is reported once until the rate falls below
+/// the threshold again.
+#[derive(Debug, Clone)]
+pub struct GestureDetector {
+    rotation: RotationConfig,
+    shake: ShakeConfig,
+    axes: [AxisState; 3],
+}
+
+impl GestureDetector {
+    /// Creates a new detector.
+    #[must_use]
+    pub fn new(rotation: RotationConfig, shake: ShakeConfig) -> Self {
+        Self {
+            rotation,
+            shake,
+            axes: [AxisState::default(); 3],
+        }
+    }
+
+    /// Resets the detector state.
+    pub fn reset(&mut self) {
+        self.axes = [AxisState::default(); 3];
+    }
+
+    /// Processes a reading taken `dt_ms` milliseconds after the previous one.
+    pub fn update<F>(&mut self, rate: F32x3, dt_ms: u32, mut callback: F)
+    where
+        F: FnMut(Gesture),
+    {
+        let rates = [(Axis::X, rate.x), (Axis::Y, rate.y), (Axis::Z, rate.z)];
+        for (state, (axis, rate)) in self.axes.iter_mut().zip(rates) {
+            if let Some(direction) = Self::update_rotation(&self.rotation, state, rate, dt_ms) {
+                callback(Gesture::Rotation { axis, direction });
+            }
+            if Self::update_shake(&self.shake, state, rate, dt_ms) {
+                callback(Gesture::Shake { axis });
+            }
+        }
+    }
+
+    fn update_rotation(
+        config: &RotationConfig,
+        state: &mut AxisState,
+        rate: f32,
+        dt_ms: u32,
+    ) -> Option<Direction> {
+        let direction = direction(rate, config.threshold);
+        if direction != state.rotation {
+            state.rotation = direction;
+            state.rotation_ms = 0;
+            state.rotation_reported = false;
+            return None;
+        }
+
+        let direction = direction?;
+        state.rotation_ms = state.rotation_ms.saturating_add(dt_ms);
+        if state.rotation_reported || state.rotation_ms < config.min_duration_ms {
+            return None;
+        }
+
+        state.rotation_reported = true;
+        Some(direction)
+    }
+
+    fn update_shake(config: &ShakeConfig, state: &mut AxisState, rate: f32, dt_ms: u32) -> bool {
+        if state.reversals > 0 {
+            state.shake_ms = state.shake_ms.saturating_add(dt_ms);
+            if state.shake_ms > config.window_ms {
+                state.reversals = 0;
+                state.shake_ms = 0;
+            }
+        }
+
+        let Some(direction) = direction(rate, config.threshold) else {
+            return false;
+        };
+
+        match state.shake {
+            Some(previous) if previous != direction => {
+                state.reversals += 1;
+            }
+            _ => {}
+        }
+        state.shake = Some(direction);
+
+        if state.reversals >= config.min_reversals {
+            state.reversals = 0;
+            state.shake_ms = 0;
+            state.shake = None;
+            return true;
+        }
+        false
+    }
+}
+
+impl Default for GestureDetector {
+    fn default() -> Self {
+        Self::new(RotationConfig::default(), ShakeConfig::default())
+    }
+}
+
+/// Determines the direction in which the rate exceeds the threshold, if any.
+fn direction(rate: f32, threshold: f32) -> Option<Direction> {
+    if rate > threshold {
+        Some(Direction::Positive)
+    } else if rate < -threshold {
+        Some(Direction::Negative)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sustained_rotation_is_reported_once() {
+        let mut detector = GestureDetector::default();
+        let mut count = 0;
+        for _ in 0..50 {
+            detector.update(F32x3::new(0.0, 0.0, -100.0), 10, |gesture| {
+                assert_eq!(
+                    gesture,
+                    Gesture::Rotation {
+                        axis: Axis::Z,
+                        direction: Direction::Negative
+                    }
+                );
+                count += 1;
+            });
+        }
+        assert_eq!(count, 1);
+    }
+
+    #[test]
+    fn shake_is_reported() {
+        let mut detector = GestureDetector::default();
+        let mut shakes = 0;
+        for i in 0..40 {
+            let rate = if (i / 5) % 2 == 0 { 200.0 } else { -200.0 };
+            detector.update(F32x3::new(rate, 0.0, 0.0), 10, |gesture| {
+                if gesture == (Gesture::Shake { axis: Axis::X }) {
+                    shakes += 1;
+                }
+            });
+        }
+        assert_eq!(shakes, 2);
+    }
+}
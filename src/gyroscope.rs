@@ -0,0 +1,98 @@
+//! Provides the object-safe [`Gyroscope`] trait.
+//!
+//! Applications that need to swap the physical sensor for the [`Simulator`](crate::sim::Simulator)
+//! at runtime, e.g. in hardware-in-the-loop test rigs, can hold a `&mut dyn Gyroscope<Error = E>`
+//! instead of a concrete driver type.
+
+use crate::{Characteristics, F32x3, I16x3, SensorData, Transport, L3GD20};
+
+/// A source of angular rate readings.
+///
+/// This trait is object-safe.
+pub trait Gyroscope {
+    /// The error type of the underlying bus.
+    type Error;
+
+    /// Identifies the sensor; see [`L3GD20::identify`].
+    fn identify(&mut self) -> Result<bool, Self::Error>;
+
+    /// Obtains the sensor characteristics; see [`L3GD20::characteristics`].
+    fn characteristics(&mut self) -> Result<Characteristics, Self::Error>;
+
+    /// Fetches the raw temperature; see [`L3GD20::temp_raw`].
+    fn temp_raw(&mut self) -> Result<u8, Self::Error>;
+
+    /// Fetches the raw X, Y and Z-axis data; see [`L3GD20::xyz_raw`].
+    fn xyz_raw(&mut self) -> Result<I16x3, Self::Error>;
+
+    /// Fetches the compensated X, Y and Z-axis data in degrees per second; see [`L3GD20::xyz_dps`].
+    fn xyz_dps(&mut self) -> Result<F32x3, Self::Error>;
+
+    /// Fetches all data off the sensor; see [`L3GD20::data_raw`].
+    fn data_raw(&mut self) -> Result<SensorData, Self::Error>;
+}
+
+impl<T, E> Gyroscope for L3GD20<T>
+where
+    T: Transport<Error = E>,
+{
+    type Error = E;
+
+    fn identify(&mut self) -> Result<bool, E> {
+        L3GD20::identify(self)
+    }
+
+    fn characteristics(&mut self) -> Result<Characteristics, E> {
+        L3GD20::characteristics(self)
+    }
+
+    fn temp_raw(&mut self) -> Result<u8, E> {
+        L3GD20::temp_raw(self)
+    }
+
+    fn xyz_raw(&mut self) -> Result<I16x3, E> {
+        L3GD20::xyz_raw(self)
+    }
+
+    fn xyz_dps(&mut self) -> Result<F32x3, E> {
+        L3GD20::xyz_dps(self)
+    }
+
+    fn data_raw(&mut self) -> Result<SensorData, E> {
+        L3GD20::data_raw(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{MockCs, MockError, MockSpi};
+    use crate::sim::Simulator;
+    use crate::L3GD20SPI;
+
+    #[test]
+    fn hardware_and_simulator_are_interchangeable() {
+        let mut spi = MockSpi::new();
+        spi.set_xyz(1, 2, 3);
+        let mut hardware = L3GD20SPI::new(spi, MockCs).unwrap();
+
+        let mut sim = Simulator::<MockError>::new();
+        sim.set_xyz(-1, -2, -3);
+        let mut simulated = L3GD20::with_transport(sim).unwrap();
+
+        let gyros: [&mut dyn Gyroscope<Error = MockError>; 2] = [&mut hardware, &mut simulated];
+        let readings = gyros.map(|gyro| {
+            assert!(gyro.identify().unwrap());
+            gyro.xyz_raw().unwrap()
+        });
+        assert_eq!(readings, [I16x3::new(1, 2, 3), I16x3::new(-1, -2, -3)]);
+    }
+
+    #[test]
+    fn transport_trait_objects_drive_the_sensor() {
+        let mut sim = Simulator::<MockError>::new();
+        let transport: &mut dyn Transport<Error = MockError> = &mut sim;
+        let mut driver = L3GD20::with_transport(transport).unwrap();
+        assert!(driver.identify().unwrap());
+    }
+}
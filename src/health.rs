@@ -0,0 +1,208 @@
+//! Provides axis health checks and a degraded operating mode.
+
+use crate::{Axis, Reading, SensorData, Transport, L3GD20};
+use l3gd20_registers::ControlRegister1;
+
+/// The kind of fault detected on an axis.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum AxisFaultKind {
+    /// The axis reported the exact same value for every fresh sample.
+    Stuck,
+    /// The axis reported a value at the limits of the output range for every fresh sample.
+    Saturated,
+}
+
+/// A fault detected on a single axis.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct AxisFault {
+    /// The affected axis.
+    pub axis: Axis,
+    /// The kind of fault.
+    pub kind: AxisFaultKind,
+}
+
+/// Tracks per-axis statistics for fault detection.
+#[derive(Debug, Copy, Clone)]
+struct AxisHealth {
+    first: i16,
+    stuck: bool,
+    saturated: bool,
+}
+
+impl AxisHealth {
+    fn new(value: i16) -> Self {
+        Self {
+            first: value,
+            stuck: true,
+            saturated: true,
+        }
+    }
+
+    fn update(&mut self, value: i16) {
+        self.stuck &= value == self.first;
+        self.saturated &= value == i16::MAX || value == i16::MIN;
+    }
+
+    fn fault(&self, axis: Axis) -> Option<AxisFault> {
+        let kind = if self.saturated {
+            AxisFaultKind::Saturated
+        } else if self.stuck {
+            AxisFaultKind::Stuck
+        } else {
+            return None;
+        };
+        Some(AxisFault { axis, kind })
+    }
+}
+
+impl<T, E> L3GD20<T>
+where
+    T: Transport<Error = E>,
+{
+    /// Checks the axes for being stuck or saturated.
+    ///
+    /// Reads until the given number of samples that are fresh on all axes were collected,
+    /// giving up after 32 reads per requested sample. An axis is considered stuck if it
+    /// reports the same value for all samples, and saturated if all its values are at the
+    /// limits of the output range. Returns the first fault found, if any; if not enough
+    /// fresh samples could be obtained, no fault is reported.
+    pub fn check_axes(&mut self, samples: u8) -> Result<Option<AxisFault>, E> {
+        let samples = samples.max(2);
+        let mut attempts = u16::from(samples) * 32;
+        let mut collected = 0;
+        let mut health: Option<[AxisHealth; 3]> = None;
+
+        while collected < samples && attempts > 0 {
+            attempts -= 1;
+            let data = self.data_raw()?;
+            if !(data.x.fresh_or_overrun()
+                && data.y.fresh_or_overrun()
+                && data.z.fresh_or_overrun())
+            {
+                continue;
+            }
+
+            let values = [*data.x, *data.y, *data.z];
+            match health.as_mut() {
+                None => health = Some(values.map(AxisHealth::new)),
+                Some(health) => {
+                    for (axis, value) in health.iter_mut().zip(values) {
+                        axis.update(value);
+                    }
+                }
+            }
+            collected += 1;
+        }
+
+        let Some(health) = health else {
+            return Ok(None);
+        };
+        if collected < samples {
+            return Ok(None);
+        }
+
+        Ok(health[0]
+            .fault(Axis::X)
+            .or_else(|| health[1].fault(Axis::Y))
+            .or_else(|| health[2].fault(Axis::Z)))
+    }
+
+    /// Switches to a degraded mode in which the faulty axis is disabled.
+    ///
+    /// The axis is disabled in `CTRL_REG1` and its readings are reported as [`Reading::Stale`]
+    /// from then on, allowing two-axis operation to continue. The degraded mode is kept
+    /// across [`power_up`](Self::power_up) and [`reset`](Self::reset).
+    pub fn enter_degraded_mode(&mut self, fault: AxisFault) -> Result<(), E> {
+        self.degraded = Some(fault);
+        self.modify_register(|reg: ControlRegister1| match fault.axis {
+            Axis::X => reg.with_x_enable(false),
+            Axis::Y => reg.with_y_enable(false),
+            Axis::Z => reg.with_z_enable(false),
+        })
+    }
+
+    /// Leaves the degraded mode and re-enables all axes.
+    pub fn leave_degraded_mode(&mut self) -> Result<(), E> {
+        self.degraded = None;
+        self.modify_register(|reg: ControlRegister1| {
+            reg.with_x_enable(true)
+                .with_y_enable(true)
+                .with_z_enable(true)
+        })
+    }
+
+    /// Gets the fault that caused the driver to operate in degraded mode, if any.
+    pub fn degraded(&self) -> Option<AxisFault> {
+        self.degraded
+    }
+
+    /// Determines whether the axis is enabled, i.e. not disabled by the degraded mode.
+    pub(crate) fn axis_enabled(&self, axis: Axis) -> bool {
+        !matches!(self.degraded, Some(fault) if fault.axis == axis)
+    }
+}
+
+/// Marks the readings of an axis disabled by the degraded mode as stale.
+pub(crate) fn tag_degraded(degraded: Option<AxisFault>, mut data: SensorData) -> SensorData {
+    if let Some(fault) = degraded {
+        let reading = match fault.axis {
+            Axis::X => &mut data.x,
+            Axis::Y => &mut data.y,
+            Axis::Z => &mut data.z,
+        };
+        *reading = Reading::Stale(**reading);
+    }
+    data
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{MockCs, MockSpi};
+    use crate::L3GD20SPI;
+
+    #[test]
+    fn stuck_axis_is_detected_and_disabled() {
+        let mut spi = MockSpi::new();
+        spi.set_xyz(1, 2, 3);
+        spi.set_status(0b0000_1111);
+        let mut driver = L3GD20SPI::new(spi, MockCs).unwrap();
+
+        // All axes are stuck in the mock; the first one is reported.
+        let fault = driver.check_axes(4).unwrap().unwrap();
+        assert_eq!(
+            fault,
+            AxisFault {
+                axis: Axis::X,
+                kind: AxisFaultKind::Stuck
+            }
+        );
+
+        driver.enter_degraded_mode(fault).unwrap();
+        let reg: ControlRegister1 = driver.read_register().unwrap();
+        assert!(!reg.x_enable());
+        assert!(reg.y_enable());
+
+        let data = driver.data_raw().unwrap();
+        assert!(data.x.stale());
+        assert!(data.y.fresh());
+
+        driver.power_up().unwrap();
+        let reg: ControlRegister1 = driver.read_register().unwrap();
+        assert!(!reg.x_enable());
+    }
+
+    #[test]
+    fn saturation_takes_precedence() {
+        let mut spi = MockSpi::new();
+        spi.set_xyz(i16::MAX, 0, 0);
+        spi.set_status(0b0000_1111);
+        let mut driver = L3GD20SPI::new(spi, MockCs).unwrap();
+
+        let fault = driver.check_axes(2).unwrap().unwrap();
+        assert_eq!(fault.kind, AxisFaultKind::Saturated);
+        assert_eq!(driver.degraded(), None);
+    }
+}
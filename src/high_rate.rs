@@ -0,0 +1,124 @@
+//! Provides temporarily raising the output data rate for burst measurements.
+
+use crate::{Error, OutputDataRateExt, Transport, L3GD20};
+use embedded_hal::blocking::delay::DelayUs;
+use l3gd20_registers::{Bandwidth, ControlRegister1, OutputDataRate};
+
+/// The number of samples discarded after raising the output data rate,
+/// while the digital filter chain settles.
+const SETTLING_SAMPLES: usize = 4;
+
+/// The number of SPI clock cycles to poll the status register and read a sample.
+const SAMPLE_CLOCK_CYCLES: u32 = (2 + 7) * 8;
+
+impl<T, E> L3GD20<T>
+where
+    T: Transport<Error = E>,
+{
+    /// Runs the closure at the highest output data rate and widest bandwidth (760 Hz, 100 Hz cutoff).
+    ///
+    /// This is intended for occasional burst measurements that need fine detail, without
+    /// running at high power all the time. The first samples after the switch are discarded
+    /// while the filters settle. The previous `CTRL_REG1` configuration is restored
+    /// afterwards, even if the closure returns an error; if it fails, its error takes
+    /// precedence over any error raised while restoring.
+    ///
+    /// If the applied [`L3gd20Config`](crate::L3gd20Config) declares the SPI clock, fails
+    /// with [`Error::SpiClockTooLow`] without changing the configuration unless the bus can
+    /// poll for and read every sample at 760 Hz.
+    pub fn with_high_rate<D, F, R>(&mut self, delay: &mut D, f: F) -> Result<R, Error<E>>
+    where
+        D: DelayUs<u32>,
+        F: FnOnce(&mut Self) -> Result<R, E>,
+    {
+        if let Some(hz) = self.spi_clock_hz {
+            let required_hz = SAMPLE_CLOCK_CYCLES * u32::from(OutputDataRate::Hz760.hz());
+            if hz < required_hz {
+                return Err(Error::SpiClockTooLow { hz, required_hz });
+            }
+        }
+
+        let previous: ControlRegister1 = self.read_register().map_err(Error::Bus)?;
+        let expected = self.expected_settings;
+
+        let result = self.enter_high_rate(previous, delay).and_then(|_| f(self));
+        let restored = self.write_register(previous);
+        self.expected_settings = expected;
+        let value = result.map_err(Error::Bus)?;
+        restored.map_err(Error::Bus)?;
+        Ok(value)
+    }
+
+    /// Switches to the highest output data rate and discards the settling samples.
+    fn enter_high_rate<D>(&mut self, previous: ControlRegister1, delay: &mut D) -> Result<(), E>
+    where
+        D: DelayUs<u32>,
+    {
+        self.write_register(
+            previous
+                .with_output_data_rate(OutputDataRate::Hz760)
+                .with_bandwidth(Bandwidth::Wide),
+        )?;
+        self.expected_settings.output_data_rate = OutputDataRate::Hz760;
+        self.expected_settings.bandwidth = Bandwidth::Wide;
+
+        for _ in 0..SETTLING_SAMPLES {
+            if !self.wait_new_sample(delay)? {
+                break;
+            }
+            self.xyz_raw()?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{MockError, NoDelay};
+    use crate::sim::Simulator;
+
+    #[test]
+    fn high_rate_is_scoped() {
+        let mut sim = Simulator::<MockError>::new();
+        sim.generate(3, |n| [n as i16, 0, 0]);
+        let mut driver = L3GD20::with_transport(sim).unwrap();
+        let before: ControlRegister1 = driver.read_register().unwrap();
+
+        let x = driver
+            .with_high_rate(&mut NoDelay, |drv| {
+                let reg: ControlRegister1 = drv.read_register()?;
+                assert_eq!(reg.output_data_rate(), OutputDataRate::Hz760);
+                assert_eq!(reg.bandwidth(), Bandwidth::Wide);
+                Ok(drv.xyz_raw()?.x)
+            })
+            .unwrap();
+
+        assert!(x >= SETTLING_SAMPLES as i16);
+        assert_eq!(driver.read_register::<ControlRegister1>().unwrap(), before);
+    }
+
+    #[test]
+    fn slow_spi_clock_is_refused() {
+        let mut driver = L3GD20::with_transport(Simulator::<MockError>::new()).unwrap();
+        driver
+            .apply_config(&crate::L3gd20Config::new().with_spi_clock_hz(50_000))
+            .unwrap();
+        let before: ControlRegister1 = driver.read_register().unwrap();
+
+        let result = driver.with_high_rate(&mut NoDelay, |_| Ok(()));
+        assert_eq!(
+            result,
+            Err(Error::SpiClockTooLow {
+                hz: 50_000,
+                required_hz: 54_720
+            })
+        );
+        assert_eq!(driver.read_register::<ControlRegister1>().unwrap(), before);
+
+        driver
+            .apply_config(&crate::L3gd20Config::new().with_spi_clock_hz(1_000_000))
+            .unwrap();
+        assert!(driver.with_high_rate(&mut NoDelay, |_| Ok(())).is_ok());
+    }
+}
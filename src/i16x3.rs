@@ -17,6 +17,42 @@ impl I16x3 {
     }
 }
 
+impl From<[i16; 3]> for I16x3 {
+    fn from([x, y, z]: [i16; 3]) -> Self {
+        Self { x, y, z }
+    }
+}
+
+impl From<I16x3> for [i16; 3] {
+    fn from(value: I16x3) -> Self {
+        [value.x, value.y, value.z]
+    }
+}
+
+#[cfg(feature = "mint")]
+#[cfg_attr(docsrs, doc(cfg(feature = "mint")))]
+impl From<mint::Vector3<i16>> for I16x3 {
+    fn from(value: mint::Vector3<i16>) -> Self {
+        Self {
+            x: value.x,
+            y: value.y,
+            z: value.z,
+        }
+    }
+}
+
+#[cfg(feature = "mint")]
+#[cfg_attr(docsrs, doc(cfg(feature = "mint")))]
+impl From<I16x3> for mint::Vector3<i16> {
+    fn from(value: I16x3) -> Self {
+        Self {
+            x: value.x,
+            y: value.y,
+            z: value.z,
+        }
+    }
+}
+
 #[cfg(feature = "defmt")]
 #[cfg_attr(docsrs, doc(cfg(feature = "defmt")))]
 impl defmt::Format for I16x3 {
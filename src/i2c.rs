@@ -0,0 +1,180 @@
+//! Provides a transport over I2C.
+//!
+//! The device answers on the slave address `0x6A` or `0x6B`, depending on the level of
+//! the SA0 pin. The SPI-style frames issued by the driver are translated into I2C
+//! transactions: the register address is sent as the sub-address, with its most
+//! significant bit set to enable auto-increment for multi-byte transfers.
+
+use crate::common::framing;
+use crate::{Error, Transport, L3GD20};
+use embedded_hal::blocking::i2c::{Write, WriteRead};
+
+/// The bit of the sub-address that enables auto-increment.
+const AUTO_INCREMENT: u8 = 0b1000_0000;
+
+/// The I2C slave address of the device, as selected by the SA0 pin.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum SlaveAddress {
+    /// SA0 is connected to ground; the address is `0x6A`.
+    Sa0Low,
+    /// SA0 is connected to the supply voltage; the address is `0x6B`.
+    #[default]
+    Sa0High,
+}
+
+impl SlaveAddress {
+    /// Gets the 7-bit slave address.
+    pub const fn address(&self) -> u8 {
+        match self {
+            SlaveAddress::Sa0Low => 0x6A,
+            SlaveAddress::Sa0High => 0x6B,
+        }
+    }
+}
+
+/// A [`Transport`] over an I2C bus.
+pub struct I2cTransport<I2C> {
+    i2c: I2C,
+    address: u8,
+}
+
+impl<I2C> I2cTransport<I2C> {
+    /// Creates a new I2C transport for the device at the given address.
+    pub fn new(i2c: I2C, address: SlaveAddress) -> Self {
+        Self {
+            i2c,
+            address: address.address(),
+        }
+    }
+
+    /// Releases the I2C bus.
+    pub fn release(self) -> I2C {
+        self.i2c
+    }
+}
+
+impl<I2C, E> Transport for I2cTransport<I2C>
+where
+    I2C: Write<Error = E> + WriteRead<Error = E>,
+{
+    type Error = E;
+
+    fn transfer(&mut self, buffer: &mut [u8]) -> Result<(), Self::Error> {
+        let command = buffer[0];
+        let mut sub_address = framing::address(command);
+        if framing::is_multi(command) {
+            sub_address |= AUTO_INCREMENT;
+        }
+
+        if framing::is_read(command) {
+            self.i2c
+                .write_read(self.address, &[sub_address], &mut buffer[1..])
+        } else {
+            buffer[0] = sub_address;
+            let result = self.i2c.write(self.address, buffer);
+            buffer[0] = command;
+            result
+        }
+    }
+}
+
+/// I2C driver for the STMicroelectronics L3GD20 gyroscope.
+pub type L3GD20I2C<I2C> = L3GD20<I2cTransport<I2C>>;
+
+impl<I2C, E> L3GD20<I2cTransport<I2C>>
+where
+    I2C: Write<Error = E> + WriteRead<Error = E>,
+{
+    /// Initialize the driver over the I2C bus, addressing the device by its SA0 pin level.
+    ///
+    /// See [`with_transport`](Self::with_transport).
+    pub fn new_i2c(i2c: I2C, address: SlaveAddress) -> Result<Self, Error<E>> {
+        Self::with_transport(I2cTransport::new(i2c, address))
+    }
+
+    /// Releases the I2C bus.
+    pub fn release(self) -> I2C {
+        self.transport.release()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::MockError;
+    use crate::sim::Simulator;
+    use crate::I16x3;
+    use std::vec::Vec;
+
+    /// An I2C bus translating transactions back into frames for the simulator.
+    struct SimI2c {
+        sim: Simulator<MockError>,
+        address: u8,
+    }
+
+    impl SimI2c {
+        fn check(&self, address: u8) -> Result<(), MockError> {
+            if address == self.address {
+                Ok(())
+            } else {
+                Err(MockError)
+            }
+        }
+    }
+
+    impl Write for SimI2c {
+        type Error = MockError;
+
+        fn write(&mut self, address: u8, bytes: &[u8]) -> Result<(), MockError> {
+            self.check(address)?;
+            let mut frame = Vec::from(bytes);
+            frame[0] = if bytes[0] & AUTO_INCREMENT != 0 {
+                framing::write_multi_cmd(bytes[0])
+            } else {
+                framing::write_single_cmd(bytes[0])
+            };
+            self.sim.transfer(&mut frame)
+        }
+    }
+
+    impl WriteRead for SimI2c {
+        type Error = MockError;
+
+        fn write_read(
+            &mut self,
+            address: u8,
+            bytes: &[u8],
+            buffer: &mut [u8],
+        ) -> Result<(), MockError> {
+            self.check(address)?;
+            let mut frame = Vec::from([0]);
+            frame.resize(buffer.len() + 1, 0);
+            frame[0] = if bytes[0] & AUTO_INCREMENT != 0 {
+                framing::read_multi_cmd(bytes[0])
+            } else {
+                framing::read_single_cmd(bytes[0])
+            };
+            self.sim.transfer(&mut frame)?;
+            buffer.copy_from_slice(&frame[1..]);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn i2c_driver_reads_the_sensor() {
+        let mut sim = Simulator::new();
+        sim.set_xyz(-1, 2, -3);
+        let i2c = SimI2c { sim, address: 0x6A };
+
+        let mut driver = L3GD20I2C::new_i2c(i2c, SlaveAddress::Sa0Low).unwrap();
+        assert!(driver.identify().unwrap());
+        assert_eq!(driver.xyz_raw().unwrap(), I16x3::new(-1, 2, -3));
+
+        let wrong = SimI2c {
+            sim: Simulator::new(),
+            address: 0x6A,
+        };
+        assert!(L3GD20I2C::new_i2c(wrong, SlaveAddress::Sa0High).is_err());
+    }
+}
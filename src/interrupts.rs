@@ -0,0 +1,533 @@
+//! Provides support for the INT1 interrupt generator.
+
+use crate::common::framing;
+use crate::events::ThresholdKind;
+use crate::{Axis, Sensitivity, Transport, L3GD20};
+use l3gd20_registers::prelude::{SPIRegister, ToBits};
+use l3gd20_registers::*;
+
+/// The largest value of the 15-bit INT1 threshold, in LSB.
+pub const MAX_THRESHOLD_LSB: u16 = 0x7FFF;
+
+/// The largest value of the seven-bit INT1 duration, in samples.
+pub const MAX_DURATION: u8 = 0x7F;
+
+/// Converts an angular rate threshold to INT1 threshold LSB at the given full scale.
+///
+/// The conversion uses integer math only and rounds to the nearest LSB, so that thresholds
+/// for fixed configurations can be computed at compile time. The result saturates at
+/// [`MAX_THRESHOLD_LSB`].
+///
+/// ```
+/// use l3gd20_ng::interrupts::{dps_to_threshold_lsb, threshold_bytes};
+/// use l3gd20_ng::Sensitivity;
+///
+/// const THRESHOLD: u16 = dps_to_threshold_lsb(Sensitivity::D250, 100);
+/// const BYTES: (u8, u8) = threshold_bytes(THRESHOLD);
+/// assert_eq!(THRESHOLD, 11429);
+/// assert_eq!(BYTES, (0x2C, 0xA5));
+/// ```
+pub const fn dps_to_threshold_lsb(full_scale: Sensitivity, dps: u16) -> u16 {
+    let sensitivity = full_scale.centi_mdps_per_lsb() as u64;
+    let lsb = (dps as u64 * 100_000 + sensitivity / 2) / sensitivity;
+    if lsb > MAX_THRESHOLD_LSB as u64 {
+        MAX_THRESHOLD_LSB
+    } else {
+        lsb as u16
+    }
+}
+
+/// Splits an INT1 threshold into the values of its high and low register.
+///
+/// Bits above the 15-bit threshold range are discarded.
+pub const fn threshold_bytes(lsb: u16) -> (u8, u8) {
+    (((lsb >> 8) & 0x7F) as u8, lsb as u8)
+}
+
+/// The contiguous block of INT1 registers, `INT1_CFG` (30h) to `INT1_DURATION` (38h).
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Int1Block {
+    /// The interrupt configuration.
+    pub config: Int1ConfigurationRegister,
+    /// The interrupt source. This register is read-only and skipped when writing the block.
+    pub source: Int1SourceRegisterA,
+    /// The X-axis threshold, high byte.
+    pub threshold_xh: Int1ThresholdRegisterXH,
+    /// The X-axis threshold, low byte.
+    pub threshold_xl: Int1ThresholdRegisterXL,
+    /// The Y-axis threshold, high byte.
+    pub threshold_yh: Int1ThresholdRegisterYH,
+    /// The Y-axis threshold, low byte.
+    pub threshold_yl: Int1ThresholdRegisterYL,
+    /// The Z-axis threshold, high byte.
+    pub threshold_zh: Int1ThresholdRegisterZH,
+    /// The Z-axis threshold, low byte.
+    pub threshold_zl: Int1ThresholdRegisterZL,
+    /// The minimum event duration.
+    pub duration: Int1DurationRegister,
+}
+
+impl Int1Block {
+    /// Sets the X, Y and Z-axis thresholds in LSB, saturating at [`MAX_THRESHOLD_LSB`].
+    pub fn with_thresholds(mut self, x: u16, y: u16, z: u16) -> Self {
+        let (xh, xl) = threshold_bytes(x.min(MAX_THRESHOLD_LSB));
+        let (yh, yl) = threshold_bytes(y.min(MAX_THRESHOLD_LSB));
+        let (zh, zl) = threshold_bytes(z.min(MAX_THRESHOLD_LSB));
+        self.threshold_xh = self.threshold_xh.with_threshold(xh);
+        self.threshold_xl = self.threshold_xl.with_threshold(xl);
+        self.threshold_yh = self.threshold_yh.with_threshold(yh);
+        self.threshold_yl = self.threshold_yl.with_threshold(yl);
+        self.threshold_zh = self.threshold_zh.with_threshold(zh);
+        self.threshold_zl = self.threshold_zl.with_threshold(zl);
+        self
+    }
+}
+
+/// How the enabled INT1 threshold events are combined.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Combination {
+    /// The interrupt is raised if any enabled event occurs.
+    #[default]
+    Or,
+    /// The interrupt is raised if all enabled events occur.
+    And,
+}
+
+/// How the INT1 interrupt is de-asserted once the condition is gone (`WAIT`).
+///
+/// In both modes, the condition must persist for the [duration](Int1Config::with_duration)
+/// before the interrupt is asserted. The modes differ in how the interrupt ends, which
+/// matters for short dips below the threshold:
+///
+/// - With [`Immediate`](Self::Immediate), the interrupt falls as soon as the rate crosses
+///   back over the threshold. A rate hovering around the threshold toggles the interrupt.
+/// - With [`Wait`](Self::Wait), the interrupt only falls after the condition was gone for
+///   the duration as well, so that a brief dip does not end the event.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum DurationMode {
+    /// The interrupt falls as soon as the condition is gone (`WAIT = 0`).
+    #[default]
+    Immediate,
+    /// The interrupt falls once the condition was gone for the duration (`WAIT = 1`).
+    Wait,
+}
+
+/// The configuration of the INT1 interrupt generator, applied by [`L3GD20::configure_int1`].
+///
+/// ```
+/// use l3gd20_ng::interrupts::{dps_to_threshold_lsb, DurationMode, Int1Config};
+/// use l3gd20_ng::events::ThresholdKind;
+/// use l3gd20_ng::{Axis, Sensitivity};
+///
+/// // Wake up when rotating faster than 50 dps around Z for two samples.
+/// let config = Int1Config::new()
+///     .with_threshold(Axis::Z, dps_to_threshold_lsb(Sensitivity::D250, 50))
+///     .with_event(Axis::Z, ThresholdKind::High, true)
+///     .with_duration(2)
+///     .with_duration_mode(DurationMode::Wait)
+///     .with_latched(true);
+/// assert!(config.has_events());
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Int1Config {
+    thresholds: [u16; 3],
+    high: [bool; 3],
+    low: [bool; 3],
+    duration: u8,
+    duration_mode: DurationMode,
+    combination: Combination,
+    latched: bool,
+}
+
+impl Int1Config {
+    /// Creates a configuration with all events disabled.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            thresholds: [0; 3],
+            high: [false; 3],
+            low: [false; 3],
+            duration: 0,
+            duration_mode: DurationMode::Immediate,
+            combination: Combination::Or,
+            latched: false,
+        }
+    }
+
+    /// Sets the threshold of an axis in LSB, saturating at [`MAX_THRESHOLD_LSB`].
+    #[must_use]
+    pub const fn with_threshold(mut self, axis: Axis, lsb: u16) -> Self {
+        self.thresholds[axis_index(axis)] = if lsb > MAX_THRESHOLD_LSB {
+            MAX_THRESHOLD_LSB
+        } else {
+            lsb
+        };
+        self
+    }
+
+    /// Enables or disables the event raised when the rate of an axis crosses its threshold
+    /// in the given direction.
+    #[must_use]
+    pub const fn with_event(mut self, axis: Axis, kind: ThresholdKind, enabled: bool) -> Self {
+        match kind {
+            ThresholdKind::High => self.high[axis_index(axis)] = enabled,
+            ThresholdKind::Low => self.low[axis_index(axis)] = enabled,
+        }
+        self
+    }
+
+    /// Sets the minimum duration of an event in samples, saturating at [`MAX_DURATION`].
+    ///
+    /// See [`DurationMode`] for how the duration applies when the event ends.
+    #[must_use]
+    pub const fn with_duration(mut self, samples: u8) -> Self {
+        self.duration = if samples > MAX_DURATION {
+            MAX_DURATION
+        } else {
+            samples
+        };
+        self
+    }
+
+    /// Selects how the interrupt is de-asserted once the condition is gone.
+    #[must_use]
+    pub const fn with_duration_mode(mut self, mode: DurationMode) -> Self {
+        self.duration_mode = mode;
+        self
+    }
+
+    /// Selects [`DurationMode::Wait`] if `wait` is set, and [`DurationMode::Immediate`]
+    /// otherwise.
+    #[must_use]
+    pub const fn with_wait(mut self, wait: bool) -> Self {
+        self.duration_mode = if wait {
+            DurationMode::Wait
+        } else {
+            DurationMode::Immediate
+        };
+        self
+    }
+
+    /// Gets how the interrupt is de-asserted once the condition is gone.
+    pub const fn duration_mode(&self) -> DurationMode {
+        self.duration_mode
+    }
+
+    /// Sets how the enabled events are combined.
+    #[must_use]
+    pub const fn with_combination(mut self, combination: Combination) -> Self {
+        self.combination = combination;
+        self
+    }
+
+    /// Sets whether the interrupt is latched until `INT1_SRC` is read.
+    #[must_use]
+    pub const fn with_latched(mut self, latched: bool) -> Self {
+        self.latched = latched;
+        self
+    }
+
+    /// Determines whether any event is enabled.
+    pub fn has_events(&self) -> bool {
+        self.high
+            .iter()
+            .chain(self.low.iter())
+            .any(|enabled| *enabled)
+    }
+
+    /// Encodes the configuration as the INT1 register block.
+    pub fn to_block(&self) -> Int1Block {
+        let [x, y, z] = self.thresholds;
+        let config = Int1ConfigurationRegister::default()
+            .with_aoi(self.combination == Combination::And)
+            .with_lir(self.latched)
+            .with_xhie(self.high[0])
+            .with_xlie(self.low[0])
+            .with_yhie(self.high[1])
+            .with_ylie(self.low[1])
+            .with_zhie(self.high[2])
+            .with_zlie(self.low[2]);
+        Int1Block {
+            config,
+            duration: Int1DurationRegister::default()
+                .with_wait(self.duration_mode == DurationMode::Wait)
+                .with_duration(self.duration),
+            ..Default::default()
+        }
+        .with_thresholds(x, y, z)
+    }
+}
+
+/// Gets the index of an axis into per-axis arrays.
+const fn axis_index(axis: Axis) -> usize {
+    match axis {
+        Axis::X => 0,
+        Axis::Y => 1,
+        Axis::Z => 2,
+    }
+}
+
+/// The decoded contents of `INT1_SRC`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Int1Source {
+    /// Whether an interrupt is active.
+    pub active: bool,
+    /// Whether the X-axis rate exceeded the high threshold.
+    pub x_high: bool,
+    /// Whether the X-axis rate fell below the low threshold.
+    pub x_low: bool,
+    /// Whether the Y-axis rate exceeded the high threshold.
+    pub y_high: bool,
+    /// Whether the Y-axis rate fell below the low threshold.
+    pub y_low: bool,
+    /// Whether the Z-axis rate exceeded the high threshold.
+    pub z_high: bool,
+    /// Whether the Z-axis rate fell below the low threshold.
+    pub z_low: bool,
+}
+
+impl Int1Source {
+    /// Determines whether the event of an axis in the given direction occurred.
+    pub fn is_set(&self, axis: Axis, kind: ThresholdKind) -> bool {
+        match (axis, kind) {
+            (Axis::X, ThresholdKind::High) => self.x_high,
+            (Axis::X, ThresholdKind::Low) => self.x_low,
+            (Axis::Y, ThresholdKind::High) => self.y_high,
+            (Axis::Y, ThresholdKind::Low) => self.y_low,
+            (Axis::Z, ThresholdKind::High) => self.z_high,
+            (Axis::Z, ThresholdKind::Low) => self.z_low,
+        }
+    }
+}
+
+impl From<Int1SourceRegisterA> for Int1Source {
+    fn from(reg: Int1SourceRegisterA) -> Self {
+        Self {
+            active: reg.ia(),
+            x_high: reg.x_high(),
+            x_low: reg.x_low(),
+            y_high: reg.y_high(),
+            y_low: reg.y_low(),
+            z_high: reg.z_high(),
+            z_low: reg.z_low(),
+        }
+    }
+}
+
+impl<T, E> L3GD20<T>
+where
+    T: Transport<Error = E>,
+{
+    /// Reads all INT1 registers in a single burst transaction.
+    pub fn read_int1_block(&mut self) -> Result<Int1Block, E> {
+        let command = framing::read_multi_cmd(*Int1ConfigurationRegister::REGISTER_ADDRESS);
+        let mut buffer = [command, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+        self.transfer(&mut buffer)?;
+
+        // skip the command byte at [0].
+        Ok(Int1Block {
+            config: Int1ConfigurationRegister::from_bits(buffer[1]),
+            source: Int1SourceRegisterA::from_bits(buffer[2]),
+            threshold_xh: Int1ThresholdRegisterXH::from_bits(buffer[3]),
+            threshold_xl: Int1ThresholdRegisterXL::from_bits(buffer[4]),
+            threshold_yh: Int1ThresholdRegisterYH::from_bits(buffer[5]),
+            threshold_yl: Int1ThresholdRegisterYL::from_bits(buffer[6]),
+            threshold_zh: Int1ThresholdRegisterZH::from_bits(buffer[7]),
+            threshold_zl: Int1ThresholdRegisterZL::from_bits(buffer[8]),
+            duration: Int1DurationRegister::from_bits(buffer[9]),
+        })
+    }
+
+    /// Writes all writable INT1 registers.
+    ///
+    /// Since `INT1_SRC` (31h) is read-only and sits between `INT1_CFG` and the threshold
+    /// registers, this issues two transactions: a single write of `INT1_CFG`, followed by
+    /// a burst write of `INT1_TSH_XH` through `INT1_DURATION`.
+    pub fn write_int1_block(&mut self, block: &Int1Block) -> Result<(), E> {
+        self.write_register(block.config)?;
+
+        let command = framing::write_multi_cmd(*Int1ThresholdRegisterXH::REGISTER_ADDRESS);
+        let mut buffer = [
+            command,
+            block.threshold_xh.to_bits(),
+            block.threshold_xl.to_bits(),
+            block.threshold_yh.to_bits(),
+            block.threshold_yl.to_bits(),
+            block.threshold_zh.to_bits(),
+            block.threshold_zl.to_bits(),
+            block.duration.to_bits(),
+        ];
+        self.transfer(&mut buffer)?;
+        Ok(())
+    }
+
+    /// Applies the INT1 interrupt generator configuration.
+    ///
+    /// The registers are written with INT1 disabled, so that no spurious events are raised
+    /// by a partially applied configuration. Afterwards, the interrupt is routed to the INT1
+    /// pin (`I1_Int1`) if any event is enabled, and disconnected from it otherwise.
+    pub fn configure_int1(&mut self, config: &Int1Config) -> Result<(), E> {
+        let block = config.to_block();
+        self.with_int1_disabled(|driver| driver.write_int1_block(&block))?;
+        let enabled = config.has_events();
+        self.modify_register(|reg: ControlRegister3| reg.with_i1int1(enabled))
+    }
+
+    /// Reads and decodes `INT1_SRC`. Reading the register clears a latched interrupt.
+    pub fn int1_source(&mut self) -> Result<Int1Source, E> {
+        Ok(self.read_register::<Int1SourceRegisterA>()?.into())
+    }
+
+    /// Runs the closure with INT1 interrupt generation disabled.
+    ///
+    /// This is intended for reconfiguring the interrupt generator (e.g. thresholds) without
+    /// spurious events being raised by a partially applied configuration. The `I1_Int1` bit
+    /// of `CTRL_REG3` is cleared in a single write before the closure is called and restored
+    /// to its previous state afterwards, even if the closure returns an error.
+    /// If the closure fails, its error takes precedence over any error raised while restoring.
+    pub fn with_int1_disabled<F, R>(&mut self, f: F) -> Result<R, E>
+    where
+        F: FnOnce(&mut Self) -> Result<R, E>,
+    {
+        let reg: ControlRegister3 = self.read_register()?;
+        if !reg.i1int1() {
+            return f(self);
+        }
+
+        self.write_register(reg.with_i1int1(false))?;
+        let result = f(self);
+        let restored = self.modify_register(|reg: ControlRegister3| reg.with_i1int1(true));
+        let value = result?;
+        restored?;
+        Ok(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{MockCs, MockError, MockSpi};
+    use crate::L3GD20SPI;
+
+    #[test]
+    fn int1_is_restored_on_error() {
+        let mut driver = L3GD20SPI::new(MockSpi::new(), MockCs).unwrap();
+        driver
+            .modify_register(|reg: ControlRegister3| reg.with_i1int1(true))
+            .unwrap();
+
+        let result: Result<(), _> = driver.with_int1_disabled(|drv| {
+            let reg: ControlRegister3 = drv.read_register()?;
+            assert!(!reg.i1int1());
+            Err(MockError)
+        });
+
+        assert_eq!(result, Err(MockError));
+        let reg: ControlRegister3 = driver.read_register().unwrap();
+        assert!(reg.i1int1());
+    }
+
+    #[test]
+    fn int1_block_roundtrip() {
+        let mut driver = L3GD20SPI::new(MockSpi::new(), MockCs).unwrap();
+        let block = Int1Block {
+            config: Int1ConfigurationRegister::default().with_zhie(true),
+            threshold_zh: Int1ThresholdRegisterZH::default().with_threshold(0x12),
+            threshold_zl: Int1ThresholdRegisterZL::default().with_threshold(0x34),
+            duration: Int1DurationRegister::default().with_duration(5),
+            ..Default::default()
+        };
+
+        driver.write_int1_block(&block).unwrap();
+        assert_eq!(driver.read_int1_block().unwrap(), block);
+    }
+
+    #[test]
+    fn threshold_conversion() {
+        assert_eq!(dps_to_threshold_lsb(Sensitivity::D250, 0), 0);
+        assert_eq!(dps_to_threshold_lsb(Sensitivity::D500, 35), 2000);
+        assert_eq!(dps_to_threshold_lsb(Sensitivity::D2000, 70), 1000);
+        assert_eq!(
+            dps_to_threshold_lsb(Sensitivity::D250, 1000),
+            MAX_THRESHOLD_LSB
+        );
+        assert_eq!(
+            dps_to_threshold_lsb(Sensitivity::D2000, u16::MAX),
+            MAX_THRESHOLD_LSB
+        );
+        assert_eq!(threshold_bytes(0xFFFF), (0x7F, 0xFF));
+
+        let block = Int1Block::default().with_thresholds(0x1234, 0, 0x7FFF);
+        assert_eq!(block.threshold_xh.threshold(), 0x12);
+        assert_eq!(block.threshold_xl.threshold(), 0x34);
+        assert_eq!(block.threshold_zh.threshold(), 0x7F);
+        assert_eq!(block.threshold_zl.threshold(), 0xFF);
+    }
+
+    #[test]
+    fn int1_config_is_applied_and_source_decoded() {
+        let mut driver = L3GD20SPI::new(MockSpi::new(), MockCs).unwrap();
+        let config = Int1Config::new()
+            .with_threshold(Axis::Y, 0x0102)
+            .with_event(Axis::Y, ThresholdKind::High, true)
+            .with_event(Axis::X, ThresholdKind::Low, true)
+            .with_duration(0xFF)
+            .with_wait(true)
+            .with_combination(Combination::And)
+            .with_latched(true);
+        driver.configure_int1(&config).unwrap();
+
+        let block = driver.read_int1_block().unwrap();
+        assert_eq!(block.config.to_bits(), 0b1100_1001);
+        assert_eq!(block.threshold_yh.threshold(), 0x01);
+        assert_eq!(block.threshold_yl.threshold(), 0x02);
+        assert!(block.duration.wait());
+        assert_eq!(block.duration.duration(), 0x7F);
+        let reg: ControlRegister3 = driver.read_register().unwrap();
+        assert!(reg.i1int1());
+
+        driver.transport.spi_mut().registers[0x31] = 0b0100_1000;
+        let source = driver.int1_source().unwrap();
+        assert!(source.active);
+        assert!(source.is_set(Axis::Y, ThresholdKind::High));
+        assert!(!source.is_set(Axis::X, ThresholdKind::Low));
+
+        driver.configure_int1(&Int1Config::new()).unwrap();
+        let reg: ControlRegister3 = driver.read_register().unwrap();
+        assert!(!reg.i1int1());
+    }
+
+    #[test]
+    fn duration_mode_selects_the_wait_bit() {
+        let config = Int1Config::new().with_duration(5);
+        assert_eq!(config.duration_mode(), DurationMode::Immediate);
+        assert_eq!(config.to_block().duration.to_bits(), 0x05);
+
+        let config = config.with_duration_mode(DurationMode::Wait);
+        assert_eq!(config.to_block().duration.to_bits(), 0x85);
+        assert_eq!(
+            config.with_wait(false).duration_mode(),
+            DurationMode::Immediate
+        );
+    }
+
+    #[test]
+    fn out_of_range_settings_saturate() {
+        let config = Int1Config::new()
+            .with_threshold(Axis::X, 0x8000)
+            .with_duration(0x80);
+        let block = config.to_block();
+        assert_eq!(block.threshold_xh.to_bits(), 0x7F);
+        assert_eq!(block.threshold_xl.to_bits(), 0xFF);
+        assert_eq!(block.duration.to_bits(), MAX_DURATION);
+
+        let block = Int1Block::default().with_thresholds(0, u16::MAX, 0);
+        assert_eq!(block.threshold_yh.to_bits(), 0x7F);
+        assert_eq!(block.threshold_yl.to_bits(), 0xFF);
+    }
+}
@@ -0,0 +1,210 @@
+//! Provides the features specific to the L3GD20H.
+//!
+//! The L3GD20H identifies as `0xD7` and is register compatible with the L3GD20, with a few
+//! additions: the `LOW_ODR` register selects the [low output data rates](LowOutputDataRate)
+//! and the polarity of the DRDY/INT2 pin (`DRDY_HL`), and the `StopOnFTH` bit of `CTRL_REG5`
+//! limits the FIFO depth to the watermark level. Its regular output data rates are nominally
+//! 100, 200, 400 and 800 Hz, see [`Variant::output_data_rate_hz`]. The self-test is
+//! provided by the [`self_test`](crate::self_test) module.
+//!
+//! The methods of this module fail with [`Error::InvalidConfig`] unless
+//! [`identify`](L3GD20::identify) or [`detect_variant`](L3GD20::detect_variant) found an
+//! L3GD20H, as the corresponding bits are reserved on the L3GD20.
+
+use crate::common::framing;
+use crate::temperature::Variant;
+use crate::{Error, Transport, L3GD20};
+use l3gd20_registers::prelude::SPIRegister;
+use l3gd20_registers::{ControlRegister1, ControlRegister5};
+
+/// The address of the `LOW_ODR` register.
+pub(crate) const LOW_ODR_ADDRESS: u8 = 0x39;
+
+/// The `Low_ODR` bit of `LOW_ODR`, selecting the low output data rates.
+pub(crate) const LOW_ODR: u8 = 0b0000_0001;
+
+/// The `DRDY_HL` bit of `LOW_ODR`, selecting an active low DRDY/INT2 pin.
+const DRDY_HL: u8 = 0b0010_0000;
+
+/// The `StopOnFTH` bit of `CTRL_REG5`.
+const STOP_ON_FTH: u8 = 0b0010_0000;
+
+/// A low output data rate of the L3GD20H.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum LowOutputDataRate {
+    /// 12.5 Hz
+    Hz12_5,
+    /// 25 Hz
+    Hz25,
+    /// 50 Hz
+    Hz50,
+}
+
+impl LowOutputDataRate {
+    /// Gets the output data rate in Hertz.
+    pub const fn hz(&self) -> f32 {
+        match self {
+            LowOutputDataRate::Hz12_5 => 12.5,
+            LowOutputDataRate::Hz25 => 25.0,
+            LowOutputDataRate::Hz50 => 50.0,
+        }
+    }
+
+    /// Gets the regular output data rate sharing the `DR` bits of `CTRL_REG1`.
+    const fn dr(&self) -> l3gd20_registers::OutputDataRate {
+        match self {
+            LowOutputDataRate::Hz12_5 => l3gd20_registers::OutputDataRate::Hz95,
+            LowOutputDataRate::Hz25 => l3gd20_registers::OutputDataRate::Hz190,
+            LowOutputDataRate::Hz50 => l3gd20_registers::OutputDataRate::Hz380,
+        }
+    }
+
+    /// Gets the low output data rate selected by the `DR` bits of `CTRL_REG1`.
+    const fn from_dr(dr: l3gd20_registers::OutputDataRate) -> Self {
+        match dr {
+            l3gd20_registers::OutputDataRate::Hz95 => LowOutputDataRate::Hz12_5,
+            l3gd20_registers::OutputDataRate::Hz190 => LowOutputDataRate::Hz25,
+            _ => LowOutputDataRate::Hz50,
+        }
+    }
+}
+
+impl Variant {
+    /// Determines whether the variant has the `LOW_ODR` register and the `StopOnFTH` bit.
+    pub const fn is_l3gd20h(&self) -> bool {
+        matches!(self, Variant::L3GD20H)
+    }
+}
+
+impl<T, E> L3GD20<T>
+where
+    T: Transport<Error = E>,
+{
+    /// Selects a low output data rate, or the regular rates with `None`.
+    ///
+    /// The low rates share the `DR` bits of `CTRL_REG1` with the regular rates, so a later
+    /// [`set_odr`](Self::set_odr) selects a low rate until this is reset to `None`.
+    pub fn set_low_odr(&mut self, rate: Option<LowOutputDataRate>) -> Result<(), Error<E>> {
+        self.require_l3gd20h()?;
+        if let Some(rate) = rate {
+            self.set_odr(rate.dr()).map_err(Error::Bus)?;
+        }
+        self.modify_raw(LOW_ODR_ADDRESS, LOW_ODR, rate.is_some())
+    }
+
+    /// Reads the low output data rate, or `None` if the regular rates are selected.
+    pub fn low_odr(&mut self) -> Result<Option<LowOutputDataRate>, Error<E>> {
+        self.require_l3gd20h()?;
+        if self.read_raw(LOW_ODR_ADDRESS)? & LOW_ODR == 0 {
+            return Ok(None);
+        }
+        let ctrl1: ControlRegister1 = self.read_register().map_err(Error::Bus)?;
+        Ok(Some(LowOutputDataRate::from_dr(ctrl1.output_data_rate())))
+    }
+
+    /// Gets the low output data rate selected through the driver, if any, without
+    /// accessing the bus.
+    #[cfg(feature = "measurement")]
+    pub(crate) fn selected_low_odr(&self) -> Option<LowOutputDataRate> {
+        let l3gd20h = matches!(self.variant(), Some(variant) if variant.is_l3gd20h());
+        (l3gd20h && self.low_odr)
+            .then(|| LowOutputDataRate::from_dr(self.expected_settings.output_data_rate))
+    }
+
+    /// Selects an active low DRDY/INT2 pin (`DRDY_HL`).
+    pub fn set_drdy_active_low(&mut self, active_low: bool) -> Result<(), Error<E>> {
+        self.require_l3gd20h()?;
+        self.modify_raw(LOW_ODR_ADDRESS, DRDY_HL, active_low)
+    }
+
+    /// Limits the FIFO depth to the watermark level (`StopOnFTH`).
+    pub fn set_fifo_stop_on_watermark(&mut self, enabled: bool) -> Result<(), Error<E>> {
+        self.require_l3gd20h()?;
+        self.modify_raw(*ControlRegister5::REGISTER_ADDRESS, STOP_ON_FTH, enabled)
+    }
+
+    /// Fails with [`Error::InvalidConfig`] unless the device is known to be an L3GD20H.
+    pub(crate) fn require_l3gd20h(&self) -> Result<(), Error<E>> {
+        match self.variant() {
+            Some(variant) if variant.is_l3gd20h() => Ok(()),
+            _ => Err(Error::InvalidConfig),
+        }
+    }
+
+    /// Reads a register that has no type in [`l3gd20_registers`].
+    pub(crate) fn read_raw(&mut self, address: u8) -> Result<u8, Error<E>> {
+        let mut buffer = [framing::read_single_cmd(address), 0];
+        self.transfer(&mut buffer).map_err(Error::Bus)?;
+        Ok(buffer[1])
+    }
+
+    /// Sets or clears the bits of `mask` in a register by read-modify-write.
+    pub(crate) fn modify_raw(&mut self, address: u8, mask: u8, set: bool) -> Result<(), Error<E>> {
+        let value = self.read_raw(address)?;
+        let value = if set { value | mask } else { value & !mask };
+        self.write_raw(address, value)
+    }
+
+    /// Writes a register that has no type in [`l3gd20_registers`], or whose reserved bits
+    /// are used.
+    pub(crate) fn write_raw(&mut self, address: u8, value: u8) -> Result<(), Error<E>> {
+        let mut buffer = [framing::write_single_cmd(address), value];
+        self.transfer(&mut buffer).map_err(Error::Bus)?;
+        self.note_register_write(address, value);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::MockError;
+    use crate::sim::Simulator;
+
+    fn l3gd20h() -> L3GD20<Simulator<MockError>> {
+        let mut sim = Simulator::<MockError>::new();
+        sim.registers_mut()[0x0F] = 0xD7;
+        let mut driver = L3GD20::with_transport(sim).unwrap();
+        assert_eq!(driver.identify(), Ok(true));
+        driver
+    }
+
+    #[test]
+    fn features_require_the_l3gd20h() {
+        let mut driver = L3GD20::with_transport(Simulator::<MockError>::new()).unwrap();
+        assert_eq!(driver.identify(), Ok(true));
+        let transfers = driver.transport.transfers();
+        assert_eq!(
+            driver.set_low_odr(Some(LowOutputDataRate::Hz25)),
+            Err(Error::InvalidConfig)
+        );
+        assert_eq!(
+            driver.set_fifo_stop_on_watermark(true),
+            Err(Error::InvalidConfig)
+        );
+        assert_eq!(driver.transport.transfers(), transfers);
+    }
+
+    #[test]
+    fn low_output_data_rates_are_selected() {
+        let mut driver = l3gd20h();
+        assert_eq!(driver.low_odr(), Ok(None));
+
+        driver.set_low_odr(Some(LowOutputDataRate::Hz25)).unwrap();
+        assert_eq!(driver.transport.registers()[0x39], LOW_ODR);
+        assert_eq!(driver.transport.registers()[0x20] >> 6, 0b01);
+        assert_eq!(driver.low_odr(), Ok(Some(LowOutputDataRate::Hz25)));
+
+        driver.set_drdy_active_low(true).unwrap();
+        driver.set_low_odr(None).unwrap();
+        assert_eq!(driver.transport.registers()[0x39], DRDY_HL);
+        assert_eq!(driver.low_odr(), Ok(None));
+
+        driver.set_fifo_stop_on_watermark(true).unwrap();
+        assert_eq!(
+            driver.transport.registers()[0x24] & STOP_ON_FTH,
+            STOP_ON_FTH
+        );
+    }
+}
@@ -0,0 +1,206 @@
+//! Provides measurements shaped for state estimators.
+
+use crate::conversions::DEG_TO_RAD;
+use crate::temperature::Variant;
+use crate::{Characteristics, F32x3, I16x3, OutputDataRate, OutputUnit, Transport, L3GD20};
+
+/// An angular rate measurement with its uncertainty, ready for consumption by an EKF or
+/// a ROS-style `Imu` message on the host side of a telemetry link.
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct RateMeasurement {
+    /// The angular rates about the X, Y and Z axes in the [`unit`](Self::unit), compensated
+    /// like in [`L3GD20::rates`].
+    pub rates: [f32; 3],
+    /// The diagonal of the rate covariance matrix, in the square of the [`unit`](Self::unit).
+    ///
+    /// The variance is derived from the rate noise density at the configured bandwidth.
+    /// Axes that did not carry fresh data are reported with infinite variance.
+    pub covariance_diag: [f32; 3],
+    /// The [output unit](L3GD20::set_output_unit) of the rates, e.g.
+    /// [`OutputUnit::RadiansPerSecond`] for a ROS-style `Imu` message.
+    pub unit: OutputUnit,
+    /// The time of the measurement in microseconds, if a time source is installed.
+    pub timestamp: Option<u64>,
+}
+
+/// An angular rate sample in the shape AHRS filters such as Madgwick or Mahony consume:
+/// the rates in radians/second and the time step they cover.
+///
+/// With the `mint` feature, the sample converts into a `(mint::Vector3<f32>, f32)` pair of
+/// rates and time step, e.g. `let (gyro, dt) = sample.into();`.
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct GyroSample {
+    /// The compensated angular rates about the X, Y and Z axes, in radians/second.
+    pub rates_rad_s: F32x3,
+    /// The time step covered by the sample, in seconds.
+    pub dt_s: f32,
+}
+
+#[cfg(feature = "mint")]
+#[cfg_attr(docsrs, doc(cfg(feature = "mint")))]
+impl From<GyroSample> for (mint::Vector3<f32>, f32) {
+    fn from(value: GyroSample) -> Self {
+        (value.rates_rad_s.into(), value.dt_s)
+    }
+}
+
+impl<T, E> L3GD20<T>
+where
+    T: Transport<Error = E>,
+{
+    /// Fetches a rate measurement along with its covariance, in the selected
+    /// [output unit](Self::set_output_unit).
+    ///
+    /// Only the sample is read; the configuration is taken from the driver state, see
+    /// [`sync_config`](Self::sync_config) if the device was configured elsewhere.
+    pub fn rate_measurement(&mut self) -> Result<RateMeasurement, E> {
+        let timestamp = self.now_micros();
+        let data = self.data_raw()?;
+
+        let fs = self.full_scale;
+        let characteristics = Characteristics::new(
+            fs,
+            self.expected_settings.output_data_rate,
+            self.expected_settings.bandwidth,
+            data.temperature,
+        );
+        let rates = self.rates_in_output_unit(I16x3::new(*data.x, *data.y, *data.z))?;
+
+        let noise = characteristics.rate_noise_density;
+        let noise = self.levels_in_output_unit(F32x3::new(noise, noise, noise))?;
+        let variance = |fresh: bool, noise: f32| {
+            if fresh {
+                noise * noise
+            } else {
+                f32::INFINITY
+            }
+        };
+
+        Ok(RateMeasurement {
+            rates: [rates.x, rates.y, rates.z],
+            covariance_diag: [
+                variance(data.x.fresh(), noise.x),
+                variance(data.y.fresh(), noise.y),
+                variance(data.z.fresh(), noise.z),
+            ],
+            unit: self.output_unit,
+            timestamp,
+        })
+    }
+
+    /// Fetches a compensated [`GyroSample`] for an AHRS filter.
+    ///
+    /// The time step is the period of the configured output data rate of the
+    /// [variant](Self::variant), including the low rates of the L3GD20H, matching a loop
+    /// driven by the data-ready signal.
+    pub fn gyro_sample(&mut self) -> Result<GyroSample, E> {
+        let dt_s = 1.0 / self.output_data_rate_hz();
+        let rates = self.xyz_dps()?;
+        Ok(GyroSample {
+            rates_rad_s: F32x3::new(
+                rates.x * DEG_TO_RAD,
+                rates.y * DEG_TO_RAD,
+                rates.z * DEG_TO_RAD,
+            ),
+            dt_s,
+        })
+    }
+
+    /// Gets the nominal output data rate in Hertz, including the low rates of the L3GD20H.
+    fn output_data_rate_hz(&self) -> f32 {
+        #[cfg(feature = "l3gd20h")]
+        if let Some(rate) = self.selected_low_odr() {
+            return rate.hz();
+        }
+        let rate = OutputDataRate::from(self.expected_settings.output_data_rate);
+        let variant = self.variant().unwrap_or(Variant::L3GD20);
+        f32::from(variant.output_data_rate_hz(rate))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::MockError;
+    use crate::sim::Simulator;
+    use crate::time::FixedStep;
+    use std::boxed::Box;
+
+    #[test]
+    fn measurement_carries_covariance_and_timestamp() {
+        let mut sim = Simulator::<MockError>::new();
+        sim.set_xyz(1000, 0, 0);
+        sim.set_status(0b0000_0011);
+        let mut driver = L3GD20::with_transport(sim).unwrap();
+        driver.set_time_source(Box::leak(Box::new(FixedStep::new(10))));
+        driver.set_output_unit(OutputUnit::RadiansPerSecond);
+
+        let transfers = driver.transport.transfers();
+        let measurement = driver.rate_measurement().unwrap();
+        assert_eq!(driver.transport.transfers(), transfers + 1);
+        assert_eq!(measurement.timestamp, Some(0));
+        assert_eq!(measurement.unit, OutputUnit::RadiansPerSecond);
+        assert!(libm::fabsf(measurement.rates[0] - 8.75 * DEG_TO_RAD) < 1e-6);
+
+        // 0.03 dps/√Hz at 12.5 Hz bandwidth.
+        let sigma = 0.03 * libm::sqrtf(12.5) * DEG_TO_RAD;
+        assert!(libm::fabsf(measurement.covariance_diag[0] - sigma * sigma) < 1e-9);
+        assert!(measurement.covariance_diag[1].is_finite());
+        assert_eq!(measurement.covariance_diag[2], f32::INFINITY);
+
+        // 100 Hz bandwidth at 760 Hz, in degrees per second.
+        driver.set_output_unit(OutputUnit::DegreesPerSecond);
+        driver
+            .set_odr(l3gd20_registers::OutputDataRate::Hz760)
+            .unwrap();
+        driver
+            .set_bandwidth(l3gd20_registers::Bandwidth::Wide)
+            .unwrap();
+        driver.transport.set_status(0b0000_0001);
+        let measurement = driver.rate_measurement().unwrap();
+        assert!(libm::fabsf(measurement.rates[0] - 8.75) < 1e-4);
+        assert!(libm::fabsf(measurement.covariance_diag[0] - 0.09) < 1e-6);
+    }
+
+    #[cfg(feature = "l3gd20h")]
+    #[test]
+    fn gyro_samples_carry_the_sampling_period_of_the_l3gd20h() {
+        use crate::l3gd20h::LowOutputDataRate;
+
+        let mut sim = Simulator::<MockError>::new();
+        sim.registers_mut()[0x0F] = 0xD7;
+        let mut driver = L3GD20::with_transport(sim).unwrap();
+        assert_eq!(driver.identify(), Ok(true));
+
+        let sample = driver.gyro_sample().unwrap();
+        assert!(libm::fabsf(sample.dt_s - 1.0 / 100.0) < 1e-9);
+
+        driver.set_low_odr(Some(LowOutputDataRate::Hz25)).unwrap();
+        let sample = driver.gyro_sample().unwrap();
+        assert!(libm::fabsf(sample.dt_s - 1.0 / 25.0) < 1e-9);
+
+        driver.set_low_odr(None).unwrap();
+        let sample = driver.gyro_sample().unwrap();
+        assert!(libm::fabsf(sample.dt_s - 1.0 / 200.0) < 1e-9);
+    }
+
+    #[test]
+    fn gyro_samples_carry_the_sampling_period() {
+        let mut sim = Simulator::<MockError>::new();
+        sim.set_xyz(0, -1000, 0);
+        let mut driver = L3GD20::with_transport(sim).unwrap();
+
+        let sample = driver.gyro_sample().unwrap();
+        assert!(libm::fabsf(sample.rates_rad_s.y + 8.75 * DEG_TO_RAD) < 1e-6);
+        assert!(libm::fabsf(sample.dt_s - 1.0 / 95.0) < 1e-9);
+
+        #[cfg(feature = "mint")]
+        {
+            let (gyro, dt): (mint::Vector3<f32>, f32) = sample.into();
+            assert_eq!(gyro, mint::Vector3::from(sample.rates_rad_s));
+            assert_eq!(dt, sample.dt_s);
+        }
+    }
+}
@@ -0,0 +1,142 @@
+//! A register-level mock of the L3GD20 used by the unit tests.
+
+use chip_select::{ChipSelect, ChipSelectGuarded};
+use embedded_hal::blocking::delay::DelayUs;
+use embedded_hal::blocking::spi::Transfer;
+
+/// The error returned by [`MockSpi`] when a failure was injected.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct MockError;
+
+#[cfg(feature = "embedded-hal-1")]
+impl embedded_hal_1::spi::Error for MockError {
+    fn kind(&self) -> embedded_hal_1::spi::ErrorKind {
+        embedded_hal_1::spi::ErrorKind::Other
+    }
+}
+
+#[cfg(all(feature = "async", not(feature = "embedded-hal-1")))]
+impl embedded_hal_async::spi::Error for MockError {
+    fn kind(&self) -> embedded_hal_async::spi::ErrorKind {
+        embedded_hal_async::spi::ErrorKind::Other
+    }
+}
+
+/// An SPI bus with a simulated L3GD20 register file behind it.
+pub struct MockSpi {
+    /// The register file, indexed by register address.
+    pub registers: [u8; 0x40],
+    /// The number of transfers executed so far.
+    pub transfers: usize,
+    /// If set, the transfer with this (zero-based) index fails.
+    pub fail_at: Option<usize>,
+    /// If set, the command byte and written bytes are replaced with the idle level of MISO,
+    /// like on a full-duplex bus.
+    pub full_duplex: bool,
+}
+
+impl MockSpi {
+    /// Creates a mock with power-on register values.
+    pub fn new() -> Self {
+        let mut registers = [0; 0x40];
+        registers[0x0F] = 0b1101_0100;
+        registers[0x20] = 0b0000_0111;
+        Self {
+            registers,
+            transfers: 0,
+            fail_at: None,
+            full_duplex: false,
+        }
+    }
+
+    /// Sets the axis output registers.
+    pub fn set_xyz(&mut self, x: i16, y: i16, z: i16) {
+        self.registers[0x28..0x2A].copy_from_slice(&x.to_le_bytes());
+        self.registers[0x2A..0x2C].copy_from_slice(&y.to_le_bytes());
+        self.registers[0x2C..0x2E].copy_from_slice(&z.to_le_bytes());
+    }
+
+    /// Sets the status register.
+    pub fn set_status(&mut self, status: u8) {
+        self.registers[0x27] = status;
+    }
+}
+
+impl Transfer<u8> for MockSpi {
+    type Error = MockError;
+
+    fn transfer<'w>(&mut self, words: &'w mut [u8]) -> Result<&'w [u8], Self::Error> {
+        let index = self.transfers;
+        self.transfers += 1;
+        if self.fail_at == Some(index) {
+            return Err(MockError);
+        }
+
+        let command = words[0];
+        let read = command & 0b1000_0000 != 0;
+        let multi = command & 0b0100_0000 != 0;
+        let mut address = usize::from(command & 0b0011_1111);
+        for word in words[1..].iter_mut() {
+            if read {
+                *word = self.registers[address];
+            } else {
+                self.registers[address] = *word;
+                if self.full_duplex {
+                    *word = 0xFF;
+                }
+            }
+            if multi {
+                address = (address + 1) & 0x3F;
+            }
+        }
+        if self.full_duplex {
+            words[0] = 0xFF;
+        }
+        Ok(words)
+    }
+}
+
+/// A delay that returns immediately.
+pub struct NoDelay;
+
+impl DelayUs<u32> for NoDelay {
+    fn delay_us(&mut self, _us: u32) {}
+}
+
+/// A chip select line that does nothing.
+pub struct MockCs;
+
+impl ChipSelect for MockCs {
+    fn select(&mut self) {}
+
+    fn deselect(&mut self) {}
+}
+
+impl ChipSelectGuarded for MockCs {
+    type Guard<'a> = ();
+
+    fn select_guard(&mut self) -> Self::Guard<'_> {}
+}
+
+/// Runs a future to completion on the current thread.
+#[cfg(feature = "async")]
+pub fn block_on<F: core::future::Future>(future: F) -> F::Output {
+    use core::task::{Context, Poll};
+    use std::sync::Arc;
+    use std::task::{Wake, Waker};
+
+    struct NoopWaker;
+
+    impl Wake for NoopWaker {
+        fn wake(self: Arc<Self>) {}
+    }
+
+    let waker = Waker::from(Arc::new(NoopWaker));
+    let mut context = Context::from_waker(&waker);
+    let mut future = core::pin::pin!(future);
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut context) {
+            return output;
+        }
+    }
+}
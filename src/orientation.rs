@@ -0,0 +1,251 @@
+//! Provides the remapping of the sensor axes to the body axes of the board.
+//!
+//! Boards often mount the sensor rotated or upside down. An [`AxisMap`] installed with
+//! [`set_axis_map`](L3GD20::set_axis_map) selects which sensor axis, possibly negated,
+//! provides each body axis. It is applied as the readings are decoded, so that all read
+//! paths, including the FIFO, report body-frame values.
+//!
+//! ```
+//! use l3gd20_ng::orientation::{AxisMap, SignedAxis};
+//! use l3gd20_ng::I16x3;
+//!
+//! // The sensor is mounted rotated by 90° about Z.
+//! let map = AxisMap::new(SignedAxis::PLUS_Y, SignedAxis::MINUS_X, SignedAxis::PLUS_Z).unwrap();
+//! assert_eq!(map.apply(I16x3::new(1, 2, 3)), I16x3::new(2, -1, 3));
+//! ```
+
+use crate::{Axis, F32x3, I16x3, SensorData, Status, Transport, L3GD20};
+
+/// A sensor axis, possibly negated.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SignedAxis {
+    /// The sensor axis.
+    pub axis: Axis,
+    /// Whether the sensor axis is negated.
+    pub negate: bool,
+}
+
+impl SignedAxis {
+    /// The sensor X axis.
+    pub const PLUS_X: Self = Self::new(Axis::X, false);
+    /// The negated sensor X axis.
+    pub const MINUS_X: Self = Self::new(Axis::X, true);
+    /// The sensor Y axis.
+    pub const PLUS_Y: Self = Self::new(Axis::Y, false);
+    /// The negated sensor Y axis.
+    pub const MINUS_Y: Self = Self::new(Axis::Y, true);
+    /// The sensor Z axis.
+    pub const PLUS_Z: Self = Self::new(Axis::Z, false);
+    /// The negated sensor Z axis.
+    pub const MINUS_Z: Self = Self::new(Axis::Z, true);
+
+    /// Creates a new instance of the [`SignedAxis`] struct.
+    #[must_use]
+    pub const fn new(axis: Axis, negate: bool) -> Self {
+        Self { axis, negate }
+    }
+
+    /// Gets the index of the sensor axis.
+    const fn index(&self) -> usize {
+        match self.axis {
+            Axis::X => 0,
+            Axis::Y => 1,
+            Axis::Z => 2,
+        }
+    }
+}
+
+/// The sensor axes providing the X, Y and Z body axes.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct AxisMap {
+    axes: [SignedAxis; 3],
+}
+
+impl AxisMap {
+    /// The identity mapping, i.e. the sensor is aligned with the board.
+    pub const IDENTITY: Self = Self {
+        axes: [SignedAxis::PLUS_X, SignedAxis::PLUS_Y, SignedAxis::PLUS_Z],
+    };
+
+    /// The mapping of a sensor mounted upside down, i.e. rotated by 180° about X.
+    pub const UPSIDE_DOWN: Self = Self {
+        axes: [SignedAxis::PLUS_X, SignedAxis::MINUS_Y, SignedAxis::MINUS_Z],
+    };
+
+    /// Creates a mapping from the sensor axes providing the X, Y and Z body axes.
+    ///
+    /// Returns `None` unless each sensor axis is used exactly once.
+    #[must_use]
+    pub const fn new(x: SignedAxis, y: SignedAxis, z: SignedAxis) -> Option<Self> {
+        let (a, b, c) = (x.index(), y.index(), z.index());
+        if a == b || b == c || a == c {
+            return None;
+        }
+        Some(Self { axes: [x, y, z] })
+    }
+
+    /// Gets the sensor axes providing the X, Y and Z body axes.
+    pub const fn axes(&self) -> [SignedAxis; 3] {
+        self.axes
+    }
+
+    /// Determines whether the mapping is a proper rotation rather than a reflection.
+    ///
+    /// A reflection, e.g. a single negated axis, turns the handedness of the coordinate
+    /// system, which is usually a configuration error.
+    pub fn is_rotation(&self) -> bool {
+        let [x, y, z] = self.axes;
+        let permutation_odd = matches!(
+            (x.index(), y.index(), z.index()),
+            (0, 2, 1) | (1, 0, 2) | (2, 1, 0)
+        );
+        let negations = self.axes.iter().filter(|axis| axis.negate).count();
+        permutation_odd == (negations % 2 == 1)
+    }
+
+    /// Maps a raw sensor reading to the body axes.
+    ///
+    /// Negating the smallest reading saturates to the largest one.
+    #[must_use]
+    pub fn apply(&self, value: I16x3) -> I16x3 {
+        let values = [value.x, value.y, value.z];
+        let [x, y, z] = self.axes.map(|axis| {
+            let value = values[axis.index()];
+            if axis.negate {
+                value.saturating_neg()
+            } else {
+                value
+            }
+        });
+        I16x3::new(x, y, z)
+    }
+
+    /// Maps a scaled sensor reading to the body axes.
+    #[must_use]
+    pub fn apply_f32(&self, value: F32x3) -> F32x3 {
+        let values = [value.x, value.y, value.z];
+        let [x, y, z] = self.axes.map(|axis| {
+            let value = values[axis.index()];
+            if axis.negate {
+                -value
+            } else {
+                value
+            }
+        });
+        F32x3::new(x, y, z)
+    }
+
+    /// Maps the readings of a sample to the body axes, along with their flags.
+    pub(crate) fn apply_data(&self, mut data: SensorData) -> SensorData {
+        let readings = [data.x, data.y, data.z];
+        let [x, y, z] = self.axes.map(|axis| {
+            let mut reading = readings[axis.index()];
+            if axis.negate {
+                *reading = reading.saturating_neg();
+            }
+            reading
+        });
+        (data.x, data.y, data.z) = (x, y, z);
+        data.status = self.apply_status(data.status);
+        data
+    }
+
+    /// Maps the per-axis flags of a status to the body axes.
+    ///
+    /// The combined `ZYXDA` and `ZYXOR` flags cover all axes and are kept.
+    pub(crate) fn apply_status(&self, status: Status) -> Status {
+        let data_available = [status.x_da, status.y_da, status.z_da];
+        let overrun = [status.x_overrun, status.y_overrun, status.z_overrun];
+        let [x, y, z] = self
+            .axes
+            .map(|axis| (data_available[axis.index()], overrun[axis.index()]));
+        Status {
+            x_da: x.0,
+            y_da: y.0,
+            z_da: z.0,
+            x_overrun: x.1,
+            y_overrun: y.1,
+            z_overrun: z.1,
+            ..status
+        }
+    }
+}
+
+impl Default for AxisMap {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+impl<T, E> L3GD20<T>
+where
+    T: Transport<Error = E>,
+{
+    /// Sets the mapping of the sensor axes to the body axes.
+    ///
+    /// The bias, gain and sensitivity override refer to the body axes; recalibrate after
+    /// changing the mapping.
+    pub fn set_axis_map(&mut self, map: AxisMap) {
+        self.axis_map = map;
+    }
+
+    /// Gets the mapping of the sensor axes to the body axes.
+    pub fn axis_map(&self) -> AxisMap {
+        self.axis_map
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::MockError;
+    use crate::sim::Simulator;
+    use crate::Reading;
+
+    #[test]
+    fn axes_are_used_once() {
+        assert_eq!(
+            AxisMap::new(SignedAxis::PLUS_X, SignedAxis::MINUS_X, SignedAxis::PLUS_Z),
+            None
+        );
+        assert!(AxisMap::IDENTITY.is_rotation());
+        assert!(AxisMap::UPSIDE_DOWN.is_rotation());
+        let mirrored =
+            AxisMap::new(SignedAxis::PLUS_Y, SignedAxis::PLUS_X, SignedAxis::PLUS_Z).unwrap();
+        assert!(!mirrored.is_rotation());
+        let rotated =
+            AxisMap::new(SignedAxis::PLUS_Y, SignedAxis::MINUS_X, SignedAxis::PLUS_Z).unwrap();
+        assert!(rotated.is_rotation());
+    }
+
+    #[test]
+    fn readings_are_mapped_to_the_body_axes() {
+        let mut driver = L3GD20::with_transport(Simulator::<MockError>::new()).unwrap();
+        driver.set_axis_map(AxisMap::UPSIDE_DOWN);
+        driver.transport.set_xyz(100, -200, i16::MIN);
+
+        assert_eq!(driver.xyz_raw().unwrap(), I16x3::new(100, 200, i16::MAX));
+        let data = driver.data_raw().unwrap();
+        assert_eq!((*data.x, *data.y, *data.z), (100, 200, i16::MAX));
+        assert!(driver.xyz_dps().unwrap().y > 0.0);
+    }
+
+    #[test]
+    fn flags_follow_the_mapped_axes() {
+        let mut driver = L3GD20::with_transport(Simulator::<MockError>::new()).unwrap();
+        let map = AxisMap::new(SignedAxis::PLUS_Z, SignedAxis::PLUS_Y, SignedAxis::MINUS_X);
+        driver.set_axis_map(map.unwrap());
+        driver.transport.set_xyz(1, 2, 3);
+        // New data on the sensor X axis, overrun on the sensor Z axis.
+        driver.transport.set_status(0b0100_0001);
+
+        let data = driver.data_raw().unwrap();
+        assert_eq!(data.x, Reading::Overrun(3));
+        assert_eq!(data.y, Reading::Stale(2));
+        assert_eq!(data.z, Reading::Fresh(-1));
+        assert!(data.status.z_da && !data.status.x_da && !data.status.y_da);
+        assert!(data.status.x_overrun && !data.status.z_overrun);
+    }
+}
@@ -0,0 +1,163 @@
+//! Provides sample-and-hold of the peak angular rate.
+//!
+//! Once enabled with [`enable_peak_hold`](crate::L3GD20::enable_peak_hold), every sample
+//! read off the sensor updates the held peaks, so that impact or crash analysis can
+//! query the largest rate seen since the last reset even if the main loop samples slowly.
+//! Draining the FIFO with [`update_peak_hold_from_fifo`](crate::L3GD20::update_peak_hold_from_fifo)
+//! additionally covers the samples the main loop did not read.
+
+#[cfg(feature = "fifo")]
+use crate::fifo::FIFO_DEPTH;
+use crate::{I16x3, Transport, L3GD20};
+
+/// A per-axis tracker of the reading with the largest magnitude.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct PeakHold {
+    peak: [i16; 3],
+    decay: u16,
+}
+
+impl PeakHold {
+    /// Creates a tracker whose held peaks decay toward zero by `decay` LSB per sample.
+    ///
+    /// A decay of zero holds the peaks until [`reset`](Self::reset) is called.
+    #[must_use]
+    pub const fn new(decay: u16) -> Self {
+        Self {
+            peak: [0; 3],
+            decay,
+        }
+    }
+
+    /// Updates the held peaks with a raw sample.
+    pub fn update(&mut self, sample: I16x3) {
+        for (peak, value) in self.peak.iter_mut().zip([sample.x, sample.y, sample.z]) {
+            let decayed = decay_toward_zero(*peak, self.decay);
+            *peak = if value.unsigned_abs() >= decayed.unsigned_abs() {
+                value
+            } else {
+                decayed
+            };
+        }
+    }
+
+    /// Gets the held raw readings with the largest magnitude, including their sign.
+    #[must_use]
+    pub fn peak(&self) -> I16x3 {
+        I16x3::new(self.peak[0], self.peak[1], self.peak[2])
+    }
+
+    /// Clears the held peaks.
+    pub fn reset(&mut self) {
+        self.peak = [0; 3];
+    }
+}
+
+/// Moves the value toward zero by the given amount, without crossing it.
+fn decay_toward_zero(value: i16, decay: u16) -> i16 {
+    let magnitude = value.unsigned_abs().saturating_sub(decay);
+    // Only negative values can have a magnitude of 32768, so the result always fits.
+    let magnitude = i32::from(magnitude);
+    (if value < 0 { -magnitude } else { magnitude }) as i16
+}
+
+impl<T, E> L3GD20<T>
+where
+    T: Transport<Error = E>,
+{
+    /// Enables tracking the peak rates of all samples read, decaying by `decay` LSB per sample.
+    ///
+    /// See [`PeakHold::new`]. Enabling the tracker again restarts it.
+    pub fn enable_peak_hold(&mut self, decay: u16) {
+        self.peak_hold = Some(PeakHold::new(decay));
+    }
+
+    /// Disables tracking the peak rates.
+    pub fn disable_peak_hold(&mut self) {
+        self.peak_hold = None;
+    }
+
+    /// Gets the held raw peak readings, if tracking is enabled.
+    pub fn peak_hold(&self) -> Option<I16x3> {
+        self.peak_hold.as_ref().map(PeakHold::peak)
+    }
+
+    /// Clears the held peaks.
+    pub fn reset_peak_hold(&mut self) {
+        if let Some(peak_hold) = self.peak_hold.as_mut() {
+            peak_hold.reset();
+        }
+    }
+
+    /// Reads all samples currently stored in the FIFO in a single burst, updating the held
+    /// peaks.
+    ///
+    /// The FIFO needs to be enabled, e.g. in Stream mode. Returns the number of samples read.
+    #[cfg(feature = "fifo")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "fifo")))]
+    pub fn update_peak_hold_from_fifo(&mut self) -> Result<u8, E> {
+        let mut samples = [I16x3::new(0, 0, 0); FIFO_DEPTH];
+        let count = self.read_fifo(&mut samples)?;
+        for &sample in &samples[..count] {
+            self.record_peak(sample);
+        }
+        Ok(count as u8)
+    }
+
+    /// Updates the held peaks with a sample that was read, if tracking is enabled.
+    pub(crate) fn record_peak(&mut self, sample: I16x3) {
+        if let Some(peak_hold) = self.peak_hold.as_mut() {
+            peak_hold.update(sample);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::MockError;
+    use crate::sim::Simulator;
+
+    #[test]
+    fn peaks_decay_and_keep_their_sign() {
+        let mut peak = PeakHold::new(10);
+        peak.update(I16x3::new(100, -300, 5));
+        peak.update(I16x3::new(-20, 200, 0));
+        assert_eq!(peak.peak(), I16x3::new(90, -290, 0));
+
+        peak.update(I16x3::new(-95, 0, i16::MIN));
+        assert_eq!(peak.peak(), I16x3::new(-95, -280, i16::MIN));
+
+        peak.reset();
+        assert_eq!(peak.peak(), I16x3::new(0, 0, 0));
+    }
+
+    #[test]
+    fn reads_feed_the_peak_hold() {
+        let mut driver = L3GD20::with_transport(Simulator::<MockError>::new()).unwrap();
+        driver.enable_peak_hold(0);
+
+        driver.transport.set_xyz(10, -40, 3);
+        driver.xyz_raw().unwrap();
+        driver.transport.set_xyz(-30, 20, 1);
+        driver.data_raw().unwrap();
+        assert_eq!(driver.peak_hold(), Some(I16x3::new(-30, -40, 3)));
+    }
+
+    #[test]
+    #[cfg(feature = "fifo")]
+    fn fifo_reads_feed_the_peak_hold() {
+        let mut driver = L3GD20::with_transport(Simulator::<MockError>::new()).unwrap();
+        driver.enable_fifo(crate::fifo::FifoMode::Stream).unwrap();
+        driver.enable_peak_hold(0);
+        driver.transport.push_fifo(10, -40, 3);
+        driver.transport.push_fifo(-30, 20, 1);
+
+        // FIFO_SRC_REG and the burst.
+        let transfers = driver.transport.transfers();
+        assert_eq!(driver.update_peak_hold_from_fifo().unwrap(), 2);
+        assert_eq!(driver.transport.transfers(), transfers + 2);
+        assert_eq!(driver.peak_hold(), Some(I16x3::new(-30, -40, 3)));
+    }
+}
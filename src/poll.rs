@@ -0,0 +1,183 @@
+//! Provides a cheap pre-read for adaptive polling loops.
+//!
+//! [`L3GD20::quick_poll`] reads `OUT_TEMP` and `STATUS_REG` in a single three-byte
+//! transaction. Polling loops can skip the full data read while no new sample is
+//! available, and still track the temperature on every poll.
+//!
+//! [`L3GD20::wait_for_data_ready`] blocks until a new sample is available and reads it,
+//! failing with [`Error::Timeout`] if none arrives in time.
+//!
+//! [`L3GD20::try_xyz`] and [`L3GD20::try_data`] are their non-blocking counterparts for
+//! `nb`-style superloops, returning [`nb::Error::WouldBlock`] while no new sample is
+//! available:
+//!
+//! ```
+//! # #[cfg(feature = "sim")] {
+//! # use l3gd20_ng::{sim::Simulator, L3GD20};
+//! # let mut driver = L3GD20::with_transport(Simulator::<()>::new()).unwrap();
+//! match driver.try_xyz() {
+//!     Ok(sample) => { /* process the sample */ }
+//!     Err(nb::Error::WouldBlock) => { /* do something else */ }
+//!     Err(nb::Error::Other(error)) => { /* handle the bus error */ }
+//! }
+//! # }
+//! ```
+
+use crate::common::framing;
+use crate::temperature::RawTemperature;
+use crate::{Error, I16x3, SensorData, Status, Transport, L3GD20};
+use embedded_hal::blocking::delay::DelayUs;
+use l3gd20_registers::prelude::SPIRegister;
+use l3gd20_registers::TemperatureRegister;
+
+/// The interval at which [`L3GD20::wait_for_data_ready`] polls the status, in microseconds.
+const DATA_READY_POLL_INTERVAL_US: u32 = 100;
+
+/// The result of [`L3GD20::quick_poll`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct QuickPoll {
+    /// The status register.
+    pub status: Status,
+    /// The temperature reading.
+    pub temperature: RawTemperature,
+}
+
+impl QuickPoll {
+    /// Determines whether a full data read is warranted, i.e. whether new data is
+    /// available or was overwritten.
+    pub fn data_ready(&self) -> bool {
+        self.status.zyx_da || self.status.zyx_overrun
+    }
+}
+
+impl<T, E> L3GD20<T>
+where
+    T: Transport<Error = E>,
+{
+    /// Reads the status and the temperature in one three-byte transaction.
+    ///
+    /// Use [`QuickPoll::data_ready`] to decide whether the full data read is needed.
+    pub fn quick_poll(&mut self) -> Result<QuickPoll, E> {
+        let mut buffer = [
+            framing::read_multi_cmd(*TemperatureRegister::REGISTER_ADDRESS),
+            0,
+            0,
+        ];
+        self.transfer(&mut buffer)?;
+
+        let [_, temperature, status] = buffer;
+        #[cfg(feature = "thermal")]
+        self.thermal.record(temperature, self.now_micros());
+        Ok(QuickPoll {
+            status: Status::from_bits(status),
+            temperature: RawTemperature::new(temperature),
+        })
+    }
+
+    /// Blocks until a new sample is available on all axes, then reads it.
+    ///
+    /// The status register is polled every 100 µs. Fails with [`Error::Timeout`] if no
+    /// sample arrived within `timeout_us` microseconds.
+    pub fn wait_for_data_ready<D>(
+        &mut self,
+        delay: &mut D,
+        timeout_us: u32,
+    ) -> Result<I16x3, Error<E>>
+    where
+        D: DelayUs<u32>,
+    {
+        let mut waited = 0;
+        while !self.has_new_sample().map_err(Error::Bus)? {
+            if waited >= timeout_us {
+                return Err(Error::Timeout);
+            }
+            self.feed_watchdog();
+            delay.delay_us(DATA_READY_POLL_INTERVAL_US);
+            waited = waited.saturating_add(DATA_READY_POLL_INTERVAL_US);
+        }
+        self.xyz_raw().map_err(Error::Bus)
+    }
+
+    /// Reads the X, Y and Z-axis data if a new sample is available on all axes (`ZYXDA`).
+    ///
+    /// Returns [`nb::Error::WouldBlock`] without reading the data otherwise.
+    pub fn try_xyz(&mut self) -> nb::Result<I16x3, E> {
+        if !self.has_new_sample()? {
+            return Err(nb::Error::WouldBlock);
+        }
+        Ok(self.xyz_raw()?)
+    }
+
+    /// Reads all data like [`data_raw`](Self::data_raw) if a new sample is available on all
+    /// axes (`ZYXDA`).
+    ///
+    /// Returns [`nb::Error::WouldBlock`] without reading the data otherwise.
+    pub fn try_data(&mut self) -> nb::Result<SensorData, E> {
+        if !self.has_new_sample()? {
+            return Err(nb::Error::WouldBlock);
+        }
+        Ok(self.data_raw()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{MockError, NoDelay};
+    use crate::sim::Simulator;
+
+    #[test]
+    fn status_and_temperature_are_read_in_one_transaction() {
+        let mut driver = L3GD20::with_transport(Simulator::<MockError>::new()).unwrap();
+        driver.transport.set_temperature(0x19);
+        driver.transport.set_status(0);
+
+        let transfers = driver.transport.transfers();
+        let poll = driver.quick_poll().unwrap();
+        assert_eq!(driver.transport.transfers(), transfers + 1);
+        assert_eq!(poll.temperature, RawTemperature::new(0x19));
+        assert!(!poll.data_ready());
+
+        driver.transport.set_status(0b0000_1111);
+        assert!(driver.quick_poll().unwrap().data_ready());
+        driver.transport.set_status(0b1000_0000);
+        assert!(driver.quick_poll().unwrap().data_ready());
+    }
+
+    #[test]
+    fn data_ready_is_awaited() {
+        let mut sim = Simulator::<MockError>::new();
+        sim.generate(5, |_| [1, 2, 3]);
+        let mut driver = L3GD20::with_transport(sim).unwrap();
+        driver.transport.set_status(0);
+
+        assert_eq!(
+            driver.wait_for_data_ready(&mut NoDelay, 1_000),
+            Ok(I16x3::new(1, 2, 3))
+        );
+
+        let mut driver = L3GD20::with_transport(Simulator::<MockError>::new()).unwrap();
+        assert_eq!(
+            driver.wait_for_data_ready(&mut NoDelay, 1_000),
+            Err(Error::Timeout)
+        );
+    }
+
+    #[test]
+    fn stale_data_is_not_read() {
+        let mut driver = L3GD20::with_transport(Simulator::<MockError>::new()).unwrap();
+        driver.transport.set_xyz(1, 2, 3);
+        driver.transport.set_status(0);
+
+        let transfers = driver.transport.transfers();
+        assert_eq!(driver.try_xyz(), Err(nb::Error::WouldBlock));
+        assert!(matches!(driver.try_data(), Err(nb::Error::WouldBlock)));
+        assert_eq!(driver.transport.transfers(), transfers + 2);
+
+        driver.transport.set_status(0b0000_1000);
+        assert_eq!(driver.try_xyz(), Ok(I16x3::new(1, 2, 3)));
+        driver.transport.set_status(0b0000_1000);
+        assert_eq!(*driver.try_data().unwrap().z, 3);
+    }
+}
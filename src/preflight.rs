@@ -0,0 +1,218 @@
+//! Provides a pre-flight check to gate arming on.
+
+use crate::{F32x3, Transport, L3GD20};
+use embedded_hal::blocking::delay::DelayUs;
+
+/// The number of samples collected to assess the bias and noise.
+const SAMPLES: u16 = 32;
+
+/// The interval at which the status register is polled for new data, in microseconds.
+const POLL_INTERVAL_US: u32 = 500;
+
+/// The time to wait for a new sample before the data ready check fails, in microseconds.
+const SAMPLE_TIMEOUT_US: u32 = 50_000;
+
+/// The limits applied by [`L3GD20::preflight_check_with_limits`].
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct PreflightLimits {
+    /// The maximum magnitude of the zero-rate level on any axis, in degrees/second.
+    pub max_bias_dps: f32,
+    /// The maximum standard deviation of the readings on any axis, in degrees/second.
+    pub max_noise_dps: f32,
+    /// The minimum raw `OUT_TEMP` reading.
+    pub min_temperature_raw: u8,
+    /// The maximum raw `OUT_TEMP` reading.
+    pub max_temperature_raw: u8,
+}
+
+impl Default for PreflightLimits {
+    /// Limits derived from the datasheet for a stationary sensor at FS=250 dps.
+    /// The temperature range is not limited, since `OUT_TEMP` is not calibrated.
+    fn default() -> Self {
+        Self {
+            max_bias_dps: 10.0,
+            max_noise_dps: 1.0,
+            min_temperature_raw: u8::MIN,
+            max_temperature_raw: u8::MAX,
+        }
+    }
+}
+
+/// The outcome of [`L3GD20::preflight_check`].
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct PreflightReport {
+    /// Whether the device identified as an L3GD20.
+    pub identified: bool,
+    /// Whether the configuration read back from the device matches the one last applied.
+    pub config_matches: bool,
+    /// Whether new samples were signaled in time, with the data ready flag clearing on reads.
+    pub data_ready: bool,
+    /// Whether the zero-rate level is within limits.
+    pub bias_ok: bool,
+    /// Whether the noise is within limits.
+    pub noise_ok: bool,
+    /// Whether the temperature is within limits.
+    pub temperature_ok: bool,
+    /// The mean reading while stationary, in degrees/second.
+    pub bias_dps: F32x3,
+    /// The standard deviation of the readings, in degrees/second.
+    pub noise_dps: F32x3,
+    /// The raw `OUT_TEMP` reading.
+    pub temperature: u8,
+}
+
+impl PreflightReport {
+    /// Determines whether all checks passed.
+    pub fn passed(&self) -> bool {
+        self.identified
+            && self.config_matches
+            && self.data_ready
+            && self.bias_ok
+            && self.noise_ok
+            && self.temperature_ok
+    }
+}
+
+impl<T, E> L3GD20<T>
+where
+    T: Transport<Error = E>,
+{
+    /// Runs the pre-flight check with the [default limits](PreflightLimits::default).
+    ///
+    /// See [`preflight_check_with_limits`](Self::preflight_check_with_limits).
+    pub fn preflight_check<D>(&mut self, delay: &mut D) -> Result<PreflightReport, E>
+    where
+        D: DelayUs<u32>,
+    {
+        self.preflight_check_with_limits(delay, &PreflightLimits::default())
+    }
+
+    /// Runs the pre-flight check: identification, configuration readback, data ready
+    /// toggling, zero-rate level, noise and temperature.
+    ///
+    /// The sensor must be powered up and stationary. The bias and noise are assessed
+    /// over 32 samples at the configured output data rate.
+    pub fn preflight_check_with_limits<D>(
+        &mut self,
+        delay: &mut D,
+        limits: &PreflightLimits,
+    ) -> Result<PreflightReport, E>
+    where
+        D: DelayUs<u32>,
+    {
+        let identified = self.identify()?;
+        let config_matches = self.settings()? == self.expected_settings;
+        let temperature = self.temp_raw()?;
+        let scale = self.scale()?;
+
+        // Clear the data ready flag before waiting for fresh samples.
+        self.xyz_raw()?;
+
+        let mut data_ready = true;
+        let mut sum = [0f32; 3];
+        let mut sum_squares = [0f32; 3];
+        for _ in 0..SAMPLES {
+            if !self.wait_new_sample(delay)? {
+                data_ready = false;
+                break;
+            }
+            let raw = self.xyz_raw()?;
+            if self.has_new_sample()? {
+                // The flag must clear once the sample was read.
+                data_ready = false;
+            }
+            let scaled = [
+                f32::from(raw.x) * scale.x,
+                f32::from(raw.y) * scale.y,
+                f32::from(raw.z) * scale.z,
+            ];
+            for (i, value) in scaled.into_iter().enumerate() {
+                sum[i] += value;
+                sum_squares[i] += value * value;
+            }
+        }
+
+        let n = f32::from(SAMPLES);
+        let mean = sum.map(|sum| sum / n);
+        let mut std_dev = [0f32; 3];
+        for i in 0..3 {
+            std_dev[i] = libm::sqrtf((sum_squares[i] / n - mean[i] * mean[i]).max(0.0));
+        }
+
+        let bias_ok = data_ready
+            && mean
+                .iter()
+                .all(|mean| libm::fabsf(*mean) <= limits.max_bias_dps);
+        let noise_ok = data_ready && std_dev.iter().all(|std| *std <= limits.max_noise_dps);
+        let temperature_ok =
+            (limits.min_temperature_raw..=limits.max_temperature_raw).contains(&temperature);
+
+        Ok(PreflightReport {
+            identified,
+            config_matches,
+            data_ready,
+            bias_ok,
+            noise_ok,
+            temperature_ok,
+            bias_dps: F32x3::new(mean[0], mean[1], mean[2]),
+            noise_dps: F32x3::new(std_dev[0], std_dev[1], std_dev[2]),
+            temperature,
+        })
+    }
+
+    /// Polls the status register until a new sample is available or the timeout elapsed.
+    pub(crate) fn wait_new_sample<D>(&mut self, delay: &mut D) -> Result<bool, E>
+    where
+        D: DelayUs<u32>,
+    {
+        let mut waited = 0;
+        while !self.has_new_sample()? {
+            if waited >= SAMPLE_TIMEOUT_US {
+                return Ok(false);
+            }
+            self.feed_watchdog();
+            delay.delay_us(POLL_INTERVAL_US);
+            waited += POLL_INTERVAL_US;
+        }
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{MockError, NoDelay};
+    use crate::sim::Simulator;
+    use l3gd20_registers::OutputDataRate;
+
+    fn jitter(index: u32) -> [i16; 3] {
+        let offset = if index % 2 == 0 { 10 } else { -10 };
+        [100 + offset, -50 + offset, offset]
+    }
+
+    #[test]
+    fn healthy_sensor_passes() {
+        let mut sim = Simulator::<MockError>::new();
+        sim.generate(4, jitter);
+        let mut driver = L3GD20::with_transport(sim).unwrap();
+
+        let report = driver.preflight_check(&mut NoDelay).unwrap();
+        assert!(report.passed(), "{report:?}");
+        assert!(libm::fabsf(report.bias_dps.x - 0.875) < 1e-4);
+        assert!(libm::fabsf(report.noise_dps.z - 0.0875) < 1e-4);
+    }
+
+    #[test]
+    fn silent_sensor_and_config_drift_fail() {
+        let mut driver = L3GD20::with_transport(Simulator::<MockError>::new()).unwrap();
+        driver.transport.registers_mut()[0x20] = OutputDataRate::Hz760.into_bits() << 6;
+
+        let report = driver.preflight_check(&mut NoDelay).unwrap();
+        assert!(report.identified);
+        assert!(!report.config_matches);
+        assert!(!report.data_ready);
+        assert!(!report.passed());
+    }
+}
@@ -0,0 +1,239 @@
+//! Provides statistics of the bus transactions issued by the driver.
+//!
+//! At high output data rates such as 380 Hz or 760 Hz, the bus may become the bottleneck.
+//! The driver counts the transactions and bytes of each [`TransferCategory`] and, if a
+//! [time source](crate::time::TimeSource) is installed via
+//! [`set_profiling_time_source`](L3GD20::set_profiling_time_source), sorts their durations
+//! into a histogram, so that the bus budget can be validated on the target.
+
+use crate::common::framing;
+use crate::time::TimeSource;
+use crate::{Transport, L3GD20};
+
+/// The number of buckets of the duration histogram.
+pub const DURATION_BUCKETS: usize = 8;
+
+/// The upper bound of the first histogram bucket, in microseconds.
+///
+/// Each following bucket doubles the bound; the last bucket is unbounded.
+pub const FIRST_BUCKET_US: u32 = 16;
+
+/// The category of a bus transaction.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum TransferCategory {
+    /// A read starting at the temperature or angular rate output registers.
+    DataRead,
+    /// A read of `STATUS_REG`, `FIFO_SRC_REG` or `INT1_SRC`.
+    StatusRead,
+    /// A read of any other register.
+    RegisterRead,
+    /// A register write.
+    RegisterWrite,
+}
+
+impl TransferCategory {
+    /// All categories, in the order of their statistics.
+    pub const ALL: [TransferCategory; 4] = [
+        TransferCategory::DataRead,
+        TransferCategory::StatusRead,
+        TransferCategory::RegisterRead,
+        TransferCategory::RegisterWrite,
+    ];
+
+    /// Determines the category of a transaction from its command byte.
+    pub const fn of(command: u8) -> Self {
+        if !framing::is_read(command) {
+            return TransferCategory::RegisterWrite;
+        }
+        match framing::address(command) {
+            0x26 | 0x28..=0x2D => TransferCategory::DataRead,
+            0x27 | 0x2F | 0x31 => TransferCategory::StatusRead,
+            _ => TransferCategory::RegisterRead,
+        }
+    }
+
+    const fn index(self) -> usize {
+        match self {
+            TransferCategory::DataRead => 0,
+            TransferCategory::StatusRead => 1,
+            TransferCategory::RegisterRead => 2,
+            TransferCategory::RegisterWrite => 3,
+        }
+    }
+}
+
+/// The statistics of the transactions of one category.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct TransferStats {
+    /// The number of transactions.
+    pub count: u32,
+    /// The number of bytes transferred, including the command bytes.
+    pub bytes: u32,
+    /// The number of transactions that were timed.
+    pub timed: u32,
+    /// The total duration of the timed transactions, in microseconds.
+    pub total_us: u64,
+    /// The longest duration of a timed transaction, in microseconds.
+    pub max_us: u32,
+    /// The number of timed transactions per duration bucket.
+    ///
+    /// Bucket `i` counts the durations below `FIRST_BUCKET_US << i` microseconds that do not
+    /// fall into a lower bucket; the last bucket counts all longer durations.
+    pub histogram: [u32; DURATION_BUCKETS],
+}
+
+impl TransferStats {
+    /// Gets the mean duration of the timed transactions, in microseconds.
+    ///
+    /// Returns `None` if no transaction was timed.
+    pub fn mean_us(&self) -> Option<u32> {
+        if self.timed == 0 {
+            return None;
+        }
+        Some((self.total_us / u64::from(self.timed)) as u32)
+    }
+
+    fn record(&mut self, len: usize, duration_us: Option<u64>) {
+        self.count = self.count.saturating_add(1);
+        self.bytes = self.bytes.saturating_add(len as u32);
+        if let Some(duration) = duration_us {
+            let duration = u32::try_from(duration).unwrap_or(u32::MAX);
+            self.timed = self.timed.saturating_add(1);
+            self.total_us = self.total_us.saturating_add(u64::from(duration));
+            self.max_us = self.max_us.max(duration);
+            let bucket = (0..DURATION_BUCKETS - 1)
+                .find(|&i| duration < FIRST_BUCKET_US << i)
+                .unwrap_or(DURATION_BUCKETS - 1);
+            self.histogram[bucket] = self.histogram[bucket].saturating_add(1);
+        }
+    }
+}
+
+/// The statistics of all bus transactions since the profile was last reset.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct TransferProfile {
+    stats: [TransferStats; 4],
+}
+
+impl TransferProfile {
+    /// Gets the statistics of a category.
+    pub fn get(&self, category: TransferCategory) -> &TransferStats {
+        &self.stats[category.index()]
+    }
+
+    /// Gets the statistics of all categories combined.
+    pub fn total(&self) -> TransferStats {
+        let mut total = TransferStats::default();
+        for stats in &self.stats {
+            total.count = total.count.saturating_add(stats.count);
+            total.bytes = total.bytes.saturating_add(stats.bytes);
+            total.timed = total.timed.saturating_add(stats.timed);
+            total.total_us = total.total_us.saturating_add(stats.total_us);
+            total.max_us = total.max_us.max(stats.max_us);
+            for (sum, count) in total.histogram.iter_mut().zip(stats.histogram) {
+                *sum = sum.saturating_add(count);
+            }
+        }
+        total
+    }
+
+    /// Records a completed transaction.
+    pub(crate) fn record(&mut self, command: u8, len: usize, duration_us: Option<u64>) {
+        self.stats[TransferCategory::of(command).index()].record(len, duration_us);
+    }
+}
+
+impl<T, E> L3GD20<T>
+where
+    T: Transport<Error = E>,
+{
+    /// Installs the time source used to time bus transactions.
+    ///
+    /// This is separate from the [time source](Self::set_time_source) of the time-dependent
+    /// features, as it is queried twice per transaction; it may be backed by the same clock.
+    pub fn set_profiling_time_source(&mut self, clock: &'static dyn TimeSource) {
+        self.profile_clock = Some(clock);
+    }
+
+    /// Gets the statistics of the bus transactions since the profile was last reset.
+    pub fn transfer_profile(&self) -> &TransferProfile {
+        &self.profile
+    }
+
+    /// Clears the statistics of the bus transactions.
+    pub fn reset_transfer_profile(&mut self) {
+        self.profile = TransferProfile::default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::MockError;
+    use crate::sim::Simulator;
+    use crate::time::FixedStep;
+    use std::boxed::Box;
+
+    #[test]
+    fn categories_follow_the_command() {
+        assert_eq!(
+            TransferCategory::of(framing::read_multi_cmd(0x28)),
+            TransferCategory::DataRead
+        );
+        assert_eq!(
+            TransferCategory::of(framing::read_single_cmd(0x27)),
+            TransferCategory::StatusRead
+        );
+        assert_eq!(
+            TransferCategory::of(framing::read_single_cmd(0x0F)),
+            TransferCategory::RegisterRead
+        );
+        assert_eq!(
+            TransferCategory::of(framing::write_multi_cmd(0x20)),
+            TransferCategory::RegisterWrite
+        );
+    }
+
+    #[test]
+    fn transactions_are_counted_and_timed() {
+        let mut driver = L3GD20::with_transport(Simulator::<MockError>::new()).unwrap();
+        assert!(driver.transfer_profile().total().count > 0);
+        assert_eq!(driver.transfer_profile().total().timed, 0);
+
+        driver.reset_transfer_profile();
+        driver.set_profiling_time_source(Box::leak(Box::new(FixedStep::new(20))));
+        driver.xyz_raw().unwrap();
+        driver.xyz_raw().unwrap();
+
+        let data = driver.transfer_profile().get(TransferCategory::DataRead);
+        assert_eq!(data.count, 2);
+        assert_eq!(data.bytes, 14);
+        assert_eq!(data.timed, 2);
+        assert_eq!(data.mean_us(), Some(20));
+        assert_eq!(data.histogram[1], 2);
+        assert_eq!(driver.transfer_profile().total().count, 2);
+    }
+
+    #[test]
+    fn full_duplex_transactions_are_categorized_by_their_command() {
+        use crate::mock::{MockCs, MockSpi};
+        use crate::L3GD20SPI;
+
+        let mut spi = MockSpi::new();
+        spi.full_duplex = true;
+        let mut driver = L3GD20SPI::new(spi, MockCs).unwrap();
+        driver.reset_transfer_profile();
+        driver.xyz_raw().unwrap();
+        driver.status().unwrap();
+        driver.set_block_data_update(true).unwrap();
+
+        let profile = driver.transfer_profile();
+        assert_eq!(profile.get(TransferCategory::DataRead).count, 1);
+        assert_eq!(profile.get(TransferCategory::StatusRead).count, 1);
+        assert_eq!(profile.get(TransferCategory::RegisterRead).count, 1);
+        assert_eq!(profile.get(TransferCategory::RegisterWrite).count, 1);
+    }
+}
@@ -74,6 +74,30 @@ impl<T> Reading<T> {
         }
     }
 
+    /// Transforms the reading into a [`Result`], mapping stale readings to `Err(err)`.
+    ///
+    /// Fresh and overrun readings both carry the most recent value and map to `Ok`.
+    /// This allows treating stale data as an error path with the `?` operator.
+    pub fn fresh_or<E>(self, err: E) -> Result<T, E> {
+        match self {
+            Reading::Stale(_) => Err(err),
+            Reading::Fresh(x) | Reading::Overrun(x) => Ok(x),
+        }
+    }
+
+    /// Transforms the reading into a [`Result`], mapping stale readings to `Err(err(value))`.
+    ///
+    /// See [`fresh_or`](Self::fresh_or); the error is computed lazily from the stale value.
+    pub fn fresh_or_else<E, F>(self, err: F) -> Result<T, E>
+    where
+        F: FnOnce(T) -> E,
+    {
+        match self {
+            Reading::Stale(x) => Err(err(x)),
+            Reading::Fresh(x) | Reading::Overrun(x) => Ok(x),
+        }
+    }
+
     /// Indicates whether this is a stale reading.
     #[must_use]
     pub fn stale(&self) -> bool {
@@ -98,3 +122,16 @@ impl<T> Reading<T> {
         matches!(self, Reading::Overrun(_))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stale_readings_map_to_errors() {
+        assert_eq!(Reading::new_fresh(1).fresh_or("stale"), Ok(1));
+        assert_eq!(Reading::new_overrun(2).fresh_or("stale"), Ok(2));
+        assert_eq!(Reading::new_stale(3).fresh_or("stale"), Err("stale"));
+        assert_eq!(Reading::new_stale(4).fresh_or_else(|x| x * 10), Err(40));
+    }
+}
@@ -0,0 +1,230 @@
+//! Provides a policy for temperature- and age-triggered bias recalibration.
+//!
+//! The zero-rate level of the sensor drifts with temperature and time. Once a
+//! [`RecalPolicy`] is attached via [`set_recal_policy`](crate::L3GD20::set_recal_policy),
+//! [`recal_update`](crate::L3GD20::recal_update) checks the current
+//! [`BiasReport`](crate::BiasReport) against its limits and either flags that a
+//! recalibration is due, or performs it while the device is stationary.
+
+use crate::calibration::{CalibrationTask, Progress};
+use crate::{Bias, Transport, L3GD20};
+
+/// A policy for recalibrating the bias.
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct RecalPolicy {
+    /// The temperature change since the bias was estimated, in °C, that triggers a recalibration.
+    pub max_temp_delta_c: f32,
+    /// The age of the bias estimate, in seconds, that triggers a recalibration, or zero for no limit.
+    ///
+    /// This requires a [time source](crate::time).
+    pub max_age_s: u32,
+    /// The action taken when a recalibration is due.
+    pub action: RecalAction,
+}
+
+/// The action taken when a recalibration is due.
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum RecalAction {
+    /// Only report that a recalibration is due.
+    Flag,
+    /// Recalibrate automatically.
+    Recalibrate {
+        /// The number of samples to average.
+        samples: u16,
+        /// The largest standard deviation of the samples per axis, in degrees/second, for
+        /// the device to be considered stationary. Calibrations exceeding it are discarded.
+        max_noise_dps: f32,
+    },
+}
+
+/// The reason a recalibration is due.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum RecalReason {
+    /// No bias was estimated yet.
+    Missing,
+    /// The temperature changed too much since the bias was estimated.
+    Temperature,
+    /// The bias estimate is too old.
+    Age,
+}
+
+/// An event of the recalibration policy.
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum RecalEvent {
+    /// A recalibration is due; reported once until the condition clears.
+    Due(RecalReason),
+    /// An automatic recalibration was started.
+    Started(RecalReason),
+    /// An automatic recalibration completed and the new bias was applied.
+    Completed(Bias),
+    /// An automatic recalibration was discarded because the device was moving.
+    /// The previous bias was kept, and the recalibration is retried.
+    Aborted(RecalReason),
+}
+
+/// The state of the recalibration policy.
+#[derive(Debug, Clone)]
+pub(crate) struct RecalState {
+    policy: RecalPolicy,
+    flagged: bool,
+    running: Option<(CalibrationTask, RecalReason)>,
+}
+
+impl<T, E> L3GD20<T>
+where
+    T: Transport<Error = E>,
+{
+    /// Attaches a recalibration policy, or detaches it with `None`.
+    pub fn set_recal_policy(&mut self, policy: Option<RecalPolicy>) {
+        self.recal = policy.map(|policy| RecalState {
+            policy,
+            flagged: false,
+            running: None,
+        });
+    }
+
+    /// Applies the recalibration policy, calling `f` for every resulting event.
+    ///
+    /// This is intended to be called periodically from the main loop. While an automatic
+    /// recalibration is running, each call collects at most one sample; see
+    /// [`CalibrationTask`]. Does nothing if no policy is attached.
+    pub fn recal_update<F>(&mut self, f: F) -> Result<(), E>
+    where
+        F: FnMut(RecalEvent),
+    {
+        let Some(mut state) = self.recal.take() else {
+            return Ok(());
+        };
+        let result = self.recal_step(&mut state, f);
+        self.recal = Some(state);
+        result
+    }
+
+    /// Advances the recalibration policy by a single step.
+    fn recal_step<F>(&mut self, state: &mut RecalState, mut f: F) -> Result<(), E>
+    where
+        F: FnMut(RecalEvent),
+    {
+        if let Some((task, reason)) = state.running.as_mut() {
+            let reason = *reason;
+            let previous = (self.compensation.bias, self.bias_epoch, self.bias_timestamp);
+            let Progress::Done(bias) = task.poll(self)? else {
+                return Ok(());
+            };
+            state.running = None;
+
+            let RecalAction::Recalibrate { max_noise_dps, .. } = state.policy.action else {
+                return Ok(());
+            };
+            let scale = self.scale()?;
+            let noise = [
+                bias.noise.x * scale.x,
+                bias.noise.y * scale.y,
+                bias.noise.z * scale.z,
+            ];
+            if noise.into_iter().all(|noise| noise <= max_noise_dps) {
+                f(RecalEvent::Completed(bias));
+            } else {
+                (self.compensation.bias, self.bias_epoch, self.bias_timestamp) = previous;
+                state.flagged = false;
+                f(RecalEvent::Aborted(reason));
+            }
+            return Ok(());
+        }
+
+        let report = self.bias_report()?;
+        let max_age_us = u64::from(state.policy.max_age_s) * 1_000_000;
+        let reason = if report.bias.samples == 0 {
+            Some(RecalReason::Missing)
+        } else if libm::fabsf(report.temperature_delta) > state.policy.max_temp_delta_c {
+            Some(RecalReason::Temperature)
+        } else if max_age_us > 0 && report.age_us.map_or(false, |age| age > max_age_us) {
+            Some(RecalReason::Age)
+        } else {
+            None
+        };
+
+        let Some(reason) = reason else {
+            state.flagged = false;
+            return Ok(());
+        };
+        if state.flagged {
+            return Ok(());
+        }
+        state.flagged = true;
+
+        match state.policy.action {
+            RecalAction::Flag => f(RecalEvent::Due(reason)),
+            RecalAction::Recalibrate { samples, .. } => {
+                state.running = Some((CalibrationTask::new(samples), reason));
+                f(RecalEvent::Started(reason));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::MockError;
+    use crate::sim::Simulator;
+    use crate::F32x3;
+    use std::vec::Vec;
+
+    fn policy(action: RecalAction) -> RecalPolicy {
+        RecalPolicy {
+            max_temp_delta_c: 5.0,
+            max_age_s: 0,
+            action,
+        }
+    }
+
+    #[test]
+    fn temperature_changes_are_flagged_once() {
+        let mut driver = L3GD20::with_transport(Simulator::<MockError>::new()).unwrap();
+        driver.set_bias(Bias {
+            temperature: 20,
+            samples: 16,
+            ..Bias::default()
+        });
+        driver.set_recal_policy(Some(policy(RecalAction::Flag)));
+
+        let mut events = Vec::new();
+        driver.transport.set_temperature(18);
+        driver.recal_update(|event| events.push(event)).unwrap();
+        assert!(events.is_empty());
+
+        driver.transport.set_temperature(10);
+        driver.recal_update(|event| events.push(event)).unwrap();
+        driver.recal_update(|event| events.push(event)).unwrap();
+        assert_eq!(events, [RecalEvent::Due(RecalReason::Temperature)]);
+    }
+
+    #[test]
+    fn missing_bias_is_recalibrated_while_stationary() {
+        let mut sim = Simulator::<MockError>::new();
+        sim.generate(1, |_| [5, -5, 0]);
+        let mut driver = L3GD20::with_transport(sim).unwrap();
+        driver.set_recal_policy(Some(policy(RecalAction::Recalibrate {
+            samples: 4,
+            max_noise_dps: 0.1,
+        })));
+
+        let mut events = Vec::new();
+        for _ in 0..6 {
+            driver.recal_update(|event| events.push(event)).unwrap();
+        }
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0], RecalEvent::Started(RecalReason::Missing));
+        let RecalEvent::Completed(bias) = events[1] else {
+            panic!("expected a completed recalibration, got {:?}", events[1]);
+        };
+        assert_eq!(bias.offset, F32x3::new(5.0, -5.0, 0.0));
+        assert_eq!(driver.bias().offset, bias.offset);
+    }
+}
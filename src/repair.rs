@@ -0,0 +1,225 @@
+//! Provides recovery from configuration sequences that failed partway.
+//!
+//! Multi-register configuration changes such as [`L3GD20::reset`] and
+//! [`L3GD20::apply_settings`] are planned up front and written register by register.
+//! If a write fails, the remaining writes are kept, so that the checked variants can
+//! report how far the sequence got and [`L3GD20::repair_config`] can complete it
+//! without repeating the writes that already succeeded.
+
+use crate::common::framing;
+use crate::{Error, L3GD20Settings, Transport, L3GD20};
+use embedded_hal::blocking::delay::DelayUs;
+use l3gd20_registers::prelude::{SPIRegister, ToBits};
+use l3gd20_registers::{
+    ControlRegister1, ControlRegister2, ControlRegister3, ControlRegister4, ControlRegister5,
+};
+
+/// The largest number of register writes in a configuration sequence.
+const MAX_WRITES: usize = 6;
+
+/// A register address and the value to write to it.
+pub(crate) type RegisterWrite = (u8, u8);
+
+/// Plans the register writes of a reset to the output data rate, bandwidth, high-pass
+/// filter, full scale and block data update selection of the settings, with the X, Y and
+/// Z axes enabled as given.
+///
+/// The sequence is shared by the blocking and the asynchronous driver.
+pub(crate) fn reset_writes(
+    settings: &L3GD20Settings,
+    [x, y, z]: [bool; 3],
+) -> [RegisterWrite; MAX_WRITES] {
+    let ctrl1 = ControlRegister1::default()
+        .with_power_up(true)
+        .with_x_enable(x)
+        .with_y_enable(y)
+        .with_z_enable(z)
+        .with_output_data_rate(settings.output_data_rate)
+        .with_bandwidth(settings.bandwidth);
+    let ctrl2 = ControlRegister2::default()
+        .with_hpm(settings.highpass_mode)
+        .with_hpcf(settings.highpass_cutoff);
+    let ctrl3 = ControlRegister3::default()
+        .with_i1int1(false)
+        .with_i1boot(false)
+        .with_int1_low(false)
+        .with_i2drdy(false)
+        .with_i2wtm(false)
+        .with_i2orun(false)
+        .with_i2empty(false)
+        .with_open_drain(false);
+    let ctrl4 = ControlRegister4::default()
+        .with_block_data_update(settings.block_data_update)
+        .with_big_endian(false)
+        .with_full_scale(settings.full_scale)
+        .with_spi_serial_3wire(false);
+    let ctrl5 = ControlRegister5::default()
+        .with_boot(false)
+        .with_fifo_enable(false)
+        .with_hpen(settings.highpass_enabled)
+        .with_int1_sel(0)
+        .with_out_sel(0);
+
+    [
+        (*ControlRegister1::REGISTER_ADDRESS, ctrl1.to_bits()),
+        (*ControlRegister2::REGISTER_ADDRESS, ctrl2.to_bits()),
+        (*ControlRegister3::REGISTER_ADDRESS, ctrl3.to_bits()),
+        (*ControlRegister4::REGISTER_ADDRESS, ctrl4.to_bits()),
+        // toggle boot
+        (
+            *ControlRegister5::REGISTER_ADDRESS,
+            ctrl5.with_boot(true).to_bits(),
+        ),
+        (*ControlRegister5::REGISTER_ADDRESS, ctrl5.to_bits()),
+    ]
+}
+
+/// A configuration sequence that was not yet written completely.
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct PendingConfig {
+    writes: [RegisterWrite; MAX_WRITES],
+    len: usize,
+    next: usize,
+    settings: L3GD20Settings,
+}
+
+impl<T, E> L3GD20<T>
+where
+    T: Transport<Error = E>,
+{
+    /// Determines whether a configuration sequence failed partway and awaits
+    /// [`repair_config`](Self::repair_config).
+    pub fn has_pending_config(&self) -> bool {
+        self.pending_config.is_some()
+    }
+
+    /// Re-attempts the remaining writes of a configuration sequence that failed partway.
+    ///
+    /// Returns `false` if no sequence was pending. If a write fails again, the error
+    /// reports the last register written and the sequence stays pending.
+    pub fn repair_config(&mut self) -> Result<bool, Error<E>> {
+        if self.pending_config.is_none() {
+            return Ok(false);
+        }
+        self.resume_config(None)
+            .map_err(|error| self.partial_config_error(error))?;
+        Ok(true)
+    }
+
+    /// Writes the configuration sequence, applying the settings once it completed.
+    ///
+    /// Any previously pending sequence is discarded. The delay, if any, is used to wait
+    /// for the device to reboot.
+    pub(crate) fn write_config(
+        &mut self,
+        writes: &[RegisterWrite],
+        settings: L3GD20Settings,
+        delay: Option<&mut dyn DelayUs<u32>>,
+    ) -> Result<(), E> {
+        let mut pending = PendingConfig {
+            writes: [(0, 0); MAX_WRITES],
+            len: writes.len(),
+            next: 0,
+            settings,
+        };
+        pending.writes[..writes.len()].copy_from_slice(writes);
+        self.pending_config = Some(pending);
+        self.resume_config(delay)
+    }
+
+    /// Maps the error of a failed configuration sequence to [`Error::PartialConfig`].
+    pub(crate) fn partial_config_error(&self, error: E) -> Error<E> {
+        let last_written = self
+            .pending_config
+            .as_ref()
+            .and_then(|pending| pending.next.checked_sub(1).map(|i| pending.writes[i].0));
+        Error::PartialConfig {
+            error,
+            last_written,
+        }
+    }
+
+    /// Writes the remaining registers of the pending sequence.
+    fn resume_config(&mut self, mut delay: Option<&mut dyn DelayUs<u32>>) -> Result<(), E> {
+        while let Some(pending) = self.pending_config {
+            let Some(&(address, value)) = pending.writes[..pending.len].get(pending.next) else {
+                self.expected_settings = pending.settings;
+                self.pending_config = None;
+                break;
+            };
+
+            let mut buffer = [framing::write_single_cmd(address), value];
+            self.transfer(&mut buffer)?;
+            self.note_register_write(address, value);
+            if address == *ControlRegister5::REGISTER_ADDRESS
+                && ControlRegister5::from_bits(value).boot()
+            {
+                self.settle(delay.as_deref_mut(), crate::settle::BOOT_TIME_US);
+            }
+            if let Some(pending) = self.pending_config.as_mut() {
+                pending.next += 1;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::MockError;
+    use crate::sim::Simulator;
+
+    #[test]
+    fn failed_reset_is_repaired() {
+        let mut driver = L3GD20::with_transport(Simulator::<MockError>::new()).unwrap();
+        assert!(!driver.has_pending_config());
+        driver.transport.registers_mut()[0x22] = 0xFF;
+        driver.transport.registers_mut()[0x23] = 0xFF;
+
+        let transfers = driver.transport.transfers();
+        driver.transport.fail_at(transfers + 2, MockError);
+        assert_eq!(
+            driver.reset_checked(),
+            Err(Error::PartialConfig {
+                error: MockError,
+                last_written: Some(0x21),
+            })
+        );
+        assert!(driver.has_pending_config());
+        assert_eq!(driver.transport.registers()[0x22], 0xFF);
+
+        assert_eq!(driver.repair_config(), Ok(true));
+        assert!(!driver.has_pending_config());
+        assert_eq!(driver.transport.registers()[0x22], 0);
+        assert_eq!(driver.transport.registers()[0x23], 0);
+        // Only the remaining four writes were issued.
+        assert_eq!(driver.transport.transfers(), transfers + 3 + 4);
+        assert_eq!(driver.repair_config(), Ok(false));
+    }
+
+    #[test]
+    fn settings_are_only_applied_once_complete() {
+        let mut driver = L3GD20::with_transport(Simulator::<MockError>::new()).unwrap();
+        let settings = L3GD20Settings {
+            block_data_update: true,
+            ..Default::default()
+        };
+
+        let transfers = driver.transport.transfers();
+        // The control block is read first, then the registers are written.
+        driver.transport.fail_at(transfers + 1, MockError);
+        assert_eq!(
+            driver.apply_settings_checked(&settings),
+            Err(Error::PartialConfig {
+                error: MockError,
+                last_written: None,
+            })
+        );
+        assert_ne!(driver.expected_settings, settings);
+
+        assert_eq!(driver.repair_config(), Ok(true));
+        assert_eq!(driver.expected_settings, settings);
+        assert!(driver.block_data_update);
+    }
+}
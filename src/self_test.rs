@@ -0,0 +1,233 @@
+//! Provides the self-test of the L3GD20H.
+//!
+//! The self-test bits `ST` of `CTRL_REG4` actuate the sensing element electrostatically,
+//! shifting the output by a defined rate. [`L3GD20::run_self_test`] compares the output
+//! with and without actuation against the expected change for the full scale in effect,
+//! for production sanity checks. On the L3GD20 these bits are reserved, so the self-test
+//! requires an L3GD20H, see the [`l3gd20h`](crate::l3gd20h) module.
+
+use crate::{characteristics, Error, F32x3, I16x3, Transport, L3GD20};
+use embedded_hal::blocking::delay::DelayUs;
+use l3gd20_registers::prelude::SPIRegister;
+use l3gd20_registers::{ControlRegister4, Sensitivity};
+
+/// The `ST` bits of `CTRL_REG4`.
+const ST_MASK: u8 = 0b0000_0110;
+
+/// The number of samples averaged per phase of [`L3GD20::run_self_test`].
+pub const SELF_TEST_SAMPLES: usize = 5;
+
+/// The time waited after changing the self-test mode, in microseconds.
+pub const SELF_TEST_SETTLE_US: u32 = 50_000;
+
+/// The time waited for each sample of [`L3GD20::run_self_test`], in microseconds.
+///
+/// This covers one sample period at the lowest output data rate of 12.5 Hz.
+const SAMPLE_TIMEOUT_US: u32 = 100_000;
+
+/// The self-test mode.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum SelfTestMode {
+    /// Normal operation (`ST = 00`).
+    #[default]
+    Normal,
+    /// Self-test with a positive output change (`ST = 01`).
+    Positive,
+    /// Self-test with a negative output change (`ST = 11`).
+    Negative,
+}
+
+impl SelfTestMode {
+    /// Gets the `ST` bits in their position in `CTRL_REG4`.
+    pub const fn bits(&self) -> u8 {
+        match self {
+            SelfTestMode::Normal => 0b0000_0000,
+            SelfTestMode::Positive => 0b0000_0010,
+            SelfTestMode::Negative => 0b0000_0110,
+        }
+    }
+
+    /// Decodes the `ST` bits from `CTRL_REG4`; the reserved code selects normal operation.
+    pub const fn from_ctrl4(ctrl4: u8) -> Self {
+        match ctrl4 & ST_MASK {
+            0b0000_0010 => SelfTestMode::Positive,
+            0b0000_0110 => SelfTestMode::Negative,
+            _ => SelfTestMode::Normal,
+        }
+    }
+}
+
+/// The accepted range of the self-test output change, in degrees/second.
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SelfTestLimits {
+    /// The smallest accepted change.
+    pub min_dps: f32,
+    /// The largest accepted change.
+    pub max_dps: f32,
+}
+
+impl SelfTestLimits {
+    /// Gets the limits for a full scale selection.
+    ///
+    /// The datasheet only gives the typical change of 130, 200 and 530 °/s at ±250, ±500
+    /// and ±2000 °/s; half to one and a half times the typical change is accepted.
+    pub const fn for_full_scale(fs: Sensitivity) -> Self {
+        let typical = typical_change_dps(fs);
+        Self {
+            min_dps: typical * 0.5,
+            max_dps: typical * 1.5,
+        }
+    }
+
+    /// Determines whether a change, in degrees/second, is within the limits.
+    pub fn contains(&self, change_dps: f32) -> bool {
+        (self.min_dps..=self.max_dps).contains(&change_dps)
+    }
+}
+
+/// Gets the typical self-test output change for a full scale selection, in degrees/second.
+pub(crate) const fn typical_change_dps(fs: Sensitivity) -> f32 {
+    match fs {
+        Sensitivity::D250 => 130.0,
+        Sensitivity::D500 => 200.0,
+        Sensitivity::D2000 | Sensitivity::D2000_11 => 530.0,
+    }
+}
+
+/// The result of [`L3GD20::run_self_test`].
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SelfTestReport {
+    /// The magnitude of the output change per axis, in degrees/second.
+    pub change_dps: F32x3,
+    /// The limits the change was checked against.
+    pub limits: SelfTestLimits,
+    /// Whether the X, Y and Z axes passed.
+    pub passed: [bool; 3],
+}
+
+impl SelfTestReport {
+    /// Checks the output change per axis against the limits.
+    pub fn new(change_dps: F32x3, limits: SelfTestLimits) -> Self {
+        Self {
+            change_dps,
+            limits,
+            passed: [
+                limits.contains(change_dps.x),
+                limits.contains(change_dps.y),
+                limits.contains(change_dps.z),
+            ],
+        }
+    }
+
+    /// Determines whether all axes passed.
+    pub fn all_passed(&self) -> bool {
+        self.passed.iter().all(|&passed| passed)
+    }
+}
+
+impl<T, E> L3GD20<T>
+where
+    T: Transport<Error = E>,
+{
+    /// Selects the self-test mode.
+    ///
+    /// Fails with [`Error::InvalidConfig`] unless the device is known to be an L3GD20H.
+    pub fn enable_self_test(&mut self, mode: SelfTestMode) -> Result<(), Error<E>> {
+        self.require_l3gd20h()?;
+        let address = *ControlRegister4::REGISTER_ADDRESS;
+        let ctrl4 = self.read_raw(address)?;
+        self.write_raw(address, (ctrl4 & !ST_MASK) | mode.bits())
+    }
+
+    /// Runs the self-test in the full scale currently selected.
+    ///
+    /// [`SELF_TEST_SAMPLES`] samples are averaged without actuation, then again in
+    /// [`SelfTestMode::Positive`] after waiting [`SELF_TEST_SETTLE_US`]; the first sample
+    /// after each mode change is discarded. The self-test is disabled afterwards, even if
+    /// reading a sample failed.
+    ///
+    /// Fails with [`Error::InvalidConfig`] unless the device is known to be an L3GD20H, and
+    /// with [`Error::Timeout`] if no sample arrives within one period at 12.5 Hz.
+    pub fn run_self_test<D>(&mut self, delay: &mut D) -> Result<SelfTestReport, Error<E>>
+    where
+        D: DelayUs<u32>,
+    {
+        self.require_l3gd20h()?;
+        let baseline = self.average_samples(delay)?;
+
+        self.enable_self_test(SelfTestMode::Positive)?;
+        self.feed_watchdog();
+        delay.delay_us(SELF_TEST_SETTLE_US);
+        let actuated = self.average_samples(delay);
+        self.enable_self_test(SelfTestMode::Normal)?;
+        let actuated = actuated?;
+
+        let sensitivity = characteristics::sensitivity(self.full_scale);
+        let change = |axis: usize| libm::fabsf(actuated[axis] - baseline[axis]) * sensitivity;
+        Ok(SelfTestReport::new(
+            F32x3::new(change(0), change(1), change(2)),
+            SelfTestLimits::for_full_scale(self.full_scale),
+        ))
+    }
+
+    /// Averages [`SELF_TEST_SAMPLES`] samples after discarding one, in LSB.
+    fn average_samples<D>(&mut self, delay: &mut D) -> Result<[f32; 3], Error<E>>
+    where
+        D: DelayUs<u32>,
+    {
+        self.wait_for_data_ready(delay, SAMPLE_TIMEOUT_US)?;
+        let mut sum = [0.0; 3];
+        for _ in 0..SELF_TEST_SAMPLES {
+            let I16x3 { x, y, z } = self.wait_for_data_ready(delay, SAMPLE_TIMEOUT_US)?;
+            for (sum, value) in sum.iter_mut().zip([x, y, z]) {
+                *sum += f32::from(value);
+            }
+        }
+        Ok(sum.map(|sum| sum / SELF_TEST_SAMPLES as f32))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{MockError, NoDelay};
+    use crate::sim::Simulator;
+
+    #[test]
+    fn self_test_checks_the_output_change() {
+        let mut sim = Simulator::<MockError>::new();
+        sim.registers_mut()[0x0F] = 0xD7;
+        sim.generate(1, |_| [10, -20, 30]);
+        let mut driver = L3GD20::with_transport(sim).unwrap();
+        driver.identify().unwrap();
+
+        let report = driver.run_self_test(&mut NoDelay).unwrap();
+        assert!(report.all_passed(), "{report:?}");
+        assert!((report.change_dps.x - 130.0).abs() < 0.1);
+        assert_eq!(
+            SelfTestMode::from_ctrl4(driver.transport.registers()[0x23]),
+            SelfTestMode::Normal
+        );
+    }
+
+    #[test]
+    fn self_test_requires_the_l3gd20h() {
+        let mut driver = L3GD20::with_transport(Simulator::<MockError>::new()).unwrap();
+        driver.identify().unwrap();
+        assert_eq!(
+            driver.run_self_test(&mut NoDelay),
+            Err(Error::InvalidConfig)
+        );
+    }
+
+    #[test]
+    fn axes_are_checked_individually() {
+        let limits = SelfTestLimits::for_full_scale(Sensitivity::D500);
+        let report = SelfTestReport::new(F32x3::new(200.0, 20.0, 290.0), limits);
+        assert_eq!(report.passed, [true, false, true]);
+        assert!(!report.all_passed());
+    }
+}
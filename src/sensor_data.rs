@@ -1,8 +1,8 @@
-use crate::{I16x3, Reading};
+use crate::{I16x3, Reading, Status};
 use l3gd20_registers::StatusRegister;
 
 /// Sensor data.
-#[derive(Debug, Clone)]
+#[derive(Debug, Copy, Clone)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct SensorData {
     /// The temperature reading
@@ -13,6 +13,9 @@ pub struct SensorData {
     pub y: Reading<i16>,
     /// The Z-axis reading.
     pub z: Reading<i16>,
+    /// The status register read along with the readings, with the per-axis flags mapped
+    /// to the body axes like the readings.
+    pub status: Status,
 }
 
 impl SensorData {
@@ -27,32 +30,89 @@ impl SensorData {
             x: Reading::map(x, status.x_da(), status.x_overrun()),
             y: Reading::map(y, status.y_da(), status.y_overrun()),
             z: Reading::map(z, status.z_da(), status.z_overrun()),
+            status: Status::from(*status),
+        }
+    }
+
+    /// Indicates whether all readings are fresh, i.e. new and not overrun.
+    #[must_use]
+    pub fn all_fresh(&self) -> bool {
+        self.readings().iter().all(Reading::fresh)
+    }
+
+    /// Indicates whether any reading is stale, i.e. was read before.
+    #[must_use]
+    pub fn any_stale(&self) -> bool {
+        self.readings().iter().any(Reading::stale)
+    }
+
+    /// Indicates whether any reading is overrun, i.e. new data overwrote a previous sample
+    /// before it was read.
+    #[must_use]
+    pub fn any_overrun(&self) -> bool {
+        self.readings().iter().any(Reading::overrun)
+    }
+
+    /// Summarizes the freshness of the readings.
+    #[must_use]
+    pub fn freshness(&self) -> Freshness {
+        if self.readings().iter().all(Reading::stale) {
+            Freshness::Stale
+        } else if self.any_stale() {
+            Freshness::Partial
+        } else if self.any_overrun() {
+            Freshness::Overrun
+        } else {
+            Freshness::Fresh
         }
     }
 
     /// Indicates whether any reading is stale.
     #[must_use]
+    #[deprecated(note = "use `any_stale` instead")]
     pub fn stale(&self) -> bool {
-        self.x.stale() || self.y.stale() || self.z.stale()
+        self.any_stale()
     }
 
     /// Indicates whether all readings are fresh.
     #[must_use]
+    #[deprecated(note = "use `all_fresh` instead")]
     pub fn fresh(&self) -> bool {
-        self.x.fresh() && self.y.stale() || self.z.stale()
+        self.all_fresh()
     }
 
     /// Indicates whether all readings are fresh or overrun.
     #[must_use]
+    #[deprecated(note = "use `!any_stale()` instead")]
     pub fn fresh_or_overrun(&self) -> bool {
-        self.x.fresh_or_overrun() && self.y.fresh_or_overrun() || self.z.fresh_or_overrun()
+        !self.any_stale()
     }
 
-    /// Indicates whether this is an overrun reading.
+    /// Indicates whether any reading is overrun.
     #[must_use]
+    #[deprecated(note = "use `any_overrun` instead")]
     pub fn overrun(&self) -> bool {
-        self.x.overrun() && self.y.overrun() || self.z.overrun()
+        self.any_overrun()
     }
+
+    /// Gets the X, Y and Z-axis readings.
+    fn readings(&self) -> [Reading<i16>; 3] {
+        [self.x, self.y, self.z]
+    }
+}
+
+/// A summary of the freshness of the readings of a [`SensorData`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Freshness {
+    /// All readings are new and none was overrun.
+    Fresh,
+    /// All readings are new, but at least one overwrote a sample that was not read.
+    Overrun,
+    /// Some readings are new, others are stale.
+    Partial,
+    /// All readings are stale.
+    Stale,
 }
 
 impl From<SensorData> for I16x3 {
@@ -64,3 +124,50 @@ impl From<SensorData> for I16x3 {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn freshness_of_all_combinations() {
+        let states = [Reading::new_stale, Reading::new_fresh, Reading::new_overrun];
+        for x in states {
+            for y in states {
+                for z in states {
+                    let data = SensorData {
+                        x: x(1),
+                        y: y(2),
+                        z: z(3),
+                        ..SensorData::new(0, 0, 0, 0, StatusRegister::default())
+                    };
+                    let readings = [data.x, data.y, data.z];
+                    let stale = readings.iter().filter(|r| r.stale()).count();
+                    let overrun = readings.iter().filter(|r| r.overrun()).count();
+
+                    assert_eq!(data.all_fresh(), stale == 0 && overrun == 0);
+                    assert_eq!(data.any_stale(), stale > 0);
+                    assert_eq!(data.any_overrun(), overrun > 0);
+                    let expected = match (stale, overrun) {
+                        (3, _) => Freshness::Stale,
+                        (1..=2, _) => Freshness::Partial,
+                        (0, 0) => Freshness::Fresh,
+                        _ => Freshness::Overrun,
+                    };
+                    assert_eq!(data.freshness(), expected, "{readings:?}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn flags_of_the_status_register() {
+        // ZYXDA with new data on X and Y, and an overrun on Y.
+        let data = SensorData::new(0, 1, 2, 3, StatusRegister::from_bits(0b0010_1011));
+        assert_eq!(data.freshness(), Freshness::Partial);
+        assert!(data.any_overrun() && data.any_stale() && !data.all_fresh());
+
+        let data = SensorData::new(0, 1, 2, 3, StatusRegister::from_bits(0b0000_1111));
+        assert_eq!(data.freshness(), Freshness::Fresh);
+    }
+}
@@ -0,0 +1,211 @@
+//! Provides a metadata header for captured sample streams.
+//!
+//! A [`SessionHeader`] records everything needed to interpret a logged dataset later on:
+//! the device variant and identification, the configuration, the characteristics, the
+//! bias estimate and free-form labels supplied by the firmware (e.g. a build or unit id).
+//!
+//! ## Layout
+//!
+//! Multi-byte values use the byte order selected when encoding. The header consists of:
+//!
+//! | Field                  | Size        | Contents                                           |
+//! |------------------------|-------------|----------------------------------------------------|
+//! | magic                  | 4           | `L3GS`                                             |
+//! | version                | 1           | `1`                                                |
+//! | `WHO_AM_I`             | 1           | the raw identification                             |
+//! | variant                | 1           | `0` = unknown, `1` = L3GD20, `2` = L3GD20H         |
+//! | settings               | 6           | [`L3GD20Settings::to_bytes`]                       |
+//! | full scale             | 2           | `u16`, in dps                                      |
+//! | characteristics        | 16          | sensitivity, zero-rate noise, zero-rate level change and rate noise density as `f32` |
+//! | bias offset            | 12          | [`F32x3`](crate::F32x3), in LSB                    |
+//! | bias temperature       | 1           | the raw temperature reading                        |
+//! | bias samples           | 2           | `u16`                                              |
+//! | bias noise             | 12          | [`F32x3`](crate::F32x3), in LSB                    |
+//! | label count            | 1           | `u8`                                               |
+//! | labels                 | variable    | per label: key length, key, value length, value; lengths as `u8`, strings as UTF-8 |
+
+use crate::encoding::ByteOrder;
+use crate::temperature::Variant;
+use crate::{Bias, Characteristics, L3GD20Settings, Transport, L3GD20};
+use l3gd20_registers::WhoAmI;
+
+/// The magic bytes the header starts with.
+const MAGIC: [u8; 4] = *b"L3GS";
+
+/// The current version of the layout.
+const VERSION: u8 = 1;
+
+/// The encoded length of all fields before the labels.
+const FIXED_LEN: usize = 4 + 1 + 1 + 1 + L3GD20Settings::ENCODED_LEN + 2 + 16 + 12 + 1 + 2 + 12 + 1;
+
+/// A free-form key/value label.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Label<'a> {
+    /// The key, at most 255 bytes long.
+    pub key: &'a str,
+    /// The value, at most 255 bytes long.
+    pub value: &'a str,
+}
+
+impl<'a> Label<'a> {
+    /// Creates a new label.
+    pub const fn new(key: &'a str, value: &'a str) -> Self {
+        Self { key, value }
+    }
+}
+
+/// An error encoding a [`SessionHeader`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum SessionHeaderError {
+    /// The output buffer is shorter than [`SessionHeader::encoded_len`].
+    BufferTooSmall,
+    /// More than 255 labels were supplied.
+    TooManyLabels,
+    /// A label key or value is longer than 255 bytes.
+    LabelTooLong,
+}
+
+/// The metadata describing a captured session.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SessionHeader<'a> {
+    /// The raw `WHO_AM_I` value.
+    pub who_am_i: u8,
+    /// The chip variant, if known.
+    pub variant: Option<Variant>,
+    /// The sensor configuration.
+    pub settings: L3GD20Settings,
+    /// The sensor characteristics.
+    pub characteristics: Characteristics,
+    /// The bias estimate applied to the readings.
+    pub bias: Bias,
+    /// The labels supplied by the firmware.
+    pub labels: &'a [Label<'a>],
+}
+
+impl<'a> SessionHeader<'a> {
+    /// Gets the length of the encoding produced by [`encode`](Self::encode).
+    pub fn encoded_len(&self) -> usize {
+        FIXED_LEN
+            + self
+                .labels
+                .iter()
+                .map(|label| 2 + label.key.len() + label.value.len())
+                .sum::<usize>()
+    }
+
+    /// Encodes the header into the buffer using the specified byte order.
+    ///
+    /// Returns the number of bytes written.
+    pub fn encode<O: ByteOrder>(&self, buffer: &mut [u8]) -> Result<usize, SessionHeaderError> {
+        let label_count =
+            u8::try_from(self.labels.len()).map_err(|_| SessionHeaderError::TooManyLabels)?;
+        let len = self.encoded_len();
+        let buffer = buffer
+            .get_mut(..len)
+            .ok_or(SessionHeaderError::BufferTooSmall)?;
+
+        let variant = match self.variant {
+            None => 0,
+            Some(Variant::L3GD20) => 1,
+            Some(Variant::L3GD20H) => 2,
+        };
+        let characteristics = &self.characteristics;
+
+        let mut cursor = Cursor { buffer, pos: 0 };
+        cursor.put(&MAGIC);
+        cursor.put(&[VERSION, self.who_am_i, variant]);
+        cursor.put(&self.settings.to_bytes());
+        cursor.put(&O::u16_to_bytes(characteristics.full_scale));
+        cursor.put(&O::f32_to_bytes(characteristics.sensitivity));
+        cursor.put(&O::f32_to_bytes(characteristics.zero_rate_noise));
+        cursor.put(&O::f32_to_bytes(characteristics.zero_rate_level_temp));
+        cursor.put(&O::f32_to_bytes(characteristics.rate_noise_density));
+        cursor.put(&self.bias.offset.to_bytes::<O>());
+        cursor.put(&[self.bias.temperature]);
+        cursor.put(&O::u16_to_bytes(self.bias.samples));
+        cursor.put(&self.bias.noise.to_bytes::<O>());
+        cursor.put(&[label_count]);
+        for label in self.labels {
+            cursor.put_str(label.key)?;
+            cursor.put_str(label.value)?;
+        }
+
+        debug_assert_eq!(cursor.pos, len);
+        Ok(len)
+    }
+}
+
+/// Writes sequentially into a buffer of sufficient length.
+struct Cursor<'b> {
+    buffer: &'b mut [u8],
+    pos: usize,
+}
+
+impl Cursor<'_> {
+    fn put(&mut self, bytes: &[u8]) {
+        self.buffer[self.pos..self.pos + bytes.len()].copy_from_slice(bytes);
+        self.pos += bytes.len();
+    }
+
+    fn put_str(&mut self, value: &str) -> Result<(), SessionHeaderError> {
+        let len = u8::try_from(value.len()).map_err(|_| SessionHeaderError::LabelTooLong)?;
+        self.put(&[len]);
+        self.put(value.as_bytes());
+        Ok(())
+    }
+}
+
+impl<T, E> L3GD20<T>
+where
+    T: Transport<Error = E>,
+{
+    /// Captures the session header from the current device state and the applied bias.
+    pub fn session_header<'a>(&mut self, labels: &'a [Label<'a>]) -> Result<SessionHeader<'a>, E> {
+        let who_am_i = self.read_register::<WhoAmI>()?.ident();
+        Ok(SessionHeader {
+            who_am_i,
+            variant: self.variant().or(Variant::from_who_am_i(who_am_i)),
+            settings: self.settings()?,
+            characteristics: self.characteristics()?,
+            bias: *self.bias(),
+            labels,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encoding::{BigEndian, LittleEndian};
+    use crate::mock::MockError;
+    use crate::sim::Simulator;
+
+    #[test]
+    fn header_is_encoded() {
+        let mut driver = L3GD20::with_transport(Simulator::<MockError>::new()).unwrap();
+        let labels = [Label::new("unit", "7"), Label::new("fw", "1.2")];
+        let header = driver.session_header(&labels).unwrap();
+        assert_eq!(header.who_am_i, 0xD4);
+        assert_eq!(header.variant, Some(Variant::L3GD20));
+
+        let mut buffer = [0; 128];
+        let len = header.encode::<BigEndian>(&mut buffer).unwrap();
+        assert_eq!(len, FIXED_LEN + 4 + 4 + 1 + 2 + 3);
+        assert_eq!(&buffer[..7], b"L3GS\x01\xD4\x01");
+        assert_eq!(
+            L3GD20Settings::from_bytes(&buffer[7..]).unwrap(),
+            header.settings
+        );
+        assert_eq!(&buffer[13..15], &250u16.to_be_bytes());
+        assert_eq!(buffer[FIXED_LEN - 1], 2);
+        assert_eq!(&buffer[FIXED_LEN..len], b"\x04unit\x017\x02fw\x031.2");
+
+        assert_eq!(
+            header.encode::<LittleEndian>(&mut buffer[..len - 1]),
+            Err(SessionHeaderError::BufferTooSmall)
+        );
+    }
+}
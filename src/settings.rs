@@ -0,0 +1,505 @@
+//! Provides persistence of the sensor configuration.
+//!
+//! [`L3GD20Settings`] captures the tunable sensor configuration and can be stored as raw
+//! bytes, e.g. in EEPROM or flash, and restored at boot.
+//!
+//! ## Layout
+//!
+//! The encoding starts with a version byte and ends with a checksum byte, which is the
+//! bitwise complement of the wrapping sum of all preceding bytes. In between, version `1`
+//! stores the configuration bits of `CTRL_REG1`, `CTRL_REG2`, `CTRL_REG4` and `CTRL_REG5`
+//! in their register layout, with all unrelated bits cleared.
+//!
+//! Later versions only ever append fields before the checksum, so that newer
+//! firmware can decode the settings stored by older firmware; fields missing from
+//! older versions take their [`Default`] values.
+
+use crate::common::framing;
+use crate::{Error, Transport, L3GD20};
+use l3gd20_registers::prelude::{SPIRegister, ToBits};
+use l3gd20_registers::*;
+
+/// The current version of the encoding.
+const VERSION: u8 = 1;
+
+/// The encoded length of version 1, including the version and checksum bytes.
+const LEN_V1: usize = 6;
+
+/// The configuration bits of `CTRL_REG1`: ODR and bandwidth.
+const CTRL1_MASK: u8 = 0b1111_0000;
+
+/// The configuration bits of `CTRL_REG2`: high-pass filter mode and cutoff.
+const CTRL2_MASK: u8 = 0b0011_1111;
+
+/// The configuration bits of `CTRL_REG4`: block data update and full scale.
+const CTRL4_MASK: u8 = 0b1011_0000;
+
+/// The configuration bits of `CTRL_REG5`: high-pass filter enable.
+const CTRL5_MASK: u8 = 0b0001_0000;
+
+/// The tunable configuration of the sensor.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct L3GD20Settings {
+    /// The output data rate.
+    pub output_data_rate: OutputDataRate,
+    /// The bandwidth.
+    pub bandwidth: Bandwidth,
+    /// The full scale selection.
+    pub full_scale: Sensitivity,
+    /// The high-pass filter mode.
+    pub highpass_mode: HighpassFilterMode,
+    /// The high-pass filter cutoff frequency configuration (`HPCF`, four bits).
+    pub highpass_cutoff: u8,
+    /// Whether the high-pass filter is enabled.
+    pub highpass_enabled: bool,
+    /// Whether block data update is enabled.
+    pub block_data_update: bool,
+}
+
+/// The contents of `CTRL_REG1` through `CTRL_REG5`.
+pub(crate) type ControlBlock = [u8; 5];
+
+/// The largest high-pass filter cutoff configuration defined by the datasheet.
+const MAX_HIGHPASS_CUTOFF: u8 = 0b1001;
+
+/// An error decoding [`L3GD20Settings`] from bytes.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum SettingsError {
+    /// The input is shorter than its version requires.
+    Truncated,
+    /// The version is unknown to this version of the crate.
+    UnsupportedVersion(u8),
+    /// The checksum does not match.
+    Checksum,
+}
+
+/// An error applying a configuration with [`L3GD20::configure`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ConfigureError<E> {
+    /// The high-pass filter cutoff configuration is not defined by the datasheet.
+    /// Nothing was written.
+    InvalidHighpassCutoff(u8),
+    /// A bus transfer failed.
+    Bus {
+        /// The bus error.
+        error: E,
+        /// The state the device was left in.
+        state: DeviceState,
+    },
+}
+
+/// The state the device was left in after a failed [`L3GD20::configure`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum DeviceState {
+    /// The failure occurred before anything was written; the configuration is unchanged.
+    Unchanged,
+    /// The previous configuration was restored.
+    Restored,
+    /// Restoring the previous configuration failed as well; the configuration is unknown.
+    Unknown,
+}
+
+impl L3GD20Settings {
+    /// The length of the encoding produced by [`to_bytes`](Self::to_bytes).
+    pub const ENCODED_LEN: usize = LEN_V1;
+
+    /// Encodes the settings in the current version of the layout.
+    #[must_use]
+    pub fn to_bytes(&self) -> [u8; Self::ENCODED_LEN] {
+        let mut bytes = [
+            VERSION,
+            self.ctrl1().to_bits() & CTRL1_MASK,
+            self.ctrl2().to_bits() & CTRL2_MASK,
+            self.ctrl4().to_bits() & CTRL4_MASK,
+            self.ctrl5().to_bits() & CTRL5_MASK,
+            0,
+        ];
+        bytes[LEN_V1 - 1] = checksum(&bytes[..LEN_V1 - 1]);
+        bytes
+    }
+
+    /// Decodes settings stored in the current or any older version of the layout.
+    ///
+    /// Trailing bytes beyond the length of the encoded version are ignored.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, SettingsError> {
+        let version = *bytes.first().ok_or(SettingsError::Truncated)?;
+        let len = match version {
+            1 => LEN_V1,
+            _ => return Err(SettingsError::UnsupportedVersion(version)),
+        };
+        let bytes = bytes.get(..len).ok_or(SettingsError::Truncated)?;
+        if checksum(&bytes[..len - 1]) != bytes[len - 1] {
+            return Err(SettingsError::Checksum);
+        }
+
+        let ctrl1 = ControlRegister1::from_bits(bytes[1]);
+        let ctrl2 = ControlRegister2::from_bits(bytes[2]);
+        let ctrl4 = ControlRegister4::from_bits(bytes[3]);
+        let ctrl5 = ControlRegister5::from_bits(bytes[4]);
+        Ok(Self {
+            output_data_rate: ctrl1.output_data_rate(),
+            bandwidth: ctrl1.bandwidth(),
+            full_scale: ctrl4.full_scale(),
+            highpass_mode: ctrl2.hpm(),
+            highpass_cutoff: ctrl2.hpcf(),
+            highpass_enabled: ctrl5.hpen(),
+            block_data_update: ctrl4.block_data_update(),
+        })
+    }
+
+    /// Decodes the settings from the contents of `CTRL_REG1` through `CTRL_REG5`.
+    fn from_control_block(block: &ControlBlock) -> Self {
+        let ctrl1 = ControlRegister1::from_bits(block[0]);
+        let ctrl2 = ControlRegister2::from_bits(block[1]);
+        let ctrl4 = ControlRegister4::from_bits(block[3]);
+        let ctrl5 = ControlRegister5::from_bits(block[4]);
+        Self {
+            output_data_rate: ctrl1.output_data_rate(),
+            bandwidth: ctrl1.bandwidth(),
+            full_scale: ctrl4.full_scale(),
+            highpass_mode: ctrl2.hpm(),
+            highpass_cutoff: ctrl2.hpcf(),
+            highpass_enabled: ctrl5.hpen(),
+            block_data_update: ctrl4.block_data_update(),
+        }
+    }
+
+    /// Updates the settings after the given value was written to a register.
+    ///
+    /// Registers other than `CTRL_REG1`, `CTRL_REG2`, `CTRL_REG4` and `CTRL_REG5` do not
+    /// affect the settings.
+    #[cfg(feature = "device")]
+    pub(crate) fn with_register_write(mut self, address: u8, value: u8) -> Self {
+        if address == *ControlRegister1::REGISTER_ADDRESS {
+            let ctrl1 = ControlRegister1::from_bits(value);
+            self.output_data_rate = ctrl1.output_data_rate();
+            self.bandwidth = ctrl1.bandwidth();
+        } else if address == *ControlRegister2::REGISTER_ADDRESS {
+            let ctrl2 = ControlRegister2::from_bits(value);
+            self.highpass_mode = ctrl2.hpm();
+            self.highpass_cutoff = ctrl2.hpcf();
+        } else if address == *ControlRegister4::REGISTER_ADDRESS {
+            let ctrl4 = ControlRegister4::from_bits(value);
+            self.full_scale = ctrl4.full_scale();
+            self.block_data_update = ctrl4.block_data_update();
+        } else if address == *ControlRegister5::REGISTER_ADDRESS {
+            self.highpass_enabled = ControlRegister5::from_bits(value).hpen();
+        }
+        self
+    }
+
+    /// Encodes the settings into the contents of `CTRL_REG1` through `CTRL_REG5`,
+    /// leaving unrelated bits untouched.
+    fn to_control_block(self, mut block: ControlBlock) -> ControlBlock {
+        let merge = |byte: u8, bits: u8, mask: u8| (byte & !mask) | (bits & mask);
+        block[0] = merge(block[0], self.ctrl1().to_bits(), CTRL1_MASK);
+        block[1] = merge(block[1], self.ctrl2().to_bits(), CTRL2_MASK);
+        block[3] = merge(block[3], self.ctrl4().to_bits(), CTRL4_MASK);
+        block[4] = merge(block[4], self.ctrl5().to_bits(), CTRL5_MASK);
+        block
+    }
+
+    fn ctrl1(&self) -> ControlRegister1 {
+        ControlRegister1::default()
+            .with_output_data_rate(self.output_data_rate)
+            .with_bandwidth(self.bandwidth)
+    }
+
+    fn ctrl2(&self) -> ControlRegister2 {
+        ControlRegister2::default()
+            .with_hpm(self.highpass_mode)
+            .with_hpcf(self.highpass_cutoff)
+    }
+
+    fn ctrl4(&self) -> ControlRegister4 {
+        ControlRegister4::default()
+            .with_block_data_update(self.block_data_update)
+            .with_full_scale(self.full_scale)
+    }
+
+    fn ctrl5(&self) -> ControlRegister5 {
+        ControlRegister5::default().with_hpen(self.highpass_enabled)
+    }
+}
+
+impl Default for L3GD20Settings {
+    /// The settings applied by [`L3GD20::reset`].
+    fn default() -> Self {
+        Self {
+            output_data_rate: OutputDataRate::Hz95,
+            bandwidth: Bandwidth::Narrowest,
+            full_scale: Sensitivity::D250,
+            highpass_mode: HighpassFilterMode::NormalModeResetFilter,
+            highpass_cutoff: 0,
+            highpass_enabled: false,
+            block_data_update: false,
+        }
+    }
+}
+
+/// Calculates the checksum over the given bytes.
+pub(crate) fn checksum(bytes: &[u8]) -> u8 {
+    !bytes.iter().fold(0u8, |sum, byte| sum.wrapping_add(*byte))
+}
+
+impl<T, E> L3GD20<T>
+where
+    T: Transport<Error = E>,
+{
+    /// Reads the current settings off the sensor.
+    pub fn settings(&mut self) -> Result<L3GD20Settings, E> {
+        let block = self.read_control_block()?;
+        Ok(L3GD20Settings::from_control_block(&block))
+    }
+
+    /// Reads the control registers and adopts their configuration as the driver state.
+    ///
+    /// This keeps the driver consistent with a configuration it did not write itself, such
+    /// as after [`with_transport_unchecked`](Self::with_transport_unchecked).
+    pub fn sync_config(&mut self) -> Result<L3GD20Settings, E> {
+        let block = self.read_control_block()?;
+        let settings = L3GD20Settings::from_control_block(&block);
+        self.cache_ctrl4(block[3]);
+        self.powered_down = !ControlRegister1::from_bits(block[0]).power_up();
+        self.expected_settings = settings;
+        self.pending_config = None;
+        Ok(settings)
+    }
+
+    /// Applies the settings to the sensor, leaving unrelated configuration untouched.
+    ///
+    /// If a write fails, the remaining writes can be completed with
+    /// [`repair_config`](Self::repair_config).
+    pub fn apply_settings(&mut self, settings: &L3GD20Settings) -> Result<(), E> {
+        self.pending_config = None;
+        let block = settings.to_control_block(self.read_control_block()?);
+        let address = |index: u8| *ControlRegister1::REGISTER_ADDRESS + index;
+        self.write_config(
+            &[
+                (address(0), block[0]),
+                (address(1), block[1]),
+                (address(3), block[3]),
+                (address(4), block[4]),
+            ],
+            *settings,
+            None,
+        )
+    }
+
+    /// Applies the settings like [`apply_settings`](Self::apply_settings), reporting how far
+    /// the sequence got if a write fails.
+    pub fn apply_settings_checked(&mut self, settings: &L3GD20Settings) -> Result<(), Error<E>> {
+        self.apply_settings(settings)
+            .map_err(|error| self.partial_config_error(error))
+    }
+
+    /// Stages multiple setting changes and applies them as a group.
+    ///
+    /// The closure modifies a copy of the current settings. The result is validated, and
+    /// only the range of control registers that actually changed is written, in a single
+    /// burst transaction. If that write fails, the previous configuration is written back;
+    /// the error reports whether this succeeded. Returns the settings now in effect.
+    ///
+    /// ```
+    /// # #[cfg(feature = "sim")] {
+    /// # use l3gd20_ng::{sim::Simulator, L3GD20, OutputDataRate, Sensitivity};
+    /// # let mut driver = L3GD20::with_transport(Simulator::<()>::new()).unwrap();
+    /// let settings = driver.configure(|cfg| {
+    ///     cfg.output_data_rate = OutputDataRate::Hz380.into();
+    ///     cfg.full_scale = Sensitivity::D500.into();
+    /// })?;
+    /// assert!(!settings.block_data_update);
+    /// # }
+    /// # Ok::<(), l3gd20_ng::settings::ConfigureError<()>>(())
+    /// ```
+    pub fn configure<F>(&mut self, f: F) -> Result<L3GD20Settings, ConfigureError<E>>
+    where
+        F: FnOnce(&mut L3GD20Settings),
+    {
+        let previous = self
+            .read_control_block()
+            .map_err(|error| ConfigureError::Bus {
+                error,
+                state: DeviceState::Unchanged,
+            })?;
+
+        let mut settings = L3GD20Settings::from_control_block(&previous);
+        f(&mut settings);
+        if settings.highpass_cutoff > MAX_HIGHPASS_CUTOFF {
+            return Err(ConfigureError::InvalidHighpassCutoff(
+                settings.highpass_cutoff,
+            ));
+        }
+
+        let staged = settings.to_control_block(previous);
+        let changed = || (0..staged.len()).filter(|&i| staged[i] != previous[i]);
+        let (Some(first), Some(last)) = (changed().next(), changed().next_back()) else {
+            self.expected_settings = settings;
+            return Ok(settings);
+        };
+
+        match self.write_control_range(&staged, first, last) {
+            Ok(()) => {
+                self.expected_settings = settings;
+                Ok(settings)
+            }
+            Err(error) => {
+                let state = match self.write_control_range(&previous, first, last) {
+                    Ok(()) => DeviceState::Restored,
+                    Err(_) => DeviceState::Unknown,
+                };
+                Err(ConfigureError::Bus { error, state })
+            }
+        }
+    }
+
+    /// Reads `CTRL_REG1` through `CTRL_REG5` in a single burst transaction.
+    pub(crate) fn read_control_block(&mut self) -> Result<ControlBlock, E> {
+        let mut buffer = [0; 6];
+        buffer[0] = framing::read_multi_cmd(*ControlRegister1::REGISTER_ADDRESS);
+        self.transfer(&mut buffer)?;
+
+        let mut block = ControlBlock::default();
+        block.copy_from_slice(&buffer[1..]);
+        Ok(block)
+    }
+
+    /// Writes the registers `first` through `last` (indices into the control block)
+    /// in a single burst transaction.
+    pub(crate) fn write_control_range(
+        &mut self,
+        block: &ControlBlock,
+        first: usize,
+        last: usize,
+    ) -> Result<(), E> {
+        let len = last - first + 1;
+        let mut buffer = [0; 6];
+        // The control registers occupy consecutive addresses, starting with CTRL_REG1.
+        buffer[0] = framing::write_multi_cmd(*ControlRegister1::REGISTER_ADDRESS + first as u8);
+        buffer[1..=len].copy_from_slice(&block[first..=last]);
+
+        // Clear the cached block data update state first, so that a failed write
+        // leaves the driver assuming BDU is disabled.
+        self.block_data_update = false;
+        self.transfer(&mut buffer[..=len])?;
+        self.note_ctrl4_write(block[3]);
+        self.powered_down = !ControlRegister1::from_bits(block[0]).power_up();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::MockError;
+    use crate::sim::Simulator;
+
+    fn tuned() -> L3GD20Settings {
+        L3GD20Settings {
+            output_data_rate: OutputDataRate::Hz380,
+            bandwidth: Bandwidth::Medium,
+            full_scale: Sensitivity::D2000,
+            highpass_mode: HighpassFilterMode::NormalMode,
+            highpass_cutoff: 0b0101,
+            highpass_enabled: true,
+            block_data_update: true,
+        }
+    }
+
+    #[test]
+    fn bytes_round_trip() {
+        let settings = tuned();
+        let bytes = settings.to_bytes();
+        assert_eq!(bytes[0], 1);
+        assert_eq!(L3GD20Settings::from_bytes(&bytes), Ok(settings));
+    }
+
+    #[test]
+    fn version_1_layout_is_stable() {
+        let bytes = [1, 0b1010_0000, 0b0010_0101, 0b1010_0000, 0b0001_0000, 0x89];
+        assert_eq!(L3GD20Settings::from_bytes(&bytes), Ok(tuned()));
+    }
+
+    #[test]
+    fn rejects_invalid_input() {
+        let mut bytes = tuned().to_bytes();
+        assert_eq!(
+            L3GD20Settings::from_bytes(&bytes[..3]),
+            Err(SettingsError::Truncated)
+        );
+        bytes[2] ^= 1;
+        assert_eq!(
+            L3GD20Settings::from_bytes(&bytes),
+            Err(SettingsError::Checksum)
+        );
+        bytes[0] = 0xFF;
+        assert_eq!(
+            L3GD20Settings::from_bytes(&bytes),
+            Err(SettingsError::UnsupportedVersion(0xFF))
+        );
+    }
+
+    #[test]
+    fn settings_are_applied_and_read_back() {
+        let mut driver = L3GD20::with_transport(Simulator::<MockError>::new()).unwrap();
+        assert_eq!(driver.settings().unwrap(), L3GD20Settings::default());
+
+        driver.apply_settings(&tuned()).unwrap();
+        assert_eq!(driver.settings().unwrap(), tuned());
+    }
+
+    #[test]
+    fn configure_writes_only_the_changed_range() {
+        let mut driver = L3GD20::with_transport(Simulator::<MockError>::new()).unwrap();
+        let ctrl3 = driver.transport.registers()[0x22];
+        let transfers = driver.transport.transfers();
+
+        let settings = driver
+            .configure(|cfg| {
+                cfg.full_scale = Sensitivity::D500;
+                cfg.block_data_update = true;
+            })
+            .unwrap();
+        assert_eq!(driver.transport.transfers(), transfers + 2);
+        assert_eq!(driver.settings().unwrap(), settings);
+        assert_eq!(driver.transport.registers()[0x22], ctrl3);
+
+        let transfers = driver.transport.transfers();
+        driver.configure(|_| {}).unwrap();
+        assert_eq!(driver.transport.transfers(), transfers + 1);
+
+        assert_eq!(
+            driver.configure(|cfg| cfg.highpass_cutoff = 0b1010),
+            Err(ConfigureError::InvalidHighpassCutoff(0b1010))
+        );
+    }
+
+    #[test]
+    fn configure_rolls_back_on_bus_errors() {
+        let mut driver = L3GD20::with_transport(Simulator::<MockError>::new()).unwrap();
+        let transfers = driver.transport.transfers();
+        driver.transport.fail_at(transfers + 1, MockError);
+        assert_eq!(
+            driver.configure(|cfg| cfg.output_data_rate = OutputDataRate::Hz760),
+            Err(ConfigureError::Bus {
+                error: MockError,
+                state: DeviceState::Restored
+            })
+        );
+        assert_eq!(driver.settings().unwrap(), L3GD20Settings::default());
+
+        let transfers = driver.transport.transfers();
+        driver.transport.fail_at(transfers, MockError);
+        assert_eq!(
+            driver.configure(|cfg| cfg.output_data_rate = OutputDataRate::Hz760),
+            Err(ConfigureError::Bus {
+                error: MockError,
+                state: DeviceState::Unchanged
+            })
+        );
+    }
+}
@@ -0,0 +1,96 @@
+//! Provides the settling waits of internal configuration sequences.
+//!
+//! Without a delay, the driver relies on the latency of the following bus transactions for
+//! the device to finish rebooting or powering up, which depends on the bus speed. The
+//! `_with_delay` variants, such as [`reset_with_delay`](L3GD20::reset_with_delay), take a
+//! delay to make these waits explicit.
+
+use crate::{L3GD20Settings, Transport, L3GD20};
+use embedded_hal::blocking::delay::DelayUs;
+
+/// The time waited after setting `BOOT` in `CTRL_REG5`, in microseconds.
+///
+/// The datasheet does not specify the duration of the reboot; this is a conservative value.
+pub const BOOT_TIME_US: u32 = 5_000;
+
+/// The time waited after leaving power-down, in microseconds.
+///
+/// The device takes a few samples to settle after power-up; this covers them at the lowest
+/// output data rate.
+pub const TURN_ON_TIME_US: u32 = 50_000;
+
+impl<T, E> L3GD20<T>
+where
+    T: Transport<Error = E>,
+{
+    /// Resets the device like [`reset`](Self::reset), waiting [`BOOT_TIME_US`] with the
+    /// delay after rebooting the memory content.
+    pub fn reset_with_delay<D>(&mut self, delay: &mut D) -> Result<(), E>
+    where
+        D: DelayUs<u32>,
+    {
+        self.reset_to(L3GD20Settings::default(), Some(delay))
+    }
+
+    /// Powers the device up like [`power_up`](Self::power_up), waiting [`TURN_ON_TIME_US`]
+    /// with the delay if it was powered down.
+    pub fn power_up_with_delay<D>(&mut self, delay: &mut D) -> Result<(), E>
+    where
+        D: DelayUs<u32>,
+    {
+        self.power_up_to(Some(delay))
+    }
+
+    /// Enters sleep mode like [`sleep_mode`](Self::sleep_mode), waiting
+    /// [`TURN_ON_TIME_US`] with the delay if the device was powered down.
+    pub fn sleep_mode_with_delay<D>(&mut self, delay: &mut D) -> Result<(), E>
+    where
+        D: DelayUs<u32>,
+    {
+        self.sleep_mode_to(Some(delay))
+    }
+
+    /// Waits for the device to settle, if a delay is given.
+    pub(crate) fn settle(&mut self, delay: Option<&mut (dyn DelayUs<u32> + '_)>, us: u32) {
+        self.feed_watchdog();
+        if let Some(delay) = delay {
+            delay.delay_us(us);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::MockError;
+    use crate::sim::Simulator;
+
+    #[derive(Default)]
+    struct RecordingDelay {
+        waited: u32,
+    }
+
+    impl DelayUs<u32> for RecordingDelay {
+        fn delay_us(&mut self, us: u32) {
+            self.waited += us;
+        }
+    }
+
+    #[test]
+    fn sequences_wait_for_the_device_to_settle() {
+        let mut driver = L3GD20::with_transport(Simulator::<MockError>::new()).unwrap();
+        let mut delay = RecordingDelay::default();
+
+        driver.reset_with_delay(&mut delay).unwrap();
+        assert_eq!(core::mem::take(&mut delay.waited), BOOT_TIME_US);
+
+        driver.power_up_with_delay(&mut delay).unwrap();
+        assert_eq!(core::mem::take(&mut delay.waited), 0);
+        driver.power_down().unwrap();
+        driver.power_up_with_delay(&mut delay).unwrap();
+        assert_eq!(core::mem::take(&mut delay.waited), TURN_ON_TIME_US);
+        driver.power_down().unwrap();
+        driver.sleep_mode_with_delay(&mut delay).unwrap();
+        assert_eq!(core::mem::take(&mut delay.waited), TURN_ON_TIME_US);
+    }
+}
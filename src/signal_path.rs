@@ -0,0 +1,175 @@
+//! Provides the selection of the output and interrupt signal paths.
+//!
+//! The angular rate passes through LPF1, the optional high-pass filter and LPF2. The
+//! `OUT_SEL` and `INT1_SEL` bits of `CTRL_REG5` select the stage the output registers and
+//! the interrupt generator are fed from. The high-pass filter only takes effect while it
+//! is enabled (`HPen`); [`L3GD20::set_signal_path`] refuses to select the high-pass filter
+//! output otherwise.
+//!
+//! [`L3GD20::signal_latency_us`] estimates the group delay of the selected output path,
+//! e.g. to budget the latency of a control loop.
+
+use crate::{BandwidthExt, Error, OutputDataRateExt, Transport, L3GD20};
+use l3gd20_registers::{Bandwidth, ControlRegister1, ControlRegister5, OutputDataRate};
+
+/// A stage of the signal path.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum SignalStage {
+    /// The output of LPF1, bypassing the high-pass filter and LPF2.
+    #[default]
+    Lpf1,
+    /// The output of the high-pass filter.
+    Hpf,
+    /// The output of LPF2, which includes the high-pass filter if it is enabled.
+    Lpf2,
+}
+
+impl SignalStage {
+    /// Gets the two-bit `OUT_SEL`/`INT1_SEL` encoding.
+    pub const fn bits(&self) -> u8 {
+        match self {
+            SignalStage::Lpf1 => 0b00,
+            SignalStage::Hpf => 0b01,
+            SignalStage::Lpf2 => 0b10,
+        }
+    }
+
+    /// Decodes the two-bit `OUT_SEL`/`INT1_SEL` encoding.
+    pub const fn from_bits(bits: u8) -> Self {
+        match bits & 0b11 {
+            0b00 => SignalStage::Lpf1,
+            0b01 => SignalStage::Hpf,
+            _ => SignalStage::Lpf2,
+        }
+    }
+}
+
+/// Estimates the group delay of the output path in microseconds.
+///
+/// Sampling and LPF1 are accounted for as one output data period. LPF2 is modeled as a
+/// first-order low-pass filter with the cutoff selected by the bandwidth, whose group
+/// delay at low frequencies is `1 / (2π f_c)`. The high-pass filter adds no delay to
+/// signals well above its cutoff. The estimate excludes the time until the sample is read.
+pub fn group_delay_us(odr: OutputDataRate, bandwidth: Bandwidth, path: DataPath) -> f32 {
+    let mut delay_us = 1e6 / f32::from(odr.hz());
+    if path == SignalStage::Lpf2 {
+        delay_us += 1e6 / (2.0 * core::f32::consts::PI * bandwidth.cutoff_hz(odr));
+    }
+    delay_us
+}
+
+/// The stage the output registers are fed from (`OUT_SEL`).
+pub type DataPath = SignalStage;
+
+/// The stage the interrupt generator is fed from (`INT1_SEL`).
+pub type InterruptPath = SignalStage;
+
+impl<T, E> L3GD20<T>
+where
+    T: Transport<Error = E>,
+{
+    /// Selects the stage the output registers are fed from.
+    pub fn set_data_path(&mut self, path: DataPath) -> Result<(), E> {
+        self.modify_register(|reg: ControlRegister5| reg.with_out_sel(path.bits()))
+    }
+
+    /// Selects the stage the interrupt generator is fed from.
+    pub fn set_interrupt_path(&mut self, path: InterruptPath) -> Result<(), E> {
+        self.modify_register(|reg: ControlRegister5| reg.with_int1_sel(path.bits()))
+    }
+
+    /// Reads the stages the output registers and the interrupt generator are fed from.
+    pub fn signal_path(&mut self) -> Result<(DataPath, InterruptPath), E> {
+        let ctrl5: ControlRegister5 = self.read_register()?;
+        Ok((
+            SignalStage::from_bits(ctrl5.out_sel()),
+            SignalStage::from_bits(ctrl5.int1_sel()),
+        ))
+    }
+
+    /// Estimates the sensor-side group delay of the output in microseconds.
+    ///
+    /// See [`group_delay_us`] for the model.
+    pub fn signal_latency_us(&mut self) -> Result<f32, E> {
+        let ctrl1: ControlRegister1 = self.read_register()?;
+        let ctrl5: ControlRegister5 = self.read_register()?;
+        Ok(group_delay_us(
+            ctrl1.output_data_rate(),
+            ctrl1.bandwidth(),
+            SignalStage::from_bits(ctrl5.out_sel()),
+        ))
+    }
+
+    /// Selects the stages the output registers and the interrupt generator are fed from.
+    ///
+    /// Fails with [`Error::HighpassDisabled`] without changing the configuration if a
+    /// path selects the high-pass filter output while the filter is disabled.
+    pub fn set_signal_path(
+        &mut self,
+        data: DataPath,
+        interrupt: InterruptPath,
+    ) -> Result<(), Error<E>> {
+        let ctrl5: ControlRegister5 = self.read_register().map_err(Error::Bus)?;
+        if !ctrl5.hpen() && (data == SignalStage::Hpf || interrupt == SignalStage::Hpf) {
+            return Err(Error::HighpassDisabled);
+        }
+        self.write_register(
+            ctrl5
+                .with_out_sel(data.bits())
+                .with_int1_sel(interrupt.bits()),
+        )
+        .map_err(Error::Bus)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::MockError;
+    use crate::sim::Simulator;
+
+    #[test]
+    fn highpass_output_requires_the_filter() {
+        let mut driver = L3GD20::with_transport(Simulator::<MockError>::new()).unwrap();
+        assert_eq!(
+            driver.set_signal_path(SignalStage::Hpf, SignalStage::Lpf1),
+            Err(Error::HighpassDisabled)
+        );
+        assert_eq!(
+            driver.signal_path(),
+            Ok((SignalStage::Lpf1, SignalStage::Lpf1))
+        );
+
+        driver
+            .set_signal_path(SignalStage::Lpf2, SignalStage::Lpf2)
+            .unwrap();
+        assert_eq!(driver.transport.registers()[0x24], 0b0000_1010);
+
+        driver.transport.registers_mut()[0x24] |= 0b0001_0000;
+        driver
+            .set_signal_path(SignalStage::Lpf2, SignalStage::Hpf)
+            .unwrap();
+        assert_eq!(
+            driver.signal_path(),
+            Ok((SignalStage::Lpf2, SignalStage::Hpf))
+        );
+    }
+
+    #[test]
+    fn latency_follows_the_output_path() {
+        let mut driver = L3GD20::with_transport(Simulator::<MockError>::new()).unwrap();
+        driver.transport.registers_mut()[0x20] = 0b0000_1111;
+
+        let period_us = 1e6 / 95.0;
+        assert!((driver.signal_latency_us().unwrap() - period_us).abs() < 0.1);
+
+        driver.set_data_path(SignalStage::Lpf2).unwrap();
+        let lpf2_us = 1e6 / (2.0 * core::f32::consts::PI * 12.5);
+        let latency_us = driver.signal_latency_us().unwrap();
+        assert!((latency_us - (period_us + lpf2_us)).abs() < 0.1);
+
+        driver.transport.registers_mut()[0x20] = 0b1111_1111;
+        assert!(driver.signal_latency_us().unwrap() < latency_us / 5.0);
+    }
+}
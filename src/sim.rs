@@ -0,0 +1,411 @@
+//! Provides a simulated L3GD20 for host-side and hardware-in-the-loop testing.
+
+#[cfg(feature = "self-test")]
+use crate::characteristics;
+#[cfg(feature = "self-test")]
+use crate::self_test::{typical_change_dps, SelfTestMode};
+use crate::{BandwidthExt, OutputDataRateExt, Transport};
+use l3gd20_registers::ControlRegister1;
+#[cfg(feature = "self-test")]
+use l3gd20_registers::ControlRegister4;
+
+/// The power-on value of the `WHO_AM_I` register.
+const WHO_AM_I: u8 = 0b1101_0100;
+
+/// The power-on value of `CTRL_REG1`.
+const CTRL_REG1: u8 = 0b0000_0111;
+
+/// The number of samples the FIFO holds.
+const FIFO_DEPTH: usize = 32;
+
+/// The high-pass filter cutoff frequencies in Hertz, indexed by `HPCF` and the output
+/// data rate (95, 190, 380 and 760 Hz).
+const HIGHPASS_CUTOFF_HZ: [[f32; 4]; 10] = [
+    [7.2, 13.5, 27.0, 51.4],
+    [3.5, 7.2, 13.5, 27.0],
+    [1.8, 3.5, 7.2, 13.5],
+    [0.9, 1.8, 3.5, 7.2],
+    [0.45, 0.9, 1.8, 3.5],
+    [0.18, 0.45, 0.9, 1.8],
+    [0.09, 0.18, 0.45, 0.9],
+    [0.045, 0.09, 0.18, 0.45],
+    [0.018, 0.045, 0.09, 0.18],
+    [0.009, 0.018, 0.045, 0.09],
+];
+
+/// A source of simulated samples, called with the index of the sample.
+pub type SampleSource = fn(u32) -> [i16; 3];
+
+/// A [`Transport`] with a simulated L3GD20 register file behind it.
+///
+/// Register accesses are served from an in-memory register file, honoring the
+/// auto-increment flag of the command byte. The simulator does not model the
+/// behavior of the device; readings are whatever was set via [`set_xyz`](Self::set_xyz)
+/// and friends.
+///
+/// Samples published by [`race`](Self::race) and [`generate`](Self::generate) pass through
+/// a model of the signal path selected by `OUT_SEL` and `HPen` in `CTRL_REG5`: a first-order
+/// high-pass filter with the `HPCF` cutoff of `CTRL_REG2`, followed by a first-order
+/// low-pass filter (LPF2) with the bandwidth of `CTRL_REG1`. Reading `REFERENCE` resets the
+/// high-pass filter in the corresponding filter mode; the reference signal and
+/// autoreset modes filter like the normal mode. LPF1 is not modeled.
+///
+/// Samples queued with [`push_fifo`](Self::push_fifo) are served from a simulated FIFO
+/// while `FIFO_EN` is set in `CTRL_REG5`: each read starting at `OUT_X_L` pops the oldest
+/// sample, and multi-byte reads wrap from `OUT_Z_H` back to `OUT_X_L`.
+///
+/// With the `self-test` feature, the `ST` bits of `CTRL_REG4` add the typical self-test
+/// output change to the samples.
+///
+/// The error type `E` is chosen to match the transport of the physical sensor, so that
+/// both can be used interchangeably through a [`Gyroscope`](crate::gyroscope::Gyroscope)
+/// trait object.
+pub struct Simulator<E> {
+    registers: [u8; 0x40],
+    transfers: usize,
+    failure: Option<(usize, E)>,
+    racing: Option<[i16; 3]>,
+    generator: Option<(usize, SampleSource)>,
+    generated: u32,
+    fifo: heapless::Deque<[i16; 3], FIFO_DEPTH>,
+    fifo_overrun: bool,
+    signal_path: SignalPath,
+}
+
+/// The state of the simulated filters.
+#[derive(Debug, Default)]
+struct SignalPath {
+    /// The low-pass filtered input subtracted by the high-pass filter.
+    highpass: [f32; 3],
+    /// The LPF2 output.
+    lowpass: [f32; 3],
+}
+
+impl SignalPath {
+    /// Filters a sample according to the configuration in the register file.
+    fn filter(&mut self, registers: &[u8; 0x40], sample: [i16; 3]) -> [i16; 3] {
+        let ctrl1 = ControlRegister1::from_bits(registers[0x20]);
+        let odr = ctrl1.output_data_rate();
+        let odr_hz = f32::from(odr.hz());
+        let hpcf = usize::from(registers[0x21] & 0b1111).min(HIGHPASS_CUTOFF_HZ.len() - 1);
+        let highpass_enabled = registers[0x24] & 0b0001_0000 != 0;
+        let out_sel = registers[0x24] & 0b11;
+
+        // The DR bits of CTRL_REG1 index the output data rates in ascending order.
+        let odr_index = usize::from(registers[0x20] >> 6);
+        let highpass_alpha = smoothing(HIGHPASS_CUTOFF_HZ[hpcf][odr_index], odr_hz);
+        let lowpass_alpha = smoothing(ctrl1.bandwidth().cutoff_hz(odr), odr_hz);
+
+        let mut out = [0; 3];
+        for (i, &value) in sample.iter().enumerate() {
+            let mut value = f32::from(value);
+            self.highpass[i] += highpass_alpha * (value - self.highpass[i]);
+            if highpass_enabled && out_sel != 0 {
+                value -= self.highpass[i];
+            }
+            self.lowpass[i] += lowpass_alpha * (value - self.lowpass[i]);
+            if out_sel & 0b10 != 0 {
+                value = self.lowpass[i];
+            }
+            out[i] = libm::roundf(value).clamp(f32::from(i16::MIN), f32::from(i16::MAX)) as i16;
+        }
+        out
+    }
+
+    /// Resets the high-pass filter.
+    fn reset_highpass(&mut self) {
+        self.highpass = [0.0; 3];
+    }
+}
+
+/// Gets the smoothing factor of a first-order filter with the given cutoff frequency.
+fn smoothing(cutoff_hz: f32, sample_rate_hz: f32) -> f32 {
+    1.0 - libm::expf(-2.0 * core::f32::consts::PI * cutoff_hz / sample_rate_hz)
+}
+
+impl<E> Simulator<E> {
+    /// Creates a simulator with power-on register values.
+    pub fn new() -> Self {
+        let mut registers = [0; 0x40];
+        registers[0x0F] = WHO_AM_I;
+        registers[0x20] = CTRL_REG1;
+        Self {
+            registers,
+            transfers: 0,
+            failure: None,
+            racing: None,
+            generator: None,
+            generated: 0,
+            fifo: heapless::Deque::new(),
+            fifo_overrun: false,
+            signal_path: SignalPath::default(),
+        }
+    }
+
+    /// Gets the register file, indexed by register address.
+    pub fn registers(&self) -> &[u8; 0x40] {
+        &self.registers
+    }
+
+    /// Gets the register file, indexed by register address, for modification.
+    pub fn registers_mut(&mut self) -> &mut [u8; 0x40] {
+        &mut self.registers
+    }
+
+    /// Gets the number of transfers executed so far.
+    pub fn transfers(&self) -> usize {
+        self.transfers
+    }
+
+    /// Sets the axis output registers, in the byte order selected by `BLE` in `CTRL_REG4`.
+    pub fn set_xyz(&mut self, x: i16, y: i16, z: i16) {
+        let big_endian = self.registers[0x23] & 0b0100_0000 != 0;
+        for (offset, value) in [(0x28, x), (0x2A, y), (0x2C, z)] {
+            let bytes = if big_endian {
+                value.to_be_bytes()
+            } else {
+                value.to_le_bytes()
+            };
+            self.registers[offset..offset + 2].copy_from_slice(&bytes);
+        }
+    }
+
+    /// Sets the temperature register.
+    pub fn set_temperature(&mut self, temperature: u8) {
+        self.registers[0x26] = temperature;
+    }
+
+    /// Sets the status register.
+    pub fn set_status(&mut self, status: u8) {
+        self.registers[0x27] = status;
+    }
+
+    /// Publishes a new sample in the middle of the next read of the axis output registers,
+    /// right after `OUT_X_L` was read, simulating a sensor racing the host.
+    ///
+    /// If block data update is enabled in `CTRL_REG4`, the sample is only published once
+    /// the transfer completes, as the output registers are locked while being read.
+    pub fn race(&mut self, x: i16, y: i16, z: i16) {
+        self.racing = Some([x, y, z]);
+    }
+
+    /// Publishes a new sample from `source` every `every` transfers, simulating a running sensor.
+    ///
+    /// The source is called with the index of the generated sample. Together with the data
+    /// available flags being cleared when `OUT_Z_H` is read, this toggles the data ready state.
+    pub fn generate(&mut self, every: usize, source: SampleSource) {
+        self.generator = Some((every.max(1), source));
+    }
+
+    /// Queues a sample in the FIFO, discarding the oldest sample and flagging an overrun
+    /// if the FIFO is full. This updates `FIFO_SRC_REG`.
+    pub fn push_fifo(&mut self, x: i16, y: i16, z: i16) {
+        if self.fifo.is_full() {
+            self.fifo.pop_front();
+            self.fifo_overrun = true;
+        }
+        let _ = self.fifo.push_back([x, y, z]);
+        self.update_fifo_source();
+    }
+
+    /// Pops the oldest FIFO sample into the axis output registers, if any.
+    fn pop_fifo(&mut self) {
+        if let Some([x, y, z]) = self.fifo.pop_front() {
+            self.set_xyz(x, y, z);
+            self.fifo_overrun = false;
+            self.update_fifo_source();
+        }
+    }
+
+    /// Updates `FIFO_SRC_REG` from the FIFO contents and the watermark in `FIFO_CTRL_REG`.
+    fn update_fifo_source(&mut self) {
+        let level = self.fifo.len();
+        let watermark = usize::from(self.registers[0x2E] & 0b0001_1111);
+        let mut source = (level % FIFO_DEPTH) as u8;
+        if level >= watermark && watermark > 0 {
+            source |= 0b1000_0000;
+        }
+        if self.fifo_overrun {
+            source |= 0b0100_0000;
+        }
+        if level == 0 {
+            source |= 0b0010_0000;
+        }
+        self.registers[0x2F] = source;
+    }
+
+    /// Publishes a sample, updating the axis output registers and the status register.
+    fn publish(&mut self, [x, y, z]: [i16; 3]) {
+        let overrun = if self.registers[0x27] & 0b0000_1000 != 0 {
+            0b1111_0000
+        } else {
+            0
+        };
+        #[cfg(feature = "self-test")]
+        let [x, y, z] = self.actuate([x, y, z]);
+        let [x, y, z] = self.signal_path.filter(&self.registers, [x, y, z]);
+        self.set_xyz(x, y, z);
+        self.set_status(overrun | 0b0000_1111);
+    }
+
+    /// Adds the typical self-test output change selected by the `ST` bits of `CTRL_REG4`.
+    #[cfg(feature = "self-test")]
+    fn actuate(&self, sample: [i16; 3]) -> [i16; 3] {
+        let ctrl4 = self.registers[0x23];
+        let sign = match SelfTestMode::from_ctrl4(ctrl4) {
+            SelfTestMode::Normal => return sample,
+            SelfTestMode::Positive => 1.0,
+            SelfTestMode::Negative => -1.0,
+        };
+        let fs = ControlRegister4::from_bits(ctrl4).full_scale();
+        let change = sign * typical_change_dps(fs) / characteristics::sensitivity(fs);
+        sample.map(|value| (f32::from(value) + change) as i16)
+    }
+
+    /// Makes the transfer with the given (zero-based) index fail with the given error.
+    pub fn fail_at(&mut self, transfer: usize, error: E) {
+        self.failure = Some((transfer, error));
+    }
+}
+
+impl<E> Default for Simulator<E> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<E> Transport for Simulator<E>
+where
+    E: Clone,
+{
+    type Error = E;
+
+    fn transfer(&mut self, buffer: &mut [u8]) -> Result<(), E> {
+        let index = self.transfers;
+        self.transfers += 1;
+        if let Some((_, error)) = self.failure.as_ref().filter(|(at, _)| *at == index) {
+            return Err(error.clone());
+        }
+        if let Some((_, source)) = self.generator.filter(|(every, _)| index % every == 0) {
+            self.publish(source(self.generated));
+            self.generated = self.generated.wrapping_add(1);
+        }
+
+        let Some((&mut command, words)) = buffer.split_first_mut() else {
+            return Ok(());
+        };
+        let read = command & 0b1000_0000 != 0;
+        let multi = command & 0b0100_0000 != 0;
+        let block_data_update = self.registers[0x23] & 0b1000_0000 != 0;
+        let fifo_enabled = self.registers[0x24] & 0b0100_0000 != 0;
+        let mut deferred = None;
+        let mut address = usize::from(command & 0b0011_1111);
+        for word in words.iter_mut() {
+            if read {
+                if fifo_enabled && address == 0x28 {
+                    self.pop_fifo();
+                }
+                *word = self.registers[address];
+                if address == 0x25 && self.registers[0x21] & 0b0011_0000 == 0 {
+                    // Reading the reference resets the high-pass filter in this mode.
+                    self.signal_path.reset_highpass();
+                }
+                if address == 0x2D {
+                    // Reading the last output register clears the status flags.
+                    self.registers[0x27] = 0;
+                }
+                if address == 0x28 {
+                    if let Some(sample) = self.racing.take() {
+                        if block_data_update {
+                            deferred = Some(sample);
+                        } else {
+                            self.publish(sample);
+                        }
+                    }
+                }
+            } else {
+                self.registers[address] = *word;
+            }
+            if multi {
+                address = if fifo_enabled && address == 0x2D {
+                    0x28
+                } else {
+                    (address + 1) & 0x3F
+                };
+            }
+        }
+        if let Some(sample) = deferred {
+            self.publish(sample);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::MockError;
+    use crate::{I16x3, L3GD20};
+
+    #[test]
+    fn racing_sensor_tears_unblocked_reads() {
+        let mut sim = Simulator::<MockError>::new();
+        sim.set_xyz(1, 1, 1);
+        sim.race(2, 2, 2);
+        let mut driver = L3GD20::with_transport(sim).unwrap();
+
+        // The stale status was read before the new sample was published mid-burst.
+        let data = driver.data_raw().unwrap();
+        assert!(!data.x.fresh());
+        assert_eq!(I16x3::from(data), I16x3::new(1, 2, 2));
+    }
+
+    #[test]
+    fn coherent_reads_match_flags_to_values() {
+        let mut sim = Simulator::<MockError>::new();
+        sim.set_xyz(1, 1, 1);
+        sim.race(2, 2, 2);
+        let mut driver = L3GD20::with_transport(sim).unwrap();
+
+        let data = driver.read_coherent().unwrap();
+        assert!(!data.x.fresh() && !data.y.fresh() && !data.z.fresh());
+        assert_eq!(I16x3::from(data), I16x3::new(1, 1, 1));
+        assert!(driver.expected_settings.block_data_update);
+        assert_eq!(driver.settings(), Ok(driver.expected_settings));
+
+        let data = driver.read_coherent().unwrap();
+        assert!(data.x.fresh() && data.y.fresh() && data.z.fresh());
+        assert_eq!(I16x3::from(data), I16x3::new(2, 2, 2));
+    }
+
+    /// Reads the X axis of `n` samples of a constant input through the given signal path.
+    fn step_response(ctrl2: u8, ctrl5: u8, n: usize) -> std::vec::Vec<i16> {
+        let mut sim = Simulator::<MockError>::new();
+        sim.generate(1, |_| [1000, 0, 0]);
+        let mut driver = L3GD20::with_transport(sim).unwrap();
+        driver.transport.registers_mut()[0x21] = ctrl2;
+        driver.transport.registers_mut()[0x24] = ctrl5;
+        (0..n).map(|_| driver.xyz_raw().unwrap().x).collect()
+    }
+
+    #[test]
+    fn default_signal_path_is_unfiltered() {
+        assert!(step_response(0, 0, 10).iter().all(|&x| x == 1000));
+    }
+
+    #[test]
+    fn highpass_filter_removes_constant_rates() {
+        let response = step_response(0b0000_0000, 0b0001_0001, 100);
+        assert!(response.windows(2).all(|w| w[1] <= w[0]));
+        assert!(response[99].abs() < 10);
+        assert_eq!(response, step_response(0b0000_0000, 0b0001_0001, 100));
+    }
+
+    #[test]
+    fn lowpass_filter_smooths_steps() {
+        let response = step_response(0, 0b0000_0010, 20);
+        assert!(response[0] < 1000);
+        assert!(response.windows(2).all(|w| w[1] >= w[0]));
+        assert_eq!(response[19], 1000);
+    }
+}
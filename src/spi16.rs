@@ -0,0 +1,144 @@
+//! Provides a transport for SPI peripherals operating with 16-bit frames.
+//!
+//! Some MCUs, e.g. the STM32 family, transfer data more efficiently in 16-bit mode.
+//! [`SpiTransport16`] packs the byte stream expected by the L3GD20 into 16-bit words,
+//! most significant byte first, so that the device sees the same bit stream as with
+//! 8-bit frames.
+
+use crate::common::framing;
+use crate::Transport;
+use chip_select::ChipSelectGuarded;
+use embedded_hal::blocking::spi::Transfer;
+
+/// The number of words transferred per call to the SPI peripheral.
+const CHUNK_WORDS: usize = 16;
+
+/// A [`Transport`] over an SPI bus in 16-bit mode with a dedicated chip select line.
+///
+/// Transfers of an odd number of bytes cannot be expressed in 16-bit frames. Reads are
+/// padded with an additional byte that is discarded. Writes are split into two
+/// transactions instead, since padding would overwrite the next register.
+pub struct SpiTransport16<CS, SPI> {
+    cs: CS,
+    spi: SPI,
+}
+
+impl<CS, SPI> SpiTransport16<CS, SPI> {
+    /// Creates a new 16-bit SPI transport.
+    pub fn new(spi: SPI, chip_select: CS) -> Self {
+        Self {
+            cs: chip_select,
+            spi,
+        }
+    }
+
+    /// Releases the SPI bus and chip select line.
+    pub fn release(self) -> (SPI, CS) {
+        (self.spi, self.cs)
+    }
+
+    /// Replaces the SPI bus, returning the previous one.
+    pub fn replace_bus(&mut self, spi: SPI) -> SPI {
+        core::mem::replace(&mut self.spi, spi)
+    }
+}
+
+impl<CS, SPI, E> SpiTransport16<CS, SPI>
+where
+    CS: ChipSelectGuarded,
+    SPI: Transfer<u16, Error = E>,
+{
+    /// Transfers the bytes in one transaction, padding them to an even length.
+    fn transaction(&mut self, buffer: &mut [u8]) -> Result<(), E> {
+        let _guard = self.cs.select_guard();
+        for chunk in buffer.chunks_mut(CHUNK_WORDS * 2) {
+            let mut words = [0u16; CHUNK_WORDS];
+            let words = &mut words[..(chunk.len() + 1) / 2];
+            for (word, bytes) in words.iter_mut().zip(chunk.chunks(2)) {
+                *word = u16::from_be_bytes([bytes[0], bytes.get(1).copied().unwrap_or(0)]);
+            }
+            self.spi.transfer(words)?;
+            for (word, bytes) in words.iter().zip(chunk.chunks_mut(2)) {
+                let [high, low] = word.to_be_bytes();
+                bytes[0] = high;
+                if let Some(byte) = bytes.get_mut(1) {
+                    *byte = low;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<CS, SPI, E> Transport for SpiTransport16<CS, SPI>
+where
+    CS: ChipSelectGuarded,
+    SPI: Transfer<u16, Error = E>,
+{
+    type Error = E;
+
+    fn transfer(&mut self, buffer: &mut [u8]) -> Result<(), E> {
+        let Some(&command) = buffer.first() else {
+            return Ok(());
+        };
+        if buffer.len() % 2 == 0 || framing::is_read(command) {
+            return self.transaction(buffer);
+        }
+
+        // Write all but the last data byte, then the last one with its own command.
+        let (head, last) = buffer.split_at_mut(buffer.len() - 1);
+        self.transaction(head)?;
+        let address = if framing::is_multi(command) {
+            framing::address(command).wrapping_add(head.len() as u8 - 1)
+        } else {
+            framing::address(command)
+        };
+        let mut tail = [framing::with_address(command, address), last[0]];
+        self.transaction(&mut tail)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{MockCs, MockError, MockSpi};
+    use crate::{I16x3, L3GD20};
+    use std::vec::Vec;
+
+    /// A 16-bit SPI bus on top of the byte-oriented mock.
+    struct MockSpi16(MockSpi);
+
+    impl Transfer<u16> for MockSpi16 {
+        type Error = MockError;
+
+        fn transfer<'w>(&mut self, words: &'w mut [u16]) -> Result<&'w [u16], MockError> {
+            let mut bytes: Vec<u8> = words.iter().flat_map(|word| word.to_be_bytes()).collect();
+            self.0.transfer(&mut bytes)?;
+            for (word, bytes) in words.iter_mut().zip(bytes.chunks(2)) {
+                *word = u16::from_be_bytes([bytes[0], bytes[1]]);
+            }
+            Ok(words)
+        }
+    }
+
+    #[test]
+    fn odd_reads_are_padded() {
+        let mut spi = MockSpi::new();
+        spi.set_xyz(1, -2, 300);
+        let transport = SpiTransport16::new(MockSpi16(spi), MockCs);
+        let mut driver = L3GD20::with_transport(transport).unwrap();
+        assert!(driver.identify().unwrap());
+        assert_eq!(driver.xyz_raw().unwrap(), I16x3::new(1, -2, 300));
+    }
+
+    #[test]
+    fn odd_writes_are_split() {
+        let mut transport = SpiTransport16::new(MockSpi16(MockSpi::new()), MockCs);
+        let mut buffer = [framing::write_multi_cmd(0x32), 1, 2, 3, 4];
+        transport.transfer(&mut buffer).unwrap();
+
+        let (spi, _) = transport.release();
+        assert_eq!(spi.0.transfers, 2);
+        assert_eq!(spi.0.registers[0x32..0x37], [1, 2, 3, 4, 0]);
+    }
+}
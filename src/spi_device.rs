@@ -0,0 +1,93 @@
+//! Provides a transport over the embedded-hal 1.0 [`SpiDevice`] abstraction.
+//!
+//! The [`SpiDevice`] contract already manages the chip select line and bus sharing,
+//! so no [`ChipSelectGuarded`](chip_select::ChipSelectGuarded) implementation is required.
+
+use crate::{Error, Transport, L3GD20};
+use embedded_hal_1::spi::SpiDevice;
+
+/// A [`Transport`] over an embedded-hal 1.0 [`SpiDevice`].
+pub struct SpiDeviceTransport<SPI> {
+    spi: SPI,
+}
+
+impl<SPI> SpiDeviceTransport<SPI> {
+    /// Creates a new SPI device transport.
+    pub fn new(spi: SPI) -> Self {
+        Self { spi }
+    }
+
+    /// Releases the SPI device.
+    pub fn release(self) -> SPI {
+        self.spi
+    }
+}
+
+impl<SPI> Transport for SpiDeviceTransport<SPI>
+where
+    SPI: SpiDevice<u8>,
+{
+    type Error = SPI::Error;
+
+    fn transfer(&mut self, buffer: &mut [u8]) -> Result<(), Self::Error> {
+        self.spi.transfer_in_place(buffer)
+    }
+}
+
+/// SPI driver for the STMicroelectronics L3GD20 gyroscope on an embedded-hal 1.0 [`SpiDevice`].
+pub type L3GD20SpiDevice<SPI> = L3GD20<SpiDeviceTransport<SPI>>;
+
+impl<SPI> L3GD20<SpiDeviceTransport<SPI>>
+where
+    SPI: SpiDevice<u8>,
+{
+    /// Initialize the driver over the SPI device.
+    ///
+    /// See [`with_transport`](Self::with_transport).
+    pub fn new_device(spi: SPI) -> Result<Self, Error<SPI::Error>> {
+        Self::with_transport(SpiDeviceTransport::new(spi))
+    }
+
+    /// Releases the SPI device.
+    pub fn release(self) -> SPI {
+        self.transport.release()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::MockError;
+    use crate::sim::Simulator;
+    use embedded_hal_1::spi::{ErrorType, Operation};
+
+    /// An SPI device backed by the simulator, framing each transaction as one transfer.
+    struct SimDevice(Simulator<MockError>);
+
+    impl ErrorType for SimDevice {
+        type Error = MockError;
+    }
+
+    impl SpiDevice<u8> for SimDevice {
+        fn transaction(&mut self, operations: &mut [Operation<'_, u8>]) -> Result<(), MockError> {
+            for operation in operations {
+                if let Operation::TransferInPlace(buffer) = operation {
+                    self.0.transfer(buffer)?;
+                }
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn driver_runs_on_an_spi_device() {
+        let mut sim = Simulator::new();
+        sim.registers_mut()[0x0F] = 0xD4;
+        sim.set_xyz(1, -2, 3);
+        let mut driver = L3GD20SpiDevice::new_device(SimDevice(sim)).unwrap();
+
+        assert!(driver.identify().unwrap());
+        let xyz = driver.xyz_raw().unwrap();
+        assert_eq!((xyz.x, xyz.y, xyz.z), (1, -2, 3));
+    }
+}
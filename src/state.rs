@@ -0,0 +1,149 @@
+//! Provides a decoded view of the power, boot and FIFO state of the device.
+//!
+//! The power modes are selected by the `PD` and `Xen`/`Yen`/`Zen` bits of `CTRL_REG1`
+//! as listed in the "Power mode selection configuration" table of the datasheet (section 7.2).
+//! The datasheet does not state that any configuration is lost in either low-power mode, so
+//! [`L3GD20::set_power_state`] re-applies nothing; the outputs only need time to settle
+//! after leaving power-down, see [`L3GD20::power_up_with_delay`].
+
+use crate::{Transport, L3GD20};
+use l3gd20_registers::prelude::ToBits;
+use l3gd20_registers::{ControlRegister1, ControlRegister5, FifoControlRegister, FifoMode};
+
+/// The power mode of the device, as determined by `CTRL_REG1`.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum PowerMode {
+    /// The device is powered down.
+    PowerDown,
+    /// The device is powered up with all axes disabled.
+    Sleep,
+    /// The device is powered up with at least one axis enabled.
+    Normal,
+}
+
+/// The operating state of the device, as returned by [`L3GD20::operating_state`].
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct OperatingState {
+    /// The power mode.
+    pub power_mode: PowerMode,
+    /// Whether the X axis is enabled.
+    pub x_enabled: bool,
+    /// Whether the Y axis is enabled.
+    pub y_enabled: bool,
+    /// Whether the Z axis is enabled.
+    pub z_enabled: bool,
+    /// Whether the device is rebooting its memory content.
+    pub booting: bool,
+    /// Whether the FIFO is enabled.
+    pub fifo_enabled: bool,
+    /// The FIFO mode, or `None` if `FIFO_CTRL_REG` holds a reserved mode.
+    pub fifo_mode: Option<FifoMode>,
+}
+
+impl OperatingState {
+    /// Decodes the state from the contents of `CTRL_REG1`, `CTRL_REG5` and `FIFO_CTRL_REG`.
+    fn decode(ctrl1: u8, ctrl5: u8, fifo_ctrl: u8) -> Self {
+        let ctrl1 = ControlRegister1::from_bits(ctrl1);
+        let ctrl5 = ControlRegister5::from_bits(ctrl5);
+        let any_axis = ctrl1.x_enable() || ctrl1.y_enable() || ctrl1.z_enable();
+        let power_mode = match (ctrl1.power_up(), any_axis) {
+            (false, _) => PowerMode::PowerDown,
+            (true, false) => PowerMode::Sleep,
+            (true, true) => PowerMode::Normal,
+        };
+
+        // Decoded by hand, since the register type panics on the reserved modes.
+        let fifo_mode = match fifo_ctrl >> 5 {
+            0b000 => Some(FifoMode::Bypass),
+            0b001 => Some(FifoMode::FIFO),
+            0b010 => Some(FifoMode::Stream),
+            0b011 => Some(FifoMode::StreamToFifo),
+            0b100 => Some(FifoMode::BypassToStream),
+            _ => None,
+        };
+
+        Self {
+            power_mode,
+            x_enabled: ctrl1.x_enable(),
+            y_enabled: ctrl1.y_enable(),
+            z_enabled: ctrl1.z_enable(),
+            booting: ctrl5.boot(),
+            fifo_enabled: ctrl5.fifo_enable(),
+            fifo_mode,
+        }
+    }
+}
+
+impl<T, E> L3GD20<T>
+where
+    T: Transport<Error = E>,
+{
+    /// Reads the power, axis enable, boot and FIFO configuration off the device.
+    pub fn operating_state(&mut self) -> Result<OperatingState, E> {
+        let block = self.read_control_block()?;
+        let fifo_ctrl: FifoControlRegister = self.read_register()?;
+        Ok(OperatingState::decode(
+            block[0],
+            block[4],
+            fifo_ctrl.to_bits(),
+        ))
+    }
+
+    /// Switches the device to the given power mode.
+    ///
+    /// Use [`power_up_with_delay`](Self::power_up_with_delay) instead to wait for the
+    /// outputs to settle when leaving power-down.
+    pub fn set_power_state(&mut self, mode: PowerMode) -> Result<(), E> {
+        match mode {
+            PowerMode::PowerDown => self.power_down(),
+            PowerMode::Sleep => self.sleep_mode(),
+            PowerMode::Normal => self.power_up(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::MockError;
+    use crate::sim::Simulator;
+
+    #[test]
+    fn decodes_power_boot_and_fifo_state() {
+        let mut driver = L3GD20::with_transport(Simulator::<MockError>::new()).unwrap();
+        let state = driver.operating_state().unwrap();
+        assert_eq!(state.power_mode, PowerMode::Normal);
+        assert!(state.x_enabled && state.y_enabled && state.z_enabled);
+        assert!(!state.booting);
+        assert!(!state.fifo_enabled);
+        assert_eq!(state.fifo_mode, Some(FifoMode::Bypass));
+
+        let registers = driver.transport.registers_mut();
+        registers[0x20] = 0b0000_1000;
+        registers[0x24] = 0b1100_0000;
+        registers[0x2E] = 0b0100_0000;
+        let state = driver.operating_state().unwrap();
+        assert_eq!(state.power_mode, PowerMode::Sleep);
+        assert!(state.booting);
+        assert!(state.fifo_enabled);
+        assert_eq!(state.fifo_mode, Some(FifoMode::Stream));
+
+        let registers = driver.transport.registers_mut();
+        registers[0x20] = 0b0000_0111;
+        registers[0x2E] = 0b1110_0000;
+        let state = driver.operating_state().unwrap();
+        assert_eq!(state.power_mode, PowerMode::PowerDown);
+        assert_eq!(state.fifo_mode, None);
+    }
+
+    #[test]
+    fn power_state_is_switched() {
+        let mut driver = L3GD20::with_transport(Simulator::<MockError>::new()).unwrap();
+        for mode in [PowerMode::Sleep, PowerMode::PowerDown, PowerMode::Normal] {
+            driver.set_power_state(mode).unwrap();
+            assert_eq!(driver.operating_state().unwrap().power_mode, mode);
+        }
+    }
+}
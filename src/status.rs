@@ -0,0 +1,127 @@
+use crate::Axis;
+use l3gd20_registers::prelude::ToBits;
+use l3gd20_registers::StatusRegister;
+
+/// The data available and overrun flags of `STATUS_REG`.
+///
+/// The flags refer to the sensor axes, regardless of the
+/// [axis mapping](crate::orientation::AxisMap) applied to the readings.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Status {
+    /// New data is available on all axes (`ZYXDA`).
+    pub zyx_da: bool,
+    /// New X-axis data is available (`XDA`).
+    pub x_da: bool,
+    /// New Y-axis data is available (`YDA`).
+    pub y_da: bool,
+    /// New Z-axis data is available (`ZDA`).
+    pub z_da: bool,
+    /// Data of any axis was overwritten before it was read (`ZYXOR`).
+    pub zyx_overrun: bool,
+    /// X-axis data was overwritten before it was read (`XOR`).
+    pub x_overrun: bool,
+    /// Y-axis data was overwritten before it was read (`YOR`).
+    pub y_overrun: bool,
+    /// Z-axis data was overwritten before it was read (`ZOR`).
+    pub z_overrun: bool,
+}
+
+impl Status {
+    /// Decodes the contents of `STATUS_REG`.
+    pub const fn from_bits(bits: u8) -> Self {
+        Self {
+            x_da: bits & 0b0000_0001 != 0,
+            y_da: bits & 0b0000_0010 != 0,
+            z_da: bits & 0b0000_0100 != 0,
+            zyx_da: bits & 0b0000_1000 != 0,
+            x_overrun: bits & 0b0001_0000 != 0,
+            y_overrun: bits & 0b0010_0000 != 0,
+            z_overrun: bits & 0b0100_0000 != 0,
+            zyx_overrun: bits & 0b1000_0000 != 0,
+        }
+    }
+
+    /// Encodes the flags in the layout of `STATUS_REG`.
+    pub const fn bits(&self) -> u8 {
+        let flags = [
+            self.x_da,
+            self.y_da,
+            self.z_da,
+            self.zyx_da,
+            self.x_overrun,
+            self.y_overrun,
+            self.z_overrun,
+            self.zyx_overrun,
+        ];
+        let mut bits = 0;
+        let mut n = 0;
+        while n < flags.len() {
+            bits |= (flags[n] as u8) << n;
+            n += 1;
+        }
+        bits
+    }
+
+    /// Determines whether new data is available on the axis.
+    pub const fn data_available(&self, axis: Axis) -> bool {
+        match axis {
+            Axis::X => self.x_da,
+            Axis::Y => self.y_da,
+            Axis::Z => self.z_da,
+        }
+    }
+
+    /// Determines whether data of the axis was overwritten before it was read.
+    pub const fn overrun(&self, axis: Axis) -> bool {
+        match axis {
+            Axis::X => self.x_overrun,
+            Axis::Y => self.y_overrun,
+            Axis::Z => self.z_overrun,
+        }
+    }
+}
+
+impl From<StatusRegister> for Status {
+    fn from(value: StatusRegister) -> Self {
+        Self::from_bits(value.to_bits())
+    }
+}
+
+impl From<Status> for StatusRegister {
+    fn from(value: Status) -> Self {
+        StatusRegister::from_bits(value.bits())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::MockError;
+    use crate::sim::Simulator;
+    use crate::L3GD20;
+
+    #[test]
+    fn status_matches_the_register_layout() {
+        for bits in 0..=u8::MAX {
+            let register = StatusRegister::from_bits(bits);
+            let status = Status::from(register);
+            assert_eq!(status.bits(), bits);
+            assert_eq!(StatusRegister::from(status), register);
+            assert_eq!(status.zyx_da, register.zyx_da());
+            assert_eq!(status.zyx_overrun, register.zyx_overrun());
+            assert_eq!(status.data_available(Axis::Y), register.y_da());
+            assert_eq!(status.overrun(Axis::Z), register.z_overrun());
+        }
+    }
+
+    #[test]
+    fn samples_carry_the_status() {
+        let mut driver = L3GD20::with_transport(Simulator::<MockError>::new()).unwrap();
+        driver.transport.set_status(0b1001_1111);
+
+        let status = driver.status().unwrap();
+        assert!(status.zyx_da && status.x_overrun && !status.y_overrun);
+        assert_eq!(driver.data_raw().unwrap().status, status);
+    }
+}
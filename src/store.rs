@@ -0,0 +1,366 @@
+//! Provides persistence of the settings and the bias through a [`ConfigStore`].
+//!
+//! The driver does not know about storage devices. Projects implement [`ConfigStore`] on
+//! top of their EEPROM or flash driver, with one store per record, and save and load the
+//! [`L3GD20Settings`] and the [`Bias`] through it. The bias is kept in a [`BiasRecord`] along
+//! with the full scale it was estimated at, and converted on load when the full scale has
+//! changed since. [`RamStore`] keeps a record in memory,
+//! e.g. for tests or to hand it over across a soft reset.
+//!
+//! ```
+//! use l3gd20_ng::store::RamStore;
+//! use l3gd20_ng::L3GD20Settings;
+//!
+//! let mut store = RamStore::<16>::new();
+//! let settings = L3GD20Settings::default();
+//! settings.save(&mut store).unwrap();
+//! assert_eq!(L3GD20Settings::load(&mut store), Ok(settings));
+//! ```
+//!
+//! ## Bias layout
+//!
+//! Like the [settings layout](crate::settings), the encoding of a [`BiasRecord`] starts with
+//! a version byte and ends with a checksum byte. Version `1` stores the full scale in the
+//! `FS` bits of a `CTRL_REG4` byte, followed, in little endian byte order, by the offset as
+//! three `f32`, the raw temperature, the number of samples as `u16` and the noise as three
+//! `f32`.
+
+use crate::encoding::LittleEndian;
+use crate::settings::{checksum, SettingsError};
+use crate::{Bias, F32x3, L3GD20Settings, Transport, L3GD20};
+use l3gd20_registers::prelude::ToBits;
+use l3gd20_registers::{ControlRegister4, Sensitivity};
+
+/// The current version of the bias encoding.
+const BIAS_VERSION: u8 = 1;
+
+/// The encoded length of version 1 of the bias, including the version and checksum bytes.
+const BIAS_LEN_V1: usize = 1 + 1 + 12 + 1 + 2 + 12 + 1;
+
+/// A storage slot holding a single record, e.g. a page of EEPROM or flash.
+pub trait ConfigStore {
+    /// The error of a storage access.
+    type Error;
+
+    /// Copies the stored record into the buffer, up to the length of the buffer.
+    ///
+    /// Returns the number of bytes copied, which is zero if nothing was stored.
+    fn load(&mut self, buffer: &mut [u8]) -> Result<usize, Self::Error>;
+
+    /// Replaces the stored record.
+    fn save(&mut self, data: &[u8]) -> Result<(), Self::Error>;
+}
+
+impl<S> ConfigStore for &mut S
+where
+    S: ConfigStore + ?Sized,
+{
+    type Error = S::Error;
+
+    fn load(&mut self, buffer: &mut [u8]) -> Result<usize, Self::Error> {
+        S::load(self, buffer)
+    }
+
+    fn save(&mut self, data: &[u8]) -> Result<(), Self::Error> {
+        S::save(self, data)
+    }
+}
+
+/// An error loading a record from a [`ConfigStore`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum StoreError<S> {
+    /// The storage access failed.
+    Store(S),
+    /// The [`L3GD20Settings`] record could not be decoded. An empty store is reported as
+    /// [`SettingsError::Truncated`].
+    Settings(SettingsError),
+    /// The [`BiasRecord`] could not be decoded. An empty store is reported as
+    /// [`SettingsError::Truncated`].
+    Bias(SettingsError),
+}
+
+/// The record is larger than the capacity of a [`RamStore`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct CapacityExceeded;
+
+/// A [`ConfigStore`] keeping a record of up to `N` bytes in memory.
+#[derive(Debug, Clone)]
+pub struct RamStore<const N: usize> {
+    data: [u8; N],
+    len: usize,
+}
+
+impl<const N: usize> RamStore<N> {
+    /// Creates an empty store.
+    pub const fn new() -> Self {
+        Self {
+            data: [0; N],
+            len: 0,
+        }
+    }
+
+    /// Gets the stored record.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.data[..self.len]
+    }
+}
+
+impl<const N: usize> Default for RamStore<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> ConfigStore for RamStore<N> {
+    type Error = CapacityExceeded;
+
+    fn load(&mut self, buffer: &mut [u8]) -> Result<usize, Self::Error> {
+        let len = self.len.min(buffer.len());
+        buffer[..len].copy_from_slice(&self.data[..len]);
+        Ok(len)
+    }
+
+    fn save(&mut self, data: &[u8]) -> Result<(), Self::Error> {
+        let target = self.data.get_mut(..data.len()).ok_or(CapacityExceeded)?;
+        target.copy_from_slice(data);
+        self.len = data.len();
+        Ok(())
+    }
+}
+
+impl L3GD20Settings {
+    /// Saves the settings to the store, see [`to_bytes`](Self::to_bytes).
+    pub fn save<S>(&self, store: &mut S) -> Result<(), S::Error>
+    where
+        S: ConfigStore,
+    {
+        store.save(&self.to_bytes())
+    }
+
+    /// Loads settings saved with [`save`](Self::save).
+    pub fn load<S>(store: &mut S) -> Result<Self, StoreError<S::Error>>
+    where
+        S: ConfigStore,
+    {
+        let mut buffer = [0; Self::ENCODED_LEN];
+        let len = store.load(&mut buffer).map_err(StoreError::Store)?;
+        Self::from_bytes(&buffer[..len]).map_err(StoreError::Settings)
+    }
+}
+
+/// A [`Bias`] together with the full scale it was estimated at.
+///
+/// The offset and noise of a [`Bias`] are raw LSB of the full scale they were estimated at,
+/// so the record keeps that [`Sensitivity`] and [`bias_at`](Self::bias_at) converts them to
+/// another full scale.
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct BiasRecord {
+    /// The bias, in raw LSB of [`full_scale`](Self::full_scale).
+    pub bias: Bias,
+    /// The full scale the bias was estimated at.
+    pub full_scale: Sensitivity,
+}
+
+impl BiasRecord {
+    /// The length of the encoding produced by [`to_bytes`](Self::to_bytes).
+    pub const ENCODED_LEN: usize = BIAS_LEN_V1;
+
+    /// Gets the bias converted to the raw LSB of the given full scale.
+    #[must_use]
+    pub fn bias_at(&self, full_scale: Sensitivity) -> Bias {
+        self.bias.rescaled(self.full_scale, full_scale)
+    }
+
+    /// Encodes the record in the current version of the [layout](crate::store#bias-layout).
+    #[must_use]
+    pub fn to_bytes(&self) -> [u8; Self::ENCODED_LEN] {
+        let mut bytes = [0; Self::ENCODED_LEN];
+        bytes[0] = BIAS_VERSION;
+        bytes[1] = ControlRegister4::new()
+            .with_full_scale(self.full_scale)
+            .to_bits();
+        bytes[2..14].copy_from_slice(&self.bias.offset.to_bytes::<LittleEndian>());
+        bytes[14] = self.bias.temperature;
+        bytes[15..17].copy_from_slice(&self.bias.samples.to_le_bytes());
+        bytes[17..29].copy_from_slice(&self.bias.noise.to_bytes::<LittleEndian>());
+        bytes[BIAS_LEN_V1 - 1] = checksum(&bytes[..BIAS_LEN_V1 - 1]);
+        bytes
+    }
+
+    /// Decodes a record stored in the current or any older version of the layout.
+    ///
+    /// Trailing bytes beyond the length of the encoded version are ignored.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, SettingsError> {
+        let version = *bytes.first().ok_or(SettingsError::Truncated)?;
+        let len = match version {
+            1 => BIAS_LEN_V1,
+            _ => return Err(SettingsError::UnsupportedVersion(version)),
+        };
+        let bytes = bytes.get(..len).ok_or(SettingsError::Truncated)?;
+        if checksum(&bytes[..len - 1]) != bytes[len - 1] {
+            return Err(SettingsError::Checksum);
+        }
+
+        let f32x3 = |range: core::ops::Range<usize>| {
+            let mut component = [0; 12];
+            component.copy_from_slice(&bytes[range]);
+            F32x3::from_bytes::<LittleEndian>(component)
+        };
+        Ok(Self {
+            bias: Bias {
+                offset: f32x3(2..14),
+                temperature: bytes[14],
+                samples: u16::from_le_bytes([bytes[15], bytes[16]]),
+                noise: f32x3(17..29),
+            },
+            full_scale: ControlRegister4::from_bits(bytes[1]).full_scale(),
+        })
+    }
+
+    /// Saves the record to the store, see [`to_bytes`](Self::to_bytes).
+    pub fn save<S>(&self, store: &mut S) -> Result<(), S::Error>
+    where
+        S: ConfigStore,
+    {
+        store.save(&self.to_bytes())
+    }
+
+    /// Loads a record saved with [`save`](Self::save).
+    pub fn load<S>(store: &mut S) -> Result<Self, StoreError<S::Error>>
+    where
+        S: ConfigStore,
+    {
+        let mut buffer = [0; Self::ENCODED_LEN];
+        let len = store.load(&mut buffer).map_err(StoreError::Store)?;
+        Self::from_bytes(&buffer[..len]).map_err(StoreError::Bias)
+    }
+}
+
+impl<T, E> L3GD20<T>
+where
+    T: Transport<Error = E>,
+{
+    /// Saves the bias together with the current full scale, see [`BiasRecord`].
+    pub fn save_bias<S>(&self, store: &mut S) -> Result<(), S::Error>
+    where
+        S: ConfigStore,
+    {
+        BiasRecord {
+            bias: self.compensation.bias,
+            full_scale: self.full_scale,
+        }
+        .save(store)
+    }
+
+    /// Loads a bias saved with [`save_bias`](Self::save_bias) and applies it, converted to
+    /// the current full scale. Returns the applied bias.
+    pub fn load_bias<S>(&mut self, store: &mut S) -> Result<Bias, StoreError<S::Error>>
+    where
+        S: ConfigStore,
+    {
+        let bias = BiasRecord::load(store)?.bias_at(self.full_scale);
+        self.set_bias(bias);
+        Ok(bias)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::MockError;
+    use crate::sim::Simulator;
+    use l3gd20_registers::OutputDataRate;
+
+    #[test]
+    fn settings_and_bias_survive_a_restart() {
+        let mut settings_store = RamStore::<{ L3GD20Settings::ENCODED_LEN }>::new();
+        let mut bias_store = RamStore::<{ BiasRecord::ENCODED_LEN }>::new();
+        let bias = Bias {
+            offset: F32x3::new(1.5, -2.0, 0.25),
+            temperature: 25,
+            samples: 500,
+            noise: F32x3::new(0.5, 0.75, 1.0),
+        };
+
+        let mut driver = L3GD20::with_transport(Simulator::<MockError>::new()).unwrap();
+        let settings = L3GD20Settings {
+            output_data_rate: OutputDataRate::Hz380,
+            full_scale: Sensitivity::D500,
+            ..L3GD20Settings::default()
+        };
+        driver.apply_settings(&settings).unwrap();
+        driver.set_bias(bias);
+        driver
+            .settings()
+            .unwrap()
+            .save(&mut settings_store)
+            .unwrap();
+        driver.save_bias(&mut bias_store).unwrap();
+
+        let mut driver = L3GD20::with_transport(Simulator::<MockError>::new()).unwrap();
+        driver
+            .apply_settings(&L3GD20Settings::load(&mut settings_store).unwrap())
+            .unwrap();
+        assert_eq!(driver.load_bias(&mut bias_store), Ok(bias));
+        assert_eq!(driver.settings(), Ok(settings));
+        assert_eq!(*driver.bias(), bias);
+    }
+
+    #[test]
+    fn bias_is_converted_to_the_current_full_scale() {
+        let mut store = RamStore::<{ BiasRecord::ENCODED_LEN }>::new();
+        let record = BiasRecord {
+            bias: Bias {
+                offset: F32x3::new(40.0, -20.0, 8.0),
+                temperature: 25,
+                samples: 500,
+                noise: F32x3::new(4.0, 4.0, 4.0),
+            },
+            full_scale: Sensitivity::D250,
+        };
+        record.save(&mut store).unwrap();
+        assert_eq!(BiasRecord::load(&mut store), Ok(record));
+
+        // The default full scale is 250 dps; at 500 dps one LSB is twice the rate.
+        let mut driver = L3GD20::with_transport(Simulator::<MockError>::new()).unwrap();
+        driver.set_scale(Sensitivity::D500).unwrap();
+        let bias = driver.load_bias(&mut store).unwrap();
+        assert_eq!(bias, record.bias_at(Sensitivity::D500));
+        assert!((bias.offset.x - 20.0).abs() < 1e-3);
+        assert!((bias.offset.y + 10.0).abs() < 1e-3);
+        assert!((bias.noise.z - 2.0).abs() < 1e-3);
+        assert_eq!(*driver.bias(), bias);
+    }
+
+    #[test]
+    fn invalid_records_are_rejected() {
+        let mut store = RamStore::<64>::new();
+        assert_eq!(
+            BiasRecord::load(&mut store),
+            Err(StoreError::Bias(SettingsError::Truncated))
+        );
+        assert_eq!(
+            L3GD20Settings::load(&mut store),
+            Err(StoreError::Settings(SettingsError::Truncated))
+        );
+
+        let record = BiasRecord {
+            bias: Bias::default(),
+            full_scale: Sensitivity::D2000,
+        };
+        record.save(&mut store).unwrap();
+        let mut corrupted = store.as_bytes().to_vec();
+        corrupted[14] ^= 1;
+        store.save(&corrupted).unwrap();
+        assert_eq!(
+            BiasRecord::load(&mut store),
+            Err(StoreError::Bias(SettingsError::Checksum))
+        );
+
+        let mut small = RamStore::<8>::new();
+        assert_eq!(record.save(&mut small), Err(CapacityExceeded));
+    }
+}
@@ -0,0 +1,201 @@
+//! Provides annotation of samples with their offset from external sync pulses.
+//!
+//! Camera/IMU synchronization rigs typically distribute a sync pulse to all sensors.
+//! Recording the pulse times (e.g. from a GPIO interrupt) in a [`SyncSource`] installed via
+//! [`set_sync_source`](crate::L3GD20::set_sync_source) lets the driver annotate each sample,
+//! or each FIFO batch, with its offset from the nearest pulse, using the installed
+//! [time source](crate::time).
+
+use crate::{F32x3, I16x3, SensorData, Transport, L3GD20};
+use core::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+
+/// A source of sync pulse timestamps, in the time base of the installed [`TimeSource`](crate::time::TimeSource).
+pub trait SyncSource {
+    /// Gets the time of the most recent sync pulse in microseconds, if any.
+    fn last_pulse_micros(&self) -> Option<u64>;
+
+    /// Gets the nominal pulse period in microseconds, if the pulses are periodic.
+    ///
+    /// This allows offsets to be measured to the next expected pulse if it is nearer
+    /// than the last one.
+    fn pulse_period_micros(&self) -> Option<u64> {
+        None
+    }
+}
+
+/// A sync source that latches the time of the last pulse.
+///
+/// [`record`](Self::record) is intended to be called from the pulse handler, which may
+/// preempt the driver; the latch can be placed in a `static`. The time is kept in two
+/// 32-bit atomics, which are available on all targets, and read back consistently as long
+/// as a reader does not preempt [`record`](Self::record).
+#[derive(Debug, Default)]
+pub struct PulseLatch {
+    seen: AtomicBool,
+    low: AtomicU32,
+    high: AtomicU32,
+    period_us: Option<u64>,
+}
+
+impl PulseLatch {
+    /// Creates a latch for pulses with the given nominal period in microseconds, if periodic.
+    #[must_use]
+    pub const fn new(period_us: Option<u64>) -> Self {
+        Self {
+            seen: AtomicBool::new(false),
+            low: AtomicU32::new(0),
+            high: AtomicU32::new(0),
+            period_us,
+        }
+    }
+
+    /// Records a sync pulse at the given time in microseconds.
+    pub fn record(&self, now_us: u64) {
+        self.low.store(now_us as u32, Ordering::Relaxed);
+        self.high.store((now_us >> 32) as u32, Ordering::Relaxed);
+        self.seen.store(true, Ordering::Release);
+    }
+}
+
+impl SyncSource for PulseLatch {
+    fn last_pulse_micros(&self) -> Option<u64> {
+        if !self.seen.load(Ordering::Acquire) {
+            return None;
+        }
+        // A pulse recorded between the reads is only torn from the low half if it changed
+        // the high half; retry in that case.
+        loop {
+            let high = self.high.load(Ordering::Acquire);
+            let low = self.low.load(Ordering::Acquire);
+            if self.high.load(Ordering::Acquire) == high {
+                return Some(u64::from(high) << 32 | u64::from(low));
+            }
+        }
+    }
+
+    fn pulse_period_micros(&self) -> Option<u64> {
+        self.period_us
+    }
+}
+
+/// A sample annotated with its offset from the nearest sync pulse.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SyncedSample {
+    /// The sample.
+    pub data: SensorData,
+    /// The rates of the sample in the [output unit](L3GD20::set_output_unit), compensated
+    /// like in [`L3GD20::rates`].
+    pub rates: F32x3,
+    /// The time the sample was read, relative to the nearest sync pulse, in microseconds.
+    ///
+    /// Negative values precede the pulse. `None` if no time source or sync source is
+    /// installed, or no pulse was seen yet.
+    pub offset_us: Option<i64>,
+}
+
+/// Determines the offset of `now` from the nearest pulse.
+fn nearest_offset(now: u64, last: u64, period: Option<u64>) -> i64 {
+    let since = (i128::from(now) - i128::from(last)) as i64;
+    match period.and_then(|period| i64::try_from(period).ok()) {
+        Some(period) if period > 0 && since >= 0 => {
+            let phase = since % period;
+            if phase * 2 > period {
+                phase - period
+            } else {
+                phase
+            }
+        }
+        _ => since,
+    }
+}
+
+impl<T, E> L3GD20<T>
+where
+    T: Transport<Error = E>,
+{
+    /// Installs the source of sync pulse timestamps.
+    pub fn set_sync_source(&mut self, source: &'static dyn SyncSource) {
+        self.sync = Some(source);
+    }
+
+    /// Gets the offset of the current time from the nearest sync pulse, in microseconds.
+    ///
+    /// This can be used to annotate FIFO batches at the time they are drained.
+    pub fn sync_offset_micros(&self) -> Option<i64> {
+        self.sync_offset_at(self.now_micros()?)
+    }
+
+    /// Fetches all data off the sensor, annotated with its offset from the nearest sync pulse
+    /// and converted to the selected [output unit](Self::set_output_unit).
+    ///
+    /// The sample is timestamped right before it is read.
+    pub fn data_synced(&mut self) -> Result<SyncedSample, E> {
+        let now = self.now_micros();
+        let data = self.data_raw()?;
+        let rates = self.rates_in_output_unit(I16x3::from(data))?;
+        Ok(SyncedSample {
+            data,
+            rates,
+            offset_us: now.and_then(|now| self.sync_offset_at(now)),
+        })
+    }
+
+    /// Gets the offset of the given time from the nearest sync pulse, in microseconds.
+    fn sync_offset_at(&self, now: u64) -> Option<i64> {
+        let source = self.sync?;
+        let last = source.last_pulse_micros()?;
+        Some(nearest_offset(now, last, source.pulse_period_micros()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::MockError;
+    use crate::sim::Simulator;
+    use crate::time::FixedStep;
+    use std::boxed::Box;
+
+    #[test]
+    fn offsets_refer_to_the_nearest_pulse() {
+        assert_eq!(nearest_offset(1_300, 1_000, None), 300);
+        assert_eq!(nearest_offset(1_300, 1_000, Some(1_000)), 300);
+        assert_eq!(nearest_offset(1_800, 1_000, Some(1_000)), -200);
+        assert_eq!(nearest_offset(3_100, 1_000, Some(1_000)), 100);
+        assert_eq!(nearest_offset(900, 1_000, Some(1_000)), -100);
+    }
+
+    #[test]
+    fn samples_are_annotated() {
+        let mut driver = L3GD20::with_transport(Simulator::<MockError>::new()).unwrap();
+        let latch: &'static PulseLatch = Box::leak(Box::new(PulseLatch::new(Some(600))));
+        driver.set_sync_source(latch);
+        assert!(driver.data_synced().unwrap().offset_us.is_none());
+
+        driver.set_time_source(Box::leak(Box::new(FixedStep::new(250))));
+        latch.record(0);
+        // The clock is queried twice per sample, for the timestamp and by the read itself.
+        assert_eq!(driver.data_synced().unwrap().offset_us, Some(0));
+        assert_eq!(driver.data_synced().unwrap().offset_us, Some(-100));
+    }
+
+    #[test]
+    fn samples_are_converted_to_the_output_unit() {
+        let mut driver = L3GD20::with_transport(Simulator::<MockError>::new()).unwrap();
+        driver.transport.set_xyz(-1000, 0, 0);
+        driver.set_output_unit(crate::OutputUnit::MilliDegreesPerSecond);
+
+        let sample = driver.data_synced().unwrap();
+        assert_eq!(*sample.data.x, -1000);
+        assert!(libm::fabsf(sample.rates.x + 8750.0) < 1e-1);
+    }
+
+    #[test]
+    fn latches_can_be_static() {
+        static LATCH: PulseLatch = PulseLatch::new(None);
+        assert_eq!(LATCH.last_pulse_micros(), None);
+        LATCH.record(0x1_2345_6789);
+        assert_eq!(LATCH.last_pulse_micros(), Some(0x1_2345_6789));
+    }
+}
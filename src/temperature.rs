@@ -0,0 +1,370 @@
+//! Provides the interpretation of the `OUT_TEMP` register.
+//!
+//! The L3GD20 and L3GD20H encode the temperature differently: the L3GD20 reports an
+//! unsigned value, while the L3GD20H reports a two's-complement value that is zero at 25 °C.
+//! Both decrease by one LSB per °C. The convention is picked from the detected
+//! [`Variant`] and can be overridden for clones, or to apply a per-part offset.
+//!
+//! ```
+//! use l3gd20_ng::temperature::TemperatureConvention;
+//!
+//! let convention = TemperatureConvention::L3GD20H;
+//! assert_eq!(convention.celsius(0), 25.0);
+//! assert_eq!(convention.celsius(0xFB), 30.0);
+//! assert_eq!(convention.with_offset(-2.0).celsius(0), 23.0);
+//! ```
+//!
+//! [`RawTemperature`] and [`TemperatureDelta`] keep raw readings apart from degrees, as
+//! they can only be converted given a convention:
+//!
+//! ```
+//! use l3gd20_ng::temperature::{RawTemperature, TemperatureConvention};
+//!
+//! let convention = TemperatureConvention::L3GD20H;
+//! let earlier = RawTemperature::new(0x02);
+//! let later = RawTemperature::new(0xFE);
+//! assert_eq!(later.celsius(&convention), 27.0);
+//! assert_eq!(later.delta_since(earlier, &convention).kelvin(&convention), 4.0);
+//! ```
+
+use crate::{OutputDataRate, Transport, L3GD20};
+use l3gd20_registers::WhoAmI;
+
+/// The `WHO_AM_I` value of the L3GD20.
+const WHO_AM_I_L3GD20: u8 = 0xD4;
+
+/// The `WHO_AM_I` value of the L3GD20H.
+const WHO_AM_I_L3GD20H: u8 = 0xD7;
+
+/// A chip variant.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Variant {
+    /// The L3GD20, identifying as `0xD4`.
+    L3GD20,
+    /// The L3GD20H, identifying as `0xD7`.
+    L3GD20H,
+}
+
+impl Variant {
+    /// Determines the variant from a `WHO_AM_I` value.
+    #[must_use]
+    pub const fn from_who_am_i(ident: u8) -> Option<Self> {
+        match ident {
+            WHO_AM_I_L3GD20 => Some(Self::L3GD20),
+            WHO_AM_I_L3GD20H => Some(Self::L3GD20H),
+            _ => None,
+        }
+    }
+
+    /// Gets the temperature convention of this variant.
+    #[must_use]
+    pub const fn temperature_convention(&self) -> TemperatureConvention {
+        match self {
+            Self::L3GD20 => TemperatureConvention::L3GD20,
+            Self::L3GD20H => TemperatureConvention::L3GD20H,
+        }
+    }
+
+    /// Gets the nominal rate of an output data rate setting on this variant, in Hertz.
+    pub fn output_data_rate_hz(&self, rate: OutputDataRate) -> u16 {
+        match self {
+            Variant::L3GD20 => rate.hz(),
+            Variant::L3GD20H => match rate {
+                OutputDataRate::Hz95 => 100,
+                OutputDataRate::Hz190 => 200,
+                OutputDataRate::Hz380 => 400,
+                OutputDataRate::Hz760 => 800,
+            },
+        }
+    }
+}
+
+/// The interpretation of raw `OUT_TEMP` readings.
+///
+/// A reading is converted as `reference_c - value * celsius_per_lsb`, where `value` is the
+/// raw byte interpreted either as unsigned or as two's complement.
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct TemperatureConvention {
+    /// Whether the raw value is two's complement.
+    pub signed: bool,
+    /// The temperature at a raw value of zero, in °C.
+    pub reference_c: f32,
+    /// The temperature decrease per LSB, in °C.
+    pub celsius_per_lsb: f32,
+}
+
+impl TemperatureConvention {
+    /// The L3GD20 convention: unsigned, -1 LSB/°C.
+    ///
+    /// The datasheet does not specify an absolute offset; the reference of 25 °C matches the
+    /// L3GD20H and should be adjusted with [`with_offset`](Self::with_offset) where accuracy matters.
+    pub const L3GD20: Self = Self {
+        signed: false,
+        reference_c: 25.0,
+        celsius_per_lsb: 1.0,
+    };
+
+    /// The L3GD20H convention: two's complement, -1 LSB/°C, zero at 25 °C.
+    pub const L3GD20H: Self = Self {
+        signed: true,
+        reference_c: 25.0,
+        celsius_per_lsb: 1.0,
+    };
+
+    /// Returns a copy of this convention with the reference shifted by `offset_c` °C.
+    #[must_use]
+    pub fn with_offset(mut self, offset_c: f32) -> Self {
+        self.reference_c += offset_c;
+        self
+    }
+
+    /// Returns a copy of this convention with the temperature at a raw value of zero set
+    /// to `reference_c` °C, e.g. as measured for the part at hand.
+    #[must_use]
+    pub const fn with_reference(mut self, reference_c: f32) -> Self {
+        self.reference_c = reference_c;
+        self
+    }
+
+    /// Converts a raw `OUT_TEMP` reading to °C.
+    #[must_use]
+    pub fn celsius(&self, raw: u8) -> f32 {
+        self.reference_c - f32::from(self.value(raw)) * self.celsius_per_lsb
+    }
+
+    /// Interprets a raw `OUT_TEMP` reading as unsigned or two's complement.
+    const fn value(&self, raw: u8) -> i16 {
+        if self.signed {
+            raw as i8 as i16
+        } else {
+            raw as i16
+        }
+    }
+}
+
+/// A raw `OUT_TEMP` reading.
+///
+/// The value is not a temperature in degrees; it can only be converted given the
+/// [`TemperatureConvention`] of the chip.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct RawTemperature(u8);
+
+impl RawTemperature {
+    /// Wraps the raw value of the `OUT_TEMP` register.
+    #[must_use]
+    pub const fn new(raw: u8) -> Self {
+        Self(raw)
+    }
+
+    /// Gets the raw value of the `OUT_TEMP` register.
+    #[must_use]
+    pub const fn bits(&self) -> u8 {
+        self.0
+    }
+
+    /// Converts the reading to °C.
+    #[must_use]
+    pub fn celsius(&self, convention: &TemperatureConvention) -> f32 {
+        convention.celsius(self.0)
+    }
+
+    /// Gets the change of the reading since an earlier one.
+    ///
+    /// The convention determines whether the raw values wrap around as unsigned or
+    /// two's complement numbers.
+    #[must_use]
+    pub const fn delta_since(
+        &self,
+        earlier: RawTemperature,
+        convention: &TemperatureConvention,
+    ) -> TemperatureDelta {
+        TemperatureDelta(convention.value(self.0) - convention.value(earlier.0))
+    }
+}
+
+/// The change between two raw `OUT_TEMP` readings, in LSB.
+///
+/// As the temperature decreases with increasing raw values, the value has the opposite
+/// sign of the temperature change; use [`kelvin`](Self::kelvin) to convert it.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct TemperatureDelta(i16);
+
+impl TemperatureDelta {
+    /// Wraps a change of the raw readings, in LSB.
+    #[must_use]
+    pub const fn new(lsb: i16) -> Self {
+        Self(lsb)
+    }
+
+    /// Gets the change of the raw readings, in LSB.
+    #[must_use]
+    pub const fn lsb(&self) -> i16 {
+        self.0
+    }
+
+    /// Converts the change to a temperature change in K (or °C).
+    #[must_use]
+    pub fn kelvin(&self, convention: &TemperatureConvention) -> f32 {
+        -f32::from(self.0) * convention.celsius_per_lsb
+    }
+}
+
+impl Default for TemperatureConvention {
+    fn default() -> Self {
+        Self::L3GD20
+    }
+}
+
+/// The temperature interpretation state of the driver.
+#[derive(Debug, Default)]
+pub(crate) struct TemperatureState {
+    pub(crate) variant: Option<Variant>,
+    convention: Option<TemperatureConvention>,
+}
+
+impl<T, E> L3GD20<T>
+where
+    T: Transport<Error = E>,
+{
+    /// Detects the chip variant by querying the `WHO_AM_I` register.
+    ///
+    /// The detected variant selects the temperature convention unless it was overridden.
+    /// Returns `None` for unknown identifiers, in which case readings are decoded as two's
+    /// complement like with the [L3GD20H convention](TemperatureConvention::L3GD20H).
+    pub fn detect_variant(&mut self) -> Result<Option<Variant>, E> {
+        let variant = Variant::from_who_am_i(self.read_register::<WhoAmI>()?.ident());
+        self.temperature.variant = variant;
+        Ok(variant)
+    }
+
+    /// Gets the chip variant found when the driver was constructed or by
+    /// [`detect_variant`](Self::detect_variant).
+    pub fn variant(&self) -> Option<Variant> {
+        self.temperature.variant
+    }
+
+    /// Overrides the temperature convention, or restores the variant default with `None`.
+    pub fn set_temperature_convention(&mut self, convention: Option<TemperatureConvention>) {
+        self.temperature.convention = convention;
+    }
+
+    /// Gets the temperature convention in effect.
+    ///
+    /// Without an override, the convention of the detected variant is used. While the
+    /// variant is unknown, e.g. after [`with_transport_unchecked`](Self::with_transport_unchecked),
+    /// readings are decoded as two's complement, which keeps temperatures around the
+    /// reference plausible on either variant.
+    pub fn temperature_convention(&self) -> TemperatureConvention {
+        self.temperature.convention.unwrap_or_else(|| {
+            self.temperature
+                .variant
+                .map_or(TemperatureConvention::L3GD20H, |variant| {
+                    variant.temperature_convention()
+                })
+        })
+    }
+
+    /// Reads the raw temperature.
+    pub fn temperature_raw(&mut self) -> Result<RawTemperature, E> {
+        Ok(RawTemperature::new(self.temp_raw()?))
+    }
+
+    /// Reads the temperature in °C, using the [temperature convention](Self::temperature_convention)
+    /// in effect.
+    pub fn temperature_celsius(&mut self) -> Result<f32, E> {
+        let raw = self.temperature_raw()?;
+        Ok(raw.celsius(&self.temperature_convention()))
+    }
+
+    /// Reads the temperature in °C relative to a reference temperature at a raw value of
+    /// zero.
+    ///
+    /// The sign handling and the slope of -1 LSB/°C follow the
+    /// [temperature convention](Self::temperature_convention) in effect; only its reference
+    /// is replaced, see [`TemperatureConvention::with_reference`].
+    pub fn temp_celsius(&mut self, reference_c: f32) -> Result<f32, E> {
+        let raw = self.temperature_raw()?;
+        Ok(raw.celsius(&self.temperature_convention().with_reference(reference_c)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::MockError;
+    use crate::sim::Simulator;
+
+    #[test]
+    fn output_data_rates_depend_on_the_variant() {
+        assert_eq!(
+            Variant::L3GD20.output_data_rate_hz(OutputDataRate::Hz380),
+            380
+        );
+        assert_eq!(
+            Variant::L3GD20H.output_data_rate_hz(OutputDataRate::Hz380),
+            400
+        );
+    }
+
+    #[test]
+    fn convention_follows_the_detected_variant() {
+        let mut sim = Simulator::<MockError>::new();
+        sim.registers_mut()[0x0F] = WHO_AM_I_L3GD20H;
+        sim.set_temperature(0xFE);
+        let mut driver = L3GD20::with_transport(sim).unwrap();
+
+        assert_eq!(driver.variant(), Some(Variant::L3GD20H));
+        assert_eq!(driver.temperature_celsius().unwrap(), 27.0);
+
+        driver.transport.registers_mut()[0x0F] = WHO_AM_I_L3GD20;
+        assert_eq!(driver.detect_variant().unwrap(), Some(Variant::L3GD20));
+        assert_eq!(driver.temperature_celsius().unwrap(), 25.0 - 254.0);
+        driver.transport.registers_mut()[0x0F] = WHO_AM_I_L3GD20H;
+        assert_eq!(driver.detect_variant().unwrap(), Some(Variant::L3GD20H));
+
+        driver.set_temperature_convention(Some(TemperatureConvention::L3GD20H.with_offset(1.5)));
+        assert_eq!(driver.temperature_celsius().unwrap(), 28.5);
+    }
+
+    #[test]
+    fn reference_is_supplied_by_the_caller() {
+        let mut sim = Simulator::<MockError>::new();
+        sim.registers_mut()[0x0F] = WHO_AM_I_L3GD20H;
+        sim.set_temperature(0xFD);
+        let mut driver = L3GD20::with_transport(sim).unwrap();
+        assert_eq!(driver.temp_celsius(20.0).unwrap(), 23.0);
+
+        driver.transport.set_temperature(0x03);
+        assert_eq!(driver.temp_celsius(20.0).unwrap(), 17.0);
+    }
+
+    #[test]
+    fn unknown_variants_decode_signed_readings() {
+        let mut sim = Simulator::<MockError>::new();
+        sim.set_temperature(0xFD);
+        let mut driver = L3GD20::with_transport_unchecked(sim);
+        assert_eq!(driver.variant(), None);
+        assert_eq!(driver.temp_celsius(25.0).unwrap(), 28.0);
+        assert_eq!(driver.temperature_celsius().unwrap(), 28.0);
+    }
+
+    #[test]
+    fn deltas_wrap_according_to_the_convention() {
+        let earlier = RawTemperature::new(0x7F);
+        let later = RawTemperature::new(0x80);
+
+        let unsigned = TemperatureConvention::L3GD20;
+        assert_eq!(later.delta_since(earlier, &unsigned).lsb(), 1);
+        assert_eq!(
+            later.delta_since(earlier, &unsigned).kelvin(&unsigned),
+            -1.0
+        );
+
+        let signed = TemperatureConvention::L3GD20H;
+        assert_eq!(later.delta_since(earlier, &signed).lsb(), -255);
+    }
+}
@@ -0,0 +1,154 @@
+//! Provides a low-rate background logger of the sensor temperature.
+//!
+//! The logger is enabled via [`set_thermal_log_interval`](crate::L3GD20::set_thermal_log_interval)
+//! and driven by calling [`thermal_tick`](crate::L3GD20::thermal_tick) from the main loop.
+//! Reads of `OUT_TEMP` made by [`temp_raw`](crate::L3GD20::temp_raw) and
+//! [`data_raw`](crate::L3GD20::data_raw) are recorded as well and restart the interval,
+//! so that the tick only reads the register if no other read did.
+
+use crate::{Transport, L3GD20};
+
+/// Statistics of the raw temperature readings.
+///
+/// The values are raw `OUT_TEMP` readings, which decrease by one LSB per °C;
+/// `min` therefore corresponds to the warmest and `max` to the coldest temperature seen.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ThermalStats {
+    /// The most recent raw reading.
+    pub current: u8,
+    /// The smallest raw reading.
+    pub min: u8,
+    /// The largest raw reading.
+    pub max: u8,
+    /// The number of readings recorded.
+    pub samples: u32,
+}
+
+/// The state of the temperature logger.
+#[derive(Debug, Default)]
+pub(crate) struct ThermalLog {
+    interval_us: Option<u64>,
+    last_us: Option<u64>,
+    stats: Option<ThermalStats>,
+}
+
+impl ThermalLog {
+    /// Records a raw temperature reading taken at the given time, if logging is enabled.
+    pub(crate) fn record(&mut self, temperature: u8, now_us: Option<u64>) {
+        if self.interval_us.is_none() {
+            return;
+        }
+
+        self.last_us = now_us;
+        self.stats = Some(match self.stats {
+            None => ThermalStats {
+                current: temperature,
+                min: temperature,
+                max: temperature,
+                samples: 1,
+            },
+            Some(stats) => ThermalStats {
+                current: temperature,
+                min: stats.min.min(temperature),
+                max: stats.max.max(temperature),
+                samples: stats.samples.saturating_add(1),
+            },
+        });
+    }
+
+    /// Determines whether a reading is due at the given time.
+    fn is_due(&self, now_us: Option<u64>) -> bool {
+        match (self.interval_us, self.last_us, now_us) {
+            (None, _, _) => false,
+            (Some(interval), Some(last), Some(now)) => now.saturating_sub(last) >= interval,
+            (Some(_), _, _) => true,
+        }
+    }
+}
+
+impl<T, E> L3GD20<T>
+where
+    T: Transport<Error = E>,
+{
+    /// Enables the temperature logger with the given sampling interval in seconds,
+    /// or disables it if the interval is zero.
+    ///
+    /// Without a [time source](crate::time), every call to
+    /// [`thermal_tick`](Self::thermal_tick) takes a reading.
+    pub fn set_thermal_log_interval(&mut self, seconds: u16) {
+        self.thermal.interval_us = match seconds {
+            0 => None,
+            seconds => Some(u64::from(seconds) * 1_000_000),
+        };
+    }
+
+    /// Reads `OUT_TEMP` if the logging interval has elapsed since the last reading.
+    ///
+    /// Returns whether a reading was taken.
+    pub fn thermal_tick(&mut self) -> Result<bool, E> {
+        if !self.thermal.is_due(self.now_micros()) {
+            return Ok(false);
+        }
+        self.temp_raw()?;
+        Ok(true)
+    }
+
+    /// Gets the statistics of the logged temperature readings.
+    ///
+    /// Returns `None` if no readings were logged yet.
+    pub fn thermal_stats(&self) -> Option<ThermalStats> {
+        self.thermal.stats
+    }
+
+    /// Clears the statistics of the logged temperature readings.
+    pub fn reset_thermal_stats(&mut self) {
+        self.thermal.stats = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::MockError;
+    use crate::sim::Simulator;
+    use crate::time::FixedStep;
+    use std::boxed::Box;
+
+    #[test]
+    fn ticks_sample_at_the_interval() {
+        let mut driver = L3GD20::with_transport(Simulator::<MockError>::new()).unwrap();
+        let clock = Box::leak(Box::new(FixedStep::new(400_000)));
+        driver.set_time_source(clock);
+        driver.set_thermal_log_interval(1);
+        assert_eq!(driver.thermal_stats(), None);
+
+        let taken = (0..6).filter(|_| driver.thermal_tick().unwrap()).count();
+        assert_eq!(taken, 2);
+        assert_eq!(driver.thermal_stats().unwrap().samples, 2);
+    }
+
+    #[test]
+    fn data_reads_are_recorded() {
+        let mut sim = Simulator::<MockError>::new();
+        sim.set_temperature(20);
+        let mut driver = L3GD20::with_transport(sim).unwrap();
+        driver.set_thermal_log_interval(10);
+        driver.data_raw().unwrap();
+        driver.transport.set_temperature(15);
+        driver.data_raw().unwrap();
+        driver.transport.set_temperature(17);
+        driver.temp_raw().unwrap();
+
+        let stats = driver.thermal_stats().unwrap();
+        assert_eq!(
+            stats,
+            ThermalStats {
+                current: 17,
+                min: 15,
+                max: 20,
+                samples: 3
+            }
+        );
+    }
+}
@@ -0,0 +1,253 @@
+//! Provides the time source abstraction used by all time-dependent features.
+//!
+//! Time-dependent features, such as the integration in
+//! [`GainCalibration::update_timed`](crate::calibration::GainCalibration::update_timed),
+//! the age tracking in [`BiasReport`](crate::BiasReport) and
+//! [`measure_odr`](crate::L3GD20SPI::measure_odr), obtain the current time from a
+//! [`TimeSource`] installed via [`set_time_source`](crate::L3GD20SPI::set_time_source).
+
+use crate::{Transport, L3GD20};
+use core::sync::atomic::{AtomicU32, Ordering};
+
+/// A monotonic source of time.
+pub trait TimeSource {
+    /// Gets the current time in microseconds since an arbitrary, fixed epoch.
+    fn now_micros(&self) -> u64;
+}
+
+impl<T> TimeSource for &T
+where
+    T: TimeSource + ?Sized,
+{
+    fn now_micros(&self) -> u64 {
+        (**self).now_micros()
+    }
+}
+
+/// A time source that advances by a fixed step every time it is queried.
+///
+/// This is mainly useful for tests and simulations, where a fixed sample interval
+/// is assumed. The state is kept in atomics, so that the source can be placed in a
+/// `static`; it is meant to be queried from one execution context at a time.
+#[derive(Debug)]
+pub struct FixedStep {
+    step_us: u64,
+    steps: AtomicU32,
+}
+
+impl FixedStep {
+    /// Creates a time source starting at zero that advances by `step_us` microseconds per query.
+    #[must_use]
+    pub const fn new(step_us: u64) -> Self {
+        Self {
+            step_us,
+            steps: AtomicU32::new(0),
+        }
+    }
+}
+
+impl TimeSource for FixedStep {
+    fn now_micros(&self) -> u64 {
+        let steps = self.steps.load(Ordering::Relaxed);
+        self.steps.store(steps.wrapping_add(1), Ordering::Relaxed);
+        u64::from(steps) * self.step_us
+    }
+}
+
+/// A time source backed by a free-running 32-bit cycle counter, such as the
+/// Cortex-M DWT `CYCCNT` register.
+///
+/// The counter is extended to 64 bits internally; it must be queried at least once
+/// per counter wrap-around (e.g. every 59 seconds at 72 MHz) to not lose time. The state
+/// is kept in atomics, so that the source can be placed in a `static`; it is meant to be
+/// queried from one execution context at a time.
+///
+/// ```
+/// use l3gd20_ng::time::{CycleCounter, TimeSource};
+///
+/// fn cycle_count() -> u32 {
+///     // e.g. cortex_m::peripheral::DWT::cycle_count()
+///     # 72
+/// }
+///
+/// let clock = CycleCounter::new(cycle_count, 72_000_000);
+/// assert_eq!(clock.now_micros(), 1);
+/// ```
+#[derive(Debug)]
+pub struct CycleCounter {
+    read: fn() -> u32,
+    frequency_hz: u32,
+    last: AtomicU32,
+    wraps: AtomicU32,
+}
+
+impl CycleCounter {
+    /// Creates a time source from a function reading the cycle counter and the core clock frequency.
+    #[must_use]
+    pub const fn new(read: fn() -> u32, frequency_hz: u32) -> Self {
+        Self {
+            read,
+            frequency_hz,
+            last: AtomicU32::new(0),
+            wraps: AtomicU32::new(0),
+        }
+    }
+}
+
+impl TimeSource for CycleCounter {
+    fn now_micros(&self) -> u64 {
+        let count = (self.read)();
+        let mut wraps = self.wraps.load(Ordering::Relaxed);
+        if count < self.last.load(Ordering::Relaxed) {
+            wraps = wraps.wrapping_add(1);
+            self.wraps.store(wraps, Ordering::Relaxed);
+        }
+        self.last.store(count, Ordering::Relaxed);
+
+        // Split the conversion so that the product cannot overflow.
+        let cycles = u64::from(wraps) << 32 | u64::from(count);
+        let frequency_hz = u64::from(self.frequency_hz.max(1));
+        cycles / frequency_hz * 1_000_000 + cycles % frequency_hz * 1_000_000 / frequency_hz
+    }
+}
+
+/// A time source backed by an [`embedded_time::Clock`].
+///
+/// If the clock fails to provide the current time, zero is returned.
+#[cfg(feature = "embedded-time")]
+#[cfg_attr(docsrs, doc(cfg(feature = "embedded-time")))]
+#[derive(Debug)]
+pub struct EmbeddedTimeClock<C>(pub C);
+
+#[cfg(feature = "embedded-time")]
+impl<C> TimeSource for EmbeddedTimeClock<C>
+where
+    C: embedded_time::Clock,
+    u64: TryFrom<C::T>,
+{
+    fn now_micros(&self) -> u64 {
+        use embedded_time::duration::Microseconds;
+        use embedded_time::fixed_point::FixedPoint;
+
+        self.0
+            .try_now()
+            .ok()
+            .and_then(|now| Microseconds::<u64>::try_from(now.duration_since_epoch()).ok())
+            .map(|us| us.integer())
+            .unwrap_or(0)
+    }
+}
+
+impl<T, E> L3GD20<T>
+where
+    T: Transport<Error = E>,
+{
+    /// Installs the time source used by time-dependent features.
+    pub fn set_time_source(&mut self, clock: &'static dyn TimeSource) {
+        self.clock = Some(clock);
+    }
+
+    /// Gets the current time from the installed time source, in microseconds.
+    ///
+    /// Returns `None` if no time source was installed.
+    pub fn now_micros(&self) -> Option<u64> {
+        self.clock.map(|clock| clock.now_micros())
+    }
+
+    /// Measures the actual output data rate, in Hertz, over the given number of samples.
+    ///
+    /// The status register is polled for new data, which is read to clear the flag.
+    /// Returns `None` if no time source was installed or if no new data arrived for 50 ms.
+    pub fn measure_odr(&mut self, samples: u8) -> Result<Option<f32>, E> {
+        const TIMEOUT_US: u64 = 50_000;
+
+        let Some(mut last) = self.now_micros() else {
+            return Ok(None);
+        };
+
+        // Synchronize to the next sample first.
+        let mut first = None;
+        let mut remaining = samples.max(1);
+        while remaining > 0 {
+            let now = self.now_micros().unwrap_or(last);
+            if now.saturating_sub(last) > TIMEOUT_US {
+                return Ok(None);
+            }
+
+            if !self.has_new_sample()? {
+                continue;
+            }
+            self.xyz_raw()?;
+            last = now;
+            match first {
+                None => first = Some(now),
+                Some(_) => remaining -= 1,
+            }
+        }
+
+        let elapsed = last.saturating_sub(first.unwrap_or(last));
+        if elapsed == 0 {
+            return Ok(None);
+        }
+        Ok(Some(
+            f32::from(samples.max(1)) * 1_000_000.0 / elapsed as f32,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fixed_step_advances() {
+        let clock = FixedStep::new(1000);
+        assert_eq!(clock.now_micros(), 0);
+        assert_eq!(clock.now_micros(), 1000);
+        assert_eq!(clock.now_micros(), 2000);
+    }
+
+    #[test]
+    fn time_sources_can_be_static() {
+        static COUNT: AtomicU32 = AtomicU32::new(0);
+        static CLOCK: CycleCounter =
+            CycleCounter::new(|| COUNT.load(Ordering::Relaxed), 72_000_000);
+        static STEPS: FixedStep = FixedStep::new(10);
+
+        COUNT.store(u32::MAX, Ordering::Relaxed);
+        assert_eq!(CLOCK.now_micros(), u64::from(u32::MAX) / 72);
+        COUNT.store(71, Ordering::Relaxed);
+        assert_eq!(CLOCK.now_micros(), ((1 << 32) + 71) / 72);
+        assert_eq!(STEPS.now_micros(), 0);
+        assert_eq!(STEPS.now_micros(), 10);
+    }
+
+    #[test]
+    fn cycle_counts_convert_without_overflow() {
+        static COUNT: AtomicU32 = AtomicU32::new(0);
+        let clock = CycleCounter::new(|| COUNT.load(Ordering::Relaxed), 1_000_000_000);
+
+        // About six hours at 1 GHz, where the cycles times 10^6 overflow u64.
+        let wraps = 5_000;
+        clock.wraps.store(wraps, Ordering::Relaxed);
+        COUNT.store(1_000, Ordering::Relaxed);
+        let cycles = u64::from(wraps) << 32 | 1_000;
+        assert!(cycles.checked_mul(1_000_000).is_none());
+        assert_eq!(clock.now_micros(), cycles / 1_000);
+    }
+
+    #[test]
+    fn measure_odr_uses_time_source() {
+        use crate::mock::{MockCs, MockSpi};
+        use crate::L3GD20SPI;
+        use std::boxed::Box;
+
+        let mut spi = MockSpi::new();
+        spi.set_status(0b0000_1111);
+        let mut driver = L3GD20SPI::new(spi, MockCs).unwrap();
+        assert_eq!(driver.measure_odr(4), Ok(None));
+
+        driver.set_time_source(Box::leak(Box::new(FixedStep::new(1000))));
+        assert_eq!(driver.measure_odr(4), Ok(Some(1000.0)));
+    }
+}
@@ -0,0 +1,372 @@
+//! Provides crate-local versions of the register enums.
+//!
+//! The enums mirror those of [`l3gd20_registers`] and convert from and into them, but
+//! can additionally be parsed from human input via [`FromStr`] and [`TryFrom<u16>`],
+//! e.g. by CLI or configuration-file driven tools on hosted targets:
+//!
+//! ```
+//! use l3gd20_ng::{OutputDataRate, Sensitivity};
+//!
+//! assert_eq!(OutputDataRate::try_from(190), Ok(OutputDataRate::Hz190));
+//! assert_eq!("500".parse(), Ok(Sensitivity::D500));
+//! assert_eq!("380 Hz".parse(), Ok(OutputDataRate::Hz380));
+//! ```
+
+use crate::conversions::{BandwidthExt, OutputDataRateExt};
+use core::str::FromStr;
+use l3gd20_registers as registers;
+
+/// The error returned when parsing or converting a value that has no matching variant.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct InvalidValue;
+
+impl core::fmt::Display for InvalidValue {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str("invalid value")
+    }
+}
+
+/// Parses a number, optionally followed by the given unit.
+fn parse_number(s: &str, unit: &str) -> Result<u16, InvalidValue> {
+    let s = s.trim();
+    let s = s.strip_suffix(unit).unwrap_or(s).trim_end();
+    s.parse().map_err(|_| InvalidValue)
+}
+
+/// Output data rate.
+#[doc(alias = "ODR")]
+#[doc(alias = "DR")]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum OutputDataRate {
+    /// 95 Hz
+    Hz95,
+    /// 190 Hz
+    Hz190,
+    /// 380 Hz
+    Hz380,
+    /// 760 Hz
+    Hz760,
+}
+
+impl OutputDataRate {
+    /// Gets the output data rate in Hertz.
+    pub fn hz(&self) -> u16 {
+        registers::OutputDataRate::from(*self).hz()
+    }
+}
+
+impl TryFrom<u16> for OutputDataRate {
+    type Error = InvalidValue;
+
+    /// Converts a rate in Hertz.
+    fn try_from(hz: u16) -> Result<Self, InvalidValue> {
+        match hz {
+            95 => Ok(OutputDataRate::Hz95),
+            190 => Ok(OutputDataRate::Hz190),
+            380 => Ok(OutputDataRate::Hz380),
+            760 => Ok(OutputDataRate::Hz760),
+            _ => Err(InvalidValue),
+        }
+    }
+}
+
+impl FromStr for OutputDataRate {
+    type Err = InvalidValue;
+
+    /// Parses a rate in Hertz, e.g. `190` or `190 Hz`.
+    fn from_str(s: &str) -> Result<Self, InvalidValue> {
+        Self::try_from(parse_number(s, "Hz")?)
+    }
+}
+
+impl From<registers::OutputDataRate> for OutputDataRate {
+    fn from(value: registers::OutputDataRate) -> Self {
+        match value {
+            registers::OutputDataRate::Hz95 => OutputDataRate::Hz95,
+            registers::OutputDataRate::Hz190 => OutputDataRate::Hz190,
+            registers::OutputDataRate::Hz380 => OutputDataRate::Hz380,
+            registers::OutputDataRate::Hz760 => OutputDataRate::Hz760,
+        }
+    }
+}
+
+impl From<OutputDataRate> for registers::OutputDataRate {
+    fn from(value: OutputDataRate) -> Self {
+        match value {
+            OutputDataRate::Hz95 => registers::OutputDataRate::Hz95,
+            OutputDataRate::Hz190 => registers::OutputDataRate::Hz190,
+            OutputDataRate::Hz380 => registers::OutputDataRate::Hz380,
+            OutputDataRate::Hz760 => registers::OutputDataRate::Hz760,
+        }
+    }
+}
+
+/// Bandwidth selection; the cutoff frequency depends on the [`OutputDataRate`].
+#[doc(alias = "BW")]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Bandwidth {
+    /// `BW = 00`; 12.5 Hz at 95 Hz ODR.
+    Narrowest,
+    /// `BW = 01`; 25 Hz at 95 Hz ODR.
+    Narrow,
+    /// `BW = 10`; 25 Hz at 95 Hz ODR, 50 Hz at higher rates.
+    Medium,
+    /// `BW = 11`; 25 Hz at 95 Hz ODR, up to 100 Hz at higher rates.
+    Wide,
+}
+
+impl Bandwidth {
+    /// Gets the low-pass cutoff frequency in Hertz at the given output data rate.
+    pub fn cutoff_hz(&self, odr: OutputDataRate) -> f32 {
+        registers::Bandwidth::from(*self).cutoff_hz(odr.into())
+    }
+}
+
+impl TryFrom<u16> for Bandwidth {
+    type Error = InvalidValue;
+
+    /// Converts the two-bit `BW` code.
+    fn try_from(code: u16) -> Result<Self, InvalidValue> {
+        match code {
+            0b00 => Ok(Bandwidth::Narrowest),
+            0b01 => Ok(Bandwidth::Narrow),
+            0b10 => Ok(Bandwidth::Medium),
+            0b11 => Ok(Bandwidth::Wide),
+            _ => Err(InvalidValue),
+        }
+    }
+}
+
+impl FromStr for Bandwidth {
+    type Err = InvalidValue;
+
+    /// Parses the variant name (case-insensitive) or the two-bit `BW` code.
+    fn from_str(s: &str) -> Result<Self, InvalidValue> {
+        let s = s.trim();
+        [
+            ("narrowest", Bandwidth::Narrowest),
+            ("narrow", Bandwidth::Narrow),
+            ("medium", Bandwidth::Medium),
+            ("wide", Bandwidth::Wide),
+        ]
+        .into_iter()
+        .find(|(name, _)| s.eq_ignore_ascii_case(name))
+        .map(|(_, bandwidth)| Ok(bandwidth))
+        .unwrap_or_else(|| Self::try_from(parse_number(s, "")?))
+    }
+}
+
+impl From<registers::Bandwidth> for Bandwidth {
+    fn from(value: registers::Bandwidth) -> Self {
+        match value {
+            registers::Bandwidth::Narrowest => Bandwidth::Narrowest,
+            registers::Bandwidth::Narrow => Bandwidth::Narrow,
+            registers::Bandwidth::Medium => Bandwidth::Medium,
+            registers::Bandwidth::Wide => Bandwidth::Wide,
+        }
+    }
+}
+
+impl From<Bandwidth> for registers::Bandwidth {
+    fn from(value: Bandwidth) -> Self {
+        match value {
+            Bandwidth::Narrowest => registers::Bandwidth::Narrowest,
+            Bandwidth::Narrow => registers::Bandwidth::Narrow,
+            Bandwidth::Medium => registers::Bandwidth::Medium,
+            Bandwidth::Wide => registers::Bandwidth::Wide,
+        }
+    }
+}
+
+/// Full scale selection.
+#[doc(alias = "FS")]
+#[doc(alias = "full scale")]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Sensitivity {
+    /// 250 degrees/second
+    D250,
+    /// 500 degrees/second
+    D500,
+    /// 2000 degrees/second
+    D2000,
+    /// 2000 degrees/second, selected via the alternative `FS = 11` code.
+    D2000_11,
+}
+
+impl Sensitivity {
+    /// Gets the full scale in degrees/second.
+    pub const fn dps(&self) -> u16 {
+        match self {
+            Sensitivity::D250 => 250,
+            Sensitivity::D500 => 500,
+            Sensitivity::D2000 | Sensitivity::D2000_11 => 2000,
+        }
+    }
+
+    /// Gets the sensitivity in hundredths of mdps/LSB.
+    #[cfg(any(feature = "interrupts", all(feature = "dsp", feature = "fixed")))]
+    pub(crate) const fn centi_mdps_per_lsb(&self) -> u32 {
+        match self {
+            Sensitivity::D250 => 875,
+            Sensitivity::D500 => 1750,
+            Sensitivity::D2000 | Sensitivity::D2000_11 => 7000,
+        }
+    }
+}
+
+impl TryFrom<u16> for Sensitivity {
+    type Error = InvalidValue;
+
+    /// Converts a full scale in degrees/second.
+    fn try_from(dps: u16) -> Result<Self, InvalidValue> {
+        match dps {
+            250 => Ok(Sensitivity::D250),
+            500 => Ok(Sensitivity::D500),
+            2000 => Ok(Sensitivity::D2000),
+            _ => Err(InvalidValue),
+        }
+    }
+}
+
+impl FromStr for Sensitivity {
+    type Err = InvalidValue;
+
+    /// Parses a full scale in degrees/second, e.g. `500` or `500 dps`.
+    fn from_str(s: &str) -> Result<Self, InvalidValue> {
+        Self::try_from(parse_number(s, "dps")?)
+    }
+}
+
+impl From<registers::Sensitivity> for Sensitivity {
+    fn from(value: registers::Sensitivity) -> Self {
+        match value {
+            registers::Sensitivity::D250 => Sensitivity::D250,
+            registers::Sensitivity::D500 => Sensitivity::D500,
+            registers::Sensitivity::D2000 => Sensitivity::D2000,
+            registers::Sensitivity::D2000_11 => Sensitivity::D2000_11,
+        }
+    }
+}
+
+impl From<Sensitivity> for registers::Sensitivity {
+    fn from(value: Sensitivity) -> Self {
+        match value {
+            Sensitivity::D250 => registers::Sensitivity::D250,
+            Sensitivity::D500 => registers::Sensitivity::D500,
+            Sensitivity::D2000 => registers::Sensitivity::D2000,
+            Sensitivity::D2000_11 => registers::Sensitivity::D2000_11,
+        }
+    }
+}
+
+/// High-pass filter mode.
+#[doc(alias = "HPM")]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum HighpassFilterMode {
+    /// Normal mode, with the filter reset by reading the `REFERENCE` register.
+    NormalModeResetFilter,
+    /// Reference signal for filtering.
+    ReferenceSignal,
+    /// Normal mode.
+    NormalMode,
+    /// Autoreset on interrupt event.
+    AutoresetOnInterrupt,
+}
+
+impl TryFrom<u16> for HighpassFilterMode {
+    type Error = InvalidValue;
+
+    /// Converts the two-bit `HPM` code.
+    fn try_from(code: u16) -> Result<Self, InvalidValue> {
+        match code {
+            0b00 => Ok(HighpassFilterMode::NormalModeResetFilter),
+            0b01 => Ok(HighpassFilterMode::ReferenceSignal),
+            0b10 => Ok(HighpassFilterMode::NormalMode),
+            0b11 => Ok(HighpassFilterMode::AutoresetOnInterrupt),
+            _ => Err(InvalidValue),
+        }
+    }
+}
+
+impl FromStr for HighpassFilterMode {
+    type Err = InvalidValue;
+
+    /// Parses the variant name (case-insensitive) or the two-bit `HPM` code.
+    fn from_str(s: &str) -> Result<Self, InvalidValue> {
+        let s = s.trim();
+        [
+            (
+                "NormalModeResetFilter",
+                HighpassFilterMode::NormalModeResetFilter,
+            ),
+            ("ReferenceSignal", HighpassFilterMode::ReferenceSignal),
+            ("NormalMode", HighpassFilterMode::NormalMode),
+            (
+                "AutoresetOnInterrupt",
+                HighpassFilterMode::AutoresetOnInterrupt,
+            ),
+        ]
+        .into_iter()
+        .find(|(name, _)| s.eq_ignore_ascii_case(name))
+        .map(|(_, mode)| Ok(mode))
+        .unwrap_or_else(|| Self::try_from(parse_number(s, "")?))
+    }
+}
+
+impl From<registers::HighpassFilterMode> for HighpassFilterMode {
+    fn from(value: registers::HighpassFilterMode) -> Self {
+        match value {
+            registers::HighpassFilterMode::NormalModeResetFilter => {
+                HighpassFilterMode::NormalModeResetFilter
+            }
+            registers::HighpassFilterMode::ReferenceSignal => HighpassFilterMode::ReferenceSignal,
+            registers::HighpassFilterMode::NormalMode => HighpassFilterMode::NormalMode,
+            registers::HighpassFilterMode::AutoresetOnInterrupt => {
+                HighpassFilterMode::AutoresetOnInterrupt
+            }
+        }
+    }
+}
+
+impl From<HighpassFilterMode> for registers::HighpassFilterMode {
+    fn from(value: HighpassFilterMode) -> Self {
+        match value {
+            HighpassFilterMode::NormalModeResetFilter => {
+                registers::HighpassFilterMode::NormalModeResetFilter
+            }
+            HighpassFilterMode::ReferenceSignal => registers::HighpassFilterMode::ReferenceSignal,
+            HighpassFilterMode::NormalMode => registers::HighpassFilterMode::NormalMode,
+            HighpassFilterMode::AutoresetOnInterrupt => {
+                registers::HighpassFilterMode::AutoresetOnInterrupt
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn human_input_is_parsed() {
+        assert_eq!("760Hz".parse(), Ok(OutputDataRate::Hz760));
+        assert_eq!(" 2000 dps ".parse(), Ok(Sensitivity::D2000));
+        assert_eq!("Wide".parse(), Ok(Bandwidth::Wide));
+        assert_eq!("1".parse(), Ok(Bandwidth::Narrow));
+        assert_eq!("normalmode".parse(), Ok(HighpassFilterMode::NormalMode));
+        assert_eq!("100".parse::<OutputDataRate>(), Err(InvalidValue));
+        assert_eq!("fast".parse::<OutputDataRate>(), Err(InvalidValue));
+    }
+
+    #[test]
+    fn register_enums_round_trip() {
+        let odr = registers::OutputDataRate::from(OutputDataRate::Hz380);
+        assert_eq!(OutputDataRate::from(odr), OutputDataRate::Hz380);
+        assert_eq!(Bandwidth::Medium.cutoff_hz(OutputDataRate::Hz190), 50.0);
+    }
+}
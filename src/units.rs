@@ -0,0 +1,177 @@
+//! Provides the selection of the unit of angular rates returned by the driver.
+//!
+//! The unit is selected once with [`set_output_unit`](crate::L3GD20::set_output_unit) and
+//! honored by all reads that do not name their unit: [`rates`](crate::L3GD20::rates),
+//! [`peak_hold_rates`](crate::L3GD20::peak_hold_rates),
+//! [`rate_measurement`](crate::L3GD20::rate_measurement) and
+//! [`data_synced`](crate::L3GD20::data_synced). They share one conversion, so the same
+//! sample is reported with the same number everywhere. Only the reads that carry the unit
+//! in their name, [`xyz_dps`](crate::L3GD20::xyz_dps) and
+//! [`xyz_rad_s`](crate::L3GD20::xyz_rad_s), and the [`GyroSample`](crate::GyroSample) of
+//! AHRS filters, which are defined in radians per second, keep their fixed unit.
+
+use crate::conversions::DEG_TO_RAD;
+use crate::{F32x3, I16x3, Transport, L3GD20};
+
+/// The unit of angular rates.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum OutputUnit {
+    /// Uncompensated raw sensor readings, in LSB.
+    Raw,
+    /// Millidegrees per second.
+    MilliDegreesPerSecond,
+    /// Degrees per second.
+    #[default]
+    DegreesPerSecond,
+    /// Radians per second.
+    RadiansPerSecond,
+}
+
+impl OutputUnit {
+    /// Converts a rate in degrees per second to this unit.
+    ///
+    /// Returns `None` for [`Raw`](Self::Raw), which cannot be derived from a scaled rate.
+    #[must_use]
+    pub fn convert_dps(&self, dps: f32) -> Option<f32> {
+        match self {
+            Self::Raw => None,
+            Self::MilliDegreesPerSecond => Some(dps * 1000.0),
+            Self::DegreesPerSecond => Some(dps),
+            Self::RadiansPerSecond => Some(dps * DEG_TO_RAD),
+        }
+    }
+
+    /// Converts rates in degrees per second to this unit, given the sensitivity in
+    /// degrees per second per LSB to recover [`Raw`](Self::Raw) values.
+    fn convert_scaled(&self, dps: F32x3, sensitivity: F32x3) -> F32x3 {
+        match self.convert_dps(1.0) {
+            Some(factor) => F32x3::new(dps.x * factor, dps.y * factor, dps.z * factor),
+            None => F32x3::new(
+                dps.x / sensitivity.x,
+                dps.y / sensitivity.y,
+                dps.z / sensitivity.z,
+            ),
+        }
+    }
+}
+
+impl<T, E> L3GD20<T>
+where
+    T: Transport<Error = E>,
+{
+    /// Selects the unit of the rates returned by the high-level read methods.
+    pub fn set_output_unit(&mut self, unit: OutputUnit) {
+        self.output_unit = unit;
+    }
+
+    /// Gets the unit of the rates returned by the high-level read methods.
+    pub fn output_unit(&self) -> OutputUnit {
+        self.output_unit
+    }
+
+    /// Fetches the X, Y and Z-axis rates in the selected [output unit](Self::set_output_unit).
+    ///
+    /// Except for [`OutputUnit::Raw`], the rates are compensated like in
+    /// [`xyz_dps`](Self::xyz_dps) and then converted to the selected unit.
+    pub fn rates(&mut self) -> Result<F32x3, E> {
+        let raw = self.xyz_raw()?;
+        self.rates_in_output_unit(raw)
+    }
+
+    /// Gets the held peak rates in the selected [output unit](Self::set_output_unit),
+    /// if tracking is enabled.
+    ///
+    /// The peaks are compensated like the readings of [`rates`](Self::rates).
+    #[cfg(feature = "dsp")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "dsp")))]
+    pub fn peak_hold_rates(&mut self) -> Result<Option<F32x3>, E> {
+        match self.peak_hold() {
+            Some(peak) => self.rates_in_output_unit(peak).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Converts a raw reading to the selected output unit.
+    ///
+    /// Raw readings are returned as-is; otherwise they are compensated like in
+    /// [`xyz_dps`](Self::xyz_dps).
+    pub(crate) fn rates_in_output_unit(&mut self, raw: I16x3) -> Result<F32x3, E> {
+        if self.output_unit == OutputUnit::Raw {
+            return Ok(F32x3::new(
+                f32::from(raw.x),
+                f32::from(raw.y),
+                f32::from(raw.z),
+            ));
+        }
+        let scale = self.scale()?;
+        let dps = self.compensation.apply(raw, scale);
+        Ok(self.output_unit.convert_scaled(dps, scale))
+    }
+
+    /// Converts per-axis rates in degrees per second, e.g. noise levels, to the selected
+    /// output unit, using the sensitivity to express them in LSB for [`OutputUnit::Raw`].
+    #[cfg(feature = "measurement")]
+    pub(crate) fn levels_in_output_unit(&mut self, dps: F32x3) -> Result<F32x3, E> {
+        let scale = self.scale()?;
+        Ok(self.output_unit.convert_scaled(dps, scale))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::MockError;
+    use crate::sim::Simulator;
+
+    #[test]
+    fn rates_honor_the_output_unit() {
+        let mut driver = L3GD20::with_transport(Simulator::<MockError>::new()).unwrap();
+        driver.transport.set_xyz(1000, -2000, 0);
+
+        assert_eq!(driver.output_unit(), OutputUnit::DegreesPerSecond);
+        let dps = driver.rates().unwrap();
+        assert!(libm::fabsf(dps.x - 8.75) < 1e-4);
+
+        driver.set_output_unit(OutputUnit::Raw);
+        assert_eq!(driver.rates().unwrap(), F32x3::new(1000.0, -2000.0, 0.0));
+
+        driver.set_output_unit(OutputUnit::MilliDegreesPerSecond);
+        assert!(libm::fabsf(driver.rates().unwrap().y + 17_500.0) < 1e-1);
+
+        driver.set_output_unit(OutputUnit::RadiansPerSecond);
+        assert!(libm::fabsf(driver.rates().unwrap().x - 8.75 * DEG_TO_RAD) < 1e-6);
+    }
+
+    #[test]
+    fn rates_share_the_compensation_of_xyz_dps() {
+        let mut driver = L3GD20::with_transport(Simulator::<MockError>::new()).unwrap();
+        driver.transport.set_xyz(0, 0, -1000);
+        driver.set_gain(F32x3::new(1.0, 1.0, 2.0));
+        driver.set_output_unit(OutputUnit::RadiansPerSecond);
+
+        let dps = driver.xyz_dps().unwrap();
+        let rates = driver.rates().unwrap();
+        assert!(libm::fabsf(rates.z - dps.z * DEG_TO_RAD) < 1e-6);
+        assert!(libm::fabsf(rates.z + 0.305_433) < 1e-5);
+    }
+
+    #[test]
+    #[cfg(feature = "dsp")]
+    fn peak_hold_rates_match_the_rates() {
+        let mut driver = L3GD20::with_transport(Simulator::<MockError>::new()).unwrap();
+        driver.set_bias(crate::Bias {
+            offset: F32x3::new(100.0, 0.0, 0.0),
+            ..crate::Bias::default()
+        });
+        driver.enable_peak_hold(0);
+        driver.transport.set_xyz(1000, 0, 0);
+
+        for unit in [OutputUnit::Raw, OutputUnit::DegreesPerSecond] {
+            driver.set_output_unit(unit);
+            let rates = driver.rates().unwrap();
+            assert_eq!(driver.peak_hold_rates().unwrap(), Some(rates));
+        }
+        assert!(libm::fabsf(driver.rates().unwrap().x - 900.0 * 0.00875) < 1e-4);
+    }
+}
@@ -0,0 +1,36 @@
+//! Provides feeding of an external watchdog during long-running operations.
+
+use crate::{Transport, L3GD20};
+
+impl<T, E> L3GD20<T>
+where
+    T: Transport<Error = E>,
+{
+    /// Registers a function that feeds the application's hardware watchdog, or removes it
+    /// with `None`.
+    ///
+    /// The function is called between the iterations of long-running operations, such as
+    /// [`wait_fifo_empty`](Self::wait_fifo_empty), [`capture_window`](Self::capture_window)
+    /// and [`preflight_check`](Self::preflight_check), so that a watchdog timeout shorter
+    /// than these operations does not reset the system.
+    ///
+    /// ```
+    /// # #[cfg(feature = "sim")] {
+    /// # use l3gd20_ng::{sim::Simulator, L3GD20};
+    /// # let mut driver = L3GD20::with_transport(Simulator::<()>::new()).unwrap();
+    /// driver.set_watchdog_feed(Some(|| {
+    ///     // e.g. reload the independent watchdog here
+    /// }));
+    /// # }
+    /// ```
+    pub fn set_watchdog_feed(&mut self, feed: Option<fn()>) {
+        self.watchdog = feed;
+    }
+
+    /// Feeds the watchdog, if one is registered.
+    pub(crate) fn feed_watchdog(&self) {
+        if let Some(feed) = self.watchdog {
+            feed();
+        }
+    }
+}
@@ -1,3 +1,7 @@
 //! Provides wrappers for SPI types.
 
+#[cfg(feature = "critical-section")]
+#[cfg_attr(docsrs, doc(cfg(feature = "critical-section")))]
+pub mod critical_section;
 pub mod refcell;
+pub mod shared;
@@ -0,0 +1,306 @@
+//! Provides a [critical section](https://docs.rs/critical-section) wrapper for SPI types.
+//!
+//! Unlike [`RefCellSPI`](super::refcell::RefCellSPI), a [`CriticalSectionSPI`] can be placed
+//! in a `static` and shared by reference between drivers running in different interrupt
+//! contexts. Each driver accesses it through its own [`CriticalSectionDevice`], which asserts
+//! the chip select line and runs the transfer inside the same critical section, so that no
+//! other device is clocked while the line is asserted.
+//!
+//! ```
+//! use embedded_hal::blocking::spi::Transfer;
+//! use l3gd20_ng::wrapper::critical_section::{CriticalSectionDevice, CriticalSectionSPI};
+//! use l3gd20_ng::L3GD20;
+//! # use chip_select::{ChipSelect, ChipSelectGuarded};
+//! # struct Spi1;
+//! # impl Transfer<u8> for Spi1 {
+//! #     type Error = ();
+//! #     fn transfer<'w>(&mut self, words: &'w mut [u8]) -> Result<&'w [u8], ()> {
+//! #         words[1] = 0xD4;
+//! #         Ok(words)
+//! #     }
+//! # }
+//! # struct Cs;
+//! # impl ChipSelect for Cs { fn select(&mut self) {} fn deselect(&mut self) {} }
+//! # impl ChipSelectGuarded for Cs {
+//! #     type Guard<'a> = ();
+//! #     fn select_guard(&mut self) -> Self::Guard<'_> {}
+//! # }
+//!
+//! static BUS: CriticalSectionSPI<Spi1> = CriticalSectionSPI::new(Spi1);
+//!
+//! // e.g. in the main loop, with other devices created the same way in interrupt handlers
+//! let gyro = L3GD20::with_transport(CriticalSectionDevice::new(&BUS, Cs)).unwrap();
+//! ```
+
+use crate::Transport;
+use chip_select::ChipSelectGuarded;
+use core::cell::RefCell;
+use critical_section::Mutex;
+use embedded_hal::blocking::spi::Transfer;
+
+/// An SPI instance shared through a critical section.
+///
+/// [`Transfer`] is implemented for shared references, so that several drivers can use the
+/// same instance.
+pub struct CriticalSectionSPI<SPI>(Mutex<RefCell<SPI>>);
+
+impl<SPI> CriticalSectionSPI<SPI> {
+    /// Initializes a new instance of the [`CriticalSectionSPI`] type.
+    pub const fn new(spi: SPI) -> Self {
+        Self(Mutex::new(RefCell::new(spi)))
+    }
+
+    /// Consumes self and returns the inner SPI instance.
+    #[inline]
+    pub fn into_inner(self) -> SPI {
+        self.0.into_inner().into_inner()
+    }
+
+    /// Runs a closure with exclusive access to the SPI instance inside a critical section.
+    pub fn lock<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce(&mut SPI) -> R,
+    {
+        critical_section::with(|cs| f(&mut self.0.borrow_ref_mut(cs)))
+    }
+}
+
+/// Transfers through a shared reference lock the bus for the transfer only.
+///
+/// Chip select lines asserted outside of the critical section may be preempted by another
+/// device on the bus; use a [`CriticalSectionDevice`] to share the bus between interrupt
+/// contexts.
+impl<SPI, E> Transfer<u8> for &CriticalSectionSPI<SPI>
+where
+    SPI: Transfer<u8, Error = E>,
+{
+    type Error = E;
+
+    fn transfer<'w>(&mut self, words: &'w mut [u8]) -> Result<&'w [u8], Self::Error> {
+        self.lock(|spi| spi.transfer(words))
+    }
+}
+
+impl<SPI, E> Transfer<u8> for CriticalSectionSPI<SPI>
+where
+    SPI: Transfer<u8, Error = E>,
+{
+    type Error = E;
+
+    fn transfer<'w>(&mut self, words: &'w mut [u8]) -> Result<&'w [u8], Self::Error> {
+        self.0.get_mut().get_mut().transfer(words)
+    }
+}
+
+/// A device on a [`CriticalSectionSPI`] with a dedicated chip select line.
+///
+/// Every transfer asserts the chip select line and clocks the bus inside a single critical
+/// section, so that devices on the same bus can be driven from different interrupt contexts.
+pub struct CriticalSectionDevice<'a, SPI, CS> {
+    bus: &'a CriticalSectionSPI<SPI>,
+    cs: CS,
+}
+
+impl<'a, SPI, CS> CriticalSectionDevice<'a, SPI, CS> {
+    /// Creates a device on the shared bus.
+    pub const fn new(bus: &'a CriticalSectionSPI<SPI>, chip_select: CS) -> Self {
+        Self {
+            bus,
+            cs: chip_select,
+        }
+    }
+
+    /// Releases the chip select line.
+    pub fn release(self) -> CS {
+        self.cs
+    }
+}
+
+impl<SPI, CS, E> Transport for CriticalSectionDevice<'_, SPI, CS>
+where
+    SPI: Transfer<u8, Error = E>,
+    CS: ChipSelectGuarded,
+{
+    type Error = E;
+
+    fn transfer(&mut self, buffer: &mut [u8]) -> Result<(), E> {
+        let cs = &mut self.cs;
+        self.bus.lock(|spi| {
+            let _guard = cs.select_guard();
+            spi.transfer(buffer)?;
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{MockCs, MockSpi};
+    use crate::{L3GD20, L3GD20SPI};
+    use chip_select::{ChipSelect, DeselectOnDrop};
+    use core::sync::atomic::{AtomicUsize, Ordering};
+    use std::boxed::Box;
+
+    #[test]
+    fn bus_is_shared_across_threads() {
+        let bus: &'static CriticalSectionSPI<MockSpi> =
+            Box::leak(Box::new(CriticalSectionSPI::new(MockSpi::new())));
+        bus.lock(|spi| spi.set_xyz(1, 2, 3));
+        let mut driver = L3GD20SPI::new(bus, MockCs).unwrap();
+
+        let other = std::thread::spawn(move || {
+            let mut spi = bus;
+            for _ in 0..100 {
+                let mut buffer = [0x8F, 0];
+                spi.transfer(&mut buffer).unwrap();
+                assert_eq!(buffer[1], 0xD4);
+            }
+        });
+        for _ in 0..100 {
+            assert_eq!(driver.xyz_raw().unwrap(), crate::I16x3::new(1, 2, 3));
+        }
+        other.join().unwrap();
+        assert!(bus.lock(|spi| spi.transfers) >= 200);
+    }
+
+    /// The chip select lines currently asserted, one bit per device.
+    static SELECTED: AtomicUsize = AtomicUsize::new(0);
+
+    /// A chip select line recording its state in [`SELECTED`].
+    struct FlagCs(usize);
+
+    impl ChipSelect for FlagCs {
+        fn select(&mut self) {
+            SELECTED.fetch_or(self.0, Ordering::SeqCst);
+        }
+
+        fn deselect(&mut self) {
+            SELECTED.fetch_and(!self.0, Ordering::SeqCst);
+        }
+    }
+
+    impl ChipSelectGuarded for FlagCs {
+        type Guard<'a> = DeselectOnDrop<'a, Self>;
+
+        fn select_guard(&mut self) -> Self::Guard<'_> {
+            self.select();
+            DeselectOnDrop::from(self)
+        }
+    }
+
+    /// An SPI bus counting the transfers that clocked more than one selected device.
+    struct SelectSpi {
+        collisions: &'static AtomicUsize,
+    }
+
+    impl Transfer<u8> for SelectSpi {
+        type Error = ();
+
+        fn transfer<'w>(&mut self, words: &'w mut [u8]) -> Result<&'w [u8], ()> {
+            // Widen the window in which another device could assert its line.
+            std::thread::yield_now();
+            if SELECTED.load(Ordering::SeqCst).count_ones() != 1 {
+                self.collisions.fetch_add(1, Ordering::SeqCst);
+            }
+            Ok(words)
+        }
+    }
+
+    #[test]
+    fn devices_are_selected_inside_the_critical_section() {
+        static COLLISIONS: AtomicUsize = AtomicUsize::new(0);
+        static BUS: CriticalSectionSPI<SelectSpi> = CriticalSectionSPI::new(SelectSpi {
+            collisions: &COLLISIONS,
+        });
+
+        std::thread::scope(|scope| {
+            for device in 0..4 {
+                scope.spawn(move || {
+                    let mut device = CriticalSectionDevice::new(&BUS, FlagCs(1 << device));
+                    for _ in 0..1_000 {
+                        device.transfer(&mut [0x8F, 0]).unwrap();
+                    }
+                });
+            }
+        });
+        assert_eq!(COLLISIONS.load(Ordering::SeqCst), 0);
+        assert_eq!(SELECTED.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn driver_runs_over_a_device() {
+        let bus = CriticalSectionSPI::new(MockSpi::new());
+        bus.lock(|spi| spi.set_xyz(1, 2, 3));
+        let mut driver = L3GD20::with_transport(CriticalSectionDevice::new(&bus, MockCs)).unwrap();
+        assert_eq!(driver.xyz_raw().unwrap(), crate::I16x3::new(1, 2, 3));
+    }
+
+    #[test]
+    #[should_panic(expected = "already")]
+    fn nested_locks_panic() {
+        let bus = CriticalSectionSPI::new(MockSpi::new());
+
+        // Critical sections nest, e.g. when an interrupt handler preempts a locked section
+        // on a single core, but the bus must not be borrowed twice.
+        bus.lock(|_| bus.lock(|_| ()));
+    }
+
+    #[test]
+    fn panics_do_not_poison_the_bus() {
+        let bus: &'static CriticalSectionSPI<MockSpi> =
+            Box::leak(Box::new(CriticalSectionSPI::new(MockSpi::new())));
+
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            bus.lock(|_| panic!("failure while the bus is locked"));
+        }));
+        assert!(result.is_err());
+
+        // Both the critical section and the borrow were released during unwinding.
+        let other = std::thread::spawn(move || bus.lock(|spi| spi.registers[0x0F]));
+        assert_eq!(other.join().unwrap(), 0xD4);
+        let mut spi = bus;
+        assert_eq!(spi.transfer(&mut [0x8F, 0]).unwrap()[1], 0xD4);
+    }
+
+    /// An SPI bus recording the number of transfers in progress at the same time.
+    struct OverlapSpi {
+        active: &'static AtomicUsize,
+        max_active: &'static AtomicUsize,
+    }
+
+    impl Transfer<u8> for OverlapSpi {
+        type Error = ();
+
+        fn transfer<'w>(&mut self, words: &'w mut [u8]) -> Result<&'w [u8], ()> {
+            let active = self.active.fetch_add(1, Ordering::SeqCst) + 1;
+            self.max_active.fetch_max(active, Ordering::SeqCst);
+            // Widen the window in which another thread could enter.
+            std::thread::yield_now();
+            self.active.fetch_sub(1, Ordering::SeqCst);
+            Ok(words)
+        }
+    }
+
+    #[test]
+    fn transfers_never_overlap() {
+        static ACTIVE: AtomicUsize = AtomicUsize::new(0);
+        static MAX_ACTIVE: AtomicUsize = AtomicUsize::new(0);
+        static BUS: CriticalSectionSPI<OverlapSpi> = CriticalSectionSPI::new(OverlapSpi {
+            active: &ACTIVE,
+            max_active: &MAX_ACTIVE,
+        });
+
+        std::thread::scope(|scope| {
+            for _ in 0..4 {
+                scope.spawn(|| {
+                    let mut spi = &BUS;
+                    for _ in 0..1_000 {
+                        spi.transfer(&mut [0x8F, 0]).unwrap();
+                    }
+                });
+            }
+        });
+        assert_eq!(MAX_ACTIVE.load(Ordering::SeqCst), 1);
+    }
+}
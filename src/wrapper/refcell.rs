@@ -57,3 +57,21 @@ where
         &mut self.0
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::MockSpi;
+
+    #[test]
+    fn panics_do_not_poison_the_bus() {
+        let mut spi = RefCellSPI::new(RefCell::new(MockSpi::new()));
+
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            let _borrowed = spi.borrow_mut();
+            panic!("failure while the bus is borrowed");
+        }));
+        assert!(result.is_err());
+        assert_eq!(spi.transfer(&mut [0x8F, 0]).unwrap()[1], 0xD4);
+    }
+}
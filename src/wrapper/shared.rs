@@ -0,0 +1,232 @@
+//! Provides sharing of an SPI bus between several devices.
+//!
+//! Each device obtains its own [`SharedSpi`] handle from a [`SharedBus`]. While one device
+//! holds the bus lock, e.g. through [`L3GD20::acquire_bus`](crate::L3GD20::acquire_bus),
+//! transfers of all other devices fail with [`SharedBusError::Busy`].
+
+use crate::bus::{BusLock, BusyError};
+use crate::SpiTransport;
+use core::cell::{Cell, RefCell};
+use embedded_hal::blocking::spi::Transfer;
+
+/// An SPI bus shared between several devices in the same execution context.
+pub struct SharedBus<SPI> {
+    spi: RefCell<SPI>,
+    owner: Cell<Option<u8>>,
+}
+
+/// An error of a transfer over a [`SharedBus`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum SharedBusError<E> {
+    /// The bus transfer failed.
+    Bus(E),
+    /// The bus is locked by another device.
+    Busy,
+}
+
+impl<E> BusyError for SharedBusError<E> {
+    fn is_busy(&self) -> bool {
+        matches!(self, SharedBusError::Busy)
+    }
+}
+
+impl<SPI> SharedBus<SPI> {
+    /// Initializes a new instance of the [`SharedBus`] type.
+    pub const fn new(spi: SPI) -> Self {
+        Self {
+            spi: RefCell::new(spi),
+            owner: Cell::new(None),
+        }
+    }
+
+    /// Creates the handle of the device with the given identifier.
+    ///
+    /// The identifier must be unique among the devices on the bus.
+    pub fn device(&self, id: u8) -> SharedSpi<'_, SPI> {
+        SharedSpi { bus: self, id }
+    }
+
+    /// Consumes self and returns the inner SPI instance.
+    pub fn into_inner(self) -> SPI {
+        self.spi.into_inner()
+    }
+}
+
+/// The handle of a device on a [`SharedBus`].
+pub struct SharedSpi<'a, SPI> {
+    bus: &'a SharedBus<SPI>,
+    id: u8,
+}
+
+impl<SPI> SharedSpi<'_, SPI> {
+    /// Locks the bus for exclusive use by this device.
+    ///
+    /// A lock held by another device is not taken over. Returns whether this device holds
+    /// the lock.
+    #[must_use]
+    pub fn lock(&mut self) -> bool {
+        match self.bus.owner.get() {
+            None => {
+                self.bus.owner.set(Some(self.id));
+                true
+            }
+            Some(owner) => owner == self.id,
+        }
+    }
+
+    /// Releases the lock, if held by this device.
+    pub fn unlock(&mut self) {
+        if self.bus.owner.get() == Some(self.id) {
+            self.bus.owner.set(None);
+        }
+    }
+}
+
+impl<SPI, E> Transfer<u8> for SharedSpi<'_, SPI>
+where
+    SPI: Transfer<u8, Error = E>,
+{
+    type Error = SharedBusError<E>;
+
+    fn transfer<'w>(&mut self, words: &'w mut [u8]) -> Result<&'w [u8], Self::Error> {
+        if matches!(self.bus.owner.get(), Some(owner) if owner != self.id) {
+            return Err(SharedBusError::Busy);
+        }
+        self.bus
+            .spi
+            .borrow_mut()
+            .transfer(words)
+            .map_err(SharedBusError::Bus)
+    }
+}
+
+impl<CS, SPI> BusLock for SpiTransport<CS, SharedSpi<'_, SPI>> {
+    fn lock_bus(&mut self) -> bool {
+        self.spi_mut().lock()
+    }
+
+    fn unlock_bus(&mut self) {
+        self.spi_mut().unlock();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{MockCs, MockSpi};
+    use crate::L3GD20;
+    use std::vec;
+    use std::vec::Vec;
+
+    #[test]
+    fn other_devices_are_blocked_while_the_bus_is_acquired() {
+        let bus = SharedBus::new(MockSpi::new());
+        let mut other = bus.device(1);
+        let mut driver = L3GD20::new(bus.device(0), MockCs).unwrap();
+
+        {
+            let mut guard = driver.acquire_bus().unwrap();
+            assert!(guard.identify().is_ok());
+            assert_eq!(other.transfer(&mut [0x8F, 0]), Err(SharedBusError::Busy));
+        }
+        assert!(other.transfer(&mut [0x8F, 0]).is_ok());
+    }
+
+    #[test]
+    fn a_bus_locked_by_another_device_is_not_acquired() {
+        let bus = SharedBus::new(MockSpi::new());
+        let mut other = bus.device(1);
+        let mut driver = L3GD20::new(bus.device(0), MockCs).unwrap();
+
+        assert!(other.lock());
+        assert_eq!(driver.acquire_bus().err(), Some(crate::Error::BusBusy));
+        assert_eq!(bus.owner.get(), Some(1));
+        assert!(other.transfer(&mut [0x8F, 0]).is_ok());
+
+        other.unlock();
+        assert!(driver.acquire_bus().is_ok());
+        assert_eq!(bus.owner.get(), None);
+    }
+
+    #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+    enum Step {
+        Lock,
+        Transfer,
+        Unlock,
+    }
+
+    /// The steps of a device reading a register within a locked section.
+    const SECTION: [Step; 4] = [Step::Lock, Step::Transfer, Step::Transfer, Step::Unlock];
+
+    /// Enumerates all interleavings of two step sequences of the given lengths, as the
+    /// sequence of devices taking a step.
+    fn interleavings(a: usize, b: usize) -> Vec<Vec<u8>> {
+        if a == 0 || b == 0 {
+            return vec![[0].repeat(a).into_iter().chain([1].repeat(b)).collect()];
+        }
+        let mut result = Vec::new();
+        for (device, rest) in [(0, interleavings(a - 1, b)), (1, interleavings(a, b - 1))] {
+            result.extend(rest.into_iter().map(|mut order| {
+                order.insert(0, device);
+                order
+            }));
+        }
+        result
+    }
+
+    #[test]
+    fn all_interleavings_respect_the_bus_lock() {
+        let orders = interleavings(SECTION.len(), SECTION.len());
+        assert_eq!(orders.len(), 70);
+
+        for order in orders {
+            let bus = SharedBus::new(MockSpi::new());
+            let mut devices = [bus.device(0), bus.device(1)];
+            let mut progress = [0; 2];
+            let mut owner = None;
+
+            for &device in &order {
+                let id = usize::from(device);
+                let step = SECTION[progress[id]];
+                progress[id] += 1;
+                let free = owner.map_or(true, |owner| owner == device);
+                match step {
+                    Step::Lock => {
+                        assert_eq!(devices[id].lock(), free, "{order:?}");
+                        if free {
+                            owner = Some(device);
+                        }
+                    }
+                    Step::Transfer => {
+                        let result = devices[id].transfer(&mut [0x8F, 0]).map(|w| w[1]);
+                        let expected = if free {
+                            Ok(0xD4)
+                        } else {
+                            Err(SharedBusError::Busy)
+                        };
+                        assert_eq!(result, expected, "{order:?}");
+                    }
+                    Step::Unlock => {
+                        devices[id].unlock();
+                        if owner == Some(device) {
+                            owner = None;
+                        }
+                    }
+                }
+            }
+            assert_eq!(bus.owner.get(), None, "{order:?}");
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "already")]
+    fn reentrant_transfers_panic() {
+        let bus = SharedBus::new(MockSpi::new());
+        let mut device = bus.device(0);
+
+        // A transfer from an interrupt preempting an ongoing transfer of the same context.
+        let _ongoing = bus.spi.borrow_mut();
+        let _ = device.transfer(&mut [0x8F, 0]);
+    }
+}
@@ -0,0 +1,147 @@
+//! Provides a zero-velocity update (ZUPT) signal.
+//!
+//! Dead-reckoning filters for pedestrians and ground robots commonly apply a zero-velocity
+//! pseudo-measurement whenever the device is known to be at rest. The [`StationaryDetector`]
+//! declares the device stationary once the angular rate stayed below a threshold for a
+//! configurable number of consecutive samples; the number of samples trades the latency
+//! of the signal for the confidence in it.
+
+use crate::characteristics::sensitivity;
+use crate::{F32x3, I16x3, Transport, L3GD20};
+
+/// The configuration of the [`StationaryDetector`].
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ZuptConfig {
+    /// The largest magnitude of the angular rate considered at rest, in degrees/second.
+    pub threshold_dps: f32,
+    /// The number of consecutive samples below the threshold required to signal rest.
+    pub confidence_samples: u16,
+}
+
+impl Default for ZuptConfig {
+    /// A threshold well above the zero-rate noise, held for ten samples.
+    fn default() -> Self {
+        Self {
+            threshold_dps: 3.0,
+            confidence_samples: 10,
+        }
+    }
+}
+
+/// Detects whether the device is stationary from a stream of angular rates.
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct StationaryDetector {
+    config: ZuptConfig,
+    still: u16,
+}
+
+impl StationaryDetector {
+    /// Creates a detector that starts out in motion.
+    pub const fn new(config: ZuptConfig) -> Self {
+        Self { config, still: 0 }
+    }
+
+    /// Gets the configuration.
+    pub fn config(&self) -> &ZuptConfig {
+        &self.config
+    }
+
+    /// Updates the detector with a bias-corrected angular rate, in degrees/second,
+    /// and returns whether the device is stationary.
+    pub fn update(&mut self, rate_dps: F32x3) -> bool {
+        let magnitude = libm::sqrtf(
+            rate_dps.x * rate_dps.x + rate_dps.y * rate_dps.y + rate_dps.z * rate_dps.z,
+        );
+        if magnitude <= self.config.threshold_dps {
+            self.still = self.still.saturating_add(1);
+        } else {
+            self.still = 0;
+        }
+        self.is_stationary()
+    }
+
+    /// Determines whether the device is stationary.
+    pub fn is_stationary(&self) -> bool {
+        self.still >= self.config.confidence_samples.max(1)
+    }
+
+    /// Resets the detector to the moving state.
+    pub fn reset(&mut self) {
+        self.still = 0;
+    }
+}
+
+impl<T, E> L3GD20<T>
+where
+    T: Transport<Error = E>,
+{
+    /// Enables the zero-velocity update signal.
+    ///
+    /// The detector is fed with every sample read from the axis output registers, corrected
+    /// for the applied bias and scaled by the configured full scale.
+    pub fn enable_zupt(&mut self, config: ZuptConfig) {
+        self.zupt = Some(StationaryDetector::new(config));
+    }
+
+    /// Disables the zero-velocity update signal.
+    pub fn disable_zupt(&mut self) {
+        self.zupt = None;
+    }
+
+    /// Determines whether a zero-velocity update is due, i.e. the device is stationary.
+    ///
+    /// Returns `false` if the signal is not enabled.
+    pub fn zupt(&self) -> bool {
+        self.zupt
+            .as_ref()
+            .map_or(false, StationaryDetector::is_stationary)
+    }
+
+    /// Updates the stationary detector with a sample that was read, if it is enabled.
+    pub(crate) fn record_zupt(&mut self, sample: I16x3) {
+        let Some(detector) = self.zupt.as_mut() else {
+            return;
+        };
+        let scale = sensitivity(self.expected_settings.full_scale);
+        let offset = self.compensation.bias.offset;
+        detector.update(F32x3::new(
+            (f32::from(sample.x) - offset.x) * scale,
+            (f32::from(sample.y) - offset.y) * scale,
+            (f32::from(sample.z) - offset.z) * scale,
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::MockError;
+    use crate::sim::Simulator;
+
+    #[test]
+    fn zupt_requires_consecutive_still_samples() {
+        let mut driver = L3GD20::with_transport(Simulator::<MockError>::new()).unwrap();
+        assert!(!driver.zupt());
+        driver.enable_zupt(ZuptConfig {
+            threshold_dps: 1.0,
+            confidence_samples: 3,
+        });
+
+        // 100 LSB at 8.75 mdps/LSB are 0.875 dps.
+        driver.transport.set_xyz(100, 0, 0);
+        driver.xyz_raw().unwrap();
+        driver.xyz_raw().unwrap();
+        assert!(!driver.zupt());
+        driver.data_raw().unwrap();
+        assert!(driver.zupt());
+
+        driver.transport.set_xyz(0, 200, 0);
+        driver.xyz_raw().unwrap();
+        assert!(!driver.zupt());
+
+        driver.disable_zupt();
+        assert!(!driver.zupt());
+    }
+}